@@ -228,6 +228,26 @@ fn bench_simd_operations(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark intersecting a rare term with a common one
+fn bench_intersect_terms(c: &mut Criterion) {
+    use Drusdenx::analysis::token::Token;
+    use Drusdenx::index::inverted::{InvertedIndex, Term};
+
+    let mut index = InvertedIndex::new();
+    for id in 0..5_000u64 {
+        let mut tokens = vec![Token::new("common".to_string(), 0, 0)];
+        if id % 500 == 0 {
+            tokens.push(Token::new("rare".to_string(), 1, 7));
+        }
+        index.add_document(DocId(id), &tokens).unwrap();
+    }
+    let terms = [Term::new("common"), Term::new("rare")];
+
+    c.bench_function("intersect_terms_rare_common", |b| {
+        b.iter(|| index.intersect_terms(black_box(&terms)).unwrap());
+    });
+}
+
 /// Benchmark transaction operations
 fn bench_transactions(c: &mut Criterion) {
     let mut group = c.benchmark_group("transactions");
@@ -376,14 +396,142 @@ fn bench_throughput(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark phrase queries with intersected postings vs. a per-document scan
+fn bench_phrase_verification(c: &mut Criterion) {
+    use Drusdenx::search::executor::ExecutionConfig;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.storage_path = temp_dir.path().to_path_buf();
+    config.writer_batch_size = 10_000;
+    {
+        let db = Database::open_with_schema(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+        for i in 0..2000 {
+            db.add_document(create_test_document(i, 100)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    // Phrase positions come from the inverted index loaded on open
+    let db = Database::open_with_schema(SchemaWithAnalyzer::new(), config).unwrap();
+
+    // Nearly every document holds both words, so most are phrase candidates.
+    // Projecting fields keeps the results out of the query cache.
+    let mut group = c.benchmark_group("phrase_verification");
+    group.sample_size(10);
+    group.bench_function("intersected", |b| {
+        let config = ExecutionConfig::bm25().with_fields(["title"]);
+        b.iter(|| db.search_with_config(black_box("content:\"quick brown\""), 10, config.clone()).unwrap());
+    });
+    group.bench_function("per_document_scan", |b| {
+        let config = ExecutionConfig::bm25().with_fields(["title"]).without_phrase_intersection();
+        b.iter(|| db.search_with_config(black_box("content:\"quick brown\""), 10, config.clone()).unwrap());
+    });
+    group.finish();
+}
+
+/// Benchmark `match_all` streamed in doc-id order vs. scored and ranked
+fn bench_match_all(c: &mut Criterion) {
+    let config = Config::default();
+    let schema = SchemaWithAnalyzer::new();
+    let db = Database::open_with_schema(schema, config).unwrap();
+
+    for i in 0..5000 {
+        db.add_document(create_test_document(i, 20)).unwrap();
+    }
+    db.flush().unwrap();
+    db.commit().unwrap();
+
+    let mut group = c.benchmark_group("match_all");
+
+    // Streamed in doc-id order without scoring
+    group.bench_function("streamed", |b| {
+        b.iter(|| db.search_json(black_box(r#""match_all""#), 10).unwrap());
+    });
+
+    // The same hits through the generic score-and-rank path
+    group.bench_function("scored", |b| {
+        b.iter(|| db.search_json(black_box(r#"{"bool": {"should": ["match_all"]}}"#), 10).unwrap());
+    });
+
+    group.finish();
+}
+
+/// Benchmark `compact` over many segments, one at a time vs. concurrently
+fn bench_compaction(c: &mut Criterion) {
+    use Drusdenx::analysis::analyzer::Analyzer;
+    use Drusdenx::core::config::MergePolicyType;
+    use Drusdenx::compression::compress::CompressionType;
+    use Drusdenx::memory::buffer_pool::BufferPool;
+    use Drusdenx::memory::pool::MemoryPool;
+    use Drusdenx::mvcc::controller::MVCCController;
+    use Drusdenx::parallel::indexer::ParallelIndexer;
+    use Drusdenx::storage::layout::StorageLayout;
+    use Drusdenx::storage::merge_policy::TieredMergePolicy;
+    use Drusdenx::writer::index_writer::IndexWriter;
+
+    let mut group = c.benchmark_group("compaction");
+    group.sample_size(10);
+
+    for threads in [1, 4] {
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let temp_dir = tempfile::tempdir().unwrap();
+                    let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+                    let mut writer = IndexWriter::new_with_merge_policy(
+                        storage,
+                        Arc::new(MVCCController::new()),
+                        MemoryPool::new(8, 4 * 1024 * 1024),
+                        Arc::new(BufferPool::new(16 * 1024 * 1024)),
+                        Arc::new(ParallelIndexer::new(2)),
+                        Arc::new(Analyzer::standard_english()),
+                        MergePolicyType::Tiered,
+                        CompressionType::LZ4,
+                    )
+                    .unwrap();
+                    // Keep every flushed segment so there are many to compact
+                    writer.merge_policy = Box::new(TieredMergePolicy {
+                        max_segments_per_tier: usize::MAX,
+                        min_segments_to_merge: usize::MAX,
+                        ..TieredMergePolicy::default()
+                    });
+                    writer.config.compaction_threads = threads;
+
+                    for segment in 0..32u64 {
+                        for n in 0..200 {
+                            writer.add_document(create_test_document(segment * 200 + n, 50)).unwrap();
+                        }
+                        writer.flush().unwrap();
+                    }
+                    for id in (0..32 * 200).step_by(4) {
+                        writer.delete_document(DocId(id)).unwrap();
+                    }
+
+                    let start = Instant::now();
+                    writer.compact().unwrap();
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_single_insert,
     bench_batch_insert,
     bench_search,
     bench_simd_operations,
+    bench_intersect_terms,
     bench_transactions,
     bench_concurrent_operations,
-    bench_throughput
+    bench_throughput,
+    bench_compaction,
+    bench_match_all,
+    bench_phrase_verification
 );
 criterion_main!(benches);
@@ -15,10 +15,11 @@ fn create_test_segment(storage: &StorageLayout, doc_count: usize) -> SegmentId {
     use Drusdenx::storage::segment_writer::SegmentWriter;
     use Drusdenx::memory::buffer_pool::BufferPool;
     use Drusdenx::index::posting::Posting;
+    use Drusdenx::compression::compress::CompressionType;
     
     let buffer_pool = Arc::new(BufferPool::new(100 * 1024 * 1024));
     let segment_id = SegmentId::new();
-    let mut writer = SegmentWriter::new(storage, segment_id, buffer_pool.clone()).unwrap();
+    let mut writer = SegmentWriter::new(storage, segment_id, buffer_pool.clone(), CompressionType::None).unwrap();
     
     // Create test documents
     for i in 0..doc_count {
@@ -0,0 +1,58 @@
+/*
+ * C interface to Drusdenx. Build the library with the `ffi` feature:
+ *
+ *     cargo build --release --features ffi
+ *
+ * which writes the shared (libDrusdenx.so / .dylib, Drusdenx.dll) and static
+ * (libDrusdenx.a, Drusdenx.lib) libraries to target/release.
+ *
+ * Conventions:
+ *   - All strings are NUL-terminated UTF-8.
+ *   - Functions returning int yield 0 on success and -1 on failure; functions
+ *     returning pointers yield NULL on failure. After a failure,
+ *     drusdenx_last_error() describes what went wrong on the calling thread.
+ *   - Strings returned by drusdenx_search() are owned by the caller and must be
+ *     released with drusdenx_string_free().
+ */
+#ifndef DRUSDENX_H
+#define DRUSDENX_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque index handle. */
+typedef struct DrusdenxIndex DrusdenxIndex;
+
+/* Open (or create) an index stored in the directory `path`. */
+DrusdenxIndex *drusdenx_open(const char *path);
+
+/* Close a handle returned by drusdenx_open(). NULL is ignored. */
+void drusdenx_close(DrusdenxIndex *index);
+
+/* Add a document encoded as JSON:
+ *   {"id": 1, "fields": {"title": {"Text": "hello"}, "year": {"Number": 2024}}}
+ */
+int drusdenx_add_document(DrusdenxIndex *index, const char *document_json);
+
+/* Flush buffered documents so they become searchable. */
+int drusdenx_flush(DrusdenxIndex *index);
+
+/* Search with a JSON request {"query": "...", "limit": 10} ("limit" optional).
+ * Returns a JSON array of {"id": ..., "score": ..., "document": ...} objects.
+ */
+char *drusdenx_search(DrusdenxIndex *index, const char *request_json);
+
+/* Free a string returned by drusdenx_search(). NULL is ignored. */
+void drusdenx_string_free(char *s);
+
+/* Message for the last failed call on this thread, or NULL.
+ * Valid until the next drusdenx_* call on the same thread; do not free.
+ */
+const char *drusdenx_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DRUSDENX_H */
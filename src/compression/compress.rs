@@ -17,11 +17,48 @@ pub enum CompressionType {
     LZ4,      // Fast compression (~500 MB/s), ratio 2-3x
     Zstd,     // Better ratio (3-5x), slower (~200 MB/s)
     Snappy,   // Balanced (2-3x ratio, ~300 MB/s)
+    ZstdDict, // Zstd with a per-segment trained dictionary, for many small similar docs
+}
+
+impl CompressionType {
+    /// Zstd level used when none is configured (balanced)
+    pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+    /// Accepted Zstd levels: 1 (fastest) to 19 (smallest)
+    pub const ZSTD_LEVELS: std::ops::RangeInclusive<i32> = 1..=19;
+
+    fn zstd_level(level: Option<i32>) -> Result<i32> {
+        let level = level.unwrap_or(Self::DEFAULT_ZSTD_LEVEL);
+        if !Self::ZSTD_LEVELS.contains(&level) {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                format!("Zstd level {} is outside 1..=19", level),
+            ));
+        }
+        Ok(level)
+    }
+}
+
+/// Train a Zstd dictionary of at most `max_size` bytes from sample payloads.
+/// Fails when the samples are too few or too small to learn from.
+pub fn train_zstd_dictionary<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+        .map_err(|e| Error::new(ErrorKind::Io, format!("Dictionary training failed: {}", e)))
 }
 
 impl CompressedBlock {
     /// Compress raw byte data (for text, binary)
     pub fn compress(data: &[u8], compression: CompressionType) -> Result<Self> {
+        Self::compress_with_level(data, compression, None)
+    }
+
+    /// Compress with an explicit codec level (`None` = codec default).
+    /// Only Zstd has levels; other codecs ignore it. Zstd frames carry
+    /// everything needed to decode them, so the level is not stored.
+    pub fn compress_with_level(
+        data: &[u8],
+        compression: CompressionType,
+        level: Option<i32>,
+    ) -> Result<Self> {
         let compressed = match compression {
             CompressionType::None => data.to_vec(),
 
@@ -29,8 +66,14 @@ impl CompressedBlock {
                 lz4::block::compress(data, None, false)?
             }
 
-            CompressionType::Zstd => {
-                zstd::encode_all(data, 3)?  // Level 3 is balanced
+            // Without a dictionary at hand, ZstdDict is plain Zstd
+            CompressionType::Zstd | CompressionType::ZstdDict => {
+                let block = zstd::encode_all(data, CompressionType::zstd_level(level)?)?;
+                return Ok(CompressedBlock {
+                    data: block,
+                    original_size: data.len(),
+                    compression: CompressionType::Zstd,
+                });
             }
 
             CompressionType::Snappy => {
@@ -48,7 +91,28 @@ impl CompressedBlock {
         })
     }
 
+    /// Zstd-compress with a trained dictionary; decompressing the block
+    /// needs the same dictionary
+    pub fn compress_with_dictionary(
+        data: &[u8],
+        dictionary: &[u8],
+        level: Option<i32>,
+    ) -> Result<Self> {
+        let mut compressor =
+            zstd::bulk::Compressor::with_dictionary(CompressionType::zstd_level(level)?, dictionary)?;
+        Ok(CompressedBlock {
+            data: compressor.compress(data)?,
+            original_size: data.len(),
+            compression: CompressionType::ZstdDict,
+        })
+    }
+
     pub fn decompress(&self) -> Result<Vec<u8>> {
+        self.decompress_with_dictionary(None)
+    }
+
+    /// Decompress a block that may have been compressed with `dictionary`
+    pub fn decompress_with_dictionary(&self, dictionary: Option<&[u8]>) -> Result<Vec<u8>> {
         match self.compression {
             CompressionType::None => Ok(self.data.clone()),
 
@@ -68,6 +132,18 @@ impl CompressedBlock {
                 decoder.decompress_vec(&self.data)
                     .map_err(|e| Error::new(ErrorKind::Io, e.to_string()))
             }
+
+            CompressionType::ZstdDict => {
+                let dictionary = dictionary.ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidState,
+                        "Block was compressed with a dictionary that was not provided".to_string(),
+                    )
+                })?;
+                zstd::bulk::Decompressor::with_dictionary(dictionary)?
+                    .decompress(&self.data, self.original_size)
+                    .map_err(|e| Error::new(ErrorKind::Io, e.to_string()))
+            }
         }
     }
 
@@ -149,4 +225,36 @@ impl EncodedIntegerBlock {
     pub fn compress_with_lz4(&self) -> Result<CompressedBlock> {
         CompressedBlock::compress(&self.data, CompressionType::LZ4)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zstd_levels_round_trip_and_trade_speed_for_size() {
+        // Compressible but not trivially so: repeated words in varying order
+        let words = ["segment", "posting", "snapshot", "analyzer", "compaction", "tombstone"];
+        let data: Vec<u8> = (0..20_000u64)
+            .map(|i| words[(i * i % 7 % 6) as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+            .into_bytes();
+
+        let sizes: Vec<usize> = [1, 3, 19]
+            .into_iter()
+            .map(|level| {
+                let block =
+                    CompressedBlock::compress_with_level(&data, CompressionType::Zstd, Some(level))
+                        .unwrap();
+                assert_eq!(block.decompress().unwrap(), data);
+                block.data.len()
+            })
+            .collect();
+        assert!(sizes[2] < sizes[0], "level 19 ({}) vs level 1 ({})", sizes[2], sizes[0]);
+        assert!(sizes[2] <= sizes[1]);
+
+        let err = CompressedBlock::compress_with_level(&data, CompressionType::Zstd, Some(25));
+        assert!(matches!(err.err().unwrap().kind, ErrorKind::InvalidArgument));
+    }
+}
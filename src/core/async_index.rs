@@ -0,0 +1,153 @@
+use crate::core::error::{Error, ErrorKind, Result};
+use crate::core::facade::SearchIndex;
+use crate::core::stats::{DatabaseStats, HealthCheckResult};
+use crate::core::types::{DocId, Document};
+use crate::search::results::ScoredDocument;
+
+/// Async wrapper over `SearchIndex` (requires the `tokio` feature).
+///
+/// Every call runs the blocking operation on tokio's blocking thread pool via
+/// `spawn_blocking`, so it never stalls the async runtime. Must be awaited
+/// from inside a tokio runtime. `Clone` is cheap.
+#[derive(Clone)]
+pub struct AsyncSearchIndex {
+    inner: SearchIndex,
+}
+
+impl AsyncSearchIndex {
+    pub fn new(index: SearchIndex) -> Self {
+        AsyncSearchIndex { inner: index }
+    }
+
+    /// The underlying synchronous index.
+    pub fn sync(&self) -> &SearchIndex {
+        &self.inner
+    }
+
+    async fn run<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(SearchIndex) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let index = self.inner.clone();
+        tokio::task::spawn_blocking(move || f(index))
+            .await
+            .map_err(|e| Error::new(ErrorKind::Internal, format!("Blocking task failed: {}", e)))?
+    }
+
+    pub async fn add_document(&self, doc: Document) -> Result<()> {
+        self.run(move |index| index.add_document(doc)).await
+    }
+
+    pub async fn add_documents(&self, docs: Vec<Document>) -> Result<()> {
+        self.run(move |index| index.add_documents(docs)).await
+    }
+
+    pub async fn delete_document(&self, id: DocId) -> Result<()> {
+        self.run(move |index| index.delete_document(id)).await
+    }
+
+    pub async fn delete_by_query(&self, query: &str) -> Result<usize> {
+        let query = query.to_string();
+        self.run(move |index| index.delete_by_query(&query)).await
+    }
+
+    pub async fn search(&self, query: &str) -> Result<Vec<ScoredDocument>> {
+        let query = query.to_string();
+        self.run(move |index| index.search(&query)).await
+    }
+
+    pub async fn search_with_limit(&self, query: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
+        let query = query.to_string();
+        self.run(move |index| index.search_with_limit(&query, limit)).await
+    }
+
+    pub async fn flush(&self) -> Result<()> {
+        self.run(|index| index.flush()).await
+    }
+
+    pub async fn commit(&self) -> Result<()> {
+        self.run(|index| index.commit()).await
+    }
+
+    pub async fn compact(&self) -> Result<()> {
+        self.run(|index| index.compact()).await
+    }
+
+    pub async fn stats(&self) -> Result<DatabaseStats> {
+        self.run(|index| index.stats()).await
+    }
+
+    pub async fn health_check(&self) -> Result<HealthCheckResult> {
+        self.run(|index| index.health_check()).await
+    }
+}
+
+impl From<SearchIndex> for AsyncSearchIndex {
+    fn from(index: SearchIndex) -> Self {
+        AsyncSearchIndex::new(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::core::types::FieldValue;
+    use crate::schema::schema::SchemaWithAnalyzer;
+    use std::collections::HashMap;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_async_searches_match_sync_api() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = AsyncSearchIndex::new(
+            SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap(),
+        );
+
+        for id in 0..20 {
+            let text = if id % 2 == 0 { "rust async search" } else { "plain text" };
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(text.to_string()),
+                    )]),
+                })
+                .await
+                .unwrap();
+        }
+        index.flush().await.unwrap();
+
+        let searches: Vec<_> = (0..8)
+            .map(|_| {
+                let index = index.clone();
+                tokio::spawn(async move { index.search_with_limit("rust", 50).await })
+            })
+            .collect();
+
+        let mut expected: Vec<DocId> = index
+            .sync()
+            .search_with_limit("rust", 50)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.doc_id)
+            .collect();
+        expected.sort();
+        assert_eq!(expected.len(), 10);
+
+        for search in searches {
+            let mut ids: Vec<DocId> = search
+                .await
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .map(|hit| hit.doc_id)
+                .collect();
+            ids.sort();
+            assert_eq!(ids, expected);
+        }
+    }
+}
@@ -1,18 +1,24 @@
 use crate::analysis::analyzer::{Analyzer, AnalyzerRegistry};
 use crate::core::config::Config;
 use crate::core::error::Result;
+use crate::core::transaction::TransactionManager;
 use crate::index::inverted::InvertedIndex;
 use crate::memory::buffer_pool::BufferPool;
 use crate::memory::low_memory::LowMemoryMode;
 use crate::memory::pool::MemoryPool;
 use crate::mvcc::controller::MVCCController;
 use crate::parallel::indexer::ParallelIndexer;
-use crate::query::cache::QueryCache;
+use crate::query::cache::{CompiledQueryCache, QueryCache};
 use crate::query::parser::QueryParser;
 use crate::reader::reader_pool::ReaderPool;
 use crate::schema::schema::SchemaWithAnalyzer;
+use crate::query::types::ValidationConfig;
 use crate::search::executor::QueryExecutor;
+use crate::storage::backend::MemoryStorage;
 use crate::storage::layout::StorageLayout;
+use crate::storage::manifest::Manifest;
+use crate::storage::merge_throttle::MergeThrottle;
+use crate::storage::tombstones::SegmentTombstones;
 use crate::writer::index_writer::{IndexWriter, WriterConfig};
 use parking_lot::{Mutex, RwLock};
 use std::sync::Arc;
@@ -27,7 +33,11 @@ pub(crate) struct EngineComponents {
     pub(crate) executor: Arc<QueryExecutor>,
     pub(crate) parser: QueryParser,
     pub(crate) cache: Arc<QueryCache>,
+    /// Parsed query strings; unaffected by writes
+    pub(crate) compiled_queries: CompiledQueryCache,
     pub(crate) storage: Arc<StorageLayout>,
+    /// Tracks transactions begun through the engine until they commit or roll back
+    pub(crate) transactions: Arc<TransactionManager>,
     /// Schema used for field-type-aware operations.
     /// Exposed through `SearchIndex::schema()`.
     pub(crate) schema: SchemaWithAnalyzer,
@@ -39,25 +49,59 @@ pub(crate) struct EngineComponents {
 impl EngineComponents {
     /// Single factory: the only function that knows the assembly DAG.
     pub(crate) fn assemble(schema: SchemaWithAnalyzer, config: Config) -> Result<Self> {
-        let storage = Arc::new(StorageLayout::new(config.storage_path.clone())?);
+        let layout = match (&config.storage_backend, config.in_memory) {
+            (Some(backend), _) => StorageLayout::with_backend(config.storage_path.clone(), backend.clone())?,
+            (None, true) => StorageLayout::with_backend(config.storage_path.clone(), Arc::new(MemoryStorage::new()))?,
+            (None, false) => StorageLayout::new(config.storage_path.clone())?,
+        };
+        let storage = Arc::new(
+            layout
+                .with_shard_prefix_len(config.segment_shard_prefix_len)
+                .with_checksum_verification(config.verify_segment_checksums),
+        );
+        // Segments/indexes whose write was cut short by a crash were never published
+        storage.remove_temp_files()?;
 
-        // Initialize MVCC
-        let mvcc = Arc::new(MVCCController::new());
-        let index = Arc::new(InvertedIndex::new());
+        // Initialize MVCC from the last flushed segments, if any
+        let mut mvcc = MVCCController::new();
+        if let Some(secs) = config.snapshot_retention_secs {
+            mvcc = mvcc.with_min_retention(Duration::from_secs(secs));
+        }
+        let mvcc = Arc::new(mvcc);
+        if let Some(manifest) = Manifest::load(&storage)? {
+            let (segments, deleted_docs) = manifest.into_parts();
+            // Deletes committed after the last flush are only in the `.del` files
+            let tombstones = SegmentTombstones::load(&storage, &segments)?;
+            let deleted_docs = Arc::new(deleted_docs.as_ref() | &tombstones.deleted_docs());
+            mvcc.create_snapshot_with_deletes(segments, deleted_docs);
+        }
+        // Term statistics and positions of the live documents already on disk
+        let snapshot = mvcc.current_snapshot();
+        let index = Arc::new(InvertedIndex::load_from_segments(
+            &storage,
+            &snapshot.segments,
+            &snapshot.deleted_docs,
+        )?);
 
         // Memory subsystem
-        let buffer_pool = Arc::new(BufferPool::new(
-            config.buffer_pool_size.unwrap_or(100 * 1024 * 1024),
-        ));
+        let mut buffer_pool = BufferPool::new(config.buffer_pool_size.unwrap_or(100 * 1024 * 1024));
+        if let Some(classes) = &config.buffer_pool_size_classes {
+            buffer_pool = buffer_pool.with_size_classes(classes.iter().copied());
+        }
+        let buffer_pool = Arc::new(buffer_pool);
 
         let block_size = 4 * 1024 * 1024;
         let num_blocks = config.memory_limit / block_size;
         let memory_pool = MemoryPool::new(num_blocks, block_size);
 
         // Parallel indexer
-        let parallel_indexer = Arc::new(ParallelIndexer::new(
+        let mut parallel_indexer = ParallelIndexer::new(
             config.indexing_threads.unwrap_or_else(|| num_cpus::get()),
-        ));
+        );
+        if let Some(max) = config.max_tokens_per_field {
+            parallel_indexer = parallel_indexer.with_max_tokens_per_field(max);
+        }
+        let parallel_indexer = Arc::new(parallel_indexer);
 
         // Analyzer
         let analyzer_registry = Arc::new(AnalyzerRegistry::new());
@@ -83,24 +127,53 @@ impl EngineComponents {
             commit_interval: Duration::from_secs(config.writer_commit_interval_secs),
             max_segment_size: config.writer_max_segment_size,
             compression: config.compression,
+            compression_level: config.compression_level,
+            parallel_batch_min_docs: config.writer_parallel_batch_docs,
+            parallel_batch_min_bytes: config.writer_parallel_batch_bytes,
+            flush_on_batch_complete: config.writer_flush_on_batch,
+            compaction_threads: config.compaction_threads.unwrap_or_else(num_cpus::get),
         };
+        // The first segment writer was created before the level was known
+        index_writer.segment_writer.compression_level = config.compression_level;
+        index_writer.merge_throttle =
+            Arc::new(MergeThrottle::new(config.merge_throttle_bytes_per_sec));
+        index_writer.positionless_fields = schema.positionless_fields();
 
         let writer = Arc::new(RwLock::new(index_writer));
 
         // Query cache
         let cache_entries = config.cache_size / 1024;
         let cache = Arc::new(QueryCache::new(cache_entries));
+        let compiled_queries = CompiledQueryCache::new(config.compiled_query_cache_size);
 
         // Reader pool
-        let reader_pool = Arc::new(ReaderPool::new(
+        let mut reader_pool = ReaderPool::new(
             mvcc.clone(),
             storage.clone(),
             index,
             config.max_readers,
-        ));
+        );
+        if let Some(secs) = config.reader_idle_timeout_secs {
+            reader_pool = reader_pool.with_idle_timeout(Duration::from_secs(secs));
+        }
+        if let Some(capacity) = config.document_cache_size {
+            reader_pool = reader_pool.with_document_cache(capacity);
+        }
+        let reader_pool = Arc::new(reader_pool);
 
         let parser = QueryParser::new();
-        let executor = Arc::new(QueryExecutor::new());
+        let executor = Arc::new(QueryExecutor::with_config(ValidationConfig {
+            positionless_fields: schema.positionless_fields(),
+            known_fields: config.reject_unknown_fields.then(|| schema.field_names()),
+            ..ValidationConfig::default()
+        }));
+
+        let mut transactions =
+            TransactionManager::new(mvcc.clone(), storage.clone()).with_writer(writer.clone());
+        if let Some(secs) = config.transaction_timeout_secs {
+            transactions = transactions.with_timeout(Duration::from_secs(secs));
+        }
+        let transactions = Arc::new(transactions);
 
         Ok(EngineComponents {
             writer,
@@ -109,7 +182,9 @@ impl EngineComponents {
             executor,
             parser,
             cache,
+            compiled_queries,
             storage,
+            transactions,
             schema,
             low_memory: Mutex::new(None),
             config,
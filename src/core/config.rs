@@ -1,5 +1,7 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 use crate::compression::compress::CompressionType;
+use crate::storage::backend::StorageBackend;
 
 /// Merge policy type selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,35 +15,83 @@ pub enum MergePolicyType {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub storage_path: PathBuf,
+    pub in_memory: bool,                        // Keep every file in RAM; nothing is written under storage_path
+    pub storage_backend: Option<Arc<dyn StorageBackend>>, // Custom file backend (None = filesystem, or RAM if in_memory)
+    pub segment_shard_prefix_len: usize,        // Segment-id hex chars per shard dir (0 = flat layout)
+    pub verify_segment_checksums: bool,         // Validate segment CRC32 whenever a segment is opened
     pub memory_limit: usize,
     pub cache_size: usize,
+    pub compiled_query_cache_size: usize,       // Parsed query strings kept for reuse (0 = parse every search)
 
     pub writer_batch_size: usize,               // WriterConfig.batch_size
     pub writer_commit_interval_secs: u64,       // WriterConfig.commit_interval
     pub writer_max_segment_size: usize,         // WriterConfig.max_segment_size
+    pub writer_parallel_batch_docs: usize,      // WriterConfig.parallel_batch_min_docs
+    pub writer_parallel_batch_bytes: usize,     // WriterConfig.parallel_batch_min_bytes
+    pub writer_flush_on_batch: bool,            // WriterConfig.flush_on_batch_complete
 
     pub max_readers: usize,                     // Max concurrent readers
+    pub reader_idle_timeout_secs: Option<u64>,  // Evict cached readers idle this long (None = never)
+    pub document_cache_size: Option<usize>,     // Documents kept by the read-path LRU (None = disabled)
+    pub default_search_limit: usize,            // Limit used by `search` without an explicit limit
+    pub enable_optimization: bool,              // Plan and rewrite queries in `search` (ExecutionConfig default)
+    pub enable_validation: bool,                // Validate queries in `search` (ExecutionConfig default)
+    pub reject_unknown_fields: bool,            // Validation rejects queries on fields missing from the schema
+    pub phrase_intersection: bool,              // Intersect posting lists before checking phrase positions (ExecutionConfig default)
+    pub health_check_timeout_ms: u64,           // Per-check timeout in `health_check`
+    pub health_check_budget_ms: u64,            // Total time budget for `health_check`
+    pub transaction_timeout_secs: Option<u64>,  // Opt-in: abort transactions open this long (None = never)
+    pub snapshot_retention_secs: Option<u64>,   // Keep snapshots this young past the version cap (None = count only)
     pub buffer_pool_size: Option<usize>,     // Default: 100MB
+    pub buffer_pool_size_classes: Option<Vec<usize>>, // Pooled buffer sizes in bytes (None = powers of two 256B-1MB)
     pub indexing_threads: Option<usize>,     // Default: num_cpus
+    pub compaction_threads: Option<usize>,   // Segments `compact` rewrites at once (Default: num_cpus)
+    pub max_tokens_per_field: Option<usize>, // Tokens indexed per text field (None = unlimited)
     pub compression: CompressionType,
+    pub compression_level: Option<i32>,      // Zstd level 1-19 (None = 3)
     pub merge_policy: MergePolicyType,       // Merge policy selection
+    pub merge_throttle_bytes_per_sec: Option<u64>, // Merge I/O rate limit (None = unlimited)
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             storage_path: PathBuf::from("./data"),
+            in_memory: false,                          // Persist to storage_path
+            storage_backend: None,
+            segment_shard_prefix_len: 2,               // Up to 256 shard dirs under segments/ and idx/
+            verify_segment_checksums: false,           // Full-file read per open; opt in
             cache_size: 10 * 1024 * 1024,              // 10MB query cache
+            compiled_query_cache_size: 256,            // Parsed queries survive writes, unlike cached results
             memory_limit: 100 * 1024 * 1024,           // 100MB (M01: general, M06: MemoryPool)
 
             writer_batch_size: 1000,                   // Flush every 1000 docs
             writer_commit_interval_secs: 60,           // Commit every 60 seconds
             writer_max_segment_size: 50 * 1024 * 1024, // 50MB max per segment
+            writer_parallel_batch_docs: 100,           // Parallel tokenizing above 100 docs
+            writer_parallel_batch_bytes: 1024 * 1024,  // ...or above 1MB per batch
+            writer_flush_on_batch: false,              // Batches wait for batch_size / explicit flush
             max_readers: 10,                           // Max 10 concurrent readers
+            reader_idle_timeout_secs: Some(300),       // Drop readers idle for 5 minutes
+            document_cache_size: None,                 // Fetch documents from segments every time
+            default_search_limit: 10,                  // Top 10 hits by default
+            enable_optimization: true,                 // Run the planner on every search
+            enable_validation: true,                   // Reject over-expensive queries
+            reject_unknown_fields: false,              // Unknown fields just match nothing
+            phrase_intersection: true,                 // Check positions only in docs holding every phrase term
+            health_check_timeout_ms: 1000,             // 1s per check
+            health_check_budget_ms: 5000,              // 5s for the whole health check
+            transaction_timeout_secs: None,            // Transactions never time out unless set
+            snapshot_retention_secs: None,             // GC snapshots by version count alone
             buffer_pool_size: Some(100 * 1024 * 1024),
+            buffer_pool_size_classes: None,
             indexing_threads: None,  // Will use num_cpus
+            compaction_threads: None, // Will use num_cpus
+            max_tokens_per_field: None, // Index every token
             compression: CompressionType::LZ4,
+            compression_level: None,
             merge_policy: MergePolicyType::Tiered,  // Default to balanced policy
+            merge_throttle_bytes_per_sec: None,     // Merges run at full speed
         }
     }
 }
\ No newline at end of file
@@ -17,6 +17,7 @@ pub struct ReadDatabase {
     query_cache: Arc<QueryCache>,
     query_executor: Arc<crate::search::executor::QueryExecutor>,
     query_parser: crate::query::parser::QueryParser,
+    default_limit: usize,
 }
 
 impl ReadDatabase {
@@ -28,6 +29,7 @@ impl ReadDatabase {
             query_cache: c.cache.clone(),
             query_executor: c.executor.clone(),
             query_parser: c.parser.clone(),
+            default_limit: c.config.default_search_limit,
         }
     }
 
@@ -40,7 +42,7 @@ impl ReadDatabase {
 
     /// Search with caching
     pub fn search(&self, query_str: &str) -> Result<Vec<ScoredDocument>> {
-        self.search_with_limit(query_str, 10)
+        self.search_with_limit(query_str, self.default_limit)
     }
 
     pub fn search_with_limit(&self, query_str: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
@@ -53,7 +55,7 @@ impl ReadDatabase {
         let query = self.query_parser.parse(query_str)?;
         let reader = self.reader_pool.get_reader()?;
         let config = ExecutionConfig::default();
-        let results = self.query_executor.execute(&reader, &query, limit, config)?;
+        let results = self.query_executor.execute(&reader, &query, limit, 0, config)?;
 
         // Cache results
         self.query_cache.put_by_str(query_str, limit, 0, results.clone());
@@ -1,22 +1,35 @@
 use crate::core::components::EngineComponents;
 use crate::core::config::Config;
-use crate::core::error::Result;
+use crate::core::error::{Error, ErrorKind, Result};
 use crate::core::stats::{
     BufferStats, DatabaseStats, HealthCheck, HealthCheckResult, HealthStatus, MemoryStats,
 };
-use crate::core::transaction::Transaction;
+use crate::core::transaction::{Transaction, TransactionOp, TransactionStats};
 use crate::core::types::{DocId, Document};
 use crate::memory::low_memory::LowMemoryConfig;
+use crate::index::index_reader::IndexReader;
+use crate::index::inverted::Term;
 use crate::mvcc::controller::IsolationLevel;
+use crate::query::ast::Query;
+use crate::query::matcher::{DocumentMatcher, SegmentSearch};
 use crate::schema::schema::SchemaWithAnalyzer;
+use crate::reader::snapshot_reader::SnapshotReader;
 use crate::search::executor::ExecutionConfig;
 use crate::search::results::SearchResults;
-use crate::storage::wal::{Operation, WAL, WALEntry};
-use parking_lot::RwLock;
+use crate::storage::manifest::NamedSnapshot;
+use crate::storage::segment::SegmentId;
+use crate::storage::validation::{ValidationReport, validate_segments};
+use crate::storage::wal::{Operation, RecoveryTarget, WAL, WALEntry};
+use crate::writer::index_writer::IndexWriter;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 
+/// Wait before the first transaction retry; doubles on each further attempt
+const TRANSACTION_RETRY_BACKOFF: Duration = Duration::from_millis(5);
+
 /// Internal coordinator.
 /// All method bodies live here; `SearchIndex` (the public facade) delegates to this.
 pub(crate) struct SearchEngine {
@@ -26,6 +39,9 @@ pub(crate) struct SearchEngine {
     write_count: AtomicU64,
     last_flush_time: Arc<RwLock<Option<SystemTime>>>,
     last_commit_time: Arc<RwLock<Option<SystemTime>>>,
+    /// Serializes transaction validation with applying the result, so each
+    /// commit validates against every commit before it
+    commit_lock: Mutex<()>,
 }
 
 impl SearchEngine {
@@ -38,31 +54,51 @@ impl SearchEngine {
             write_count: AtomicU64::new(0),
             last_flush_time: Arc::new(RwLock::new(None)),
             last_commit_time: Arc::new(RwLock::new(None)),
+            commit_lock: Mutex::new(()),
         })
     }
 
     pub(crate) fn write_document(&self, doc: Document) -> Result<()> {
         self.write_count.fetch_add(1, Ordering::Relaxed);
+        self.reserve_write_memory(doc.estimated_size())?;
+        let doc_id = doc.id;
+        self.components.writer.write().add_document(doc)?;
+        self.invalidate_cached_document(doc_id);
+        Ok(())
+    }
 
-        // Estimate document size
-        let doc_size = doc
-            .fields
-            .iter()
-            .map(|(k, v)| {
-                k.len()
-                    + match v {
-                        crate::core::types::FieldValue::Text(s) => s.len(),
-                        crate::core::types::FieldValue::Number(_) => 8,
-                        crate::core::types::FieldValue::Date(_) => 8,
-                        crate::core::types::FieldValue::Boolean(_) => 1,
-                    }
-            })
-            .sum::<usize>()
-            + 100;
+    /// Write a batch through the writer's parallel batch path. Memory is
+    /// reserved and pressure checked once for the whole batch.
+    pub(crate) fn write_documents(&self, docs: Vec<Document>) -> Result<()> {
+        if docs.is_empty() {
+            return Ok(());
+        }
+        self.write_count.fetch_add(docs.len() as u64, Ordering::Relaxed);
+        self.reserve_write_memory(docs.iter().map(Document::estimated_size).sum())?;
+        let doc_ids: Vec<DocId> = docs.iter().map(|doc| doc.id).collect();
+        self.components.writer.write().add_documents_batch(docs)?;
+        for doc_id in doc_ids {
+            self.invalidate_cached_document(doc_id);
+        }
+        Ok(())
+    }
 
+    /// Flush transaction commits still in the writer's buffer, so reads see them
+    fn publish_transactions(&self) -> Result<()> {
+        IndexWriter::publish_shared(&self.components.writer)
+    }
+
+    /// Forget the read-path copy of a document that was updated or deleted
+    fn invalidate_cached_document(&self, doc_id: DocId) {
+        if let Some(cache) = self.components.reader_pool.document_cache() {
+            cache.invalidate(doc_id);
+        }
+    }
+
+    fn reserve_write_memory(&self, bytes: usize) -> Result<()> {
         if let Some(low_mem) = self.components.low_memory.lock().as_ref().cloned() {
             let lm = low_mem.read();
-            lm.memory_tracker.allocate(doc_size)?;
+            lm.memory_tracker.allocate(bytes)?;
         }
 
         if let Some(pressure) = self.get_memory_pressure() {
@@ -70,8 +106,7 @@ impl SearchEngine {
                 self.maybe_reclaim_memory()?;
             }
         }
-
-        self.components.writer.write().add_document(doc)
+        Ok(())
     }
 
     pub(crate) fn delete_document_by_id(&self, doc_id: DocId) -> Result<()> {
@@ -82,11 +117,14 @@ impl SearchEngine {
             lm.memory_tracker.deallocate(500);
         }
 
-        self.components.writer.write().delete_document(doc_id)
+        self.components.writer.write().delete_document(doc_id)?;
+        self.invalidate_cached_document(doc_id);
+        Ok(())
     }
 
     pub(crate) fn delete_by_query(&self, query_str: &str) -> Result<usize> {
         let query = self.components.parser.parse(query_str)?;
+        self.publish_transactions()?;
         let reader = self.components.reader_pool.get_reader()?;
         let results = reader.search(&query)?;
         let mut deleted_count = 0;
@@ -99,36 +137,165 @@ impl SearchEngine {
         Ok(deleted_count)
     }
 
+    /// Delete every document matching `query_str` and rewrite only the
+    /// segments that held them, in one pass
+    pub(crate) fn purge(&self, query_str: &str) -> Result<usize> {
+        let query = self.components.parser.parse(query_str)?;
+        self.publish_transactions()?;
+        let reader = self.components.reader_pool.get_reader()?;
+        let matcher = DocumentMatcher::new(reader.index.clone());
+
+        let mut doc_ids = HashSet::new();
+        let mut segment_ids = HashSet::new();
+        for segment_reader in &reader.segments {
+            let segment = segment_reader.read();
+            for hit in segment.search(&query, &matcher)? {
+                if !reader.deleted_docs.contains(hit.doc_id.0 as u32) {
+                    doc_ids.insert(hit.doc_id);
+                    segment_ids.insert(segment.segment_id);
+                }
+            }
+        }
+
+        if doc_ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_count.fetch_add(doc_ids.len() as u64, Ordering::Relaxed);
+        self.components.writer.write().purge(&doc_ids, &segment_ids)?;
+        for &doc_id in &doc_ids {
+            self.invalidate_cached_document(doc_id);
+        }
+        Ok(doc_ids.len())
+    }
+
+    /// Stored copy of a live document, read through the current reader
+    pub(crate) fn fetch_document(&self, doc_id: DocId) -> Result<Option<Document>> {
+        self.publish_transactions()?;
+        let reader = self.components.reader_pool.get_reader()?;
+        Ok(reader.get_document(doc_id)?.map(Arc::unwrap_or_clone))
+    }
+
+    /// Terms indexed for `field` of a live document, with their frequency
+    /// and field-relative positions, read from its segment's postings.
+    /// Sorted by term; empty if the document has no such field.
+    pub(crate) fn term_vector(&self, doc_id: DocId, field: &str) -> Result<Vec<(String, u32, Vec<u32>)>> {
+        self.publish_transactions()?;
+        let reader = self.components.reader_pool.get_reader()?;
+        let (segment_id, doc) = reader.locate_document(doc_id)?.ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("Document {} not found", doc_id.0))
+        })?;
+
+        // Re-indexing the stored document reproduces its token layout,
+        // which tells which positions of the postings belong to `field`
+        let indexed = {
+            let writer = self.components.writer.read();
+            writer.parallel_indexer.index_document(&doc, &writer.analyzer)?
+        };
+        let Some((_, range)) = indexed.field_ranges.iter().find(|(name, _)| name == field) else {
+            return Ok(Vec::new());
+        };
+
+        let mut term_freqs: BTreeMap<&str, u32> = BTreeMap::new();
+        for token in &indexed.tokens[range.clone()] {
+            *term_freqs.entry(token.text.as_str()).or_default() += 1;
+        }
+
+        let index = IndexReader::open(&self.components.storage, segment_id)?;
+        let mut vector = Vec::with_capacity(term_freqs.len());
+        for (text, term_freq) in term_freqs {
+            let positions = index
+                .get_postings(&Term::new(text))
+                .and_then(|postings| postings.iter().find(|p| p.doc_id == doc_id))
+                .map(|posting| {
+                    posting
+                        .positions
+                        .iter()
+                        .filter(|&&pos| range.contains(&(pos as usize)))
+                        .map(|pos| pos - range.start as u32)
+                        .collect()
+                })
+                .unwrap_or_default();
+            vector.push((text.to_string(), term_freq, positions));
+        }
+        Ok(vector)
+    }
+
     pub(crate) fn compact(&self) -> Result<()> {
         self.components.writer.write().compact()
     }
 
+    /// Execution settings for searches that don't pass their own, seeded
+    /// from the configured optimization, validation and phrase defaults
+    pub(crate) fn default_execution_config(&self) -> ExecutionConfig {
+        let config = &self.components.config;
+        ExecutionConfig {
+            enable_optimization: config.enable_optimization,
+            enable_validation: config.enable_validation,
+            phrase_intersection: config.phrase_intersection,
+            ..ExecutionConfig::default()
+        }
+    }
+
     pub(crate) fn run_search(
         &self,
         query_str: &str,
         limit: usize,
+        offset: usize,
         config: ExecutionConfig,
     ) -> Result<SearchResults> {
         self.query_count.fetch_add(1, Ordering::Relaxed);
+        self.publish_transactions()?;
+
+        // Only default-config results are cached: a cached BM25 hit list must
+        // not answer a TF-IDF, projected or explained search (or vice versa)
+        let cacheable = config.has_default_results();
 
-        if let Some(cached_results) = self.components.cache.get_by_str(query_str, limit, 0) {
+        if cacheable
+            && let Some(cached_results) = self.components.cache.get_by_str(query_str, limit, offset)
+        {
             return Ok(cached_results);
         }
 
-        let query = self.components.parser.parse(query_str)?;
+        let query = self
+            .components
+            .compiled_queries
+            .get_or_parse(query_str, &self.components.parser)?;
         let reader = self.components.reader_pool.get_reader()?;
         let results = self
             .components
             .executor
-            .execute(&reader, &query, limit, config)?;
+            .execute(&reader, &query, limit, offset, config)?;
 
-        self.components
-            .cache
-            .put_by_str(query_str, limit, 0, results.clone());
+        if cacheable {
+            // Full hit documents are what later id lookups will ask for
+            if reader.document_cache.is_some() {
+                for doc in results.hits.iter().filter_map(|hit| hit.document.as_ref()) {
+                    reader.cache_document(Arc::new(doc.clone()));
+                }
+            }
+            self.components
+                .cache
+                .put_by_str(query_str, limit, offset, results.clone());
+        }
 
         Ok(results)
     }
 
+    /// Execute an already-built query. Bypasses the query cache, which is
+    /// keyed by query strings.
+    pub(crate) fn run_query(
+        &self,
+        query: &Query,
+        limit: usize,
+        config: ExecutionConfig,
+    ) -> Result<SearchResults> {
+        self.query_count.fetch_add(1, Ordering::Relaxed);
+        self.publish_transactions()?;
+        let reader = self.components.reader_pool.get_reader()?;
+        self.components.executor.execute(&reader, query, limit, 0, config)
+    }
+
     pub(crate) fn flush_segments(&self) -> Result<()> {
         let result = self.components.writer.write().flush();
         if result.is_ok() {
@@ -145,7 +312,41 @@ impl SearchEngine {
         result
     }
 
+    /// Flush, then save the current snapshot under `name`
+    pub(crate) fn save_snapshot(&self, name: &str) -> Result<()> {
+        self.flush_segments()?;
+        let snapshot = self.components.mvcc.current_snapshot();
+        NamedSnapshot::from_snapshot(name, &snapshot)?.save(&self.components.storage)
+    }
+
+    /// Read-only view of a snapshot saved with `save_snapshot`
+    pub(crate) fn open_snapshot(&self, name: &str) -> Result<SnapshotReader> {
+        let saved = NamedSnapshot::load(&self.components.storage, name)?.ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("No snapshot named '{}'", name))
+        })?;
+        SnapshotReader::new(
+            Arc::new(saved.into_snapshot()),
+            &self.components.storage,
+            self.components.reader_pool.index.clone(),
+        )
+    }
+
+    pub(crate) fn begin_bulk_load(&self) {
+        self.components.writer.write().begin_bulk_load();
+    }
+
     pub(crate) fn recover(&self) -> Result<()> {
+        self.replay_wal(None)
+    }
+
+    /// Replay uncommitted WAL entries only up to `target`, then commit so the
+    /// entries after it are never replayed. Operations already committed to
+    /// segments are not rolled back.
+    pub(crate) fn recover_to(&self, target: RecoveryTarget) -> Result<()> {
+        self.replay_wal(Some(target))
+    }
+
+    fn replay_wal(&self, target: Option<RecoveryTarget>) -> Result<()> {
         let storage = self.components.storage.clone();
         let wal_sequences = WAL::find_wal_files(&storage)?;
 
@@ -158,7 +359,7 @@ impl SearchEngine {
         for sequence in wal_sequences {
             let mut wal = WAL::open(&storage, sequence)?;
             let entries = wal.read_entries()?;
-            let pending_operations = operations_after_last_commit(entries);
+            let pending_operations = operations_after_last_commit(entries, target);
 
             for operation in pending_operations {
                 let mut writer = self.components.writer.write();
@@ -169,7 +370,7 @@ impl SearchEngine {
             }
         }
 
-        if recovered_count > 0 {
+        if recovered_count > 0 || target.is_some() {
             self.commit_wal()?;
         }
         eprintln!(
@@ -179,47 +380,153 @@ impl SearchEngine {
         Ok(())
     }
 
-    pub(crate) fn begin_transaction(&self, isolation: IsolationLevel) -> Arc<Transaction> {
-        Arc::new(Transaction::begin(
-            self.components.mvcc.clone(),
-            self.components.storage.clone(),
-            isolation,
-        ))
+    pub(crate) fn begin_transaction(&self, isolation: IsolationLevel) -> Result<Arc<Transaction>> {
+        // Expire stale transactions before pinning another snapshot
+        self.components.transactions.cleanup();
+        self.components.transactions.begin_transaction(isolation)
+    }
+
+    /// Validate and apply a transaction's operations, then stop tracking it.
+    /// The operations are applied all-or-nothing and are durable once the WAL
+    /// is synced; they reach a segment with the next regular flush, or as soon
+    /// as a search or another transaction needs them.
+    pub(crate) fn commit_transaction(&self, tx: &Transaction) -> Result<()> {
+        let _commit = self.commit_lock.lock();
+        let committed = tx.commit();
+        self.components.transactions.remove(tx.id);
+        self.apply_transaction_ops(committed?)
+    }
+
+    fn apply_transaction_ops(&self, ops: Vec<TransactionOp>) -> Result<()> {
+        let mut operations = Vec::with_capacity(ops.len());
+        let mut touched = Vec::with_capacity(ops.len());
+        let mut added_bytes = 0;
+        let mut deletes = 0;
+        for op in ops {
+            match op {
+                TransactionOp::Insert(doc) => {
+                    added_bytes += doc.estimated_size();
+                    touched.push(doc.id);
+                    operations.push(Operation::AddDocument(doc));
+                }
+                TransactionOp::Update(doc_id, doc) => {
+                    added_bytes += doc.estimated_size();
+                    deletes += 1;
+                    touched.extend([doc_id, doc.id]);
+                    operations.push(Operation::DeleteDocument(doc_id));
+                    operations.push(Operation::AddDocument(doc));
+                }
+                TransactionOp::Delete(doc_id) => {
+                    deletes += 1;
+                    touched.push(doc_id);
+                    operations.push(Operation::DeleteDocument(doc_id));
+                }
+            }
+        }
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        // Reserve memory for the whole transaction up front, so running out
+        // rejects it before anything is written
+        self.reserve_write_memory(added_bytes)?;
+        {
+            self.components.writer.write().apply_transaction(operations)?;
+        }
+
+        self.write_count.fetch_add(touched.len() as u64, Ordering::Relaxed);
+        if deletes > 0
+            && let Some(low_mem) = self.components.low_memory.lock().as_ref().cloned()
+        {
+            low_mem.read().memory_tracker.deallocate(500 * deletes);
+        }
+        for doc_id in touched {
+            self.invalidate_cached_document(doc_id);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rollback_transaction(&self, tx: &Transaction) -> Result<()> {
+        self.components.transactions.remove(tx.id);
+        tx.rollback()
+    }
+
+    pub(crate) fn transaction_stats(&self) -> TransactionStats {
+        self.components.transactions.cleanup();
+        self.components.transactions.stats()
     }
 
     pub(crate) fn with_transaction<F, R>(&self, isolation: IsolationLevel, f: F) -> Result<R>
     where
         F: FnOnce(&Transaction) -> Result<R>,
     {
-        let tx = self.begin_transaction(isolation);
+        let tx = self.begin_transaction(isolation)?;
 
         match f(&tx) {
             Ok(result) => {
-                let ops = tx.commit()?;
-                for op in ops {
-                    match op {
-                        crate::core::transaction::TransactionOp::Insert(doc) => {
-                            self.write_document(doc)?;
-                        }
-                        crate::core::transaction::TransactionOp::Update(doc_id, doc) => {
-                            self.delete_document_by_id(doc_id)?;
-                            self.write_document(doc)?;
-                        }
-                        crate::core::transaction::TransactionOp::Delete(doc_id) => {
-                            self.delete_document_by_id(doc_id)?;
-                        }
-                    }
-                }
-                self.flush_segments()?;
+                self.commit_transaction(&tx)?;
                 Ok(result)
             }
             Err(e) => {
-                tx.rollback()?;
+                self.rollback_transaction(&tx)?;
                 Err(e)
             }
         }
     }
 
+    /// Re-run `f` in a fresh transaction while commit fails on a conflict,
+    /// at most `max_retries` extra times
+    pub(crate) fn with_transaction_retry<F, R>(
+        &self,
+        isolation: IsolationLevel,
+        max_retries: u32,
+        mut f: F,
+    ) -> Result<R>
+    where
+        F: FnMut(&Transaction) -> Result<R>,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.with_transaction(isolation, &mut f) {
+                Err(e) if matches!(e.kind, ErrorKind::Conflict(_)) && attempt < max_retries => {
+                    std::thread::sleep(TRANSACTION_RETRY_BACKOFF * 2u32.pow(attempt.min(10)));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<ValidationReport> {
+        let snapshot = self.components.mvcc.current_snapshot();
+        let manifest: Vec<SegmentId> = snapshot.segments.iter().map(|seg| seg.id).collect();
+        validate_segments(&self.components.storage, &manifest)
+    }
+
+    pub(crate) fn repair(&self) -> Result<ValidationReport> {
+        // Hold the writer so no flush publishes a snapshot in between
+        let _writer = self.components.writer.write();
+        let report = self.validate()?;
+
+        let dropped = report.unrecoverable_segments();
+        if !dropped.is_empty() {
+            let snapshot = self.components.mvcc.current_snapshot();
+            let segments = snapshot
+                .segments
+                .iter()
+                .filter(|seg| !dropped.contains(&seg.id))
+                .cloned()
+                .collect();
+            self.components
+                .mvcc
+                .create_snapshot_with_deletes(segments, snapshot.deleted_docs.clone());
+            self.components.reader_pool.clear();
+            self.components.cache.clear();
+        }
+
+        Ok(report)
+    }
+
     pub(crate) fn collect_stats(&self) -> Result<DatabaseStats> {
         let snapshot = self.components.mvcc.current_snapshot();
         let cache_stats = self.components.cache.stats();
@@ -238,7 +545,14 @@ impl SearchEngine {
             0.0
         };
 
-        let wal_size = self.components.writer.read().wal.position;
+        let (wal_size, merge_throttle, truncated_fields) = {
+            let writer = self.components.writer.read();
+            (
+                writer.wal.position,
+                writer.merge_throttle.clone(),
+                writer.parallel_indexer.get_truncated_fields(),
+            )
+        };
         let reader_segment_open_failures = self.components.reader_pool.segment_open_failure_count();
 
         let index_size_bytes: u64 = snapshot
@@ -270,88 +584,96 @@ impl SearchEngine {
             reader_pool_size: self.components.reader_pool.max_readers,
             reader_segment_open_failures,
             cache_stats,
+            document_cache_stats: self
+                .components
+                .reader_pool
+                .document_cache()
+                .map(|cache| cache.stats()),
             queries_per_second,
             avg_query_latency_ms: 0.0,
             writes_per_second,
             pending_writes: 0,
+            truncated_fields,
             last_flush_time: self.last_flush_time.read().clone(),
             last_commit_time: self.last_commit_time.read().clone(),
+            merge_bytes_total: merge_throttle.bytes_merged(),
+            merge_throughput_bytes_per_sec: merge_throttle.throughput(),
         })
     }
 
     pub(crate) fn run_health_check(&self) -> Result<HealthCheckResult> {
-        let mut checks = Vec::new();
-        let _start = Instant::now();
+        let mut probes: Vec<(&'static str, HealthProbe)> = Vec::new();
 
-        let wal_check_start = Instant::now();
-        let wal_status = match self.components.writer.try_write() {
-            Some(_) => HealthStatus::Healthy,
-            None => HealthStatus::Degraded("WAL is locked".to_string()),
-        };
-        checks.push(HealthCheck {
-            name: "WAL".to_string(),
-            status: wal_status,
-            message: None,
-            latency_ms: wal_check_start.elapsed().as_millis() as u64,
-        });
+        let writer = self.components.writer.clone();
+        probes.push((
+            "WAL",
+            Box::new(move || {
+                let status = match writer.try_write() {
+                    Some(_) => HealthStatus::Healthy,
+                    None => HealthStatus::Degraded("WAL is locked".to_string()),
+                };
+                (status, None)
+            }),
+        ));
 
-        let reader_check_start = Instant::now();
-        let reader_failures = self.components.reader_pool.segment_open_failure_count();
-        let reader_status = match self.components.reader_pool.get_reader() {
-            Ok(_) if reader_failures > 0 => HealthStatus::Degraded(format!(
-                "Reader has skipped {} segment open failures",
-                reader_failures
-            )),
-            Ok(_) => HealthStatus::Healthy,
-            Err(e) => HealthStatus::Unhealthy(format!("Cannot get reader: {}", e)),
-        };
-        checks.push(HealthCheck {
-            name: "ReaderPool".to_string(),
-            status: reader_status,
-            message: Some(format!("segment_open_failures={}", reader_failures)),
-            latency_ms: reader_check_start.elapsed().as_millis() as u64,
-        });
+        let reader_pool = self.components.reader_pool.clone();
+        probes.push((
+            "ReaderPool",
+            Box::new(move || {
+                let reader_failures = reader_pool.segment_open_failure_count();
+                let status = match reader_pool.get_reader() {
+                    Ok(_) if reader_failures > 0 => HealthStatus::Degraded(format!(
+                        "Reader has skipped {} segment open failures",
+                        reader_failures
+                    )),
+                    Ok(_) => HealthStatus::Healthy,
+                    Err(e) => HealthStatus::Unhealthy(format!("Cannot get reader: {}", e)),
+                };
+                (status, Some(format!("segment_open_failures={}", reader_failures)))
+            }),
+        ));
 
-        let cache_check_start = Instant::now();
-        let cache_status = HealthStatus::Healthy;
-        checks.push(HealthCheck {
-            name: "QueryCache".to_string(),
-            status: cache_status,
-            message: Some(format!(
-                "Hit rate: {:.2}%",
-                self.components.cache.stats().hit_rate() * 100.0
-            )),
-            latency_ms: cache_check_start.elapsed().as_millis() as u64,
-        });
+        let cache = self.components.cache.clone();
+        probes.push((
+            "QueryCache",
+            Box::new(move || {
+                let message = format!("Hit rate: {:.2}%", cache.stats().hit_rate() * 100.0);
+                (HealthStatus::Healthy, Some(message))
+            }),
+        ));
 
-        let disk_check_start = Instant::now();
-        checks.push(HealthCheck {
-            name: "DiskSpace".to_string(),
-            status: HealthStatus::Healthy,
-            message: None,
-            latency_ms: disk_check_start.elapsed().as_millis() as u64,
-        });
+        probes.push(("DiskSpace", Box::new(|| (HealthStatus::Healthy, None))));
 
-        let memory_check_start = Instant::now();
-        if let Some(pressure) = self.get_memory_pressure() {
-            let memory_status = if pressure > 0.9 {
-                HealthStatus::Unhealthy(format!(
-                    "Memory pressure critical: {:.1}%",
-                    pressure * 100.0
-                ))
-            } else if pressure > 0.8 {
-                HealthStatus::Degraded(format!("Memory pressure high: {:.1}%", pressure * 100.0))
-            } else {
-                HealthStatus::Healthy
-            };
-            checks.push(HealthCheck {
-                name: "Memory".to_string(),
-                status: memory_status,
-                message: Some(format!("Pressure: {:.1}%", pressure * 100.0)),
-                latency_ms: memory_check_start.elapsed().as_millis() as u64,
-            });
+        if let Some(low_mem) = self.components.low_memory.lock().as_ref().cloned() {
+            probes.push((
+                "Memory",
+                Box::new(move || {
+                    let pressure = low_mem.read().memory_pressure();
+                    let status = if pressure > 0.9 {
+                        HealthStatus::Unhealthy(format!(
+                            "Memory pressure critical: {:.1}%",
+                            pressure * 100.0
+                        ))
+                    } else if pressure > 0.8 {
+                        HealthStatus::Degraded(format!(
+                            "Memory pressure high: {:.1}%",
+                            pressure * 100.0
+                        ))
+                    } else {
+                        HealthStatus::Healthy
+                    };
+                    (status, Some(format!("Pressure: {:.1}%", pressure * 100.0)))
+                }),
+            ));
         }
 
+        let config = &self.components.config;
+        let checks = run_health_probes(
+            probes,
+            Duration::from_millis(config.health_check_timeout_ms),
+            Duration::from_millis(config.health_check_budget_ms),
+        );
+
         let overall_status = if checks.iter().all(|c| c.status == HealthStatus::Healthy) {
             HealthStatus::Healthy
         } else if checks
@@ -388,12 +710,70 @@ impl SearchEngine {
         if let Some(low_mem) = self.components.low_memory.lock().as_ref().cloned() {
             let mut lm = low_mem.write();
             lm.maybe_reclaim()?;
+
+            // Idle readers pin segment files and memory; drop them first, and
+            // every cached reader if that still leaves us over the threshold
+            self.components.reader_pool.evict_idle_readers();
+            if lm.memory_pressure() > lm.config.gc_threshold {
+                self.components.reader_pool.clear();
+            }
         }
         Ok(())
     }
 }
 
-fn operations_after_last_commit(entries: Vec<WALEntry>) -> Vec<Operation> {
+/// A single health check body, run on its own thread so it can be timed out.
+type HealthProbe = Box<dyn FnOnce() -> (HealthStatus, Option<String>) + Send>;
+
+/// Run probes sequentially, giving each at most `per_check` (and never more
+/// than what is left of `budget`). A probe that does not answer in time is
+/// reported `Unhealthy` and left to finish in the background; once the budget
+/// is spent the remaining probes are skipped.
+fn run_health_probes(
+    probes: Vec<(&'static str, HealthProbe)>,
+    per_check: Duration,
+    budget: Duration,
+) -> Vec<HealthCheck> {
+    let start = Instant::now();
+    let mut checks = Vec::with_capacity(probes.len());
+
+    for (name, probe) in probes {
+        let remaining = budget.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            break;
+        }
+        let timeout = per_check.min(remaining);
+
+        let check_start = Instant::now();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(probe());
+        });
+
+        let (status, message) = match rx.recv_timeout(timeout) {
+            Ok(outcome) => outcome,
+            Err(_) => (
+                HealthStatus::Unhealthy(format!(
+                    "Check timed out after {} ms",
+                    timeout.as_millis()
+                )),
+                None,
+            ),
+        };
+
+        checks.push(HealthCheck {
+            name: name.to_string(),
+            status,
+            message,
+            latency_ms: check_start.elapsed().as_millis() as u64,
+        });
+    }
+
+    checks
+}
+
+/// Operations logged after the last commit marker, stopping at `target`
+fn operations_after_last_commit(entries: Vec<WALEntry>, target: Option<RecoveryTarget>) -> Vec<Operation> {
     let start = entries
         .iter()
         .rposition(|entry| matches!(entry.operation, Operation::Commit))
@@ -403,6 +783,7 @@ fn operations_after_last_commit(entries: Vec<WALEntry>) -> Vec<Operation> {
     entries
         .into_iter()
         .skip(start)
+        .take_while(|entry| target.is_none_or(|target| target.includes(entry)))
         .map(|entry| entry.operation)
         .collect()
 }
@@ -447,7 +828,7 @@ mod tests {
             },
         ];
 
-        let operations = operations_after_last_commit(entries);
+        let operations = operations_after_last_commit(entries, None);
         assert_eq!(operations.len(), 1);
         assert!(matches!(operations[0], Operation::DeleteDocument(DocId(2))));
     }
@@ -467,7 +848,7 @@ mod tests {
             },
         ];
 
-        let operations = operations_after_last_commit(entries);
+        let operations = operations_after_last_commit(entries, None);
         assert!(operations.is_empty());
     }
 
@@ -500,6 +881,56 @@ mod tests {
         assert!(matches!(err.kind, ErrorKind::OutOfMemory));
     }
 
+    #[test]
+    fn failed_transaction_apply_leaves_no_partial_effects() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let engine = SearchEngine::new(SchemaWithAnalyzer::new(), config).unwrap();
+        let text_doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        engine.write_document(text_doc(1, "keeper")).unwrap();
+        engine.flush_segments().unwrap();
+
+        // Room for two of the three inserts: applied one at a time, the
+        // delete and the first two inserts would land before the third failed
+        let staged: Vec<Document> = (2..5).map(|id| text_doc(id, "staged write")).collect();
+        engine.enable_low_memory_mode(LowMemoryConfig {
+            heap_limit: staged[0].estimated_size() * 5 / 2,
+            buffer_size: 1024,
+            cache_size: 1024,
+            batch_size: 1,
+            enable_compression: true,
+            swap_to_disk: false,
+            gc_threshold: 0.9,
+        });
+
+        let err = engine
+            .with_transaction(IsolationLevel::ReadCommitted, |tx| {
+                tx.delete(DocId(1))?;
+                for doc in &staged {
+                    tx.insert(doc.clone())?;
+                }
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::OutOfMemory));
+
+        *engine.components.low_memory.lock() = None;
+        engine.flush_segments().unwrap();
+        let reader = engine.components.reader_pool.get_reader().unwrap();
+        assert!(!reader.deleted_docs.contains(1));
+        let search = |text: &str| {
+            let query = engine.components.parser.parse(text).unwrap();
+            reader.search(&query).unwrap().hits.len()
+        };
+        assert_eq!(search("keeper"), 1);
+        assert_eq!(search("staged"), 0);
+    }
+
     #[test]
     fn stats_and_health_expose_reader_segment_open_failures() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -539,4 +970,120 @@ mod tests {
         ));
         assert_eq!(reader.message.as_deref(), Some("segment_open_failures=1"));
     }
+
+    #[test]
+    fn run_search_projects_requested_fields() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let engine = SearchEngine::new(SchemaWithAnalyzer::new(), config).unwrap();
+
+        engine
+            .write_document(Document {
+                id: DocId(1),
+                fields: HashMap::from([
+                    ("title".to_string(), FieldValue::Text("rust book".to_string())),
+                    ("content".to_string(), FieldValue::Text("learning rust".to_string())),
+                    ("pages".to_string(), FieldValue::Number(300.0)),
+                ]),
+            })
+            .unwrap();
+        engine.flush_segments().unwrap();
+
+        let projected = engine
+            .run_search("rust", 10, 0, ExecutionConfig::default().with_fields(["title"]))
+            .unwrap();
+        assert_eq!(projected.hits.len(), 1);
+        let doc = projected.hits[0].document.as_ref().unwrap();
+        assert_eq!(doc.fields.len(), 1);
+        assert!(doc.fields.contains_key("title"));
+
+        let full = engine
+            .run_search("rust", 10, 0, ExecutionConfig::default())
+            .unwrap();
+        assert_eq!(full.hits.len(), 1);
+        let doc = full.hits[0].document.as_ref().unwrap();
+        assert_eq!(doc.fields.len(), 3);
+    }
+
+    #[test]
+    fn health_probes_respect_timeout_and_budget() {
+        let slow = || -> HealthProbe {
+            Box::new(|| {
+                std::thread::sleep(Duration::from_secs(5));
+                (HealthStatus::Healthy, None)
+            })
+        };
+        let probes: Vec<(&'static str, HealthProbe)> = vec![
+            ("Fast", Box::new(|| (HealthStatus::Healthy, None))),
+            ("Slow", slow()),
+            ("Slower", slow()),
+            ("Slowest", slow()),
+        ];
+
+        let start = Instant::now();
+        let checks = run_health_probes(
+            probes,
+            Duration::from_millis(100),
+            Duration::from_millis(150),
+        );
+        let elapsed = start.elapsed();
+
+        assert!(elapsed < Duration::from_secs(1), "took {:?}", elapsed);
+        assert_eq!(checks[0].name, "Fast");
+        assert!(checks[0].status.is_healthy());
+        assert!(checks.len() < 4, "budget should cut off remaining checks");
+        assert!(
+            checks[1..]
+                .iter()
+                .all(|c| matches!(c.status, HealthStatus::Unhealthy(_)))
+        );
+    }
+
+    #[test]
+    fn purge_rewrites_only_segments_holding_matches() {
+        use crate::storage::merge_policy::TieredMergePolicy;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let engine = SearchEngine::new(SchemaWithAnalyzer::new(), config).unwrap();
+        engine.components.writer.write().merge_policy = Box::new(TieredMergePolicy {
+            max_segments_per_tier: usize::MAX,
+            min_segments_to_merge: usize::MAX,
+            ..TieredMergePolicy::default()
+        });
+        let text_doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        for batch in [
+            [(1, "alpha one"), (2, "alpha two"), (3, "alpha three")],
+            [(4, "doomed four"), (5, "doomed five"), (6, "beta six")],
+            [(7, "gamma seven"), (8, "gamma eight"), (9, "gamma nine")],
+        ] {
+            for (id, text) in batch {
+                engine.write_document(text_doc(id, text)).unwrap();
+            }
+            engine.flush_segments().unwrap();
+        }
+        let before = engine.components.mvcc.current_snapshot().segments.clone();
+        assert_eq!(before.len(), 3);
+
+        assert_eq!(engine.purge("doomed").unwrap(), 2);
+
+        let after = engine.components.mvcc.current_snapshot().segments.clone();
+        assert_eq!(after.len(), 3);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[2].id, before[2].id);
+        assert_ne!(after[1].id, before[1].id);
+        assert_eq!(after[1].doc_count, 1);
+        assert!(after[1].metadata.size_bytes < before[1].metadata.size_bytes);
+
+        let hits = |q: &str| engine.run_search(q, 10, 0, ExecutionConfig::default()).unwrap().hits.len();
+        assert_eq!(hits("doomed"), 0);
+        assert_eq!(hits("beta"), 1);
+        assert_eq!(hits("gamma"), 3);
+    }
 }
@@ -1,4 +1,5 @@
 use std::fmt;
+use crate::core::types::DocId;
 
 #[derive(Debug)]
 pub enum ErrorKind {
@@ -10,7 +11,19 @@ pub enum ErrorKind {
     InvalidInput,
     OutOfMemory,
     InvalidState,
-    UnsupportedQuery
+    UnsupportedQuery,
+    /// Transaction commit found documents changed by another writer
+    Conflict(Vec<Conflict>),
+}
+
+/// A document that changed between a transaction's snapshot and its commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub doc_id: DocId,
+    /// Snapshot version the transaction read the document at
+    pub read_version: u64,
+    /// Snapshot version in which the document was found to differ
+    pub current_version: u64,
 }
 
 #[derive(Debug)]
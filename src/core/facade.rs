@@ -3,13 +3,17 @@ use crate::core::config::Config;
 use crate::core::engine::SearchEngine;
 use crate::core::error::Result;
 use crate::core::stats::{DatabaseStats, HealthCheckResult};
-use crate::core::transaction::Transaction;
+use crate::core::transaction::{Transaction, TransactionStats};
 use crate::core::types::{Document, DocId};
 use crate::memory::low_memory::LowMemoryConfig;
 use crate::mvcc::controller::IsolationLevel;
+use crate::query::ast::Query;
+use crate::reader::snapshot_reader::SnapshotReader;
 use crate::schema::schema::SchemaWithAnalyzer;
 use crate::search::executor::ExecutionConfig;
 use crate::search::results::{ScoredDocument, SearchResults};
+use crate::storage::validation::ValidationReport;
+use crate::storage::wal::RecoveryTarget;
 
 /// Public facade over `SearchEngine`.
 /// All user-facing methods delegate to `Arc<SearchEngine>`.
@@ -32,6 +36,11 @@ impl SearchIndex {
         self.0.write_document(doc)
     }
 
+    /// Add many documents at once through the parallel batch path
+    pub fn add_documents(&self, docs: Vec<Document>) -> Result<()> {
+        self.0.write_documents(docs)
+    }
+
     pub fn delete_document(&self, id: DocId) -> Result<()> {
         self.0.delete_document_by_id(id)
     }
@@ -40,6 +49,24 @@ impl SearchIndex {
         self.0.delete_by_query(query_str)
     }
 
+    /// The live stored copy of a document, or `None` if it was never
+    /// flushed or has been deleted. The newest copy wins after an update.
+    pub fn get_document(&self, doc_id: DocId) -> Result<Option<Document>> {
+        self.0.fetch_document(doc_id)
+    }
+
+    /// What was indexed for `field` of a document: `(term, term frequency,
+    /// positions within the field)` per distinct term, sorted by term
+    pub fn term_vector(&self, doc_id: DocId, field: &str) -> Result<Vec<(String, u32, Vec<u32>)>> {
+        self.0.term_vector(doc_id, field)
+    }
+
+    /// Delete the documents matching `query_str` and reclaim their space
+    /// immediately, rewriting only the segments that held them
+    pub fn purge(&self, query_str: &str) -> Result<usize> {
+        self.0.purge(query_str)
+    }
+
     pub fn compact(&self) -> Result<()> {
         self.0.compact()
     }
@@ -52,16 +79,42 @@ impl SearchIndex {
         self.0.commit_wal()
     }
 
+    /// Skip the WAL until the next `commit`, for initial loads that can be
+    /// replayed from their source. Nothing written in the session survives a
+    /// crash before `commit`, which fsyncs segments and the manifest.
+    pub fn begin_bulk_load(&self) {
+        self.0.begin_bulk_load()
+    }
+
+    /// Flush and save the current state under `name`; it can be read with
+    /// `open_snapshot` after more writes and across restarts. Reusing a name
+    /// replaces the earlier snapshot.
+    pub fn save_snapshot(&self, name: &str) -> Result<()> {
+        self.0.save_snapshot(name)
+    }
+
+    /// Read-only view of a snapshot saved with `save_snapshot`
+    pub fn open_snapshot(&self, name: &str) -> Result<SnapshotReader> {
+        self.0.open_snapshot(name)
+    }
+
     pub fn recover(&self) -> Result<()> {
         self.0.recover()
     }
 
+    /// Point-in-time recovery: like `recover`, but replays the WAL only up to
+    /// a sequence number or timestamp, e.g. to stop before a bad write.
+    pub fn recover_to(&self, target: RecoveryTarget) -> Result<()> {
+        self.0.recover_to(target)
+    }
+
+    /// Search using `Config::default_search_limit` as the result limit.
     pub fn search(&self, query: &str) -> Result<Vec<ScoredDocument>> {
-        self.search_n(query, 10)
+        self.search_n(query, self.0.components.config.default_search_limit)
     }
 
     pub fn search_n(&self, query: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
-        let results = self.0.run_search(query, limit, ExecutionConfig::default())?;
+        let results = self.0.run_search(query, limit, 0, self.0.default_execution_config())?;
         Ok(results.hits)
     }
 
@@ -69,8 +122,63 @@ impl SearchIndex {
         self.search_n(query, limit)
     }
 
+    /// One page of results: ranks `[offset, offset + limit)` of the full
+    /// ranking, e.g. `offset = 20, limit = 10` for the third page of ten.
+    pub fn search_with_limit_offset(
+        &self,
+        query: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<ScoredDocument>> {
+        let results = self.0.run_search(query, limit, offset, self.0.default_execution_config())?;
+        Ok(results.hits)
+    }
+
+    /// Search with full control over execution: scoring algorithm,
+    /// optimization, validation, timeout, explanations, projection and sort
+    pub fn search_with_config(
+        &self,
+        query: &str,
+        limit: usize,
+        config: ExecutionConfig,
+    ) -> Result<SearchResults> {
+        self.0.run_search(query, limit, 0, config)
+    }
+
+    /// Search with a query in the JSON DSL described on [`Query`], e.g.
+    /// `{"term": {"field": "title", "value": "rust"}}`
+    pub fn search_json(&self, json_query: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
+        let query = Query::from_json(json_query)?;
+        let results = self.0.run_query(&query, limit, self.0.default_execution_config())?;
+        Ok(results.hits)
+    }
+
+    /// Search returning only the given stored fields in each hit's document.
+    pub fn search_with_fields(
+        &self,
+        query: &str,
+        limit: usize,
+        fields: &[&str],
+    ) -> Result<Vec<ScoredDocument>> {
+        let config = self.0.default_execution_config().with_fields(fields.iter().copied());
+        let results = self.0.run_search(query, limit, 0, config)?;
+        Ok(results.hits)
+    }
+
     pub fn search_debug(&self, query_str: &str, limit: usize) -> Result<SearchResults> {
-        self.0.run_search(query_str, limit, ExecutionConfig::debug())
+        self.0.run_search(query_str, limit, 0, ExecutionConfig::debug())
+    }
+
+    /// Scan every segment and report missing, orphaned or corrupt data.
+    /// Read-only.
+    pub fn validate(&self) -> Result<ValidationReport> {
+        self.0.validate()
+    }
+
+    /// Validate, then drop unrecoverable segments from the current snapshot.
+    /// Returns the report the decision was based on; files are left on disk.
+    pub fn repair(&self) -> Result<ValidationReport> {
+        self.0.repair()
     }
 
     pub fn stats(&self) -> Result<DatabaseStats> {
@@ -81,10 +189,24 @@ impl SearchIndex {
         self.0.run_health_check()
     }
 
-    pub fn begin_transaction(&self, isolation: IsolationLevel) -> Arc<Transaction> {
+    pub fn begin_transaction(&self, isolation: IsolationLevel) -> Result<Arc<Transaction>> {
         self.0.begin_transaction(isolation)
     }
 
+    /// Commit a transaction from `begin_transaction` and apply its operations
+    pub fn commit_transaction(&self, tx: &Transaction) -> Result<()> {
+        self.0.commit_transaction(tx)
+    }
+
+    pub fn rollback_transaction(&self, tx: &Transaction) -> Result<()> {
+        self.0.rollback_transaction(tx)
+    }
+
+    /// Transactions begun on this index that have not committed or rolled back
+    pub fn transaction_stats(&self) -> TransactionStats {
+        self.0.transaction_stats()
+    }
+
     pub fn transaction<F, R>(&self, isolation: IsolationLevel, f: F) -> Result<R>
     where
         F: FnOnce(&Transaction) -> Result<R>,
@@ -92,6 +214,20 @@ impl SearchIndex {
         self.0.with_transaction(isolation, f)
     }
 
+    /// Like `transaction`, but retries with exponential backoff when commit
+    /// fails on a conflict. `f` runs once per attempt, so it must be re-runnable.
+    pub fn with_transaction_retry<F, R>(
+        &self,
+        isolation: IsolationLevel,
+        max_retries: u32,
+        f: F,
+    ) -> Result<R>
+    where
+        F: FnMut(&Transaction) -> Result<R>,
+    {
+        self.0.with_transaction_retry(isolation, max_retries, f)
+    }
+
     /// Legacy compat: `with_transaction`
     pub fn with_transaction<F, R>(&self, isolation: IsolationLevel, f: F) -> Result<R>
     where
@@ -126,3 +262,1290 @@ impl SearchIndex {
         self.0.components.schema.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::ErrorKind;
+    use crate::core::types::FieldValue;
+    use crate::storage::backend::{MemoryStorage, StorageBackend, StorageFile};
+    use crate::storage::layout::StorageLayout;
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn search_honors_configured_default_limit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        config.default_search_limit = 3;
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 0..5 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("rust search".to_string()),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+
+        assert_eq!(index.search("rust").unwrap().len(), 3);
+        assert_eq!(index.search_with_limit("rust", 5).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn repair_drops_corrupt_segment_from_snapshot() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 0..3 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("rust search".to_string()),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+        assert!(index.validate().unwrap().is_clean());
+
+        let segment = index.0.components.mvcc.current_snapshot().segments[0].id;
+        let path = index.0.components.storage.segment_path(&segment);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 5]).unwrap();
+
+        let report = index.validate().unwrap();
+        assert_eq!(report.corrupt_segments.len(), 1);
+        assert_eq!(report.corrupt_segments[0].segment_id, segment);
+        assert_eq!(index.stats().unwrap().segment_count, 1);
+
+        let report = index.repair().unwrap();
+        assert!(report.unrecoverable_segments().contains(&segment));
+        assert_eq!(index.stats().unwrap().segment_count, 0);
+
+        // The file stays on disk for manual recovery, now unreferenced
+        let report = index.validate().unwrap();
+        assert!(report.corrupt_segments.is_empty());
+        assert_eq!(report.orphaned_segments, vec![segment]);
+    }
+
+    #[test]
+    fn phrase_queries_rejected_on_positionless_field() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let schema = SchemaWithAnalyzer::new()
+            .add_text_field("content", None)
+            .disable_positions("content");
+        let index = SearchIndex::open(schema, config).unwrap();
+
+        index
+            .add_document(Document {
+                id: DocId(1),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("rust search engine".to_string()),
+                )]),
+            })
+            .unwrap();
+        index.flush().unwrap();
+
+        assert_eq!(index.search("rust").unwrap().len(), 1);
+
+        let err = index.search("\"rust search\"").unwrap_err();
+        assert!(matches!(err.kind, crate::core::error::ErrorKind::UnsupportedQuery));
+        assert!(err.context.contains("without positions"));
+    }
+
+    fn open_with_content_doc(reject_unknown_fields: bool) -> (tempfile::TempDir, SearchIndex) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        config.reject_unknown_fields = reject_unknown_fields;
+        let schema = SchemaWithAnalyzer::new()
+            .add_text_field("content", None)
+            .add_text_field("title", None);
+        let index = SearchIndex::open(schema, config).unwrap();
+        index
+            .add_document(Document {
+                id: DocId(1),
+                fields: HashMap::from([("content".to_string(), FieldValue::Text("rust".to_string()))]),
+            })
+            .unwrap();
+        index.flush().unwrap();
+        (temp_dir, index)
+    }
+
+    #[test]
+    fn queries_on_unknown_fields_are_rejected_in_strict_mode() {
+        let (_dir, index) = open_with_content_doc(true);
+
+        assert_eq!(index.search("content:rust").unwrap().len(), 1);
+        assert!(index.search("title:rust").unwrap().is_empty());
+
+        for query in ["contnet:rust", "rust AND contnet:rus*", "contnet:\"rust search\""] {
+            let err = index.search(query).expect_err(query);
+            assert!(matches!(err.kind, crate::core::error::ErrorKind::InvalidInput), "{}", query);
+        }
+        let err = index.search("contnet:rust").unwrap_err();
+        assert!(err.context.contains("unknown field 'contnet'"));
+        assert!(err.context.contains("content, title"));
+    }
+
+    #[test]
+    fn queries_on_unknown_fields_match_nothing_in_lax_mode() {
+        let (_dir, index) = open_with_content_doc(false);
+
+        assert!(index.search("contnet:rust").unwrap().is_empty());
+        assert_eq!(index.search("rust OR contnet:rust").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn numeric_fields_leave_text_scores_unchanged() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+
+        let text = ("content".to_string(), FieldValue::Text("rust search engine".to_string()));
+        index
+            .add_document(Document { id: DocId(1), fields: HashMap::from([text.clone()]) })
+            .unwrap();
+        index
+            .add_document(Document {
+                id: DocId(2),
+                fields: HashMap::from([
+                    text,
+                    ("a_price".to_string(), FieldValue::Number(12.5)),
+                    ("year".to_string(), FieldValue::Number(2024.0)),
+                ]),
+            })
+            .unwrap();
+        index.flush().unwrap();
+        drop(index);
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let hits = index.search("rust").unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].score, hits[1].score);
+        // Numbers stay searchable by range
+        assert_eq!(index.search("year:[2000 TO 2030]").unwrap().len(), 1);
+        // and don't shift the positions phrases match on
+        assert_eq!(index.search("\"rust search\"").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reopened_index_holds_only_postings_of_live_document_versions() {
+        use crate::index::inverted::Term;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+        index.add_document(doc(1, "rust search")).unwrap();
+        index.add_document(doc(2, "python search")).unwrap();
+        index.flush().unwrap();
+        // The update drops "rust"; the old copy stays in the first segment
+        index.add_document(doc(1, "search only")).unwrap();
+        index.flush().unwrap();
+        index.delete_document(DocId(2)).unwrap();
+        index.commit().unwrap();
+        drop(index);
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let inverted = &index.0.components.reader_pool.index;
+        assert!(inverted.search_term(&Term::new("rust")).is_none());
+        assert!(inverted.search_term(&Term::new("python")).is_none());
+        let search: Vec<DocId> = inverted
+            .search_term(&Term::new("search"))
+            .unwrap()
+            .iter()
+            .unwrap()
+            .into_iter()
+            .map(|posting| posting.doc_id)
+            .collect();
+        assert_eq!(search, vec![DocId(1)]);
+        assert_eq!(inverted.doc_count, 1);
+    }
+
+    #[test]
+    fn add_documents_uses_parallel_batch_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        let docs: Vec<Document> = (0..500)
+            .map(|id| Document {
+                id: DocId(id),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text(format!("bulk rust document {}", id)),
+                )]),
+            })
+            .collect();
+        index.add_documents(docs).unwrap();
+
+        // The parallel indexer tokenized the whole batch in one call
+        let indexer = index.0.components.writer.read().parallel_indexer.clone();
+        assert_eq!(indexer.get_progress(), 500);
+
+        index.flush().unwrap();
+        assert_eq!(index.search_with_limit("rust", 1000).unwrap().len(), 500);
+        assert_eq!(index.stats().unwrap().total_documents, 500);
+    }
+
+    #[test]
+    fn flush_on_batch_complete_persists_small_batches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let open = |flush_on_batch: bool| {
+            let mut config = Config::default();
+            config.storage_path = temp_dir.path().to_path_buf();
+            config.writer_flush_on_batch = flush_on_batch;
+            SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap()
+        };
+        let batch = |ids: std::ops::Range<u64>| -> Vec<Document> {
+            ids.map(|id| Document {
+                id: DocId(id),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("durable batch".to_string()),
+                )]),
+            })
+            .collect()
+        };
+
+        // Well under `writer_batch_size`: stays buffered without the option
+        let index = open(false);
+        index.add_documents(batch(0..5)).unwrap();
+        assert!(index.search("durable").unwrap().is_empty());
+        drop(index);
+        assert!(open(false).search("durable").unwrap().is_empty());
+
+        let index = open(true);
+        index.add_documents(batch(10..15)).unwrap();
+        assert_eq!(index.search("durable").unwrap().len(), 5);
+        drop(index);
+
+        let mut ids: Vec<u64> = open(false)
+            .search("durable")
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.doc_id.0)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![10, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn bulk_load_skips_wal_and_is_durable_after_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let mut config = Config::default();
+            config.storage_path = temp_dir.path().to_path_buf();
+            config.writer_batch_size = 10; // Roll segments mid-session
+            SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap()
+        };
+        let docs = |ids: std::ops::Range<u64>| -> Vec<Document> {
+            ids.map(|id| Document {
+                id: DocId(id),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("bulk loaded".to_string()),
+                )]),
+            })
+            .collect()
+        };
+        let wal_len = || {
+            std::fs::metadata(temp_dir.path().join("wal").join("wal_00000000.log"))
+                .unwrap()
+                .len()
+        };
+
+        // Session abandoned without commit: nothing survives
+        let index = open();
+        index.begin_bulk_load();
+        index.add_documents(docs(0..25)).unwrap();
+        drop(index);
+        assert!(open().search("bulk").unwrap().is_empty());
+
+        let index = open();
+        let wal_before = wal_len();
+        index.begin_bulk_load();
+        index.add_documents(docs(100..125)).unwrap();
+        for doc in docs(125..130) {
+            index.add_document(doc).unwrap();
+        }
+        assert_eq!(wal_len(), wal_before);
+
+        index.commit().unwrap();
+        assert!(!index.0.components.writer.read().is_bulk_loading());
+        drop(index);
+
+        let hits = open().search_with_limit("bulk", 100).unwrap();
+        assert_eq!(hits.len(), 30);
+        assert!(hits.iter().all(|hit| hit.doc_id.0 >= 100));
+    }
+
+    #[test]
+    fn document_cache_hits_repeats_and_drops_updated_copies() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        config.document_cache_size = Some(16);
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let doc = |text: &str| Document {
+            id: DocId(1),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+        let fetch = || {
+            let reader = index.0.components.reader_pool.get_reader().unwrap();
+            let doc = reader.get_document(DocId(1)).unwrap().unwrap();
+            doc.get_field("content").cloned()
+        };
+
+        index.add_document(doc("first version")).unwrap();
+        index.flush().unwrap();
+        assert_eq!(fetch(), Some(FieldValue::Text("first version".to_string())));
+        assert_eq!(fetch(), Some(FieldValue::Text("first version".to_string())));
+
+        let stats = index.stats().unwrap().document_cache_stats.unwrap();
+        assert_eq!((stats.hit_count, stats.miss_count), (1, 1));
+
+        // Re-adding the id drops the cached copy, so the next fetch reads segments again
+        index.add_document(doc("second version")).unwrap();
+        assert_eq!(index.stats().unwrap().document_cache_stats.unwrap().size, 0);
+        fetch();
+        let stats = index.stats().unwrap().document_cache_stats.unwrap();
+        assert_eq!((stats.hit_count, stats.miss_count), (1, 2));
+    }
+
+    #[test]
+    fn transaction_stats_track_open_transactions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        let committed = index.begin_transaction(IsolationLevel::ReadCommitted).unwrap();
+        let rolled_back = index.begin_transaction(IsolationLevel::ReadCommitted).unwrap();
+        assert_eq!(index.transaction_stats().active, 2);
+
+        committed
+            .insert(Document {
+                id: DocId(1),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("transactional write".to_string()),
+                )]),
+            })
+            .unwrap();
+        index.commit_transaction(&committed).unwrap();
+        assert_eq!(index.transaction_stats().active, 1);
+        assert_eq!(index.search("transactional").unwrap().len(), 1);
+
+        index.rollback_transaction(&rolled_back).unwrap();
+        let stats = index.transaction_stats();
+        assert_eq!((stats.total, stats.active), (0, 0));
+    }
+
+    #[test]
+    fn commit_applies_operations_surviving_a_savepoint_rollback() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        index
+            .transaction(IsolationLevel::ReadCommitted, |tx| {
+                tx.insert(doc(1, "alpha step"))?;
+                tx.savepoint("second")?;
+                tx.insert(doc(2, "beta step"))?;
+                tx.rollback_to("second")
+            })
+            .unwrap();
+
+        assert_eq!(index.search("alpha").unwrap().len(), 1);
+        assert!(index.search("beta").unwrap().is_empty());
+    }
+
+    #[test]
+    fn conflicting_transaction_succeeds_on_retry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        // Give the transactions a committed snapshot to start from
+        index.add_document(doc(9, "seed")).unwrap();
+        index.flush().unwrap();
+
+        let mut attempts = 0;
+        index
+            .with_transaction_retry(IsolationLevel::Serializable, 3, |tx| {
+                attempts += 1;
+                tx.read(DocId(1))?;
+                if attempts == 1 {
+                    // Another writer changes what this transaction read
+                    index.add_document(doc(1, "concurrent write"))?;
+                    index.flush()?;
+                }
+                tx.insert(doc(2, "retried write"))
+            })
+            .unwrap();
+
+        assert_eq!(attempts, 2);
+        assert_eq!(index.search("retried").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn small_transactions_share_segments_and_recover_from_wal() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        config.writer_batch_size = 10_000;
+
+        {
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+            for id in 0..1000 {
+                index
+                    .transaction(IsolationLevel::ReadCommitted, |tx| {
+                        tx.insert(Document {
+                            id: DocId(id),
+                            fields: HashMap::from([(
+                                "content".to_string(),
+                                FieldValue::Text(format!("durable entry{}", id)),
+                            )]),
+                        })
+                    })
+                    .unwrap();
+            }
+            // Nothing was flushed; the transactions only live in the WAL
+            assert!(index.0.components.mvcc.current_snapshot().segments.is_empty());
+        }
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        index.recover().unwrap();
+        assert!(index.0.components.mvcc.current_snapshot().segments.len() <= 1);
+        assert_eq!(index.search_with_limit("durable", 2000).unwrap().len(), 1000);
+        assert_eq!(index.search("entry999").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn read_committed_transaction_sees_another_transactions_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        let reader = index.begin_transaction(IsolationLevel::ReadCommitted).unwrap();
+        assert!(reader.read(DocId(5)).unwrap().is_none());
+
+        index
+            .transaction(IsolationLevel::ReadCommitted, |tx| {
+                tx.insert(Document {
+                    id: DocId(5),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("committed elsewhere".to_string()),
+                    )]),
+                })
+            })
+            .unwrap();
+
+        let doc = reader.read(DocId(5)).unwrap().unwrap();
+        assert!(matches!(
+            doc.fields.get("content"),
+            Some(FieldValue::Text(text)) if text == "committed elsewhere"
+        ));
+        index.rollback_transaction(&reader).unwrap();
+    }
+
+    #[test]
+    fn second_transaction_inserting_the_same_id_conflicts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let doc = |text: &str| Document {
+            id: DocId(7),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        // Give the transactions a committed snapshot to start from
+        index.add_document(Document { id: DocId(1), fields: HashMap::new() }).unwrap();
+        index.flush().unwrap();
+
+        let first = index.begin_transaction(IsolationLevel::Serializable).unwrap();
+        let second = index.begin_transaction(IsolationLevel::Serializable).unwrap();
+        first.insert(doc("first writer")).unwrap();
+        second.insert(doc("second writer")).unwrap();
+
+        index.commit_transaction(&first).unwrap();
+        let err = index.commit_transaction(&second).unwrap_err();
+        assert!(matches!(&err.kind, ErrorKind::Conflict(conflicts) if conflicts[0].doc_id == DocId(7)));
+
+        assert_eq!(index.search("writer").unwrap().len(), 1);
+        assert_eq!(index.search("first").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn concurrent_disjoint_serializable_inserts_all_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        index
+            .add_document(Document {
+                id: DocId(0),
+                fields: HashMap::new(),
+            })
+            .unwrap();
+        index.flush().unwrap();
+
+        const THREADS: u64 = 8;
+        const PER_THREAD: u64 = 10;
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let index = &index;
+                scope.spawn(move || {
+                    for n in 0..PER_THREAD {
+                        let id = 1 + thread * PER_THREAD + n;
+                        index
+                            .transaction(IsolationLevel::Serializable, |tx| {
+                                tx.insert(Document {
+                                    id: DocId(id),
+                                    fields: HashMap::from([(
+                                        "content".to_string(),
+                                        FieldValue::Text("disjoint insert".to_string()),
+                                    )]),
+                                })?;
+                                // Move the global snapshot on before this transaction commits
+                                index.delete_document(DocId(1_000_000 + id))
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        index.flush().unwrap();
+        let hits = index.search_with_limit("disjoint", 1000).unwrap();
+        assert_eq!(hits.len() as u64, THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_overlapping_serializable_inserts_commit_only_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        index
+            .add_document(Document {
+                id: DocId(0),
+                fields: HashMap::new(),
+            })
+            .unwrap();
+        index.flush().unwrap();
+
+        const THREADS: usize = 8;
+        // Every transaction begins before any of them commits
+        let began = std::sync::Barrier::new(THREADS);
+        let results: Vec<Result<()>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let (index, began) = (&index, &began);
+                    scope.spawn(move || {
+                        let tx = index.begin_transaction(IsolationLevel::Serializable).unwrap();
+                        for id in 1..=5 {
+                            tx.insert(Document {
+                                id: DocId(id),
+                                fields: HashMap::from([(
+                                    "content".to_string(),
+                                    FieldValue::Text("overlapping insert".to_string()),
+                                )]),
+                            })
+                            .unwrap();
+                        }
+                        began.wait();
+                        index.commit_transaction(&tx)
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        for err in results.into_iter().filter_map(Result::err) {
+            assert!(matches!(err.kind, ErrorKind::Conflict(_)));
+        }
+        let hits = index.search_with_limit("overlapping", 100).unwrap();
+        assert_eq!(hits.len(), 5);
+    }
+
+    #[test]
+    fn committed_deletes_survive_reopen_without_wal_replay() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        {
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+            for id in 1..=3 {
+                index.add_document(doc(id, "tombstone candidate")).unwrap();
+            }
+            index.commit().unwrap();
+
+            // Nothing is buffered, so this commit writes no new segment
+            index.delete_document(DocId(2)).unwrap();
+            index.commit().unwrap();
+        }
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let mut ids: Vec<u64> = index
+            .search("candidate")
+            .unwrap()
+            .iter()
+            .map(|hit| hit.doc_id.0)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn inverted_index_is_reloaded_from_segments_on_reopen() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        {
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+            // Nothing on disk yet: the index starts empty
+            assert_eq!(index.0.components.reader_pool.index.doc_count, 0);
+            index.add_document(doc(1, "persistent quick fox")).unwrap();
+            index.add_document(doc(2, "persistent slow turtle")).unwrap();
+            index.flush().unwrap();
+        }
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let loaded = &index.0.components.reader_pool.index;
+        assert_eq!(loaded.doc_count, 2);
+        let persist = crate::index::inverted::Term::new("persist");
+        assert_eq!(loaded.search_term(&persist).unwrap().doc_freq(), 2);
+
+        assert_eq!(index.search("persistent").unwrap().len(), 2);
+        let hits = index.search("\"quick fox\"").unwrap();
+        assert_eq!(hits.iter().map(|hit| hit.doc_id.0).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn named_snapshot_reads_earlier_state_after_restart() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+        let query = |text: &str| crate::query::parser::QueryParser::new().parse(text).unwrap();
+
+        {
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+            index.add_document(doc(1, "before snapshot")).unwrap();
+            index.save_snapshot("v1").unwrap();
+
+            index.add_document(doc(2, "after snapshot")).unwrap();
+            index.flush().unwrap();
+            assert_eq!(index.search("snapshot").unwrap().len(), 2);
+        }
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let view = index.open_snapshot("v1").unwrap();
+        let hits = view.search(&query("snapshot")).unwrap();
+        assert_eq!(hits.iter().map(|h| h.doc_id).collect::<Vec<_>>(), vec![DocId(1)]);
+        assert!(view.get_document(DocId(2)).unwrap().is_none());
+
+        assert!(matches!(index.open_snapshot("v2").err().unwrap().kind, ErrorKind::NotFound));
+        assert!(matches!(index.save_snapshot("../v2").unwrap_err().kind, ErrorKind::InvalidArgument));
+    }
+
+    #[test]
+    fn searching_empty_and_single_document_index_yields_finite_scores() {
+        let open = |dir: &tempfile::TempDir| {
+            let mut config = Config::default();
+            config.storage_path = dir.path().to_path_buf();
+            SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap()
+        };
+
+        let empty_dir = tempfile::tempdir().unwrap();
+        let empty = open(&empty_dir);
+        for query in ["rust", "rust AND search", "content:rus*", "\"rust search\""] {
+            assert!(empty.search(query).unwrap().is_empty());
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let index = open(&temp_dir);
+        index
+            .add_document(Document {
+                id: DocId(1),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("rust search".to_string()),
+                )]),
+            })
+            .unwrap();
+        index.flush().unwrap();
+
+        for query in ["rust", "rust AND search", "content:rus*"] {
+            let hits = index.search(query).unwrap();
+            assert_eq!(hits.len(), 1, "{}", query);
+            assert!(hits[0].score.is_finite(), "{} scored {}", query, hits[0].score);
+        }
+    }
+
+    #[test]
+    fn id_query_returns_exactly_the_requested_live_document() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 1..=10 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(format!("document number {}", id)),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+        index.delete_document(DocId(7)).unwrap();
+
+        let ids = |query: &str| {
+            index.search(query).unwrap().iter().map(|hit| hit.doc_id.0).collect::<Vec<_>>()
+        };
+        assert_eq!(ids("_id:5"), vec![5]);
+        assert!(ids("_id:7").is_empty());
+        assert!(ids("_id:42").is_empty());
+        assert_eq!(ids("_id:3,7,9,3"), vec![3, 9]);
+
+        let hit = &index.search("_id:2").unwrap()[0];
+        assert!(matches!(
+            hit.document.as_ref().unwrap().fields.get("content"),
+            Some(FieldValue::Text(text)) if text == "document number 2"
+        ));
+        assert!(matches!(index.search("_id:five").unwrap_err().kind, ErrorKind::Parse));
+    }
+
+    #[test]
+    fn search_json_executes_every_query_variant() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+
+        for (id, content, year) in [
+            (1, "rust async runtime", 2021.0),
+            (2, "java virtual machine", 2015.0),
+            (3, "rust borrow checker", 2023.0),
+        ] {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([
+                        ("content".to_string(), FieldValue::Text(content.to_string())),
+                        ("year".to_string(), FieldValue::Number(year)),
+                    ]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+
+        let term = |value: &str| format!(r#"{{"term": {{"field": "content", "value": "{}"}}}}"#, value);
+        let cases = [
+            (term("rust"), vec![1, 3]),
+            (
+                format!(r#"{{"boosting": {{"positive": {}, "negative": {}, "negative_boost": 0.5}}}}"#, term("rust"), term("async")),
+                vec![1, 3],
+            ),
+            (format!(r#"{{"dis_max": {{"queries": [{}, {}]}}}}"#, term("java"), term("borrow")), vec![2, 3]),
+            (format!(r#"{{"bool": {{"must": [{}], "must_not": [{{"ids": {{"values": [3]}}}}]}}}}"#, term("rust")), vec![1]),
+            (r#"{"range": {"field": "year", "gte": 2020, "lt": 2023}}"#.to_string(), vec![1]),
+            (r#"{"prefix": {"field": "content", "prefix": "virt"}}"#.to_string(), vec![2]),
+            (r#"{"wildcard": {"field": "content", "pattern": "bor?ow"}}"#.to_string(), vec![3]),
+            (r#"{"fuzzy": {"field": "content", "term": "jawa", "max_edits": 1}}"#.to_string(), vec![2]),
+            (r#"{"ids": {"values": [2, 3]}}"#.to_string(), vec![2, 3]),
+            (r#""match_all""#.to_string(), vec![1, 2, 3]),
+        ];
+
+        for (json, expected) in cases {
+            let mut ids: Vec<u64> = index.search_json(&json, 10).unwrap().iter().map(|h| h.doc_id.0).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, expected, "query {}", json);
+        }
+
+        // Positional queries read the postings loaded on open. Like `term`,
+        // their words are matched as indexed terms, so they avoid stemmed words.
+        drop(index);
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let positional = [
+            (r#"{"phrase": {"field": "content", "phrase": ["rust", "async"]}}"#.to_string(), vec![1]),
+            (r#"{"phrase": {"field": "content", "phrase": ["async", "rust"]}}"#.to_string(), vec![]),
+            (
+                format!(r#"{{"near": {{"field": "content", "clauses": [{}, {}], "slop": 1}}}}"#, term("rust"), term("checker")),
+                vec![3],
+            ),
+            (
+                format!(r#"{{"near": {{"field": "content", "clauses": [{}, {}], "slop": 0}}}}"#, term("rust"), term("checker")),
+                vec![],
+            ),
+        ];
+        for (json, expected) in positional {
+            let mut ids: Vec<u64> = index.search_json(&json, 10).unwrap().iter().map(|h| h.doc_id.0).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, expected, "query {}", json);
+        }
+
+        let err = index.search_json(r#"{"predicate": {}}"#, 10).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Parse));
+    }
+
+    #[test]
+    fn search_with_config_applies_the_requested_scoring() {
+        use crate::analysis::token::Token;
+        use crate::index::inverted::InvertedIndex;
+        use crate::reader::reader_pool::ReaderPool;
+        use crate::search::executor::ScoringAlgorithm;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let mut engine = SearchEngine::new(SchemaWithAnalyzer::new(), config).unwrap();
+
+        let docs: Vec<Document> = [
+            (1, "rust rust rust rust rust rust rust rust"),
+            (2, "rust java"),
+            (3, "java java java"),
+            (4, "python go"),
+        ]
+        .into_iter()
+        .map(|(id, text)| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        })
+        .collect();
+
+        // Term statistics come from the reader's in-memory index
+        let mut postings = InvertedIndex::new();
+        for doc in &docs {
+            let Some(FieldValue::Text(text)) = doc.get_field("content") else { unreachable!() };
+            let tokens: Vec<Token> = text
+                .split_whitespace()
+                .enumerate()
+                .map(|(i, word)| Token::new(word.to_string(), i as u32, 0))
+                .collect();
+            postings.add_document(doc.id, &tokens).unwrap();
+            engine.write_document(doc.clone()).unwrap();
+        }
+        engine.flush_segments().unwrap();
+        let components = &mut engine.components;
+        components.reader_pool = Arc::new(ReaderPool::new(
+            components.mvcc.clone(),
+            components.storage.clone(),
+            Arc::new(postings),
+            4,
+        ));
+        let index = SearchIndex(Arc::new(engine));
+
+        let run = |config: ExecutionConfig| -> Vec<(u64, f32)> {
+            let results = index.search_with_config("rust java", 10, config).unwrap();
+            results.hits.iter().map(|h| (h.doc_id.0, h.score)).collect()
+        };
+        let order = |hits: Vec<(u64, f32)>| hits.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+        // BM25 saturates term frequency, so the doc with both terms wins;
+        // TF-IDF keeps rewarding the repeated term. The BM25 run is cached
+        // and must not answer the TF-IDF one.
+        assert_eq!(order(run(ExecutionConfig::bm25())), vec![2, 1, 3]);
+        assert_eq!(order(run(ExecutionConfig::tfidf())), vec![1, 3, 2]);
+        assert!(run(ExecutionConfig::with_scoring(ScoringAlgorithm::None)).iter().all(|(_, score)| *score == 1.0));
+
+        let explained = index.search_with_config("rust java", 10, ExecutionConfig::debug()).unwrap();
+        assert!(explained.hits.iter().all(|hit| hit.explanation.is_some()));
+    }
+
+    #[test]
+    fn configured_optimization_off_skips_the_planner() {
+        let open = |enable_optimization: bool| {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let mut config = Config::default();
+            config.storage_path = temp_dir.path().to_path_buf();
+            config.enable_optimization = enable_optimization;
+            config.enable_validation = enable_optimization;
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+            index
+                .add_document(Document {
+                    id: DocId(1),
+                    fields: HashMap::from([("content".to_string(), FieldValue::Text("rust search".to_string()))]),
+                })
+                .unwrap();
+            index.flush().unwrap();
+            (temp_dir, index)
+        };
+
+        let (_dir, fast) = open(false);
+        assert_eq!(fast.search("rust").unwrap().len(), 1);
+        assert_eq!(fast.0.components.executor.planned_queries(), 0);
+
+        let (_dir, planned) = open(true);
+        assert_eq!(planned.search("rust").unwrap().len(), 1);
+        assert_eq!(planned.0.components.executor.planned_queries(), 1);
+    }
+
+    #[test]
+    fn get_document_returns_only_live_documents() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let doc = |id: u64, text: &str| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+
+        index.add_document(doc(1, "first")).unwrap();
+        index.add_document(doc(2, "second")).unwrap();
+        index.flush().unwrap();
+        // An update in a newer segment wins over the old copy
+        index.add_document(doc(1, "first, updated")).unwrap();
+        index.flush().unwrap();
+        index.delete_document(DocId(2)).unwrap();
+
+        let present = index.get_document(DocId(1)).unwrap().unwrap();
+        assert_eq!(present.fields, doc(1, "first, updated").fields);
+        assert!(index.get_document(DocId(2)).unwrap().is_none());
+        assert!(index.get_document(DocId(3)).unwrap().is_none());
+    }
+
+    #[test]
+    fn term_vector_matches_the_analyzed_field() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        let content = "The quick dog chased the QUICK fox";
+        index
+            .add_document(Document {
+                id: DocId(7),
+                fields: HashMap::from([
+                    ("title".to_string(), FieldValue::Text("Quick Fox".to_string())),
+                    ("content".to_string(), FieldValue::Text(content.to_string())),
+                ]),
+            })
+            .unwrap();
+        index.flush().unwrap();
+
+        let tokens = index.0.components.writer.read().analyzer.analyze(content);
+        let mut expected: Vec<(String, u32, Vec<u32>)> = Vec::new();
+        for (pos, token) in tokens.iter().enumerate() {
+            match expected.iter_mut().find(|(term, _, _)| *term == token.text) {
+                Some((_, tf, positions)) => {
+                    *tf += 1;
+                    positions.push(pos as u32);
+                }
+                None => expected.push((token.text.clone(), 1, vec![pos as u32])),
+            }
+        }
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let vector = index.term_vector(DocId(7), "content").unwrap();
+        assert_eq!(vector, expected);
+        assert!(vector.iter().any(|(term, tf, _)| term == "quick" && *tf == 2));
+
+        let title = index.term_vector(DocId(7), "title").unwrap();
+        assert_eq!(title, vec![("fox".to_string(), 1, vec![1]), ("quick".to_string(), 1, vec![0])]);
+
+        assert!(index.term_vector(DocId(7), "missing").unwrap().is_empty());
+        let err = index.term_vector(DocId(8), "content").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn recover_to_sequence_replays_only_earlier_operations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        config.writer_batch_size = 10_000;
+        let doc = |id: u64| Document {
+            id: DocId(id),
+            fields: HashMap::from([(
+                "content".to_string(),
+                FieldValue::Text(format!("logged entry{}", id)),
+            )]),
+        };
+
+        {
+            let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+            // WAL sequences 0..=3, then the bad write at 4
+            for id in 1..=4 {
+                index.add_document(doc(id)).unwrap();
+            }
+            index.delete_document(DocId(1)).unwrap();
+            index.0.components.writer.write().sync_wal().unwrap();
+        }
+
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config.clone()).unwrap();
+        index.recover_to(RecoveryTarget::Sequence(2)).unwrap();
+        let found = |index: &SearchIndex| {
+            let mut ids: Vec<u64> = index
+                .search_with_limit("logged", 10)
+                .unwrap()
+                .iter()
+                .map(|hit| hit.doc_id.0)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(found(&index), vec![1, 2, 3]);
+        drop(index);
+
+        // The skipped tail stays discarded on the next recovery
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        index.recover().unwrap();
+        assert_eq!(found(&index), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn in_memory_index_searches_without_touching_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().join("index");
+        config.in_memory = true;
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 0..3 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("rust search".to_string()),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+
+        assert_eq!(index.search_with_limit("rust", 10).unwrap().len(), 3);
+        assert!(index.validate().unwrap().is_clean());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn search_pages_through_results_in_windows() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 0..25 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("rust ".repeat(1 + id as usize % 4) + "search"),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+
+        let ids = |hits: Vec<ScoredDocument>| hits.iter().map(|hit| hit.doc_id).collect::<Vec<_>>();
+        let all = ids(index.search_with_limit("rust", 25).unwrap());
+        assert_eq!(all.len(), 25);
+
+        let pages: Vec<Vec<DocId>> = [0, 10, 20]
+            .iter()
+            .map(|&offset| ids(index.search_with_limit_offset("rust", 10, offset).unwrap()))
+            .collect();
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), vec![10, 10, 5]);
+        assert_eq!(pages.concat(), all);
+
+        // Pages are cached separately, so repeating one doesn't return another
+        assert_eq!(ids(index.search_with_limit_offset("rust", 10, 10).unwrap()), pages[1]);
+        assert_eq!(ids(index.search_with_limit_offset("rust", 10, 0).unwrap()), pages[0]);
+        assert!(index.search_with_limit_offset("rust", 10, 30).unwrap().is_empty());
+    }
+
+    #[test]
+    fn repeated_search_strings_are_parsed_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().to_path_buf();
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+        let parses = || index.0.components.compiled_queries.parse_count();
+        let doc = |id: u64| Document {
+            id: DocId(id),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text("rust search".to_string()))]),
+        };
+
+        index.add_document(doc(1)).unwrap();
+        index.flush().unwrap();
+        assert_eq!(index.search("rust AND search").unwrap().len(), 1);
+        assert_eq!(parses(), 1);
+
+        // Other windows miss the result cache but reuse the parsed query,
+        // and see documents written since it was parsed
+        index.add_document(doc(2)).unwrap();
+        index.flush().unwrap();
+        assert_eq!(index.search_with_limit_offset("rust AND search", 5, 0).unwrap().len(), 2);
+        assert_eq!(index.search_with_limit_offset("rust AND search", 1, 1).unwrap().len(), 1);
+        assert_eq!(parses(), 1);
+
+        index.search("rust OR search").unwrap();
+        assert_eq!(parses(), 2);
+        assert!(index.search("(rust").is_err());
+        assert!(index.search("(rust").is_err());
+        assert_eq!(parses(), 4);
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        inner: MemoryStorage,
+        ops: parking_lot::Mutex<Vec<(&'static str, PathBuf)>>,
+    }
+
+    impl RecordingBackend {
+        fn record(&self, op: &'static str, path: &Path) {
+            self.ops.lock().push((op, path.to_path_buf()));
+        }
+
+        fn recorded(&self, op: &str) -> Vec<PathBuf> {
+            self.ops.lock().iter().filter(|(name, _)| *name == op).map(|(_, path)| path.clone()).collect()
+        }
+    }
+
+    impl StorageBackend for RecordingBackend {
+        fn create(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+            self.record("create", path);
+            self.inner.create(path)
+        }
+
+        fn open(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+            self.record("open", path);
+            self.inner.open(path)
+        }
+
+        fn open_append(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+            self.record("open_append", path);
+            self.inner.open_append(path)
+        }
+
+        fn read(&self, path: &Path) -> Result<Vec<u8>> {
+            self.record("read", path);
+            self.inner.read(path)
+        }
+
+        fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+            self.record("write", path);
+            self.inner.write(path, data)
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.inner.exists(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.inner.is_dir(path)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+            self.record("rename", to);
+            self.inner.rename(from, to)
+        }
+
+        fn remove(&self, path: &Path) -> Result<()> {
+            self.record("remove", path);
+            self.inner.remove(path)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> Result<()> {
+            self.record("create_dir_all", path);
+            self.inner.create_dir_all(path)
+        }
+
+        fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+            self.record("list", dir);
+            self.inner.list(dir)
+        }
+    }
+
+    #[test]
+    fn custom_backend_serves_every_file_operation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(RecordingBackend::default());
+        let mut config = Config::default();
+        config.storage_path = temp_dir.path().join("index");
+        config.storage_backend = Some(backend.clone());
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config).unwrap();
+
+        for id in 0..3 {
+            index
+                .add_document(Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text("rust search".to_string()),
+                    )]),
+                })
+                .unwrap();
+        }
+        index.flush().unwrap();
+        assert_eq!(index.search_with_limit("rust", 10).unwrap().len(), 3);
+
+        let storage = &index.0.components.storage;
+        let segment = &index.0.components.mvcc.current_snapshot().segments[0];
+        let segment_path = storage.segment_path(&segment.id);
+        assert!(backend.recorded("open_append").iter().any(|path| path.starts_with(&storage.wal_dir)));
+        assert!(backend.recorded("create").contains(&StorageLayout::temp_path(&segment_path)));
+        assert!(backend.recorded("rename").contains(&segment_path));
+        assert!(backend.recorded("open").contains(&segment_path));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+}
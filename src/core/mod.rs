@@ -9,7 +9,11 @@ pub mod utils;
 pub(crate) mod components;
 pub(crate) mod engine;
 pub mod facade;
+#[cfg(feature = "tokio")]
+pub mod async_index;
 
 // Backward compatibility type alias
 pub use facade::SearchIndex;
-pub type Database = SearchIndex;
\ No newline at end of file
+pub type Database = SearchIndex;
+#[cfg(feature = "tokio")]
+pub use async_index::AsyncSearchIndex;
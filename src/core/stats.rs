@@ -24,14 +24,20 @@ pub struct DatabaseStats {
 
     // Query metrics
     pub cache_stats: CacheStats,
+    pub document_cache_stats: Option<CacheStats>,
     pub queries_per_second: f64,
     pub avg_query_latency_ms: f64,
 
     // Write metrics
     pub writes_per_second: f64,
     pub pending_writes: usize,
+    pub truncated_fields: usize, // Text fields cut short by `max_tokens_per_field`
     pub last_flush_time: Option<SystemTime>,
     pub last_commit_time: Option<SystemTime>,
+
+    // Merge metrics
+    pub merge_bytes_total: u64,
+    pub merge_throughput_bytes_per_sec: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
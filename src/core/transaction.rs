@@ -1,14 +1,15 @@
-use crate::core::error::{Error, ErrorKind, Result};
+use crate::core::error::{Conflict, Error, ErrorKind, Result};
 use crate::core::types::{DocId, Document};
 use crate::mvcc::controller::{IsolationLevel, MVCCController, Snapshot};
 use crate::storage::layout::StorageLayout;
-use crate::storage::segment::{SegmentHeader, SegmentId};
+use crate::storage::segment::SegmentId;
+use crate::storage::segment_reader::SegmentReader;
+use crate::writer::index_writer::IndexWriter;
 use parking_lot::RwLock;
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::Read;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Transaction ID generator
 static TRANSACTION_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
@@ -36,11 +37,24 @@ pub struct Transaction {
     pub isolation_level: IsolationLevel,
     pub state: Arc<RwLock<TransactionState>>,
     pub operations: Arc<Mutex<Vec<TransactionOp>>>,
-    pub snapshot: Arc<Snapshot>,
+    /// Version of the snapshot this transaction reads from
+    pub snapshot_version: u64,
+    /// When the transaction began; `TransactionManager` aborts it once this is too old
+    pub started_at: Instant,
+    /// How long the transaction may stay active (`None` = never expires)
+    timeout: Option<Duration>,
+    /// Pins `snapshot_version` against MVCC GC until the transaction ends.
+    /// `ReadCommitted` transactions read the latest snapshot and pin nothing.
+    snapshot: RwLock<Option<Arc<Snapshot>>>,
     pub read_set: Arc<RwLock<HashMap<DocId, u64>>>, // Track reads for validation
     pub write_set: Arc<RwLock<HashMap<DocId, Document>>>, // Track writes
+    /// Named savepoints with the length of the operation log when each was set
+    savepoints: Mutex<Vec<(String, usize)>>,
     storage: Arc<StorageLayout>,
     mvcc: Arc<MVCCController>, // Use MVCC directly instead of Database
+    /// Publishes transaction commits still buffered in the writer before the
+    /// latest state is read (`None` = read the MVCC snapshot as is)
+    writer: Option<Arc<RwLock<IndexWriter>>>,
 }
 
 impl Transaction {
@@ -58,33 +72,41 @@ impl Transaction {
             isolation_level,
             state: Arc::new(RwLock::new(TransactionState::Active)),
             operations: Arc::new(Mutex::new(Vec::new())),
-            snapshot,
+            snapshot_version: snapshot.version,
+            started_at: Instant::now(),
+            timeout: None,
+            snapshot: RwLock::new(
+                (isolation_level != IsolationLevel::ReadCommitted).then_some(snapshot),
+            ),
             read_set: Arc::new(RwLock::new(HashMap::new())),
             write_set: Arc::new(RwLock::new(HashMap::new())),
+            savepoints: Mutex::new(Vec::new()),
             storage,
             mvcc,
+            writer: None,
         }
     }
 
     /// Read document within transaction
     pub fn read(&self, doc_id: DocId) -> Result<Option<Document>> {
-        // Check if we're still active
-        if *self.state.read() != TransactionState::Active {
-            return Err(Error::new(
-                ErrorKind::InvalidState,
-                "Transaction is not active".to_string(),
-            ));
-        }
+        self.check_active()?;
 
         // First check write set (read your own writes)
         if let Some(doc) = self.write_set.read().get(&doc_id) {
             return Ok(Some(doc.clone()));
         }
 
+        // ReadCommitted sees every committed change; stricter levels keep
+        // reading the snapshot taken at begin
+        let snapshot = match self.isolation_level {
+            IsolationLevel::ReadCommitted => self.latest_snapshot()?,
+            IsolationLevel::RepeatableRead | IsolationLevel::Serializable => self.snapshot()?,
+        };
+
         // Track read for validation
-        self.read_set.write().insert(doc_id, self.snapshot.version);
+        self.read_set.write().insert(doc_id, snapshot.version);
 
-        self.read_from_snapshot(&self.snapshot, doc_id)
+        self.read_from_snapshot(&snapshot, doc_id)
     }
 
     /// Insert document in transaction
@@ -135,9 +157,73 @@ impl Transaction {
         Ok(())
     }
 
+    /// Mark the current point in the transaction; reusing a name moves the savepoint
+    pub fn savepoint(&self, name: &str) -> Result<()> {
+        self.check_active()?;
+
+        let op_count = self.operations.lock().unwrap().len();
+        let mut savepoints = self.savepoints.lock().unwrap();
+        savepoints.retain(|(existing, _)| existing != name);
+        savepoints.push((name.to_string(), op_count));
+        Ok(())
+    }
+
+    /// Undo every operation made after the savepoint.
+    /// The savepoint itself stays set; later savepoints are dropped.
+    pub fn rollback_to(&self, name: &str) -> Result<()> {
+        self.check_active()?;
+
+        let mut savepoints = self.savepoints.lock().unwrap();
+        let index = Self::find_savepoint(&savepoints, name)?;
+        let op_count = savepoints[index].1;
+        savepoints.truncate(index + 1);
+
+        let mut operations = self.operations.lock().unwrap();
+        operations.truncate(op_count);
+
+        // The write set is exactly the replay of the surviving operations
+        let mut write_set = self.write_set.write();
+        write_set.clear();
+        for op in operations.iter() {
+            match op {
+                TransactionOp::Insert(doc) => {
+                    write_set.insert(doc.id, doc.clone());
+                }
+                TransactionOp::Update(doc_id, doc) => {
+                    write_set.insert(*doc_id, doc.clone());
+                }
+                TransactionOp::Delete(doc_id) => {
+                    write_set.remove(doc_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the savepoint and any set after it, keeping their operations
+    pub fn release(&self, name: &str) -> Result<()> {
+        self.check_active()?;
+
+        let mut savepoints = self.savepoints.lock().unwrap();
+        let index = Self::find_savepoint(&savepoints, name)?;
+        savepoints.truncate(index);
+        Ok(())
+    }
+
+    fn find_savepoint(savepoints: &[(String, usize)], name: &str) -> Result<usize> {
+        savepoints
+            .iter()
+            .position(|(existing, _)| existing == name)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("No savepoint named '{}'", name))
+            })
+    }
+
     /// Commit transaction with 2-phase commit
     /// Returns the list of operations to be executed
     pub fn commit(&self) -> Result<Vec<TransactionOp>> {
+        self.expire_at(Instant::now())?;
+
         // Phase 1: Prepare
         {
             let mut state = self.state.write();
@@ -152,11 +238,13 @@ impl Transaction {
 
         // Validate read set (optimistic concurrency control)
         if self.isolation_level != IsolationLevel::ReadCommitted {
-            if !self.validate_reads()? {
+            let conflicts = self.validate_reads()?;
+            if !conflicts.is_empty() {
                 self.abort()?;
+                let doc_ids: Vec<u64> = conflicts.iter().map(|c| c.doc_id.0).collect();
                 return Err(Error::new(
-                    ErrorKind::InvalidState,
-                    "Transaction validation failed".to_string(),
+                    ErrorKind::Conflict(conflicts),
+                    format!("Transaction validation failed: documents {:?} changed", doc_ids),
                 ));
             }
         }
@@ -167,6 +255,7 @@ impl Transaction {
 
         // Mark transaction as committed
         *self.state.write() = TransactionState::Committed;
+        self.snapshot.write().take();
 
         Ok(operations)
     }
@@ -181,19 +270,22 @@ impl Transaction {
 
         // Clear all in-memory state
         self.operations.lock().unwrap().clear();
+        self.savepoints.lock().unwrap().clear();
         self.read_set.write().clear();
         self.write_set.write().clear();
+        self.snapshot.write().take();
 
         Ok(())
     }
 
-    /// Validate that read set hasn't changed
-    fn validate_reads(&self) -> Result<bool> {
-        let current_snapshot = self.mvcc.current_snapshot();
+    /// Validate that read set hasn't changed, returning the documents that did
+    fn validate_reads(&self) -> Result<Vec<Conflict>> {
+        let current_snapshot = self.latest_snapshot()?;
 
-        if current_snapshot.version == self.snapshot.version {
-            return Ok(true);
+        if current_snapshot.version == self.snapshot_version {
+            return Ok(Vec::new());
         }
+        let snapshot = self.snapshot()?;
 
         let mut tracked_doc_ids: HashSet<DocId> = self.read_set.read().keys().copied().collect();
         tracked_doc_ids.extend(self.write_set.read().keys().copied());
@@ -208,15 +300,42 @@ impl Transaction {
             }
         }
 
+        let mut tracked_doc_ids: Vec<DocId> = tracked_doc_ids.into_iter().collect();
+        tracked_doc_ids.sort();
+
+        let read_set = self.read_set.read();
+        let mut conflicts = Vec::new();
         for doc_id in tracked_doc_ids {
-            let original_doc = self.read_from_snapshot(&self.snapshot, doc_id)?;
+            let original_doc = self.read_from_snapshot(&snapshot, doc_id)?;
             let current_doc = self.read_from_snapshot(&current_snapshot, doc_id)?;
             if !Self::documents_equal(&original_doc, &current_doc) {
-                return Ok(false);
+                conflicts.push(Conflict {
+                    doc_id,
+                    read_version: read_set.get(&doc_id).copied().unwrap_or(self.snapshot_version),
+                    current_version: current_snapshot.version,
+                });
             }
         }
 
-        Ok(true)
+        Ok(conflicts)
+    }
+
+    /// Latest committed state, including commits of other transactions
+    /// that have not reached a segment yet
+    fn latest_snapshot(&self) -> Result<Arc<Snapshot>> {
+        if let Some(writer) = &self.writer {
+            IndexWriter::publish_shared(writer)?;
+        }
+        Ok(self.mvcc.current_snapshot())
+    }
+
+    fn snapshot(&self) -> Result<Arc<Snapshot>> {
+        self.snapshot.read().clone().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidState,
+                "Transaction has released its snapshot".to_string(),
+            )
+        })
     }
 
     fn read_from_snapshot(&self, snapshot: &Snapshot, doc_id: DocId) -> Result<Option<Document>> {
@@ -239,28 +358,7 @@ impl Transaction {
         segment_id: SegmentId,
         doc_id: DocId,
     ) -> Result<Option<Document>> {
-        let path = self.storage.segment_path(&segment_id);
-        let mut file = File::open(path)?;
-        let header: SegmentHeader = bincode::deserialize_from(&mut file)?;
-
-        for _ in 0..header.doc_count {
-            let mut len_buf = [0u8; 4];
-            file.read_exact(&mut len_buf)?;
-            let len = u32::from_le_bytes(len_buf) as usize;
-
-            let mut block_buf = vec![0u8; len];
-            file.read_exact(&mut block_buf)?;
-            let compressed: crate::compression::compress::CompressedBlock =
-                bincode::deserialize(&block_buf)?;
-            let raw = compressed.decompress()?;
-            let doc: Document = bincode::deserialize(&raw)?;
-
-            if doc.id == doc_id {
-                return Ok(Some(doc));
-            }
-        }
-
-        Ok(None)
+        SegmentReader::open(&self.storage, segment_id)?.get_document(doc_id)
     }
 
     fn documents_equal(left: &Option<Document>, right: &Option<Document>) -> bool {
@@ -271,7 +369,23 @@ impl Transaction {
         }
     }
 
+    /// Abort the transaction if it has outlived its timeout, unpinning its snapshot
+    fn expire_at(&self, now: Instant) -> Result<()> {
+        if let Some(timeout) = self.timeout
+            && *self.state.read() == TransactionState::Active
+            && now.saturating_duration_since(self.started_at) > timeout
+        {
+            self.abort()?;
+            return Err(Error::new(
+                ErrorKind::InvalidState,
+                format!("Transaction {} timed out after {:?}", self.id, timeout),
+            ));
+        }
+        Ok(())
+    }
+
     fn check_active(&self) -> Result<()> {
+        self.expire_at(Instant::now())?;
         if *self.state.read() != TransactionState::Active {
             return Err(Error::new(
                 ErrorKind::InvalidState,
@@ -287,6 +401,10 @@ pub struct TransactionManager {
     active_transactions: Arc<RwLock<HashMap<u64, Arc<Transaction>>>>,
     mvcc: Arc<MVCCController>,
     storage: Arc<StorageLayout>,
+    /// Active transactions older than this are aborted on their next use or by `cleanup` (`None` = never)
+    timeout: Option<Duration>,
+    /// Writer buffering committed transactions; see `Transaction::latest_snapshot`
+    writer: Option<Arc<RwLock<IndexWriter>>>,
 }
 
 impl TransactionManager {
@@ -295,18 +413,39 @@ impl TransactionManager {
             active_transactions: Arc::new(RwLock::new(HashMap::new())),
             mvcc,
             storage,
+            timeout: None,
+            writer: None,
         }
     }
 
+    /// Abort transactions that stay active for longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Publish transaction commits buffered in `writer` whenever a
+    /// transaction needs the latest state
+    pub fn with_writer(mut self, writer: Arc<RwLock<IndexWriter>>) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
     /// Begin new transaction
-    pub fn begin_transaction(&self, isolation_level: IsolationLevel) -> Arc<Transaction> {
-        let tx = Arc::new(Transaction::begin(
-            self.mvcc.clone(),
-            self.storage.clone(),
-            isolation_level,
-        ));
+    pub fn begin_transaction(&self, isolation_level: IsolationLevel) -> Result<Arc<Transaction>> {
+        // A pinned snapshot must hold every transaction committed so far
+        if isolation_level != IsolationLevel::ReadCommitted
+            && let Some(writer) = &self.writer
+        {
+            IndexWriter::publish_shared(writer)?;
+        }
+
+        let mut tx = Transaction::begin(self.mvcc.clone(), self.storage.clone(), isolation_level);
+        tx.timeout = self.timeout;
+        tx.writer = self.writer.clone();
+        let tx = Arc::new(tx);
         self.active_transactions.write().insert(tx.id, tx.clone());
-        tx
+        Ok(tx)
     }
 
     /// Get active transaction by ID
@@ -314,13 +453,28 @@ impl TransactionManager {
         self.active_transactions.read().get(&tx_id).cloned()
     }
 
-    /// Clean up completed transactions
+    /// Stop tracking a committed or rolled-back transaction
+    pub fn remove(&self, tx_id: u64) -> Option<Arc<Transaction>> {
+        self.active_transactions.write().remove(&tx_id)
+    }
+
+    /// Clean up completed transactions, aborting any that have timed out
     pub fn cleanup(&self) {
+        self.cleanup_at(Instant::now());
+    }
+
+    fn cleanup_at(&self, now: Instant) {
         let mut transactions = self.active_transactions.write();
         transactions.retain(|_, tx| {
+            // Aborting drops the transaction's snapshot, unpinning its version
+            let _ = tx.expire_at(now);
+
             let state = *tx.state.read();
             state == TransactionState::Active || state == TransactionState::Preparing
         });
+        drop(transactions);
+
+        self.mvcc.gc();
     }
 
     /// Get transaction statistics
@@ -425,4 +579,176 @@ mod tests {
         let commit_result = tx.commit();
         assert!(commit_result.is_err());
     }
+
+    #[test]
+    fn rollback_to_savepoint_discards_only_later_operations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let tx = Transaction::begin(
+            Arc::new(MVCCController::new()),
+            storage,
+            IsolationLevel::ReadCommitted,
+        );
+
+        tx.insert(make_doc(1, "kept")).unwrap();
+        tx.savepoint("step").unwrap();
+        tx.insert(make_doc(2, "discarded")).unwrap();
+        tx.update(DocId(1), make_doc(1, "overwritten")).unwrap();
+
+        tx.rollback_to("step").unwrap();
+        assert_eq!(tx.read(DocId(1)).unwrap().unwrap().fields, make_doc(1, "kept").fields);
+        assert!(!tx.write_set.read().contains_key(&DocId(2)));
+
+        // The savepoint survives a rollback and can be reused
+        tx.delete(DocId(1)).unwrap();
+        tx.rollback_to("step").unwrap();
+        assert!(tx.write_set.read().contains_key(&DocId(1)));
+
+        tx.release("step").unwrap();
+        assert!(matches!(tx.rollback_to("step").unwrap_err().kind, ErrorKind::NotFound));
+
+        let ops = tx.commit().unwrap();
+        assert_eq!(ops.len(), 1);
+        assert!(matches!(&ops[0], TransactionOp::Insert(doc) if doc.id == DocId(1)));
+    }
+
+    #[test]
+    fn cleanup_aborts_timed_out_transactions_and_unpins_their_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mut mvcc = MVCCController::new();
+        mvcc.max_versions = 1;
+        let mvcc = Arc::new(mvcc);
+        mvcc.create_snapshot(Vec::new());
+
+        let manager = TransactionManager::new(mvcc.clone(), storage)
+            .with_timeout(Duration::from_secs(30));
+        let tx = manager.begin_transaction(IsolationLevel::RepeatableRead).unwrap();
+        let pinned = tx.snapshot_version;
+
+        mvcc.create_snapshot(Vec::new());
+        assert!(mvcc.versions.read().contains_key(&pinned));
+
+        // Not yet expired
+        manager.cleanup_at(tx.started_at + Duration::from_secs(10));
+        assert_eq!(manager.stats().active, 1);
+
+        manager.cleanup_at(tx.started_at + Duration::from_secs(31));
+        assert_eq!(*tx.state.read(), TransactionState::Aborted);
+        assert_eq!(manager.stats().total, 0);
+        assert!(tx.insert(make_doc(1, "late")).is_err());
+        assert!(!mvcc.versions.read().contains_key(&pinned));
+    }
+
+    #[test]
+    fn expired_transaction_fails_on_use_without_waiting_for_cleanup() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mut mvcc = MVCCController::new();
+        mvcc.max_versions = 1;
+        let mvcc = Arc::new(mvcc);
+        mvcc.create_snapshot(Vec::new());
+
+        let manager = TransactionManager::new(mvcc.clone(), storage)
+            .with_timeout(Duration::from_secs(30));
+        let tx = manager.begin_transaction(IsolationLevel::RepeatableRead).unwrap();
+        let pinned = tx.snapshot_version;
+        mvcc.create_snapshot(Vec::new());
+
+        assert!(tx.expire_at(tx.started_at + Duration::from_secs(10)).is_ok());
+        assert!(tx.expire_at(tx.started_at + Duration::from_secs(31)).is_err());
+        assert_eq!(*tx.state.read(), TransactionState::Aborted);
+        assert!(tx.read(DocId(1)).is_err());
+        assert!(tx.commit().is_err());
+
+        mvcc.gc();
+        assert!(!mvcc.versions.read().contains_key(&pinned));
+    }
+
+    #[test]
+    fn read_and_commit_abort_a_transaction_past_its_timeout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        mvcc.create_snapshot(Vec::new());
+
+        let manager = TransactionManager::new(mvcc, storage).with_timeout(Duration::from_millis(1));
+        let reader = manager.begin_transaction(IsolationLevel::RepeatableRead).unwrap();
+        let writer = manager.begin_transaction(IsolationLevel::RepeatableRead).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        let err = reader.read(DocId(1)).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        let err = writer.commit().unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert_eq!(*writer.state.read(), TransactionState::Aborted);
+    }
+
+    #[test]
+    fn conflict_error_names_the_changed_document() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone());
+
+        writer.add_document(make_doc(1, "contested")).unwrap();
+        writer.add_document(make_doc(2, "untouched")).unwrap();
+        writer.commit().unwrap();
+
+        let tx = Transaction::begin(mvcc.clone(), storage, IsolationLevel::Serializable);
+        tx.read(DocId(1)).unwrap();
+        tx.read(DocId(2)).unwrap();
+        tx.insert(make_doc(3, "new")).unwrap();
+
+        // A delete moves the snapshot on without a second segment to merge
+        writer.delete_document(DocId(1)).unwrap();
+        writer.commit().unwrap();
+
+        let err = tx.commit().unwrap_err();
+        let ErrorKind::Conflict(conflicts) = &err.kind else {
+            panic!("expected a conflict, got {:?}", err.kind);
+        };
+        assert_eq!(
+            conflicts,
+            &vec![Conflict {
+                doc_id: DocId(1),
+                read_version: tx.snapshot_version,
+                current_version: mvcc.current_snapshot().version,
+            }]
+        );
+        assert!(err.context.contains("[1]"));
+    }
+
+    #[test]
+    fn repeatable_read_ignores_concurrent_insert_while_read_committed_sees_it() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mut mvcc = MVCCController::new();
+        mvcc.max_versions = 1;
+        let mvcc = Arc::new(mvcc);
+        let mut writer = make_writer(storage.clone(), mvcc.clone());
+
+        writer.add_document(make_doc(1, "existing")).unwrap();
+        writer.commit().unwrap();
+
+        let repeatable =
+            Transaction::begin(mvcc.clone(), storage.clone(), IsolationLevel::RepeatableRead);
+        let committed = Transaction::begin(mvcc.clone(), storage, IsolationLevel::ReadCommitted);
+        assert!(repeatable.read(DocId(2)).unwrap().is_none());
+        assert!(committed.read(DocId(2)).unwrap().is_none());
+
+        writer.add_document(make_doc(2, "concurrent")).unwrap();
+        writer.commit().unwrap();
+        mvcc.gc();
+
+        // The begin snapshot stays pinned past GC
+        assert!(mvcc.versions.read().contains_key(&repeatable.snapshot_version));
+        assert!(repeatable.read(DocId(1)).unwrap().is_some());
+        assert!(repeatable.read(DocId(2)).unwrap().is_none());
+        assert!(committed.read(DocId(2)).unwrap().is_some());
+
+        repeatable.rollback().unwrap();
+        mvcc.gc();
+        assert!(!mvcc.versions.read().contains_key(&repeatable.snapshot_version));
+    }
 }
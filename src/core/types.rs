@@ -50,4 +50,21 @@ impl Document {
     pub fn get_field(&self, name: &str) -> Option<&FieldValue> {
         self.fields.get(name)
     }
+
+    /// Rough in-memory size used for memory accounting and batching decisions
+    pub fn estimated_size(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|(k, v)| {
+                k.len()
+                    + match v {
+                        FieldValue::Text(s) => s.len(),
+                        FieldValue::Number(_) => 8,
+                        FieldValue::Date(_) => 8,
+                        FieldValue::Boolean(_) => 1,
+                    }
+            })
+            .sum::<usize>()
+            + 100
+    }
 }
\ No newline at end of file
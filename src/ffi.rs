@@ -0,0 +1,298 @@
+//! C ABI for embedding the index from other languages (requires the `ffi` feature).
+//!
+//! Handles are opaque pointers returned by `drusdenx_open` and released with
+//! `drusdenx_close`. Functions never unwind across the boundary: errors and
+//! panics are turned into a failure return value, and the message is kept in
+//! a thread-local slot readable through `drusdenx_last_error`.
+//! The matching declarations live in `include/drusdenx.h`.
+
+use crate::core::config::Config;
+use crate::core::error::{Error, ErrorKind, Result};
+use crate::core::facade::SearchIndex;
+use crate::core::types::Document;
+use crate::schema::schema::SchemaWithAnalyzer;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::PathBuf;
+use std::ptr;
+
+/// Opaque handle handed out to C callers
+pub struct DrusdenxIndex {
+    index: SearchIndex,
+}
+
+/// JSON body accepted by `drusdenx_search`
+#[derive(Debug, Deserialize)]
+struct SearchRequest {
+    query: String,
+    limit: Option<usize>,
+}
+
+/// One hit in the JSON array returned by `drusdenx_search`
+#[derive(Debug, Serialize)]
+struct SearchHit {
+    id: u64,
+    score: f32,
+    document: Option<Document>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Interior NULs would truncate the message on the C side
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Run `f`, converting errors and panics into `on_error` plus a last-error message
+fn guard<T>(on_error: T, f: impl FnOnce() -> Result<T>) -> T {
+    clear_last_error();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            on_error
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("Internal: panic: {}", message));
+            on_error
+        }
+    }
+}
+
+/// Borrow a NUL-terminated UTF-8 string from C
+///
+/// # Safety
+/// `s` must be null or point to a valid NUL-terminated string.
+unsafe fn read_str<'a>(s: *const c_char, what: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(Error::new(ErrorKind::InvalidArgument, format!("{} is null", what)));
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|e| Error::new(ErrorKind::InvalidArgument, format!("{} is not UTF-8: {}", what, e)))
+}
+
+/// # Safety
+/// `handle` must be null or a pointer returned by `drusdenx_open` that has not been closed.
+unsafe fn read_handle<'a>(handle: *const DrusdenxIndex) -> Result<&'a DrusdenxIndex> {
+    unsafe { handle.as_ref() }
+        .ok_or_else(|| Error::new(ErrorKind::InvalidArgument, "index handle is null".to_string()))
+}
+
+/// Open (or create) an index stored at `path`. Returns null on failure.
+///
+/// # Safety
+/// `path` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_open(path: *const c_char) -> *mut DrusdenxIndex {
+    guard(ptr::null_mut(), || {
+        let path = unsafe { read_str(path, "path") }?;
+        let config = Config {
+            storage_path: PathBuf::from(path),
+            ..Config::default()
+        };
+        let index = SearchIndex::open(SchemaWithAnalyzer::new(), config)?;
+        Ok(Box::into_raw(Box::new(DrusdenxIndex { index })))
+    })
+}
+
+/// Close an index handle. Passing null is a no-op.
+///
+/// # Safety
+/// `handle` must be null or a pointer returned by `drusdenx_open`, and must
+/// not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_close(handle: *mut DrusdenxIndex) {
+    if handle.is_null() {
+        return;
+    }
+    guard((), || {
+        drop(unsafe { Box::from_raw(handle) });
+        Ok(())
+    })
+}
+
+/// Add a document given as JSON, e.g.
+/// `{"id": 1, "fields": {"title": {"Text": "hello"}}}`. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `handle` must be a live handle and `json` a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_add_document(
+    handle: *mut DrusdenxIndex,
+    json: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let handle = unsafe { read_handle(handle) }?;
+        let json = unsafe { read_str(json, "document") }?;
+        let doc: Document = serde_json::from_str(json)
+            .map_err(|e| Error::new(ErrorKind::Parse, format!("Invalid document JSON: {}", e)))?;
+        handle.index.add_document(doc)?;
+        Ok(0)
+    })
+}
+
+/// Flush buffered documents so they become searchable. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `handle` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_flush(handle: *mut DrusdenxIndex) -> c_int {
+    guard(-1, || {
+        unsafe { read_handle(handle) }?.index.flush()?;
+        Ok(0)
+    })
+}
+
+/// Run a search given as JSON (`{"query": "...", "limit": 10}`, `limit` optional).
+///
+/// Returns a JSON array of `{"id", "score", "document"}` objects that must be
+/// released with `drusdenx_string_free`, or null on failure.
+///
+/// # Safety
+/// `handle` must be a live handle and `request` a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_search(
+    handle: *mut DrusdenxIndex,
+    request: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let handle = unsafe { read_handle(handle) }?;
+        let request = unsafe { read_str(request, "request") }?;
+        let request: SearchRequest = serde_json::from_str(request)
+            .map_err(|e| Error::new(ErrorKind::Parse, format!("Invalid search JSON: {}", e)))?;
+
+        let hits = match request.limit {
+            Some(limit) => handle.index.search_with_limit(&request.query, limit)?,
+            None => handle.index.search(&request.query)?,
+        };
+        let hits: Vec<SearchHit> = hits
+            .into_iter()
+            .map(|hit| SearchHit {
+                id: hit.doc_id.0,
+                score: hit.score,
+                document: hit.document,
+            })
+            .collect();
+
+        let json = serde_json::to_string(&hits)
+            .map_err(|e| Error::new(ErrorKind::Internal, format!("Failed to encode results: {}", e)))?;
+        let json = CString::new(json)
+            .map_err(|e| Error::new(ErrorKind::Internal, format!("Results contain NUL: {}", e)))?;
+        Ok(json.into_raw())
+    })
+}
+
+/// Release a string returned by `drusdenx_search`. Passing null is a no-op.
+///
+/// # Safety
+/// `s` must be null or a pointer returned by this library, freed at most once.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn drusdenx_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Message of the last failed call on this thread, or null if it succeeded.
+///
+/// The pointer stays valid until the next `drusdenx_*` call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn drusdenx_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let message = drusdenx_last_error();
+        (!message.is_null())
+            .then(|| unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn round_trip_through_c_abi() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = CString::new(temp_dir.path().to_str().unwrap()).unwrap();
+
+        unsafe {
+            let handle = drusdenx_open(path.as_ptr());
+            assert!(!handle.is_null(), "open failed: {:?}", last_error());
+
+            for (id, text) in [(1, "rust ffi bindings"), (2, "python wrapper"), (3, "rust core")] {
+                let doc = CString::new(format!(
+                    r#"{{"id": {}, "fields": {{"content": {{"Text": "{}"}}}}}}"#,
+                    id, text
+                ))
+                .unwrap();
+                assert_eq!(drusdenx_add_document(handle, doc.as_ptr()), 0, "{:?}", last_error());
+            }
+            assert_eq!(drusdenx_flush(handle), 0);
+
+            let request = CString::new(r#"{"query": "rust", "limit": 10}"#).unwrap();
+            let results = drusdenx_search(handle, request.as_ptr());
+            assert!(!results.is_null(), "search failed: {:?}", last_error());
+            let json: serde_json::Value =
+                serde_json::from_str(CStr::from_ptr(results).to_str().unwrap()).unwrap();
+            drusdenx_string_free(results);
+
+            let mut ids: Vec<u64> = json
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|hit| hit["id"].as_u64().unwrap())
+                .collect();
+            ids.sort();
+            assert_eq!(ids, vec![1, 3]);
+
+            drusdenx_close(handle);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported_instead_of_unwinding() {
+        unsafe {
+            assert!(drusdenx_open(ptr::null()).is_null());
+            assert!(last_error().unwrap().contains("path is null"));
+
+            let request = CString::new(r#"{"query": "x"}"#).unwrap();
+            assert!(drusdenx_search(ptr::null_mut(), request.as_ptr()).is_null());
+            assert!(last_error().unwrap().contains("handle is null"));
+
+            let temp_dir = tempfile::tempdir().unwrap();
+            let path = CString::new(temp_dir.path().to_str().unwrap()).unwrap();
+            let handle = drusdenx_open(path.as_ptr());
+            assert!(last_error().is_none());
+
+            let bad = CString::new("{not json").unwrap();
+            assert_eq!(drusdenx_add_document(handle, bad.as_ptr()), -1);
+            assert!(last_error().unwrap().contains("Invalid document JSON"));
+
+            drusdenx_close(handle);
+            drusdenx_close(ptr::null_mut());
+        }
+
+        assert_eq!(guard(7, || -> Result<i32> { panic!("boom") }), 7);
+        assert!(last_error().unwrap().contains("boom"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::core::error::Result;
+use crate::index::index_reader::IndexReader;
+use crate::index::inverted::Term;
+use crate::index::posting::Posting;
+use crate::storage::layout::StorageLayout;
+use crate::storage::manifest::write_durably;
+use crate::storage::segment::SegmentId;
+
+/// A segment holding a term, and how many of its documents contain it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentTermInfo {
+    pub segment_id: SegmentId,
+    pub doc_freq: u32,
+}
+
+/// Term → segments containing it, across every published segment.
+///
+/// Kept up to date as segments are flushed, merged and compacted, so
+/// resolving a term is a single lookup instead of probing the `.idx` of
+/// every segment. Saved alongside the manifest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlobalTermDictionary {
+    terms: HashMap<Term, Vec<SegmentTermInfo>>,
+    segments: HashSet<SegmentId>, // Segments whose terms are recorded
+}
+
+impl GlobalTermDictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the saved dictionary for the `live` segments. Dropped segments
+    /// are forgotten; live ones it has never seen are read from their `.idx`.
+    pub fn load(storage: &StorageLayout, live: &HashSet<SegmentId>) -> Result<Self> {
+        let path = storage.term_dictionary_path();
+        let mut dictionary: Self = if storage.backend().exists(&path) {
+            bincode::deserialize(&storage.backend().read(&path)?)?
+        } else {
+            Self::new()
+        };
+        dictionary.retain_segments(|id| live.contains(id));
+
+        for &segment_id in live {
+            if !dictionary.segments.contains(&segment_id) {
+                let index = IndexReader::open(storage, segment_id)?;
+                dictionary.add_segment(
+                    segment_id,
+                    index.inverted_index.iter().map(|(term, postings)| (term.clone(), postings.len() as u32)),
+                );
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Durably replace the saved dictionary
+    pub fn save(&self, storage: &StorageLayout) -> Result<()> {
+        write_durably(storage, &storage.term_dictionary_path(), &bincode::serialize(self)?)
+    }
+
+    /// Record the terms, with their document frequencies, of a newly published segment
+    pub fn add_segment<I>(&mut self, segment_id: SegmentId, terms: I)
+    where
+        I: IntoIterator<Item = (Term, u32)>,
+    {
+        self.segments.insert(segment_id);
+        for (term, doc_freq) in terms {
+            self.terms.entry(term).or_default().push(SegmentTermInfo { segment_id, doc_freq });
+        }
+    }
+
+    /// Forget segments that were merged away or compacted
+    pub fn remove_segments(&mut self, segment_ids: &[SegmentId]) {
+        self.retain_segments(|id| !segment_ids.contains(id));
+    }
+
+    fn retain_segments(&mut self, mut keep: impl FnMut(&SegmentId) -> bool) {
+        self.segments.retain(|id| keep(id));
+        self.terms.retain(|_, segments| {
+            segments.retain(|info| keep(&info.segment_id));
+            !segments.is_empty()
+        });
+    }
+
+    /// Segments containing `term` (empty if none do)
+    pub fn segments_for(&self, term: &Term) -> &[SegmentTermInfo] {
+        self.terms.get(term).map_or(&[], Vec::as_slice)
+    }
+
+    /// Documents containing `term` across all segments
+    pub fn doc_freq(&self, term: &Term) -> u32 {
+        self.segments_for(term).iter().map(|info| info.doc_freq).sum()
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Postings of `term`, reading only the segments that contain it.
+    /// `load` fetches one segment's postings for the term.
+    pub fn postings<F>(&self, term: &Term, mut load: F) -> Result<Vec<(SegmentId, Vec<Posting>)>>
+    where
+        F: FnMut(SegmentId, &Term) -> Result<Option<Vec<Posting>>>,
+    {
+        let mut found = Vec::new();
+        for info in self.segments_for(term) {
+            if let Some(postings) = load(info.segment_id, term)? {
+                found.push((info.segment_id, postings));
+            }
+        }
+        Ok(found)
+    }
+}
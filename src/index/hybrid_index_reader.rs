@@ -47,8 +47,8 @@ impl HybridIndexReader {
         let actual_strategy = match strategy {
             LoadingStrategy::Adaptive => {
                 // Auto-select based on file size
-                if let Ok(metadata) = std::fs::metadata(&index_path) {
-                    let size_mb = metadata.len() / (1024 * 1024);
+                if let Ok(len) = storage.backend().open(&index_path).and_then(|file| Ok(file.size()?)) {
+                    let size_mb = len / (1024 * 1024);
                     if size_mb < 50 {
                         LoadingStrategy::Eager  // < 50MB → load all
                     } else {
@@ -96,10 +96,10 @@ impl HybridIndexReader {
     }
     
     /// Get all terms
-    pub fn terms(&self) -> Vec<Term> {
+    pub fn terms(&self) -> Result<Vec<Term>> {
         match self {
             HybridIndexReader::Eager(reader) => {
-                reader.terms().into_iter().cloned().collect()
+                Ok(reader.terms().into_iter().cloned().collect())
             },
             HybridIndexReader::Lazy(reader) => {
                 reader.terms()
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use serde::{Deserialize, Serialize};
+use crate::compression::compress::{CompressedBlock, CompressionType};
+use crate::core::error::{Error, ErrorKind, Result};
+use crate::index::inverted::Term;
+use crate::index::posting::Posting;
+
+/// Layout of a segment's `.idx` file:
+///
+/// ```text
+/// [postings block]*  [dictionary block]*  [block index]  [footer]
+/// ```
+///
+/// Each term's postings are their own compressed block, so one term can be
+/// read without touching the others. Terms are written in sorted order and
+/// the dictionary mapping them to their postings is split into blocks of
+/// `DICTIONARY_BLOCK_TERMS` entries. The block index holds only the first
+/// term of each dictionary block, so opening a file reads one small block
+/// and a lookup reads one dictionary block. The fixed-size footer at the end
+/// of the file locates the block index.
+///
+/// Older files are still read: `DIRECTORY_MAGIC` files end in one directory
+/// of every term, and files without a footer magic are a single compressed
+/// block of the whole index.
+pub const INDEX_FILE_MAGIC: u32 = 0x3258_4449; // "IDX2"
+
+/// Footer magic of files whose directory lists every term
+pub const DIRECTORY_MAGIC: u32 = 0x3158_4449; // "IDX1"
+
+/// Directory offset (u64) + directory length (u64) + magic (u32)
+pub const FOOTER_SIZE: u64 = 20;
+
+/// Terms per dictionary block
+pub const DICTIONARY_BLOCK_TERMS: usize = 128;
+
+/// Where a term's postings block (or a dictionary block) lives in the `.idx` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermOffset {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Sparse index over the dictionary blocks: the first term of each block
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockIndex {
+    pub term_count: u64,
+    pub blocks: Vec<(Term, TermOffset)>,
+}
+
+impl BlockIndex {
+    /// The dictionary block that would hold `term`, if any can
+    pub fn block_for(&self, term: &Term) -> Option<TermOffset> {
+        let after = self.blocks.partition_point(|(first, _)| first <= term);
+        after.checked_sub(1).map(|i| self.blocks[i].1)
+    }
+}
+
+/// The term directory found at the end of a `.idx` file
+#[derive(Debug, Clone)]
+pub enum TermDirectory {
+    /// Every term's postings location (files written before dictionary blocks)
+    Full(HashMap<Term, TermOffset>),
+    /// First term of each dictionary block
+    Blocks(BlockIndex),
+}
+
+/// Write `index` in the block-per-term layout. Postings must already be
+/// sorted by doc id.
+pub fn write_index<W: Write>(
+    out: &mut W,
+    index: &HashMap<Term, Vec<Posting>>,
+    compression: CompressionType,
+    level: Option<i32>,
+) -> Result<()> {
+    let mut terms: Vec<&Term> = index.keys().collect();
+    terms.sort_unstable();
+
+    let mut offset = 0u64;
+    let mut write_block = |out: &mut W, data: &[u8]| -> Result<TermOffset> {
+        let block = CompressedBlock::compress_with_level(data, compression, level)?;
+        let data = bincode::serialize(&block)?;
+        out.write_all(&data)?;
+        let location = TermOffset { offset, length: data.len() as u64 };
+        offset += location.length;
+        Ok(location)
+    };
+
+    let mut dictionary = Vec::with_capacity(terms.len());
+    for term in terms {
+        let location = write_block(out, &bincode::serialize(&index[term])?)?;
+        dictionary.push((term.clone(), location));
+    }
+
+    let mut block_index = BlockIndex {
+        term_count: dictionary.len() as u64,
+        blocks: Vec::with_capacity(dictionary.len().div_ceil(DICTIONARY_BLOCK_TERMS)),
+    };
+    for entries in dictionary.chunks(DICTIONARY_BLOCK_TERMS) {
+        let location = write_block(out, &bincode::serialize(entries)?)?;
+        block_index.blocks.push((entries[0].0.clone(), location));
+    }
+
+    let location = write_block(out, &bincode::serialize(&block_index)?)?;
+    out.write_all(&location.offset.to_le_bytes())?;
+    out.write_all(&location.length.to_le_bytes())?;
+    out.write_all(&INDEX_FILE_MAGIC.to_le_bytes())?;
+    Ok(())
+}
+
+/// Read only the term directory of a `.idx` file of `len` bytes: the block
+/// index, or the full directory of an older file.
+/// `None` for files in the old single-block layout.
+pub fn read_directory<R: Read + Seek>(file: &mut R, len: u64) -> Result<Option<TermDirectory>> {
+    if len < FOOTER_SIZE {
+        return Ok(None);
+    }
+    let mut footer = [0u8; FOOTER_SIZE as usize];
+    file.seek(SeekFrom::Start(len - FOOTER_SIZE))?;
+    file.read_exact(&mut footer)?;
+    let magic = u32::from_le_bytes(footer[16..20].try_into().unwrap());
+    if magic != INDEX_FILE_MAGIC && magic != DIRECTORY_MAGIC {
+        return Ok(None);
+    }
+
+    let location = TermOffset {
+        offset: u64::from_le_bytes(footer[0..8].try_into().unwrap()),
+        length: u64::from_le_bytes(footer[8..16].try_into().unwrap()),
+    };
+    if location.offset + location.length > len - FOOTER_SIZE {
+        return Err(Error::new(
+            ErrorKind::Parse,
+            "Index file directory lies outside the file".to_string(),
+        ));
+    }
+    if magic == DIRECTORY_MAGIC {
+        let directory: Vec<(Term, TermOffset)> = read_block(file, location)?;
+        return Ok(Some(TermDirectory::Full(directory.into_iter().collect())));
+    }
+    Ok(Some(TermDirectory::Blocks(read_block(file, location)?)))
+}
+
+/// Read one dictionary block: its terms, in order, with their postings locations
+pub fn read_dictionary_block<R: Read + Seek>(file: &mut R, location: TermOffset) -> Result<Vec<(Term, TermOffset)>> {
+    read_block(file, location)
+}
+
+/// Read one term's postings block
+pub fn read_postings<R: Read + Seek>(file: &mut R, location: TermOffset) -> Result<Vec<Posting>> {
+    read_block(file, location)
+}
+
+/// Decode a whole `.idx` file, in any layout
+pub fn decode_index(data: &[u8]) -> Result<HashMap<Term, Vec<Posting>>> {
+    let mut cursor = std::io::Cursor::new(data);
+    let directory = match read_directory(&mut cursor, data.len() as u64)? {
+        Some(TermDirectory::Full(directory)) => directory,
+        Some(TermDirectory::Blocks(block_index)) => {
+            let mut directory = HashMap::with_capacity(block_index.term_count as usize);
+            for (_, location) in &block_index.blocks {
+                directory.extend(read_dictionary_block(&mut cursor, *location)?);
+            }
+            directory
+        }
+        None => {
+            let block: CompressedBlock = bincode::deserialize(data)?;
+            return Ok(bincode::deserialize(&block.decompress()?)?);
+        }
+    };
+
+    directory
+        .into_iter()
+        .map(|(term, location)| Ok((term, read_postings(&mut cursor, location)?)))
+        .collect()
+}
+
+fn read_block<R: Read + Seek, T: serde::de::DeserializeOwned>(file: &mut R, location: TermOffset) -> Result<T> {
+    let mut data = vec![0u8; location.length as usize];
+    file.seek(SeekFrom::Start(location.offset))?;
+    file.read_exact(&mut data)?;
+    let block: CompressedBlock = bincode::deserialize(&data)?;
+    Ok(bincode::deserialize(&block.decompress()?)?)
+}
@@ -1,7 +1,5 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use crate::compression::compress::CompressedBlock;
+use crate::index::index_file;
 use crate::index::inverted::Term;
 use crate::index::posting::Posting;
 use crate::storage::layout::StorageLayout;
@@ -20,7 +18,7 @@ impl IndexReader {
         let index_path = storage.index_path(&segment_id);
         
         // Check if index file exists
-        if !index_path.exists() {
+        if !storage.backend().exists(&index_path) {
             // Return empty index if file doesn't exist
             return Ok(IndexReader {
                 segment_id,
@@ -28,19 +26,9 @@ impl IndexReader {
             });
         }
         
-        // Read compressed index file
-        let mut index_file = File::open(index_path)?;
-        let mut compressed_block_data = Vec::new();
-        index_file.read_to_end(&mut compressed_block_data)?;
-        
-        // Deserialize CompressedBlock
-        let compressed_block: CompressedBlock = bincode::deserialize(&compressed_block_data)?;
-        
-        // Decompress
-        let decompressed = CompressedBlock::decompress(&compressed_block)?;
-        
-        // Deserialize inverted index
-        let inverted_index: HashMap<Term, Vec<Posting>> = bincode::deserialize(&decompressed)?;
+        // Read and decode every term's postings
+        let data = storage.backend().read(&index_path)?;
+        let inverted_index = index_file::decode_index(&data)?;
         
         Ok(IndexReader {
             segment_id,
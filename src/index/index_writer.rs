@@ -49,7 +49,7 @@ impl IndexWriter {
                     doc_id: doc.id,
                     term_freq: positions.len() as u32,
                     positions: positions.into_iter().map(|p| p as u32).collect(),
-                    field_norm: 1.0 / (indexed_doc.terms.len() as f32).sqrt(),
+                    field_norm: indexed_doc.field_norm(),
                 };
                 
                 self.inverted_index
@@ -57,6 +57,12 @@ impl IndexWriter {
                     .or_insert_with(Vec::new)
                     .push(posting);
             }
+            for term in &indexed_doc.numeric_terms {
+                self.inverted_index
+                    .entry(term.clone())
+                    .or_default()
+                    .push(indexed_doc.numeric_posting());
+            }
         }
         
         Ok(())
@@ -1,14 +1,23 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use regex::Regex;
+use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
 use crate::analysis::token::Token;
 use crate::core::error::{Error, ErrorKind, Result};
 use crate::core::types::DocId;
+use crate::index::numeric::{indexed_number, numeric_field_prefix, numeric_term_text, NUMERIC_TERM_MARKER};
+use crate::query::ast::RangeQuery;
+use std::ops::Bound;
 use crate::core::utils::levenshtein_distance;
+use crate::index::index_reader::IndexReader;
 use crate::index::posting::{Posting, PostingList};
+use crate::scoring::scorer::IdfFormula;
 use crate::index::skiplist::SkipList;
 use crate::search::prefix::PrefixIndex;
 use crate::simd::operation::SimdOps;
+use crate::storage::layout::StorageLayout;
+use crate::storage::segment::Segment;
 
 /// Index statistics for scoring and monitoring
 #[derive(Debug, Clone)]
@@ -20,7 +29,7 @@ pub struct IndexStats {
 }
 
 /// Term representation
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Term(Vec<u8>);
 
 impl Term {
@@ -28,6 +37,16 @@ impl Term {
         Term(text.as_bytes().to_vec())
     }
 
+    /// Sortable term for a number or date stored in `field`
+    pub fn numeric(field: &str, value: f64) -> Self {
+        Term::new(&numeric_term_text(field, value))
+    }
+
+    /// Whether this term encodes a number rather than an analyzed word
+    pub fn is_numeric(&self) -> bool {
+        self.0.first() == Some(&(NUMERIC_TERM_MARKER as u8))
+    }
+
     pub fn as_str(&self) -> Result<&str> {
         std::str::from_utf8(&self.0)
             .map_err(|_| Error::new(ErrorKind::Parse, "Invalid UTF-8 in term".to_string()))
@@ -56,6 +75,61 @@ impl InvertedIndex {
         }
     }
 
+    /// Rebuild the index from the `.idx` files of `segments`, e.g. when a
+    /// database is reopened. A document stored in several segments keeps
+    /// only the postings of the last one listed (the newest copy), so terms
+    /// an update removed are gone; `deleted_docs` get no postings at all.
+    /// No segments yields an empty index.
+    pub fn load_from_segments(
+        storage: &StorageLayout,
+        segments: &[Arc<Segment>],
+        deleted_docs: &RoaringBitmap,
+    ) -> Result<Self> {
+        let indexes = segments
+            .iter()
+            .map(|segment| Ok(IndexReader::open(storage, segment.id)?.inverted_index))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The newest segment holding each live document
+        let mut owners: HashMap<DocId, usize> = HashMap::new();
+        for (ordinal, segment_index) in indexes.iter().enumerate() {
+            for posting in segment_index.values().flatten() {
+                if !deleted_docs.contains(posting.doc_id.0 as u32) {
+                    owners.insert(posting.doc_id, ordinal);
+                }
+            }
+        }
+
+        let mut merged: HashMap<Term, Vec<Posting>> = HashMap::new();
+        for (ordinal, segment_index) in indexes.into_iter().enumerate() {
+            for (term, postings) in segment_index {
+                let live = postings
+                    .into_iter()
+                    .filter(|posting| owners.get(&posting.doc_id) == Some(&ordinal));
+                merged.entry(term).or_default().extend(live);
+            }
+        }
+
+        let mut index = InvertedIndex::new();
+        for (term, mut postings) in merged {
+            if postings.is_empty() {
+                continue;
+            }
+            postings.sort_by_key(|p| p.doc_id);
+            for posting in &postings {
+                index.total_tokens += posting.term_freq as usize;
+            }
+
+            let posting_list = PostingList::new(postings)?;
+            index.dictionary.add_term(&term, posting_list.doc_freq());
+            index.skip_lists.insert(term.clone(), SkipList::build(&posting_list)?);
+            index.postings.insert(term, posting_list);
+        }
+        index.doc_count = owners.len();
+
+        Ok(index)
+    }
+
     pub fn build_prefix_index(&mut self) -> Result<()> {
         let terms_with_freq = self.dictionary.term_map.iter()
             .map(|(term, idx)| {
@@ -132,20 +206,63 @@ impl InvertedIndex {
         Ok(())
     }
     
-    /// Get current index statistics
-    pub fn stats(&self) -> IndexStats {
-        let unique_terms = self.dictionary.term_count();
-        let avg_doc_length = if self.doc_count > 0 {
+    /// Remove a document's postings, keeping `doc_count`/`total_tokens` in sync.
+    /// Returns `false` if the document was not indexed.
+    pub fn remove_document(&mut self, doc_id: DocId) -> Result<bool> {
+        let mut removed_tokens = 0usize;
+        let mut found = false;
+        let mut emptied = Vec::new();
+
+        for (term, list) in self.postings.iter_mut() {
+            let postings = list.iter()?;
+            let Some(pos) = postings.iter().position(|p| p.doc_id == doc_id) else {
+                continue;
+            };
+            found = true;
+
+            let mut remaining = postings;
+            removed_tokens += remaining.remove(pos).term_freq as usize;
+
+            if remaining.is_empty() {
+                emptied.push(term.clone());
+                continue;
+            }
+
+            *list = PostingList::new(remaining)?;
+            self.dictionary.add_term(term, list.doc_freq());
+            self.skip_lists.insert(term.clone(), SkipList::build(list)?);
+        }
+
+        for term in emptied {
+            self.postings.remove(&term);
+            self.skip_lists.remove(&term);
+            self.dictionary.add_term(&term, 0);
+        }
+
+        if found {
+            self.doc_count = self.doc_count.saturating_sub(1);
+            self.total_tokens = self.total_tokens.saturating_sub(removed_tokens);
+        }
+
+        Ok(found)
+    }
+
+    /// Average document length in tokens, from the cached counters (O(1))
+    pub fn avg_doc_length(&self) -> f32 {
+        if self.doc_count > 0 {
             self.total_tokens as f32 / self.doc_count as f32
         } else {
             0.0
-        };
-        
+        }
+    }
+
+    /// Get current index statistics
+    pub fn stats(&self) -> IndexStats {
         IndexStats {
             doc_count: self.doc_count,
             total_tokens: self.total_tokens,
-            unique_terms,
-            avg_doc_length,
+            unique_terms: self.dictionary.term_count(),
+            avg_doc_length: self.avg_doc_length(),
         }
     }
 
@@ -154,35 +271,28 @@ impl InvertedIndex {
             return Ok(Vec::new());
         }
 
-        // Get posting lists and convert to sorted arrays for SIMD operations
-        let mut sorted_arrays: Vec<Vec<u32>> = Vec::new();
-
+        // Resolve every posting list up front; a missing term empties the result
+        let mut lists: Vec<&PostingList> = Vec::with_capacity(terms.len());
         for term in terms {
-            if let Some(list) = self.postings.get(term) {
-                // Extract doc IDs as sorted u32 array
-                let doc_ids: Vec<u32> = list.iter()?
-                    .into_iter()
-                    .map(|posting| posting.doc_id.0 as u32)
-                    .collect();
-                sorted_arrays.push(doc_ids);
-            } else {
-                return Ok(Vec::new());  // Term not found
+            match self.postings.get(term) {
+                Some(list) => lists.push(list),
+                None => return Ok(Vec::new()), // Term not found
             }
         }
-        
-        if sorted_arrays.is_empty() {
-            return Ok(Vec::new());
-        }
 
-        // Use SIMD operations for fast intersection
-        let mut result = sorted_arrays[0].clone();
-        for i in 1..sorted_arrays.len() {
-            result = SimdOps::intersect_sorted(&result, &sorted_arrays[i]);
+        // Smallest list first so the running result shrinks as fast as possible
+        lists.sort_by_key(|list| list.len());
+
+        // Use SIMD operations for fast intersection; larger lists are only
+        // decoded while the running result is still non-empty
+        let mut result = lists[0].decode_doc_ids()?;
+        for list in &lists[1..] {
             if result.is_empty() {
                 break;
             }
+            result = SimdOps::intersect_sorted(&result, &list.decode_doc_ids()?);
         }
-        
+
         // Convert back to DocId
         Ok(result.into_iter().map(|id| DocId(id as u64)).collect())
     }
@@ -221,10 +331,69 @@ impl InvertedIndex {
         Ok(result.into_iter().map(|id| DocId(id as u64)).collect())
     }
     
+    /// Physically remove `deleted` documents and prune dictionary terms no
+    /// live document contains any more, so term scans (wildcard, fuzzy,
+    /// prefix) stop visiting them. Returns the number of pruned terms.
+    pub fn compact(&mut self, deleted: impl IntoIterator<Item = DocId>) -> Result<usize> {
+        for doc_id in deleted {
+            self.remove_document(doc_id)?;
+        }
+        let postings = &self.postings;
+        let pruned = self
+            .dictionary
+            .retain(|term, info| info.doc_freq > 0 && postings.contains_key(term));
+        if pruned > 0 && self.prefix_index.is_some() {
+            self.build_prefix_index()?;
+        }
+        Ok(pruned)
+    }
+
     pub fn search_term(&self, term: &Term) -> Option<&PostingList> {
         self.postings.get(term)
     }
 
+    /// Documents whose numeric or date `field` falls in the range, found from
+    /// the encoded terms alone without loading any document
+    pub fn numeric_range_docs(&self, q: &RangeQuery) -> Result<Vec<DocId>> {
+        let bound = |inclusive: &Option<_>, exclusive: &Option<_>| -> Result<Bound<Term>> {
+            let encode = |value| {
+                indexed_number(value).map(|n| Term::numeric(&q.field, n)).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Range bound on '{}' is not a number or date", q.field),
+                    )
+                })
+            };
+            Ok(match (inclusive, exclusive) {
+                (Some(value), _) => Bound::Included(encode(value)?),
+                (None, Some(value)) => Bound::Excluded(encode(value)?),
+                (None, None) => Bound::Unbounded,
+            })
+        };
+        let lower = bound(&q.gte, &q.gt)?;
+        let upper = bound(&q.lte, &q.lt)?;
+        let prefix = numeric_field_prefix(&q.field);
+
+        let terms: Vec<Term> = self
+            .postings
+            .keys()
+            .filter(|term| term.0.starts_with(prefix.as_bytes()))
+            .filter(|term| match &lower {
+                Bound::Included(low) => term.0 >= low.0,
+                Bound::Excluded(low) => term.0 > low.0,
+                Bound::Unbounded => true,
+            })
+            .filter(|term| match &upper {
+                Bound::Included(high) => term.0 <= high.0,
+                Bound::Excluded(high) => term.0 < high.0,
+                Bound::Unbounded => true,
+            })
+            .cloned()
+            .collect();
+
+        self.union_terms(&terms)
+    }
+
     /// Get an iterator over all terms in the index
     pub fn terms(&self) -> impl Iterator<Item = &Term> {
         self.postings.keys()
@@ -242,7 +411,7 @@ impl InvertedIndex {
 
         // Search through all terms in dictionary
         let mut matching_terms = Vec::new();
-        for term in self.dictionary.term_map.keys() {
+        for term in self.dictionary.term_map.keys().filter(|term| !term.is_numeric()) {
             let term_str = String::from_utf8_lossy(&term.0);
             if regex.is_match(&term_str) {
                 matching_terms.push(term_str.to_string());
@@ -263,7 +432,7 @@ impl InvertedIndex {
         };
 
         // Search through all terms in dictionary
-        for dict_term in self.dictionary.term_map.keys() {
+        for dict_term in self.dictionary.term_map.keys().filter(|term| !term.is_numeric()) {
             let dict_term_str = String::from_utf8_lossy(&dict_term.0);
 
             // Check prefix match if required
@@ -329,9 +498,13 @@ impl TermDictionary {
     }
 
     pub fn calculate_idf(&mut self, total_docs: usize) {
+        self.calculate_idf_with(total_docs, IdfFormula::Smoothed);
+    }
+
+    /// Recompute the stored `idf` of every term with the given formula
+    pub fn calculate_idf_with(&mut self, total_docs: usize, formula: IdfFormula) {
         for term_info in &mut self.term_infos {
-            // IDF = log(N / df) where N is total docs, df is doc frequency
-            term_info.idf = ((total_docs as f32 + 1.0) / (term_info.doc_freq as f32 + 1.0)).ln();
+            term_info.idf = formula.idf(term_info.doc_freq, total_docs);
         }
     }
 
@@ -350,4 +523,163 @@ impl TermDictionary {
     pub fn term_count(&self) -> usize {
         self.term_map.len()
     }
-}
\ No newline at end of file
+
+    /// Drop terms for which `keep` is false, re-packing `term_infos`.
+    /// Returns how many terms were dropped.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Term, &TermInfo) -> bool) -> usize {
+        let before = self.term_infos.len();
+        let mut entries: Vec<(Term, usize)> = self.term_map.drain().collect();
+        entries.sort_by_key(|(_, index)| *index);
+
+        let mut term_infos = Vec::with_capacity(entries.len());
+        for (term, index) in entries {
+            let info = &self.term_infos[index];
+            if keep(&term, info) {
+                self.term_map.insert(term, term_infos.len());
+                term_infos.push(info.clone());
+            }
+        }
+        self.term_infos = term_infos;
+        before - self.term_infos.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        text.split_whitespace()
+            .enumerate()
+            .map(|(i, word)| Token::new(word.to_string(), i as u32, 0))
+            .collect()
+    }
+
+    /// Recompute (doc_count, total_length) by scanning every posting list
+    fn scanned_stats(index: &InvertedIndex) -> (usize, usize) {
+        let mut docs = HashSet::new();
+        let mut total_length = 0usize;
+        for list in index.postings.values() {
+            for posting in list.iter().unwrap() {
+                docs.insert(posting.doc_id);
+                total_length += posting.term_freq as usize;
+            }
+        }
+        (docs.len(), total_length)
+    }
+
+    #[test]
+    fn cached_length_stats_match_scan_after_inserts_and_deletes() {
+        let mut index = InvertedIndex::new();
+        index.add_document(DocId(1), &tokens("rust is fast and rust is safe")).unwrap();
+        index.add_document(DocId(2), &tokens("search engines index text")).unwrap();
+        index.add_document(DocId(3), &tokens("rust search")).unwrap();
+
+        assert!(index.remove_document(DocId(2)).unwrap());
+        assert!(!index.remove_document(DocId(42)).unwrap());
+        index.add_document(DocId(4), &tokens("fast text search")).unwrap();
+        assert!(index.remove_document(DocId(1)).unwrap());
+
+        let (docs, total_length) = scanned_stats(&index);
+        assert_eq!(index.doc_count, docs);
+        assert_eq!(index.total_tokens, total_length);
+        assert_eq!(index.avg_doc_length(), total_length as f32 / docs as f32);
+        assert!(index.search_term(&Term::new("engines")).is_none());
+    }
+
+    #[test]
+    fn intersect_terms_is_order_independent() {
+        let mut index = InvertedIndex::new();
+        for id in 0..50u64 {
+            let text = if id % 10 == 0 { "common rare" } else { "common" };
+            index.add_document(DocId(id), &tokens(text)).unwrap();
+        }
+        let common = Term::new("common");
+        let rare = Term::new("rare");
+        let expected: Vec<DocId> = (0..50).step_by(10).map(DocId).collect();
+
+        assert_eq!(index.intersect_terms(&[common.clone(), rare.clone()]).unwrap(), expected);
+        assert_eq!(index.intersect_terms(&[rare.clone(), common.clone()]).unwrap(), expected);
+        assert!(
+            index
+                .intersect_terms(&[common, rare, Term::new("missing")])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn numeric_and_date_fields_are_range_searchable_from_the_index() {
+        use crate::analysis::analyzer::Analyzer;
+        use crate::core::types::{Document, FieldValue};
+        use crate::parallel::indexer::ParallelIndexer;
+        use chrono::{TimeZone, Utc};
+        use std::sync::Arc;
+
+        let docs: Vec<Document> = [(1, -12.5, 2021), (2, 0.0, 2022), (3, 7.0, 2023), (4, 99.9, 2024)]
+            .into_iter()
+            .map(|(id, price, year)| Document {
+                id: DocId(id),
+                fields: HashMap::from([
+                    ("title".to_string(), FieldValue::Text("rust book".to_string())),
+                    ("price".to_string(), FieldValue::Number(price)),
+                    (
+                        "published".to_string(),
+                        FieldValue::Date(Utc.with_ymd_and_hms(year, 6, 1, 0, 0, 0).unwrap()),
+                    ),
+                ]),
+            })
+            .collect();
+        let indexed = ParallelIndexer::new(2)
+            .index_batch(docs, &Arc::new(Analyzer::standard_english()))
+            .unwrap();
+        let mut index = InvertedIndex::new();
+        for doc in &indexed {
+            let numeric = doc.numeric_terms.iter().map(|term| Token::new(term.as_str().unwrap().to_string(), 0, 0));
+            let tokens: Vec<Token> = doc.tokens.iter().cloned().chain(numeric).collect();
+            index.add_document(doc.doc_id, &tokens).unwrap();
+        }
+
+        let range = |field: &str, gt, gte, lt, lte| RangeQuery {
+            field: field.to_string(),
+            gt,
+            gte,
+            lt,
+            lte,
+            boost: None,
+        };
+        let num = |n: f64| Some(FieldValue::Number(n));
+        let date = |year: i32| Some(FieldValue::Date(Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap()));
+        let ids = |docs: Vec<DocId>| docs.into_iter().map(|d| d.0).collect::<Vec<_>>();
+
+        assert_eq!(ids(index.numeric_range_docs(&range("price", None, num(-20.0), None, num(7.0))).unwrap()), vec![1, 2, 3]);
+        assert_eq!(ids(index.numeric_range_docs(&range("price", num(0.0), None, num(99.9), None)).unwrap()), vec![3]);
+        assert_eq!(ids(index.numeric_range_docs(&range("price", None, num(50.0), None, None)).unwrap()), vec![4]);
+        assert_eq!(ids(index.numeric_range_docs(&range("published", None, date(2022), None, date(2024))).unwrap()), vec![2, 3]);
+        // Numbers of one field never answer a range on another
+        assert!(index.numeric_range_docs(&range("year", None, None, None, None)).unwrap().is_empty());
+        // Encoded terms stay out of text term expansion
+        assert!(index.wildcard_search("*").unwrap().iter().all(|t| !t.starts_with('\0')));
+    }
+
+    #[test]
+    fn compact_prunes_terms_without_live_documents() {
+        let mut index = InvertedIndex::new();
+        index.add_document(DocId(1), &tokens("common zygote")).unwrap();
+        index.add_document(DocId(2), &tokens("common zymurgy")).unwrap();
+        index.add_document(DocId(3), &tokens("common words")).unwrap();
+        index.build_prefix_index().unwrap();
+
+        index.remove_document(DocId(1)).unwrap();
+        // Deletes alone leave the dead term in the dictionary
+        assert_eq!(index.wildcard_search("zy*").unwrap().len(), 2);
+
+        assert_eq!(index.compact([DocId(2)]).unwrap(), 2);
+        assert!(index.terms().all(|term| !term.as_str().unwrap().starts_with("zy")));
+        assert!(index.wildcard_search("zy*").unwrap().is_empty());
+        assert!(index.prefix_search("zy").unwrap().is_empty());
+        assert_eq!(index.dictionary.term_count(), 2);
+        assert_eq!(index.dictionary.get_term_info(&Term::new("common")).unwrap().doc_freq, 1);
+    }
+}
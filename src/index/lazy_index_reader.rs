@@ -1,89 +1,84 @@
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::sync::Arc;
 use parking_lot::Mutex;
 use lru::LruCache;
 use std::num::NonZeroUsize;
-use crate::compression::compress::CompressedBlock;
+use crate::index::index_file::{self, TermDirectory, TermOffset};
 use crate::index::inverted::Term;
 use crate::index::posting::Posting;
+use crate::storage::backend::StorageFile;
 use crate::storage::layout::StorageLayout;
 use crate::storage::segment::SegmentId;
 use crate::core::error::Result;
 
+/// Dictionary blocks kept decoded per reader
+const DICTIONARY_CACHE_BLOCKS: usize = 64;
+
+/// One decoded dictionary block: its terms in order, with their postings locations
+type DictionaryBlock = Arc<Vec<(Term, TermOffset)>>;
+
 /// Lazy loading index reader with LRU cache
 pub struct LazyIndexReader {
     pub segment_id: SegmentId,
-    term_offsets: HashMap<Term, TermOffset>,  // Term -> file offset
-    file: Arc<Mutex<File>>,
+    directory: TermDirectory,  // Sparse block index, or every term's location for older files
+    file: Option<Arc<Mutex<Box<dyn StorageFile>>>>,  // None when the segment has no index file
+    legacy: bool,  // Old single-block file: every lookup decodes the whole file
     cache: Arc<Mutex<LruCache<Term, Arc<Vec<Posting>>>>>,  // LRU cache for postings
+    blocks: Mutex<LruCache<u64, DictionaryBlock>>,  // Decoded dictionary blocks by file offset
     cache_hits: std::sync::atomic::AtomicU64,
     cache_misses: std::sync::atomic::AtomicU64,
-}
-
-#[derive(Clone)]
-#[allow(dead_code)]
-struct TermOffset {
-    offset: u64,
-    length: u64,
+    bytes_read: std::sync::atomic::AtomicU64,  // Read from the file to serve cache misses
 }
 
 impl LazyIndexReader {
-    /// Open index file and load only the term dictionary (lightweight)
+    /// Open index file and load only the sparse dictionary block index (lightweight)
     pub fn open(storage: &StorageLayout, segment_id: SegmentId, cache_size: usize) -> Result<Self> {
         let index_path = storage.index_path(&segment_id);
         
         // Check if index file exists
-        if !index_path.exists() {
+        if !storage.backend().exists(&index_path) {
             return Ok(LazyIndexReader {
                 segment_id,
-                term_offsets: HashMap::new(),
-                file: Arc::new(Mutex::new(File::open("/dev/null")?)),
+                directory: TermDirectory::Full(HashMap::new()),
+                file: None,
+                legacy: false,
                 cache: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(1).unwrap()))),
+                blocks: Mutex::new(LruCache::new(NonZeroUsize::new(1).unwrap())),
                 cache_hits: std::sync::atomic::AtomicU64::new(0),
                 cache_misses: std::sync::atomic::AtomicU64::new(0),
+                bytes_read: std::sync::atomic::AtomicU64::new(0),
             });
         }
         
-        // Read the full index file (we'll optimize this later)
-        let mut index_file = File::open(&index_path)?;
-        let mut compressed_block_data = Vec::new();
-        index_file.read_to_end(&mut compressed_block_data)?;
-        
-        // Deserialize and decompress
-        let compressed_block: CompressedBlock = bincode::deserialize(&compressed_block_data)?;
-        let decompressed = CompressedBlock::decompress(&compressed_block)?;
-        
-        // Deserialize to get full index
-        let full_index: HashMap<Term, Vec<Posting>> = bincode::deserialize(&decompressed)?;
-        
-        // Build term offsets dictionary (for now, we'll store serialized data per term)
-        let mut term_offsets = HashMap::new();
-        let mut term_data_map = HashMap::new();
-        
-        for (term, postings) in full_index.into_iter() {
-            // Serialize each term's postings
-            let serialized = bincode::serialize(&postings)?;
-            let offset = 0u64; // Will be used later with proper file format
-            let length = serialized.len() as u64;
-            
-            term_offsets.insert(term.clone(), TermOffset { offset, length });
-            term_data_map.insert(term, serialized);
-        }
-        
-        // Re-open file for seeking
-        let file = File::open(&index_path)?;
+        // Read only the footer and block index; dictionary blocks and postings stay on disk
+        let mut file = storage.backend().open(&index_path)?;
+        let len = file.size()?;
+        let (directory, legacy) = match index_file::read_directory(&mut file, len)? {
+            Some(directory) => (directory, false),
+            None => {
+                // Old layout has no directory: decode once just to list the terms
+                let whole = TermOffset { offset: 0, length: len };
+                let mut data = Vec::with_capacity(len as usize);
+                file.seek(SeekFrom::Start(0))?;
+                file.read_to_end(&mut data)?;
+                let terms = index_file::decode_index(&data)?.into_keys().map(|term| (term, whole));
+                (TermDirectory::Full(terms.collect()), true)
+            }
+        };
         
         Ok(LazyIndexReader {
             segment_id,
-            term_offsets,
-            file: Arc::new(Mutex::new(file)),
+            directory,
+            file: Some(Arc::new(Mutex::new(file))),
+            legacy,
             cache: Arc::new(Mutex::new(LruCache::new(
                 NonZeroUsize::new(cache_size).unwrap_or(NonZeroUsize::new(1000).unwrap())
             ))),
+            blocks: Mutex::new(LruCache::new(NonZeroUsize::new(DICTIONARY_CACHE_BLOCKS).unwrap())),
             cache_hits: std::sync::atomic::AtomicU64::new(0),
             cache_misses: std::sync::atomic::AtomicU64::new(0),
+            bytes_read: std::sync::atomic::AtomicU64::new(0),
         })
     }
     
@@ -98,13 +93,11 @@ impl LazyIndexReader {
             }
         }
         
-        // Cache miss - load from "disk" (currently from deserialized data)
+        // Cache miss - read the term's block from disk
         self.cache_misses.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         
-        if let Some(_term_offset) = self.term_offsets.get(term) {
-            // For now, we'll re-read the full index (temporary implementation)
-            // TODO: Implement proper offset-based reading
-            let postings = self.load_postings_for_term(term)?;
+        if let Some(term_offset) = self.locate(term)? {
+            let postings = self.load_postings_for_term(term, term_offset)?;
             
             if let Some(postings) = postings {
                 let arc_postings = Arc::new(postings);
@@ -120,30 +113,73 @@ impl LazyIndexReader {
         Ok(None)
     }
     
-    /// Load postings for a specific term from file
-    fn load_postings_for_term(&self, term: &Term) -> Result<Option<Vec<Posting>>> {
-        // Temporary: re-read full index (will optimize with proper file format later)
-        let mut file = self.file.lock();
-        file.seek(SeekFrom::Start(0))?;
-        
-        let mut compressed_block_data = Vec::new();
-        file.read_to_end(&mut compressed_block_data)?;
-        
-        let compressed_block: CompressedBlock = bincode::deserialize(&compressed_block_data)?;
-        let decompressed = CompressedBlock::decompress(&compressed_block)?;
-        let full_index: HashMap<Term, Vec<Posting>> = bincode::deserialize(&decompressed)?;
-        
-        Ok(full_index.get(term).cloned())
+    /// Find the postings block of `term`: binary-search the block index,
+    /// then scan the one dictionary block that can hold it
+    fn locate(&self, term: &Term) -> Result<Option<TermOffset>> {
+        let block_index = match &self.directory {
+            TermDirectory::Full(term_offsets) => return Ok(term_offsets.get(term).copied()),
+            TermDirectory::Blocks(block_index) => block_index,
+        };
+        let Some(location) = block_index.block_for(term) else {
+            return Ok(None);
+        };
+        let entries = self.dictionary_block(location)?;
+        Ok(entries.iter().find(|(t, _)| t == term).map(|&(_, location)| location))
+    }
+
+    /// Read a dictionary block, or take it from the block cache
+    fn dictionary_block(&self, location: TermOffset) -> Result<DictionaryBlock> {
+        if let Some(entries) = self.blocks.lock().get(&location.offset) {
+            return Ok(entries.clone());
+        }
+        let Some(file) = &self.file else {
+            return Ok(Arc::default());
+        };
+        let entries = {
+            let mut file = file.lock();
+            self.bytes_read.fetch_add(location.length, std::sync::atomic::Ordering::Relaxed);
+            Arc::new(index_file::read_dictionary_block(&mut *file, location)?)
+        };
+        self.blocks.lock().put(location.offset, entries.clone());
+        Ok(entries)
+    }
+
+    /// Load postings for a specific term from file: seek to its block and
+    /// read only that (old single-block files are decoded in full)
+    fn load_postings_for_term(&self, term: &Term, location: TermOffset) -> Result<Option<Vec<Posting>>> {
+        let Some(file) = &self.file else {
+            return Ok(None);
+        };
+        let mut file = file.lock();
+        self.bytes_read.fetch_add(location.length, std::sync::atomic::Ordering::Relaxed);
+
+        if self.legacy {
+            let mut data = Vec::with_capacity(location.length as usize);
+            file.seek(SeekFrom::Start(0))?;
+            file.read_to_end(&mut data)?;
+            return Ok(index_file::decode_index(&data)?.remove(term));
+        }
+        Ok(Some(index_file::read_postings(&mut *file, location)?))
     }
     
-    /// Check if term exists
+    /// Check if term exists (reads at most one dictionary block).
+    /// A dictionary block that cannot be read counts as not containing it.
     pub fn contains_term(&self, term: &Term) -> bool {
-        self.term_offsets.contains_key(term)
+        matches!(self.locate(term), Ok(Some(_)))
     }
     
-    /// Get all terms (from dictionary only - no loading needed)
-    pub fn terms(&self) -> Vec<Term> {
-        self.term_offsets.keys().cloned().collect()
+    /// Get all terms (from the dictionary blocks - no postings are loaded)
+    pub fn terms(&self) -> Result<Vec<Term>> {
+        match &self.directory {
+            TermDirectory::Full(term_offsets) => Ok(term_offsets.keys().cloned().collect()),
+            TermDirectory::Blocks(block_index) => {
+                let mut terms = Vec::with_capacity(block_index.term_count as usize);
+                for (_, location) in &block_index.blocks {
+                    terms.extend(self.dictionary_block(*location)?.iter().map(|(term, _)| term.clone()));
+                }
+                Ok(terms)
+            }
+        }
     }
     
     /// Get cache statistics
@@ -162,13 +198,17 @@ impl LazyIndexReader {
             misses,
             hit_rate,
             size: self.cache.lock().len(),
+            bytes_read: self.bytes_read.load(std::sync::atomic::Ordering::Relaxed),
         }
     }
     
     /// Get index statistics
     pub fn stats(&self) -> IndexStats {
         IndexStats {
-            unique_terms: self.term_offsets.len(),
+            unique_terms: match &self.directory {
+                TermDirectory::Full(term_offsets) => term_offsets.len(),
+                TermDirectory::Blocks(block_index) => block_index.term_count as usize,
+            },
         }
     }
 }
@@ -178,8 +218,115 @@ pub struct CacheStats {
     pub misses: u64,
     pub hit_rate: f64,
     pub size: usize,
+    pub bytes_read: u64,  // Index file bytes read for cache misses
 }
 
 pub struct IndexStats {
     pub unique_terms: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::compress::{CompressedBlock, CompressionType};
+    use crate::core::types::DocId;
+    use crate::memory::buffer_pool::BufferPool;
+    use crate::storage::segment_writer::SegmentWriter;
+
+    fn posting(doc_id: u64) -> Posting {
+        Posting {
+            doc_id: DocId(doc_id),
+            term_freq: 1,
+            positions: vec![0],
+            field_norm: 1.0,
+        }
+    }
+
+    fn index_with_terms(count: u64) -> HashMap<Term, Vec<Posting>> {
+        (0..count)
+            .map(|t| (Term::new(&format!("term{}", t)), (0..50).map(|d| posting(t * 100 + d)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn term_lookup_reads_only_its_block() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut writer = SegmentWriter::new(
+            &storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            CompressionType::LZ4,
+        )
+        .unwrap();
+        writer.inverted_index = index_with_terms(200);
+        let segment = writer.finish(&storage).unwrap();
+        let file_len = std::fs::metadata(storage.index_path(&segment.id)).unwrap().len();
+
+        let reader = LazyIndexReader::open(&storage, segment.id, 10).unwrap();
+        assert_eq!(reader.stats().unique_terms, 200);
+        let postings = reader.get_postings(&Term::new("term42")).unwrap().unwrap();
+        assert_eq!(postings.len(), 50);
+        assert_eq!(postings[0].doc_id, DocId(4200));
+
+        let bytes_read = reader.cache_stats().bytes_read;
+        assert!(bytes_read > 0);
+        assert!(bytes_read * 50 < file_len, "read {} of {} bytes", bytes_read, file_len);
+        assert!(reader.get_postings(&Term::new("missing")).unwrap().is_none());
+    }
+
+    #[test]
+    fn open_reads_only_the_block_index_of_a_large_dictionary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut writer = SegmentWriter::new(
+            &storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            CompressionType::LZ4,
+        )
+        .unwrap();
+        writer.inverted_index = (0..100_000u64)
+            .map(|t| (Term::new(&format!("term{:06}", t)), vec![posting(t)]))
+            .collect();
+        let segment = writer.finish(&storage).unwrap();
+
+        let reader = LazyIndexReader::open(&storage, segment.id, 10).unwrap();
+        assert_eq!(reader.stats().unique_terms, 100_000);
+        let TermDirectory::Blocks(block_index) = &reader.directory else {
+            panic!("expected a dictionary block index");
+        };
+        assert_eq!(block_index.blocks.len(), 100_000usize.div_ceil(index_file::DICTIONARY_BLOCK_TERMS));
+        assert_eq!(reader.cache_stats().bytes_read, 0);
+
+        assert!(reader.contains_term(&Term::new("term071234")));
+        let postings = reader.get_postings(&Term::new("term071234")).unwrap().unwrap();
+        assert_eq!(postings[0].doc_id, DocId(71_234));
+        assert!(!reader.contains_term(&Term::new("term071234x")));
+        assert!(!reader.contains_term(&Term::new("a")));
+        assert!(reader.get_postings(&Term::new("zzz")).unwrap().is_none());
+
+        // The first term of a block and the last term of the file resolve too
+        let first = Term::new(&format!("term{:06}", index_file::DICTIONARY_BLOCK_TERMS));
+        assert!(reader.contains_term(&first));
+        assert!(reader.contains_term(&Term::new("term099999")));
+        assert_eq!(reader.terms().unwrap().len(), 100_000);
+    }
+
+    #[test]
+    fn single_block_index_files_are_still_readable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let segment_id = SegmentId::new();
+        let index = index_with_terms(3);
+        let block = CompressedBlock::compress(&bincode::serialize(&index).unwrap(), CompressionType::LZ4).unwrap();
+        let index_path = storage.index_path(&segment_id);
+        std::fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        std::fs::write(&index_path, bincode::serialize(&block).unwrap()).unwrap();
+
+        let reader = LazyIndexReader::open(&storage, segment_id, 10).unwrap();
+        let postings = reader.get_postings(&Term::new("term1")).unwrap().unwrap();
+        let ids = |postings: &[Posting]| postings.iter().map(|p| p.doc_id).collect::<Vec<_>>();
+        assert_eq!(ids(&postings), ids(&index[&Term::new("term1")]));
+    }
+}
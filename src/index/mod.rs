@@ -1,7 +1,10 @@
 pub mod inverted;
 pub mod posting;
+pub mod numeric;
+pub mod global_dictionary;
 pub mod index_writer;
 pub mod index_reader;
+pub mod index_file;
 pub mod lazy_index_reader;
 pub mod hybrid_index_reader;
 pub mod index_cache;
@@ -0,0 +1,51 @@
+use crate::core::types::FieldValue;
+
+/// Leads every numeric term so it can never collide with an analyzed word
+pub const NUMERIC_TERM_MARKER: char = '\0';
+
+/// Map an `f64` to a `u64` whose unsigned order matches numeric order
+/// (negatives flip every bit, non-negatives flip only the sign bit)
+pub fn sortable_bits(value: f64) -> u64 {
+    // -0.0 and 0.0 index as the same term
+    let bits = if value == 0.0 { 0 } else { value.to_bits() };
+    if bits >> 63 == 1 { !bits } else { bits | (1 << 63) }
+}
+
+/// Numeric value indexed for a field, or `None` for text, booleans and NaN.
+/// Dates are indexed as milliseconds since the epoch.
+pub fn indexed_number(value: &FieldValue) -> Option<f64> {
+    let number = match value {
+        FieldValue::Number(n) => *n,
+        FieldValue::Date(date) => date.timestamp_millis() as f64,
+        FieldValue::Text(_) | FieldValue::Boolean(_) => return None,
+    };
+    (!number.is_nan()).then_some(number)
+}
+
+/// Term text for `value` in `field`: `\0<field>\0<16 hex digits>`.
+/// Fixed-width hex keeps byte order equal to numeric order within a field.
+pub fn numeric_term_text(field: &str, value: f64) -> String {
+    format!("{}{}{}{:016x}", NUMERIC_TERM_MARKER, field, NUMERIC_TERM_MARKER, sortable_bits(value))
+}
+
+/// Prefix shared by every numeric term of `field`
+pub fn numeric_field_prefix(field: &str) -> String {
+    format!("{}{}{}", NUMERIC_TERM_MARKER, field, NUMERIC_TERM_MARKER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoded_terms_sort_like_their_numbers() {
+        let values = [f64::NEG_INFINITY, -1e9, -2.5, -0.0, 0.0, 1e-9, 3.0, 42.5, 1e12, f64::INFINITY];
+        let terms: Vec<String> = values.iter().map(|v| numeric_term_text("price", *v)).collect();
+
+        let mut sorted = terms.clone();
+        sorted.sort();
+        assert_eq!(sorted, terms);
+        assert!(terms.iter().all(|t| t.starts_with(&numeric_field_prefix("price"))));
+        assert_eq!(indexed_number(&FieldValue::Number(f64::NAN)), None);
+    }
+}
@@ -11,12 +11,24 @@ pub struct Posting {
     pub field_norm: f32,      // Length normalization factor
 }
 
+impl Posting {
+    /// Token count of the document, recovered from `field_norm` (1/sqrt(len))
+    pub fn doc_length(&self) -> usize {
+        if self.field_norm > 0.0 {
+            (1.0 / (self.field_norm * self.field_norm)).round() as usize
+        } else {
+            0
+        }
+    }
+}
+
 /// Posting list for a term
 /// Note: Sorted by doc_id for efficient merging
 pub struct PostingList {
     pub doc_ids: EncodedIntegerBlock,
     pub term_freqs: Vec<u32>,
     pub positions: Vec<EncodedIntegerBlock>,
+    pub field_norms: Vec<f32>,
 }
 
 impl PostingList {
@@ -44,6 +56,7 @@ impl PostingList {
             doc_ids: encoded_ids,
             term_freqs: postings.iter().map(|p| p.term_freq).collect(),
             positions,
+            field_norms: postings.iter().map(|p| p.field_norm).collect(),
         })
     }
 
@@ -59,7 +72,7 @@ impl PostingList {
             doc_id: DocId(doc_ids[index] as u64),
             term_freq: self.term_freqs[index],
             positions,
-            field_norm: 1.0,
+            field_norm: self.field_norms[index],
         })
     }
 
@@ -95,7 +108,7 @@ impl PostingList {
                 doc_id: DocId(doc_ids[i] as u64),
                 term_freq: self.term_freqs[i],
                 positions,
-                field_norm: 1.0,
+                field_norm: self.field_norms[i],
             });
         }
 
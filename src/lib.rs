@@ -14,6 +14,8 @@ pub mod memory;
 pub mod compression;
 pub mod simd;
 pub mod parallel;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 /*
 ┌────────────────────────────────────────────────────────────────────────────────────────────┐
@@ -1,11 +1,15 @@
 use std::collections::{HashMap, VecDeque};
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use parking_lot::Mutex;  // Thread-safe interior mutability
 
 /// Buffer pool for memory reuse
 /// Wrapped in Arc<Mutex<>> for shared mutable access across threads
 pub struct BufferPool {
     pools: Mutex<HashMap<usize, BufferQueue>>,
+    /// Bucket sizes in ascending order
+    size_classes: Vec<usize>,
+    /// Buffers handed out that didn't come from a queue
+    allocations: AtomicUsize,
     /// Running total of allocated bytes; updated on acquire/release.
     #[allow(dead_code)]
     total_memory: AtomicUsize,
@@ -31,39 +35,68 @@ impl BufferQueue {
 }
 
 impl BufferPool {
-    pub fn new(memory_limit: usize) -> Self {
-        let mut pools = HashMap::new();
-
-        // Pre-allocate common buffer sizes
-        for size in [256, 1024, 4096, 16384, 65536] {
-            pools.insert(size, BufferQueue::new(size));
-        }
+    /// Default size classes: every power of two from 256 B to 1 MiB
+    pub const DEFAULT_SIZE_CLASSES: [usize; 13] = [
+        256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536, 131072, 262144, 524288, 1048576,
+    ];
 
+    pub fn new(memory_limit: usize) -> Self {
         BufferPool {
-            pools: Mutex::new(pools),
+            pools: Mutex::new(HashMap::new()),
+            size_classes: Vec::new(),
+            allocations: AtomicUsize::new(0),
             total_memory: AtomicUsize::new(0),
             memory_limit,
         }
+        .with_size_classes(Self::DEFAULT_SIZE_CLASSES)
+    }
+
+    /// Replace the bucket sizes. Requests are rounded up to the nearest
+    /// class; requests larger than every class bypass the pool.
+    pub fn with_size_classes(mut self, classes: impl IntoIterator<Item = usize>) -> Self {
+        let mut size_classes: Vec<usize> = classes.into_iter().filter(|&size| size > 0).collect();
+        size_classes.sort_unstable();
+        size_classes.dedup();
+
+        self.pools = Mutex::new(
+            size_classes.iter().map(|&size| (size, BufferQueue::new(size))).collect(),
+        );
+        self.size_classes = size_classes;
+        self
     }
 
-    /// Get buffer from pool (thread-safe)
+    pub fn size_classes(&self) -> &[usize] {
+        &self.size_classes
+    }
+
+    /// Buffers allocated because no pooled one was available
+    pub fn allocation_count(&self) -> usize {
+        self.allocations.load(Ordering::Relaxed)
+    }
+
+    /// Get an empty buffer with capacity for at least `size` bytes (thread-safe)
     pub fn get(&self, size: usize) -> Vec<u8> {
-        let size_class = size.next_power_of_two();
+        // Smallest class that fits, so nearby sizes share a bucket
+        let Some(&size_class) = self.size_classes.iter().find(|&&class| class >= size) else {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            return Vec::with_capacity(size);
+        };
 
-        let mut pools = self.pools.lock();
-        if let Some(queue) = pools.get_mut(&size_class) {
-            if let Some(buf) = queue.buffers.pop_front() {
-                return buf;
-            }
+        if let Some(buf) = self.pools.lock().get_mut(&size_class).and_then(|queue| queue.buffers.pop_front()) {
+            return buf;
         }
 
         // Allocate new buffer if pool is empty
-        vec![0u8; size_class]
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        Vec::with_capacity(size_class)
     }
 
     /// Return buffer to pool (thread-safe)
     pub fn return_buffer(&self, mut buf: Vec<u8>) {
-        let size_class = buf.capacity().next_power_of_two();
+        // Largest class the buffer can still serve; a grown buffer keeps its bucket
+        let Some(&size_class) = self.size_classes.iter().rev().find(|&&class| class <= buf.capacity()) else {
+            return;
+        };
         buf.clear();
 
         let mut pools = self.pools.lock();
@@ -73,4 +106,47 @@ impl BufferPool {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearby_sizes_share_pooled_buffers() {
+        let pool = BufferPool::new(1024 * 1024);
+
+        for size in 1000..1500 {
+            let mut buf = pool.get(size);
+            assert!(buf.is_empty() && buf.capacity() >= size);
+            buf.extend(std::iter::repeat_n(1, size));
+            pool.return_buffer(buf);
+        }
+        // 1000..=1024 and 1025..1500 fall into two classes
+        assert_eq!(pool.allocation_count(), 2);
+
+        // Holding several at once only allocates as many as are outstanding
+        let held: Vec<_> = (0..8).map(|i| pool.get(1100 + i * 37)).collect();
+        assert_eq!(pool.allocation_count(), 9);
+        held.into_iter().for_each(|buf| pool.return_buffer(buf));
+        (0..8).for_each(|i| pool.return_buffer(pool.get(1050 + i)));
+        assert_eq!(pool.allocation_count(), 9);
+    }
+
+    #[test]
+    fn custom_size_classes_bound_the_buckets() {
+        let pool = BufferPool::new(1024 * 1024).with_size_classes([4096, 512, 512]);
+        assert_eq!(pool.size_classes(), &[512, 4096]);
+
+        pool.return_buffer(pool.get(100));
+        pool.return_buffer(pool.get(500));
+        assert_eq!(pool.allocation_count(), 1);
+
+        // Too large for any class: allocated exactly and never pooled
+        let big = pool.get(10_000);
+        assert_eq!(big.capacity(), 10_000);
+        pool.return_buffer(big);
+        pool.get(10_000);
+        assert_eq!(pool.allocation_count(), 3);
+    }
+}
@@ -1,13 +1,14 @@
-use crate::core::error::Result;
+use crate::core::error::{Error, ErrorKind, Result};
 use crate::core::types::{DocId, Document};
 use crate::mvcc::snapshot::{SnapshotLease, Version};
-use crate::storage::segment::Segment;
+use crate::storage::segment::{Segment, SegmentId};
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use roaring::RoaringBitmap;
 use std::collections::{BTreeMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 /// Transaction ID
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -36,6 +37,8 @@ pub struct MVCCController {
     pub active_txns: Arc<RwLock<HashSet<TxId>>>,
     pub current_version: Arc<AtomicU64>,
     pub max_versions: usize,
+    /// Versions younger than this are never evicted, even past `max_versions`
+    pub min_retention: Option<Duration>,
 }
 
 /// Snapshot of index at a point in time.
@@ -52,6 +55,14 @@ pub struct Snapshot {
     _lease: Arc<SnapshotLease>,
 }
 
+/// What changed between two snapshot versions, for incremental sync
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    pub added_segments: Vec<Arc<Segment>>,  // In `to` but not in `from`
+    pub removed_segments: Vec<SegmentId>,   // In `from` but not in `to`
+    pub newly_deleted_docs: RoaringBitmap,  // Deleted in `to` but not in `from`
+}
+
 /// Transaction for write operations
 pub struct Transaction {
     pub id: TxId,
@@ -75,9 +86,16 @@ impl MVCCController {
             active_txns: Arc::new(RwLock::new(HashSet::new())),
             current_version: Arc::new(AtomicU64::new(0)),
             max_versions: 100,
+            min_retention: None,
         }
     }
 
+    /// Keep every version created within `retention`, regardless of the count cap
+    pub fn with_min_retention(mut self, retention: Duration) -> Self {
+        self.min_retention = Some(retention);
+        self
+    }
+
     pub fn create_snapshot(&self, segments: Vec<Arc<Segment>>) -> Arc<Snapshot> {
         self.create_snapshot_with_deletes(segments, Arc::new(RoaringBitmap::new()))
     }
@@ -139,7 +157,9 @@ impl MVCCController {
     }
 
     /// Safe GC that uses Weak<SnapshotLease> to detect live snapshots.
-    /// A version is eligible for eviction only when its Weak::strong_count() == 0.
+    /// Once there are more than `max_versions`, the oldest versions are evicted
+    /// unless they are the latest, pinned by a live `Snapshot` or an active
+    /// transaction, or younger than `min_retention`.
     pub fn gc(&self) {
         let mut versions = self.versions.write();
         self.gc_old_versions_leased(&mut versions);
@@ -154,12 +174,28 @@ impl MVCCController {
             .current_version
             .load(Ordering::Acquire)
             .saturating_sub(1);
+        // Transactions began at `TxId(current_version)`, so they read the version before it
+        let txn_versions: HashSet<u64> = self
+            .active_txns
+            .read()
+            .iter()
+            .filter_map(|tx| tx.0.checked_sub(1))
+            .collect();
+        let retained_since = self
+            .min_retention
+            .and_then(|retention| chrono::Duration::from_std(retention).ok())
+            .and_then(|retention| Utc::now().checked_sub_signed(retention));
         let mut leases = self.leases.write();
 
         while versions.len() > self.max_versions {
             let mut removed_any = false;
             for version in versions.keys().copied().collect::<Vec<_>>() {
-                if version == latest_version {
+                if version == latest_version || txn_versions.contains(&version) {
+                    continue;
+                }
+                if let Some(since) = retained_since
+                    && versions[&version].timestamp > since
+                {
                     continue;
                 }
 
@@ -184,6 +220,39 @@ impl MVCCController {
         }
     }
 
+    /// Changes needed to bring a copy of `from_version` up to `to_version`.
+    /// Both versions must still be retained (see `gc`).
+    pub fn diff(&self, from_version: u64, to_version: u64) -> Result<SnapshotDiff> {
+        let versions = self.versions.read();
+        let snapshot = |version: u64| {
+            versions.get(&version).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Snapshot version {} is not retained", version),
+                )
+            })
+        };
+        let (from, to) = (snapshot(from_version)?, snapshot(to_version)?);
+
+        let from_ids: HashSet<SegmentId> = from.segments.iter().map(|s| s.id).collect();
+        let to_ids: HashSet<SegmentId> = to.segments.iter().map(|s| s.id).collect();
+        Ok(SnapshotDiff {
+            added_segments: to
+                .segments
+                .iter()
+                .filter(|s| !from_ids.contains(&s.id))
+                .cloned()
+                .collect(),
+            removed_segments: from
+                .segments
+                .iter()
+                .map(|s| s.id)
+                .filter(|id| !to_ids.contains(id))
+                .collect(),
+            newly_deleted_docs: to.deleted_docs.as_ref() - from.deleted_docs.as_ref(),
+        })
+    }
+
     pub fn begin_transaction(&self, isolation: IsolationLevel) -> Transaction {
         let tx_id = TxId::new(self.current_version.load(Ordering::Acquire));
         self.active_txns.write().insert(tx_id);
@@ -202,6 +271,28 @@ impl MVCCController {
     }
 }
 
+impl Snapshot {
+    /// A snapshot that no controller tracks, e.g. one reloaded from disk.
+    /// Its version is informational only and may repeat a live version.
+    pub fn detached(
+        version: u64,
+        timestamp: DateTime<Utc>,
+        segments: Vec<Arc<Segment>>,
+        deleted_docs: Arc<RoaringBitmap>,
+    ) -> Self {
+        Snapshot {
+            version,
+            doc_count: segments.iter().map(|s| s.doc_count as usize).sum(),
+            segments,
+            timestamp,
+            deleted_docs,
+            _lease: Arc::new(SnapshotLease {
+                version: Version(version),
+            }),
+        }
+    }
+}
+
 impl Default for Snapshot {
     fn default() -> Self {
         // Default snapshot has no lease — it's a placeholder
@@ -252,4 +343,83 @@ mod tests {
         assert!(versions.contains_key(&2));
         assert!(versions.contains_key(&3));
     }
+
+    #[test]
+    fn reader_pinned_version_survives_gc_past_count_cap() {
+        let mut mvcc = MVCCController::new();
+        mvcc.max_versions = 1;
+
+        let reader = mvcc.create_snapshot(Vec::new()); // version 0, held by a reader
+        for _ in 0..5 {
+            let _ = mvcc.create_snapshot(Vec::new());
+        }
+        mvcc.gc();
+
+        let versions = mvcc.versions.read();
+        assert!(versions.contains_key(&reader.version), "reader's version must survive");
+        assert!(versions.contains_key(&5), "latest version should remain");
+        assert_eq!(versions.len(), 2);
+    }
+
+    #[test]
+    fn gc_retains_recent_and_transaction_versions() {
+        let mut mvcc = MVCCController::new().with_min_retention(Duration::from_secs(3600));
+        mvcc.max_versions = 1;
+        for _ in 0..4 {
+            let _ = mvcc.create_snapshot(Vec::new());
+        }
+        assert_eq!(mvcc.versions.read().len(), 4, "versions inside the window are kept");
+
+        mvcc.min_retention = None;
+        // Reads version 3 without holding its snapshot
+        let tx = mvcc.begin_transaction(IsolationLevel::RepeatableRead);
+        drop(tx.snapshot);
+        let _ = mvcc.create_snapshot(Vec::new()); // version 4
+
+        let versions = mvcc.versions.read();
+        assert_eq!(versions.keys().copied().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    fn segment(doc_count: u32) -> Arc<Segment> {
+        Arc::new(Segment {
+            id: SegmentId::new(),
+            doc_count,
+            metadata: crate::storage::segment::SegmentMetadata::empty(),
+        })
+    }
+
+    #[test]
+    fn diff_lists_changed_segments_and_new_deletes() {
+        let mvcc = MVCCController::new();
+        let first = segment(3);
+        let second = segment(2);
+        let merged = segment(4);
+
+        let v0 = mvcc.create_snapshot(vec![first.clone()]).version;
+        let v1 = mvcc
+            .create_snapshot_with_deletes(
+                vec![first.clone(), second.clone()],
+                Arc::new(RoaringBitmap::from_iter([1])),
+            )
+            .version;
+        let v2 = mvcc
+            .create_snapshot_with_deletes(vec![merged.clone()], Arc::new(RoaringBitmap::from_iter([1, 4])))
+            .version;
+
+        let inserted = mvcc.diff(v0, v1).unwrap();
+        assert_eq!(inserted.added_segments.iter().map(|s| s.id).collect::<Vec<_>>(), vec![second.id]);
+        assert!(inserted.removed_segments.is_empty());
+        assert_eq!(inserted.newly_deleted_docs.iter().collect::<Vec<_>>(), vec![1]);
+
+        let merged_away = mvcc.diff(v1, v2).unwrap();
+        assert_eq!(merged_away.added_segments.iter().map(|s| s.id).collect::<Vec<_>>(), vec![merged.id]);
+        assert_eq!(
+            merged_away.removed_segments.iter().copied().collect::<HashSet<_>>(),
+            HashSet::from([first.id, second.id])
+        );
+        assert_eq!(merged_away.newly_deleted_docs.iter().collect::<Vec<_>>(), vec![4]);
+
+        assert!(mvcc.diff(v2, v2).unwrap().added_segments.is_empty());
+        assert!(matches!(mvcc.diff(v0, 99).unwrap_err().kind, ErrorKind::NotFound));
+    }
 }
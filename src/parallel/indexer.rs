@@ -2,10 +2,12 @@ use rayon::prelude::*;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::HashMap;
+use std::ops::Range;
 use crate::analysis::analyzer::Analyzer;
 use crate::analysis::token::Token;
 use crate::core::types::{DocId, Document, FieldValue};
 use crate::index::inverted::Term;
+use crate::index::numeric::{indexed_number, numeric_term_text};
 use crate::index::posting::Posting;
 use crate::core::error::Result;
 
@@ -14,6 +16,10 @@ pub struct ParallelIndexer {
     pub workers: usize,
     pub batch_size: usize,
     pub progress: Arc<AtomicUsize>,
+    /// Tokens kept per text field; the rest of the field is not indexed (None = unlimited)
+    pub max_tokens_per_field: Option<usize>,
+    /// Text fields cut short by `max_tokens_per_field` across all batches
+    pub truncated_fields: Arc<AtomicUsize>,
 }
 
 impl ParallelIndexer {
@@ -28,14 +34,27 @@ impl ParallelIndexer {
             workers,
             batch_size: 1000,
             progress: Arc::new(AtomicUsize::new(0)),
+            max_tokens_per_field: None,
+            truncated_fields: Arc::new(AtomicUsize::new(0)),
         }
     }
+
+    /// Stop indexing a text field after `max` tokens
+    pub fn with_max_tokens_per_field(mut self, max: usize) -> Self {
+        self.max_tokens_per_field = Some(max);
+        self
+    }
     
     /// Get current progress
     pub fn get_progress(&self) -> usize {
         self.progress.load(Ordering::Relaxed)
     }
 
+    /// Text fields indexed only up to `max_tokens_per_field` so far
+    pub fn get_truncated_fields(&self) -> usize {
+        self.truncated_fields.load(Ordering::Relaxed)
+    }
+
     /// Index batch of documents in parallel
     pub fn index_batch(&self, documents: Vec<Document>, analyzer: &Arc<Analyzer>) -> Result<Vec<IndexedDoc>> {
         self.progress.store(0, Ordering::Relaxed);
@@ -48,7 +67,10 @@ impl ParallelIndexer {
                 let batch_results: Vec<IndexedDoc> = batch
                     .par_iter()
                     .filter_map(|doc| {
-                        let result = self.index_document(doc, analyzer);
+                        let result = self.tokenize_document(doc, analyzer).map(|(indexed, truncated)| {
+                            self.truncated_fields.fetch_add(truncated, Ordering::Relaxed);
+                            indexed
+                        });
                         self.progress.fetch_add(1, Ordering::Relaxed);
                         
                         // Log progress every 1000 documents
@@ -96,7 +118,7 @@ impl ParallelIndexer {
                     doc_id: indexed_doc.doc_id,
                     term_freq: positions.len() as u32,
                     positions,
-                    field_norm: 1.0 / (indexed_doc.terms.len() as f32).sqrt(),
+                    field_norm: indexed_doc.field_norm(),
                 };
                 
                 inverted
@@ -104,6 +126,9 @@ impl ParallelIndexer {
                     .or_insert_with(Vec::new)
                     .push(posting);
             }
+            for term in &indexed_doc.numeric_terms {
+                inverted.entry(term.clone()).or_default().push(indexed_doc.numeric_posting());
+            }
         }
         
         // Sort postings by doc_id for each term
@@ -114,14 +139,37 @@ impl ParallelIndexer {
         Ok(inverted)
     }
 
-    fn index_document(&self, doc: &Document, analyzer: &Arc<Analyzer>) -> Result<IndexedDoc> {
+    /// Tokenize one document. Fields are laid out in name order, so a stored
+    /// document re-indexed later gets the same token positions.
+    pub fn index_document(&self, doc: &Document, analyzer: &Arc<Analyzer>) -> Result<IndexedDoc> {
+        self.tokenize_document(doc, analyzer).map(|(indexed, _)| indexed)
+    }
+
+    /// `index_document`, also returning how many fields were truncated
+    fn tokenize_document(&self, doc: &Document, analyzer: &Arc<Analyzer>) -> Result<(IndexedDoc, usize)> {
         let mut terms = Vec::new();
         let mut all_tokens = Vec::new();
+        let mut field_ranges = Vec::new();
+        let mut numeric_terms = Vec::new();
+        let mut truncated = 0;
 
-        for (_field, value) in &doc.fields {
+        let mut fields: Vec<_> = doc.fields.iter().collect();
+        fields.sort_unstable_by_key(|(field, _)| *field);
+        for (field, value) in fields {
             if let FieldValue::Text(text) = value {
-                let tokens = analyzer.analyze(text);
+                let mut tokens = analyzer.analyze(text);
+                if let Some(max) = self.max_tokens_per_field
+                    && tokens.len() > max
+                {
+                    tokens.truncate(max);
+                    truncated += 1;
+                }
+                let start = all_tokens.len();
                 all_tokens.extend(tokens);
+                field_ranges.push((field.clone(), start..all_tokens.len()));
+            } else if let Some(number) = indexed_number(value) {
+                // One sortable term, so range queries can be answered from the index
+                numeric_terms.push(Term::new(&numeric_term_text(field, number)));
             }
         }
         
@@ -130,11 +178,14 @@ impl ParallelIndexer {
             terms.push(Term::new(&token.text));
         }
 
-        Ok(IndexedDoc {
+        let indexed = IndexedDoc {
             doc_id: doc.id,
             terms,
             tokens: all_tokens,
-        })
+            field_ranges,
+            numeric_terms,
+        };
+        Ok((indexed, truncated))
     }
 }
 
@@ -142,4 +193,70 @@ pub struct IndexedDoc {
     pub doc_id: DocId,
     pub terms: Vec<Term>,
     pub tokens: Vec<Token>,
-}
\ No newline at end of file
+    /// Field each slice of `tokens` came from
+    pub field_ranges: Vec<(String, Range<usize>)>,
+    /// Sortable term of each numeric or date field. Kept out of `tokens` so
+    /// numbers neither count toward the document length nor shift positions.
+    pub numeric_terms: Vec<Term>,
+}
+
+impl IndexedDoc {
+    /// Length normalization of the document's postings, from its text tokens
+    pub fn field_norm(&self) -> f32 {
+        1.0 / (self.terms.len().max(1) as f32).sqrt()
+    }
+
+    /// Posting of one of `numeric_terms`: no positions, the text's norm
+    pub fn numeric_posting(&self) -> Posting {
+        Posting {
+            doc_id: self.doc_id,
+            term_freq: 1,
+            positions: Vec::new(),
+            field_norm: self.field_norm(),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::filters::lowercase::LowercaseFilter;
+    use crate::analysis::tokenizer::StandardTokenizer;
+    use crate::index::inverted::InvertedIndex;
+    use crate::query::ast::{PhraseQuery, Query};
+    use crate::query::matcher::DocumentMatcher;
+
+    #[test]
+    fn huge_field_is_indexed_only_up_to_the_token_cap() {
+        let indexer = ParallelIndexer::new(2).with_max_tokens_per_field(100);
+        let analyzer = Arc::new(
+            Analyzer::new("plain".to_string(), Box::new(StandardTokenizer::default()))
+                .add_filter(Box::new(LowercaseFilter)),
+        );
+        let text = (0..10_000).map(|i| format!("w{}", i)).collect::<Vec<_>>().join(" ");
+        let doc = Document {
+            id: DocId(1),
+            fields: HashMap::from([("body".to_string(), FieldValue::Text(text))]),
+        };
+
+        let indexed = indexer.index_batch(vec![doc.clone()], &analyzer).unwrap();
+        assert_eq!(indexed[0].tokens.len(), 100);
+        assert_eq!(indexed[0].field_ranges, vec![("body".to_string(), 0..100)]);
+        assert_eq!(indexer.get_truncated_fields(), 1);
+
+        let mut index = InvertedIndex::new();
+        index.add_document(DocId(1), &indexed[0].tokens).unwrap();
+        let matcher = DocumentMatcher::new(Arc::new(index));
+        let phrase = |words: &[&str]| {
+            Query::Phrase(PhraseQuery {
+                field: "body".to_string(),
+                phrase: words.iter().map(|w| w.to_string()).collect(),
+                slop: 0,
+                boost: None,
+            })
+        };
+
+        assert!(matcher.matches(&doc, &phrase(&["w98", "w99"])).unwrap());
+        assert!(!matcher.matches(&doc, &phrase(&["w99", "w100"])).unwrap());
+        assert!(!matcher.matches(&doc, &phrase(&["w5000", "w5001"])).unwrap());
+    }
+}
@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use chrono::Utc;
 use crate::core::types::DocId;
 use crate::storage::segment::{Segment, SegmentId, SegmentMetadata};
 use crate::core::error::Result;
@@ -53,18 +52,19 @@ impl SegmentMerger {
         // 3. Write merged result using SegmentWriter
         // TODO: (Actual merge logic would use InvertedIndex with SkipList)
 
-        let new_metadata = SegmentMetadata {
-            created_at: Utc::now(),
-            size_bytes: total_size,
-            min_doc_id: segments.iter()
-                .map(|s| s.metadata.min_doc_id)
-                .min()
-                .unwrap_or(DocId(0)),
-            max_doc_id: segments.iter()
-                .map(|s| s.metadata.max_doc_id)
-                .max()
-                .unwrap_or(DocId(0)),
-        };
+        // Empty segments have no id range and must not widen the merged one
+        let ranges: Vec<(DocId, DocId)> = segments.iter()
+            .filter_map(|s| s.metadata.doc_id_range())
+            .collect();
+        let mut new_metadata = SegmentMetadata::empty();
+        new_metadata.size_bytes = total_size;
+        if let (Some(min), Some(max)) = (
+            ranges.iter().map(|r| r.0).min(),
+            ranges.iter().map(|r| r.1).max(),
+        ) {
+            new_metadata.min_doc_id = min;
+            new_metadata.max_doc_id = max;
+        }
 
         Ok(Segment {
             id: SegmentId::new(),
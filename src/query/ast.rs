@@ -1,18 +1,49 @@
 use serde::{Serialize, Deserialize};
-use crate::core::types::FieldValue;
-use crate::core::error::Result;
+use std::fmt;
+use std::sync::Arc;
+use crate::core::types::{DocId, Document, FieldValue};
+use crate::core::error::{Error, ErrorKind, Result};
 use crate::query::visitor::QueryVisitor;
 
 /// Main query enum representing all query types
+///
+/// Deserializable from a JSON DSL: each query is an object with a single
+/// snake_case key naming the variant, holding that variant's fields.
+/// Omitted `boost`s and clause lists default to none/empty, `slop` to 0.
+///
+/// ```json
+/// {"bool": {
+///     "must": [{"term": {"field": "content", "value": "rust"}}],
+///     "should": [{"phrase": {"field": "title", "phrase": ["async", "io"]}}],
+///     "filter": [{"range": {"field": "year", "gte": 2020, "lt": 2024}}],
+///     "must_not": [{"ids": {"values": [7]}}]
+/// }}
+/// ```
+///
+/// Other shapes: `{"prefix": {"field", "prefix"}}`,
+/// `{"wildcard": {"field", "pattern"}}`,
+/// `{"fuzzy": {"field", "term", "max_edits", "prefix_length"}}`,
+/// `{"near": {"field", "clauses", "slop", "in_order"}}`,
+/// `{"boosting": {"positive", "negative", "negative_boost"}}`,
+/// `{"dis_max": {"queries", "tie_breaker"}}` and `"match_all"`.
+/// Range bounds are plain JSON values: numbers, booleans, RFC 3339 dates
+/// or other strings. `Predicate` has no JSON form.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Query {
     Term(TermQuery),         // Single term search
     Phrase(PhraseQuery),     // Exact phrase match
+    Near(NearQuery),         // Clauses within a position window
+    Boosting(BoostingQuery), // Demote (not exclude) negative matches
+    DisMax(DisMaxQuery),     // Best sub-query score plus tie-breaker share
     Bool(BoolQuery),         // Boolean combinations
     Range(RangeQuery),       // Numeric/date range
     Prefix(PrefixQuery),
     Wildcard(WildcardQuery), // Pattern matching (defined in M07)
     Fuzzy(FuzzyQuery),       // Typo tolerance (defined in M07)
+    Ids(IdsQuery),           // Exact document ids, looked up without scanning
+    #[serde(skip)]
+    Predicate(PredicateQuery), // Arbitrary per-document filter (not serializable)
     MatchAll,                // Match all documents
 }
 
@@ -29,16 +60,99 @@ pub struct TermQuery {
 pub struct PhraseQuery {
     pub field: String,
     pub phrase: Vec<String>,
+    #[serde(default)]
     pub slop: u32,  // Max distance between terms
     pub boost: Option<f32>,
 }
 
+/// Span query: every clause occurs within a window of positions
+///
+/// With `in_order` each clause must follow the previous one with at most
+/// `slop` positions in between (like a sloppy phrase). Without it the clauses
+/// may appear in any order, as long as all of them fit in a window of
+/// `clauses.len() + slop` positions. Clauses must be term queries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NearQuery {
+    pub field: String,
+    pub clauses: Vec<Query>,
+    #[serde(default)]
+    pub slop: u32,
+    #[serde(default)]
+    pub in_order: bool,
+    pub boost: Option<f32>,
+}
+
+/// Boosting query: returns documents matching `positive`; those that also
+/// match `negative` keep their place in the results but have their score
+/// multiplied by `negative_boost` (expected in `[0.0, 1.0)`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoostingQuery {
+    pub positive: Box<Query>,
+    pub negative: Box<Query>,
+    pub negative_boost: f32,
+}
+
+/// Disjunction-max query: matches like an OR of `queries`, but scores as
+/// `max(sub_scores) + tie_breaker * sum(other_sub_scores)`, so a term found in
+/// several fields doesn't outrank a strong single-field match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisMaxQuery {
+    pub queries: Vec<Query>,
+    #[serde(default)]
+    pub tie_breaker: f32,
+    pub boost: Option<f32>,
+}
+
+/// Predicate query: keeps documents for which a caller-supplied closure
+/// returns `true`, for filters the query language can't express
+/// (e.g. "field A > field B").
+///
+/// It cannot use the inverted index: every candidate document is loaded and
+/// passed to the closure, so it costs a full scan of whatever reaches the
+/// matcher. Prefer putting it in a `Bool` `filter` next to indexed clauses.
+/// Scores as a constant and can't be serialized; equality is identity of the
+/// closure.
+#[derive(Clone)]
+pub struct PredicateQuery {
+    pub name: String,
+    pub predicate: Arc<dyn Fn(&Document) -> bool + Send + Sync>,
+}
+
+impl PredicateQuery {
+    pub fn new(name: impl Into<String>, predicate: impl Fn(&Document) -> bool + Send + Sync + 'static) -> Self {
+        PredicateQuery {
+            name: name.into(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    pub fn evaluate(&self, doc: &Document) -> bool {
+        (self.predicate)(doc)
+    }
+}
+
+impl fmt::Debug for PredicateQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PredicateQuery").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for PredicateQuery {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.predicate, &other.predicate)
+    }
+}
+
 /// Boolean query with must/should/must_not clauses
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BoolQuery {
+    #[serde(default)]
     pub must: Vec<Query>,      // All must match (AND)
+    #[serde(default)]
     pub should: Vec<Query>,    // At least one must match (OR)
+    #[serde(default)]
     pub must_not: Vec<Query>,  // None must match (NOT)
+    #[serde(default)]
     pub filter: Vec<Query>,    // Must match but don't affect score
     pub minimum_should_match: Option<u32>,
     pub boost: Option<f32>,
@@ -48,9 +162,13 @@ pub struct BoolQuery {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RangeQuery {
     pub field: String,
+    #[serde(default, with = "plain_bound")]
     pub gt: Option<FieldValue>,   // Greater than
+    #[serde(default, with = "plain_bound")]
     pub gte: Option<FieldValue>,  // Greater than or equal
+    #[serde(default, with = "plain_bound")]
     pub lt: Option<FieldValue>,   // Less than
+    #[serde(default, with = "plain_bound")]
     pub lte: Option<FieldValue>,  // Less than or equal
     pub boost: Option<f32>,
 }
@@ -80,17 +198,73 @@ pub struct FuzzyQuery {
     pub boost: Option<f32>,
 }
 
+/// Documents by id (`_id:5`), resolved against segments instead of matched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdsQuery {
+    pub values: Vec<DocId>,
+    pub boost: Option<f32>,
+}
+
+/// Range bounds as untagged values (`10`, `"2020-01-01T00:00:00Z"`) rather
+/// than `FieldValue`'s tagged form, so JSON ranges read naturally
+mod plain_bound {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use crate::core::types::FieldValue;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Plain {
+        Number(f64),
+        Boolean(bool),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(value: &Option<FieldValue>, serializer: S) -> Result<S::Ok, S::Error> {
+        let plain = value.as_ref().map(|value| match value {
+            FieldValue::Number(n) => Plain::Number(*n),
+            FieldValue::Boolean(b) => Plain::Boolean(*b),
+            FieldValue::Date(date) => Plain::Text(date.to_rfc3339()),
+            FieldValue::Text(text) => Plain::Text(text.clone()),
+        });
+        plain.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<FieldValue>, D::Error> {
+        let plain = Option::<Plain>::deserialize(deserializer)?;
+        Ok(plain.map(|plain| match plain {
+            Plain::Number(n) => FieldValue::Number(n),
+            Plain::Boolean(b) => FieldValue::Boolean(b),
+            Plain::Text(text) => match DateTime::parse_from_rfc3339(&text) {
+                Ok(date) => FieldValue::Date(date.with_timezone(&Utc)),
+                Err(_) => FieldValue::Text(text),
+            },
+        }))
+    }
+}
+
 impl Query {
+    /// Parse a query from its JSON DSL form (see [`Query`])
+    pub fn from_json(json: &str) -> Result<Query> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::new(ErrorKind::Parse, format!("Invalid JSON query: {}", e)))
+    }
+
     /// Single place where variant → method mapping lives.
     pub fn accept<V: QueryVisitor>(&self, visitor: &V) -> Result<V::Output> {
         match self {
             Query::Term(q)     => visitor.visit_term(q),
             Query::Phrase(q)   => visitor.visit_phrase(q),
+            Query::Near(q)     => visitor.visit_near(q),
+            Query::Boosting(q) => visitor.visit_boosting(q),
+            Query::DisMax(q)   => visitor.visit_dis_max(q),
             Query::Bool(q)     => visitor.visit_bool(q),
             Query::Range(q)    => visitor.visit_range(q),
             Query::Prefix(q)   => visitor.visit_prefix(q),
             Query::Wildcard(q) => visitor.visit_wildcard(q),
             Query::Fuzzy(q)    => visitor.visit_fuzzy(q),
+            Query::Ids(q)      => visitor.visit_ids(q),
+            Query::Predicate(q) => visitor.visit_predicate(q),
             Query::MatchAll    => visitor.visit_match_all(),
         }
     }
@@ -127,10 +301,68 @@ impl BoolQuery {
         self.filter.push(query);
         self
     }
+
+    pub fn with_minimum_should_match(mut self, minimum: u32) -> Self {
+        self.minimum_should_match = Some(minimum);
+        self
+    }
+
+    /// Should clauses a document has to match: `minimum_should_match` when
+    /// set, otherwise 1 for a query of should clauses alone and 0 (should
+    /// clauses only add score) when there are must or filter clauses
+    pub fn required_should_matches(&self) -> usize {
+        match self.minimum_should_match {
+            Some(minimum) => minimum as usize,
+            None if self.must.is_empty() && self.filter.is_empty() && !self.should.is_empty() => 1,
+            None => 0,
+        }
+    }
 }
 
 impl Default for BoolQuery {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn json_dsl_fills_defaults_and_reads_plain_range_bounds() {
+        let query = Query::from_json(
+            r#"{"bool": {
+                "should": [{"phrase": {"field": "title", "phrase": ["async", "io"]}}],
+                "filter": [{"range": {"field": "created", "gte": "2020-01-01T00:00:00Z", "lt": 5}}]
+            }}"#,
+        )
+        .unwrap();
+
+        let expected = Query::Bool(BoolQuery {
+            should: vec![Query::Phrase(PhraseQuery {
+                field: "title".to_string(),
+                phrase: vec!["async".to_string(), "io".to_string()],
+                slop: 0,
+                boost: None,
+            })],
+            filter: vec![Query::Range(RangeQuery {
+                field: "created".to_string(),
+                gt: None,
+                gte: Some(FieldValue::Date(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap())),
+                lt: Some(FieldValue::Number(5.0)),
+                lte: None,
+                boost: None,
+            })],
+            ..BoolQuery::new()
+        });
+        assert_eq!(query, expected);
+
+        // Serializing writes the same shape back
+        let json = serde_json::to_string(&query).unwrap();
+        assert_eq!(Query::from_json(&json).unwrap(), expected);
+        assert!(json.starts_with(r#"{"bool":"#));
+
+        assert!(Query::from_json(r#"{"term": {"field": "title"}}"#).is_err());
+    }
+}
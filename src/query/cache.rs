@@ -4,6 +4,9 @@ use std::sync::{Arc, RwLock};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use crate::core::error::Result;
+use crate::query::ast::Query;
+use crate::query::parser::QueryParser;
 use crate::search::results::SearchResults;
 
 /// Query cache for avoiding recomputation
@@ -117,6 +120,61 @@ impl QueryCache {
     }
 }
 
+/// Parsed queries by query string, so a repeated search skips the parser.
+///
+/// Unlike `QueryCache`, entries depend only on the query text and the
+/// parser, never on indexed data, so writes don't invalidate them. The
+/// schema and analyzers are fixed for an open index; call `clear` if the
+/// parser's configuration ever changes.
+pub struct CompiledQueryCache {
+    cache: Option<parking_lot::Mutex<LruCache<String, Arc<Query>>>>,  // None = caching disabled
+    parse_count: AtomicUsize,
+}
+
+impl CompiledQueryCache {
+    /// Cache up to `capacity` parsed queries (0 = parse every time)
+    pub fn new(capacity: usize) -> Self {
+        CompiledQueryCache {
+            cache: NonZeroUsize::new(capacity).map(|cap| parking_lot::Mutex::new(LruCache::new(cap))),
+            parse_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// The parsed form of `query_str`, parsing it only on a miss.
+    /// Strings that fail to parse are not cached.
+    pub fn get_or_parse(&self, query_str: &str, parser: &QueryParser) -> Result<Arc<Query>> {
+        if let Some(query) = self.cache.as_ref().and_then(|cache| cache.lock().get(query_str).cloned()) {
+            return Ok(query);
+        }
+
+        self.parse_count.fetch_add(1, Ordering::Relaxed);
+        let query = Arc::new(parser.parse(query_str)?);
+        if let Some(cache) = &self.cache {
+            cache.lock().put(query_str.to_string(), query.clone());
+        }
+        Ok(query)
+    }
+
+    /// Query strings handed to the parser so far
+    pub fn parse_count(&self) -> usize {
+        self.parse_count.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| cache.lock().len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lock().clear();
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CacheStats {
     pub hit_count: usize,
@@ -1,20 +1,32 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
 use regex::Regex;
 use crate::core::types::{Document, FieldValue};
-use crate::query::ast::{Query, TermQuery, PhraseQuery, BoolQuery, RangeQuery, PrefixQuery, FuzzyQuery, WildcardQuery};
-use crate::core::error::Result;
+use crate::query::ast::{Query, TermQuery, PhraseQuery, NearQuery, BoostingQuery, DisMaxQuery, BoolQuery, RangeQuery, PrefixQuery, FuzzyQuery, WildcardQuery, IdsQuery, PredicateQuery};
+use crate::core::error::{Error, ErrorKind, Result};
+use crate::core::types::DocId;
 use crate::core::utils::levenshtein_distance;
 use crate::index::inverted::{InvertedIndex, Term};
+use crate::index::posting::Posting;
 use crate::search::results::ScoredDocument;
 use crate::storage::segment_reader::SegmentReader;
-use crate::storage::segment::SegmentHeader;
 use crate::query::visitor::QueryVisitor;
+use parking_lot::Mutex;
+
+/// Each phrase term's posting in every document that holds all of the terms
+type PhraseCandidates = HashMap<DocId, Vec<Posting>>;
 
 /// Document matcher - implements query matching logic
 /// This is the search functionality that M02 didn't have
 pub struct DocumentMatcher {
     // Configuration for matching
     index: Arc<InvertedIndex>,
+    /// Intersect a phrase's posting lists once and check positions only in
+    /// the documents left, instead of scanning every posting list per document
+    phrase_intersection: bool,
+    /// Candidates of each phrase seen so far, keyed by its terms
+    phrase_candidates: Mutex<HashMap<Vec<String>, Arc<PhraseCandidates>>>,
 }
 
 /// Thin per-call context. Stack-allocated. Carries doc reference without
@@ -42,33 +54,38 @@ impl QueryVisitor for MatchContext<'_> {
         let phrase = &q.phrase;
         let slop = q.slop;
 
-        let mut term_positions: Vec<Vec<u32>> = Vec::new();
+        let Some(postings) = self.matcher.phrase_postings(phrase, self.doc.id)? else {
+            return Ok(false);
+        };
+        let term_positions: Vec<Vec<u32>> = postings.into_iter().map(|p| p.positions).collect();
 
-        for term_text in phrase {
-            let term = Term::new(term_text);
+        if slop == 0 {
+            Ok(self.matcher.check_adjacent_positions(&term_positions))
+        } else {
+            Ok(self.matcher.check_proximity_positions(&term_positions, slop))
+        }
+    }
 
-            if let Some(posting_list) = self.matcher.index.search_term(&term) {
-                let mut found = false;
-                for posting in &posting_list.iter()? {
-                    if posting.doc_id == self.doc.id {
-                        term_positions.push(posting.positions.clone());
-                        found = true;
-                        break;
-                    }
-                }
+    fn visit_near(&self, q: &NearQuery) -> Result<bool> {
+        let mut term_positions: Vec<Vec<u32>> = Vec::new();
 
-                if !found {
-                    return Ok(false);
-                }
-            } else {
-                return Ok(false);
+        for clause in &q.clauses {
+            let Query::Term(term_query) = clause else {
+                return Err(Error::new(
+                    ErrorKind::UnsupportedQuery,
+                    "Near query clauses must be term queries".to_string(),
+                ));
+            };
+            match self.matcher.term_positions(&term_query.value, self.doc.id)? {
+                Some(positions) => term_positions.push(positions),
+                None => return Ok(false),
             }
         }
 
-        if slop == 0 {
-            Ok(self.matcher.check_adjacent_positions(&term_positions))
+        if q.in_order {
+            Ok(self.matcher.check_proximity_positions(&term_positions, q.slop))
         } else {
-            Ok(self.matcher.check_proximity_positions(&term_positions, slop))
+            Ok(self.matcher.check_unordered_window(&term_positions, q.slop))
         }
     }
 
@@ -87,19 +104,20 @@ impl QueryVisitor for MatchContext<'_> {
             }
         }
 
-        // Should clauses: at least one must match (OR)
-        if !q.should.is_empty() {
-            let mut any_match = false;
-            for should_clause in &q.should {
-                if should_clause.accept(self)? {
-                    any_match = true;
-                    break;
-                }
+        // Should clauses: at least `required_should_matches` must match
+        let required = q.required_should_matches();
+        let mut matched = 0;
+        for should_clause in &q.should {
+            if matched >= required {
+                break;
             }
-            if !any_match {
-                return Ok(false);
+            if should_clause.accept(self)? {
+                matched += 1;
             }
         }
+        if matched < required {
+            return Ok(false);
+        }
 
         // Filter clauses: must match but don't affect score
         for filter_clause in &q.filter {
@@ -117,8 +135,8 @@ impl QueryVisitor for MatchContext<'_> {
                 FieldValue::Number(num) => {
                     Ok(self.matcher.number_in_range(*num, q))
                 },
-                FieldValue::Date(_date) => {
-                    Ok(true) // Placeholder
+                FieldValue::Date(date) => {
+                    Ok(self.matcher.date_in_range(date, q))
                 },
                 _ => Ok(false),
             }
@@ -193,6 +211,28 @@ impl QueryVisitor for MatchContext<'_> {
         }
     }
 
+    fn visit_ids(&self, q: &IdsQuery) -> Result<bool> {
+        Ok(q.values.contains(&self.doc.id))
+    }
+
+    fn visit_boosting(&self, q: &BoostingQuery) -> Result<bool> {
+        // `negative` only affects scoring
+        q.positive.accept(self)
+    }
+
+    fn visit_dis_max(&self, q: &DisMaxQuery) -> Result<bool> {
+        for query in &q.queries {
+            if query.accept(self)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn visit_predicate(&self, q: &PredicateQuery) -> Result<bool> {
+        Ok(q.evaluate(self.doc))
+    }
+
     fn visit_match_all(&self) -> Result<bool> {
         Ok(true)
     }
@@ -200,7 +240,24 @@ impl QueryVisitor for MatchContext<'_> {
 
 impl DocumentMatcher {
     pub fn new(index: Arc<InvertedIndex>) -> Self {
-        DocumentMatcher { index }
+        DocumentMatcher {
+            index,
+            phrase_intersection: true,
+            phrase_candidates: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Choose how phrase queries find their documents: by intersecting the
+    /// terms' posting lists up front (default), or by scanning each term's
+    /// postings for every document checked
+    pub fn with_phrase_intersection(mut self, enabled: bool) -> Self {
+        self.phrase_intersection = enabled;
+        self
+    }
+
+    /// Index used for positional checks
+    pub fn index(&self) -> &InvertedIndex {
+        &self.index
     }
 
     /// The public interface collapses to a one-liner.
@@ -209,88 +266,190 @@ impl DocumentMatcher {
         query.accept(&ctx)
     }
 
-    /// Check if term positions are adjacent (exact phrase match)
-    fn check_adjacent_positions(&self, term_positions: &[Vec<u32>]) -> bool {
-        if term_positions.is_empty() {
-            return false;
-        }
-
-        for start_pos in &term_positions[0] {
-            let mut current_pos = *start_pos;
-            let mut found = true;
+    /// Positions of `term_text` in `doc_id`, or `None` if the document lacks the term
+    fn term_positions(&self, term_text: &str, doc_id: DocId) -> Result<Option<Vec<u32>>> {
+        let Some(posting_list) = self.index.search_term(&Term::new(term_text)) else {
+            return Ok(None);
+        };
+        Ok(posting_list
+            .iter()?
+            .into_iter()
+            .find(|posting| posting.doc_id == doc_id)
+            .map(|posting| posting.positions))
+    }
 
-            for positions in &term_positions[1..] {
-                current_pos += 1;
-                if !positions.contains(&current_pos) {
-                    found = false;
-                    break;
+    /// Each phrase term's posting in `doc_id`, in phrase order, or `None` if
+    /// the document lacks one of the terms
+    pub(crate) fn phrase_postings(&self, phrase: &[String], doc_id: DocId) -> Result<Option<Vec<Posting>>> {
+        if !self.phrase_intersection {
+            let mut postings = Vec::with_capacity(phrase.len());
+            for term_text in phrase {
+                let Some(posting_list) = self.index.search_term(&Term::new(term_text)) else {
+                    return Ok(None);
+                };
+                match posting_list.iter()?.into_iter().find(|posting| posting.doc_id == doc_id) {
+                    Some(posting) => postings.push(posting),
+                    None => return Ok(None),
                 }
             }
+            return Ok(Some(postings));
+        }
 
-            if found {
-                return true;
+        let cached = self.phrase_candidates.lock().get(phrase).cloned();
+        let candidates = match cached {
+            Some(candidates) => candidates,
+            None => {
+                let candidates = Arc::new(self.intersect_phrase(phrase)?);
+                self.phrase_candidates.lock().insert(phrase.to_vec(), candidates.clone());
+                candidates
             }
+        };
+        Ok(candidates.get(&doc_id).cloned())
+    }
+
+    /// Intersect the phrase terms' doc ids, then read each term's posting in
+    /// the remaining documents by index instead of by scanning
+    fn intersect_phrase(&self, phrase: &[String]) -> Result<PhraseCandidates> {
+        let terms: Vec<Term> = phrase.iter().map(|text| Term::new(text)).collect();
+        let doc_ids = self.index.intersect_terms(&terms)?;
+        let mut candidates: PhraseCandidates = doc_ids
+            .iter()
+            .map(|&doc_id| (doc_id, Vec::with_capacity(terms.len())))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(candidates);
         }
 
-        false
+        for term in &terms {
+            let Some(posting_list) = self.index.search_term(term) else {
+                return Ok(PhraseCandidates::new());
+            };
+            let list_ids = posting_list.decode_doc_ids()?;
+            for &doc_id in &doc_ids {
+                let Ok(i) = list_ids.binary_search(&(doc_id.0 as u32)) else {
+                    continue;
+                };
+                candidates.get_mut(&doc_id).unwrap().push(Posting {
+                    doc_id,
+                    term_freq: posting_list.term_freqs[i],
+                    positions: posting_list.positions[i].decode()?,
+                    field_norm: posting_list.field_norms[i],
+                });
+            }
+        }
+        candidates.retain(|_, postings| postings.len() == terms.len());
+        Ok(candidates)
     }
 
-    /// Check if term positions satisfy proximity constraint with slop
-    fn check_proximity_positions(&self, term_positions: &[Vec<u32>], slop: u32) -> bool {
+    /// Check if one position from every list fits in a window of
+    /// `term_positions.len() + slop` positions, in any order
+    fn check_unordered_window(&self, term_positions: &[Vec<u32>], slop: u32) -> bool {
         if term_positions.is_empty() {
             return false;
         }
 
-        for start_pos in &term_positions[0] {
-            let mut current_pos = *start_pos;
-            let mut found = true;
-
-            for positions in &term_positions[1..] {
-                let min_pos = current_pos + 1;
-                let max_pos = current_pos + slop + 1;
-
-                if let Some(&next_pos) = positions.iter()
-                    .find(|&&p| p >= min_pos && p <= max_pos)
-                {
-                    current_pos = next_pos;
-                } else {
-                    found = false;
-                    break;
-                }
+        let window = term_positions.len() as u32 + slop;
+
+        // Sweep all positions in order, tracking the smallest span covering every list
+        let mut events: Vec<(u32, usize)> = term_positions
+            .iter()
+            .enumerate()
+            .flat_map(|(list, positions)| positions.iter().map(move |&p| (p, list)))
+            .collect();
+        events.sort_unstable();
+
+        let mut counts = vec![0usize; term_positions.len()];
+        let mut covered = 0;
+        let mut start = 0;
+        for end in 0..events.len() {
+            let list = events[end].1;
+            counts[list] += 1;
+            if counts[list] == 1 {
+                covered += 1;
             }
 
-            if found {
-                return true;
+            while covered == term_positions.len() {
+                if events[end].0 - events[start].0 < window {
+                    return true;
+                }
+                let list = events[start].1;
+                counts[list] -= 1;
+                if counts[list] == 0 {
+                    covered -= 1;
+                }
+                start += 1;
             }
         }
 
         false
     }
 
+    /// Check if term positions are adjacent (exact phrase match)
+    fn check_adjacent_positions(&self, term_positions: &[Vec<u32>]) -> bool {
+        self.phrase_occurrences(term_positions, 0) > 0
+    }
+
+    /// Check if term positions satisfy proximity constraint with slop
+    fn check_proximity_positions(&self, term_positions: &[Vec<u32>], slop: u32) -> bool {
+        self.phrase_occurrences(term_positions, slop) > 0
+    }
+
+    /// Number of positions of the first term that start a match: each next
+    /// term follows the previous one with at most `slop` positions between
+    pub(crate) fn phrase_occurrences(&self, term_positions: &[Vec<u32>], slop: u32) -> usize {
+        let Some((first, rest)) = term_positions.split_first() else {
+            return 0;
+        };
+
+        first
+            .iter()
+            .filter(|&&start_pos| {
+                let mut current_pos = start_pos;
+                rest.iter().all(|positions| {
+                    let min_pos = current_pos + 1;
+                    let max_pos = current_pos + slop + 1;
+                    match positions.iter().find(|&&p| p >= min_pos && p <= max_pos) {
+                        Some(&next_pos) => {
+                            current_pos = next_pos;
+                            true
+                        }
+                        None => false,
+                    }
+                })
+            })
+            .count()
+    }
+
     fn number_in_range(&self, num: f64, range_query: &RangeQuery) -> bool {
-        if let Some(FieldValue::Number(gt)) = &range_query.gt {
-            if num <= *gt {
-                return false;
-            }
-        }
-        if let Some(FieldValue::Number(gte)) = &range_query.gte {
-            if num < *gte {
-                return false;
-            }
-        }
+        Self::within_bounds(&num, range_query, |bound| match bound {
+            FieldValue::Number(n) => Some(*n),
+            _ => None,
+        })
+    }
 
-        if let Some(FieldValue::Number(lt)) = &range_query.lt {
-            if num >= *lt {
-                return false;
-            }
-        }
-        if let Some(FieldValue::Number(lte)) = &range_query.lte {
-            if num > *lte {
-                return false;
-            }
-        }
+    fn date_in_range(&self, date: &DateTime<Utc>, range_query: &RangeQuery) -> bool {
+        Self::within_bounds(date, range_query, |bound| match bound {
+            FieldValue::Date(d) => Some(*d),
+            _ => None,
+        })
+    }
 
-        true
+    /// Whether `value` satisfies every bound set on the range. `bound` reads a
+    /// bound as the field's type; a bound of another type never matches.
+    fn within_bounds<T: PartialOrd>(
+        value: &T,
+        range_query: &RangeQuery,
+        bound: impl Fn(&FieldValue) -> Option<T>,
+    ) -> bool {
+        let holds = |limit: &Option<FieldValue>, ok: fn(&T, &T) -> bool| {
+            limit
+                .as_ref()
+                .is_none_or(|limit| bound(limit).is_some_and(|limit| ok(value, &limit)))
+        };
+        holds(&range_query.gt, |v, l| v > l)
+            && holds(&range_query.gte, |v, l| v >= l)
+            && holds(&range_query.lt, |v, l| v < l)
+            && holds(&range_query.lte, |v, l| v <= l)
     }
 
     /// Check if specific field contains text (case-insensitive)
@@ -329,18 +488,13 @@ impl SegmentSearch for SegmentReader {
     /// This is the search() method that M02 didn't have
     fn search(&self, query: &Query, matcher: &DocumentMatcher) -> Result<Vec<ScoredDocument>> {
         use std::io::{Read, Seek, SeekFrom};
-        use crate::compression::compress::CompressedBlock;
         let mut results = Vec::new();
 
         // Use lazy iteration pattern directly here
         let mut file = self.file.lock().unwrap();
 
-        // Seek to start of documents (read header size to skip it)
-        file.seek(SeekFrom::Start(0))?;
-        // Skip header by deserializing it (variable length)
-        let _: SegmentHeader = bincode::deserialize_from(&mut *file)?;
-
-        // Now positioned right after header, ready to read documents
+        // Seek to start of documents (past the header and any dictionary)
+        file.seek(SeekFrom::Start(self.data_start()))?;
 
         // Iterate through documents one by one
         for _ in 0..self.header.doc_count {
@@ -355,12 +509,7 @@ impl SegmentSearch for SegmentReader {
             let mut block_buf = vec![0u8; len];
             file.read_exact(&mut block_buf)?;
 
-            // Deserialize CompressedBlock (includes original_size metadata)
-            let compressed_block: CompressedBlock = bincode::deserialize(&block_buf)?;
-            let decompressed = compressed_block.decompress()?;
-
-            // Deserialize document
-            let doc: Document = bincode::deserialize(&decompressed)?;
+            let doc = self.decode_document(&block_buf)?;
 
             // Apply query matching
             if matcher.matches(&doc, query)? {
@@ -376,3 +525,175 @@ impl SegmentSearch for SegmentReader {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::token::Token;
+    use std::collections::HashMap;
+
+    /// Index one document and return a matcher over it
+    fn matcher_for(text: &str) -> (DocumentMatcher, Document) {
+        let tokens: Vec<Token> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| Token::new(word.to_string(), i as u32, 0))
+            .collect();
+        let mut index = InvertedIndex::new();
+        index.add_document(DocId(1), &tokens).unwrap();
+
+        let doc = Document {
+            id: DocId(1),
+            fields: HashMap::from([("content".to_string(), FieldValue::Text(text.to_string()))]),
+        };
+        (DocumentMatcher::new(Arc::new(index)), doc)
+    }
+
+    fn near(terms: &[&str], slop: u32, in_order: bool) -> Query {
+        Query::Near(NearQuery {
+            field: "content".to_string(),
+            clauses: terms
+                .iter()
+                .map(|t| {
+                    Query::Term(TermQuery {
+                        field: "content".to_string(),
+                        value: t.to_string(),
+                        boost: None,
+                    })
+                })
+                .collect(),
+            slop,
+            in_order,
+            boost: None,
+        })
+    }
+
+    #[test]
+    fn ordered_near_requires_clause_order() {
+        let (matcher, doc) = matcher_for("fast search engine for rust code");
+
+        assert!(matcher.matches(&doc, &near(&["search", "rust"], 2, true)).unwrap());
+        assert!(!matcher.matches(&doc, &near(&["rust", "search"], 2, true)).unwrap());
+        // Too far apart for the window
+        assert!(!matcher.matches(&doc, &near(&["search", "rust"], 1, true)).unwrap());
+    }
+
+    #[test]
+    fn unordered_near_accepts_either_order_within_same_window() {
+        let (matcher, doc) = matcher_for("fast search engine for rust code");
+
+        assert!(matcher.matches(&doc, &near(&["rust", "search"], 2, false)).unwrap());
+        assert!(matcher.matches(&doc, &near(&["search", "rust"], 2, false)).unwrap());
+        assert!(!matcher.matches(&doc, &near(&["rust", "search"], 1, false)).unwrap());
+
+        // Three clauses spread over positions 0, 1 and 4
+        assert!(matcher.matches(&doc, &near(&["rust", "fast", "search"], 2, false)).unwrap());
+        assert!(!matcher.matches(&doc, &near(&["rust", "fast", "search"], 1, false)).unwrap());
+        assert!(!matcher.matches(&doc, &near(&["rust", "missing"], 10, false)).unwrap());
+    }
+
+    #[test]
+    fn predicate_filters_on_comparison_between_fields() {
+        let number = |doc: &Document, field: &str| match doc.fields.get(field) {
+            Some(FieldValue::Number(n)) => Some(*n),
+            _ => None,
+        };
+        let over_budget = Query::Predicate(PredicateQuery::new("spent > budget", move |doc| {
+            matches!((number(doc, "spent"), number(doc, "budget")), (Some(s), Some(b)) if s > b)
+        }));
+
+        let matcher = DocumentMatcher::new(Arc::new(InvertedIndex::new()));
+        let docs: Vec<Document> = [(1, 120.0, 100.0), (2, 80.0, 100.0), (3, 100.0, 100.0), (4, 5.0, 1.0)]
+            .into_iter()
+            .map(|(id, spent, budget)| Document {
+                id: DocId(id),
+                fields: HashMap::from([
+                    ("spent".to_string(), FieldValue::Number(spent)),
+                    ("budget".to_string(), FieldValue::Number(budget)),
+                    ("content".to_string(), FieldValue::Text(format!("project {}", id))),
+                ]),
+            })
+            .collect();
+
+        let matching: Vec<u64> = docs
+            .iter()
+            .filter(|doc| matcher.matches(doc, &over_budget).unwrap())
+            .map(|doc| doc.id.0)
+            .collect();
+        assert_eq!(matching, vec![1, 4]);
+
+        // As a post-filter alongside other clauses
+        let filtered = Query::Bool(BoolQuery {
+            must: vec![Query::Term(TermQuery {
+                field: "content".to_string(),
+                value: "1".to_string(),
+                boost: None,
+            })],
+            filter: vec![over_budget.clone()],
+            ..BoolQuery::new()
+        });
+        assert!(matcher.matches(&docs[0], &filtered).unwrap());
+        assert!(!matcher.matches(&docs[3], &filtered).unwrap());
+
+        assert!(serde_json::to_string(&over_budget).is_err());
+    }
+
+    #[test]
+    fn date_range_compares_dates_and_rejects_other_bound_types() {
+        use crate::query::parser::QueryParser;
+
+        let (matcher, _) = matcher_for("unused");
+        let dated = |value: &str| Document {
+            id: DocId(1),
+            fields: HashMap::from([(
+                "published".to_string(),
+                FieldValue::Date(DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)),
+            )]),
+        };
+        let inclusive = QueryParser::new().parse("published:[2020-01-01 TO 2021-01-01]").unwrap();
+        let exclusive = QueryParser::new().parse("published:{2020-01-01 TO 2021-01-01}").unwrap();
+
+        for (date, in_inclusive, in_exclusive) in [
+            ("2019-12-31T23:59:59Z", false, false),
+            ("2020-01-01T00:00:00Z", true, false),
+            ("2020-06-15T12:00:00Z", true, true),
+            ("2021-01-01T00:00:00Z", true, false),
+            ("2021-01-01T00:00:01Z", false, false),
+        ] {
+            let doc = dated(date);
+            assert_eq!(matcher.matches(&doc, &inclusive).unwrap(), in_inclusive, "{}", date);
+            assert_eq!(matcher.matches(&doc, &exclusive).unwrap(), in_exclusive, "{}", date);
+        }
+
+        let numeric = QueryParser::new().parse("published:[0 TO 99999999999]").unwrap();
+        assert!(!matcher.matches(&dated("2020-06-15T12:00:00Z"), &numeric).unwrap());
+    }
+
+    #[test]
+    fn bool_requires_minimum_should_match_clauses() {
+        let (matcher, doc) = matcher_for("fast search engine for rust code");
+        let term = |value: &str| {
+            Query::Term(TermQuery { field: "content".to_string(), value: value.to_string(), boost: None })
+        };
+        let three_should = |minimum: Option<u32>, words: [&str; 3]| {
+            let mut bool_query = BoolQuery::new();
+            for word in words {
+                bool_query = bool_query.with_should(term(word));
+            }
+            bool_query.minimum_should_match = minimum;
+            Query::Bool(bool_query)
+        };
+
+        assert!(matcher.matches(&doc, &three_should(Some(2), ["rust", "search", "java"])).unwrap());
+        assert!(!matcher.matches(&doc, &three_should(Some(2), ["rust", "java", "python"])).unwrap());
+        assert!(matcher.matches(&doc, &three_should(Some(3), ["rust", "search", "code"])).unwrap());
+        // Without a minimum, one should clause is enough when nothing else is required
+        assert!(matcher.matches(&doc, &three_should(None, ["rust", "java", "python"])).unwrap());
+        assert!(!matcher.matches(&doc, &three_should(None, ["go", "java", "python"])).unwrap());
+
+        // Beside a must clause, should clauses are optional unless a minimum is set
+        let with_must = BoolQuery::new().with_must(term("fast")).with_should(term("java"));
+        assert!(matcher.matches(&doc, &Query::Bool(with_must.clone())).unwrap());
+        assert!(!matcher.matches(&doc, &Query::Bool(with_must.with_minimum_should_match(1))).unwrap());
+    }
+}
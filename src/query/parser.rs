@@ -1,9 +1,10 @@
 use crate::core::error::{Error, ErrorKind, Result};
-use crate::core::types::FieldValue;
+use crate::core::types::{DocId, FieldValue};
 use crate::query::ast::{
-    BoolQuery, FuzzyQuery, PhraseQuery, PrefixQuery, Query, RangeQuery, TermQuery, WildcardQuery,
+    BoolQuery, FuzzyQuery, IdsQuery, PhraseQuery, PrefixQuery, Query, RangeQuery, TermQuery, WildcardQuery,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::mem;
 
 /// Query parser for converting string queries to AST
 #[derive(Clone)]
@@ -36,143 +37,170 @@ impl QueryParser {
     /// - "rust AND programming" -> AND query
     /// - "title:rust" -> Field query
     /// - "\"exact phrase\"" -> Phrase query
-    /// - "price:[10 TO 100]" -> Range query
-    /// - "rust~2" -> Fuzzy query
-    /// - "rus*" -> Wildcard query
+    /// - "title:\"exact phrase\"" -> Phrase query on a field
+    /// - "price:[10 TO 100]" or "created:[2020-01-01 TO *]" -> Range query
+    /// - "rust~2" or "title:rust~1" -> Fuzzy query
+    /// - "rus*" or "title:rus*" -> Prefix query
+    /// - "ru?t" or "title:r*t" -> Wildcard query
+    /// - "_id:5" or "_id:5,8" -> Ids query
+    /// - "(a AND b) OR NOT c" -> Nested Bool query
+    /// - "title:rust^3" or "\"quick brown\"^2.5" -> Boosted query
+    ///
+    /// Grammar:
+    /// - Words are separated by whitespace outside double quotes and ranges;
+    ///   unescaped `(` and `)` are tokens of their own.
+    /// - `AND`, `OR` and `NOT` bind tightest to loosest as NOT > AND > OR;
+    ///   words without an operator between them combine with
+    ///   `default_operator`. Parentheses group, to any depth.
+    /// - `field:value` splits at the first `:` that is neither escaped nor
+    ///   quoted, so `url:http://x` searches `url` for `http://x`.
+    /// - A value in double quotes is literal; `:`, `[`, `*` and `~` inside
+    ///   it have no special meaning.
+    /// - A backslash makes the next character literal (`\:`, `\[`, `\*`,
+    ///   `\?`, `\~`, `\"`, `\\`, `\ `, `\^`). A trailing lone backslash is a parse error.
+    /// - A `^boost` suffix on a clause or a closing `)` multiplies its score;
+    ///   the boost must be a non-negative number.
+    /// - Range bounds are numbers, RFC 3339 timestamps or `YYYY-MM-DD` dates
+    ///   (midnight UTC); anything else is a text bound. A `*` bound leaves
+    ///   that side of the range open, and a quoted bound is read literally.
     pub fn parse(&self, input: &str) -> Result<Query> {
-        // Simplified parser implementation
-        let tokens: Vec<&str> = input.split_whitespace().collect();
+        let tokens = tokenize(input)?;
 
         if tokens.is_empty() {
             return Ok(Query::MatchAll);
         }
 
-        // Check for phrase query
-        if input.starts_with('"') && input.ends_with('"') {
-            let phrase = input.trim_matches('"');
-            let terms: Vec<String> = phrase.split_whitespace().map(String::from).collect();
-            return Ok(Query::Phrase(PhraseQuery {
-                field: self.default_field.clone(),
-                phrase: terms,
-                slop: 0,
-                boost: None,
-            }));
+        // Check for boolean operators and grouping
+        if tokens.iter().any(|t| matches!(t.as_str(), "AND" | "OR" | "NOT" | "(" | ")")) {
+            return self.parse_boolean_query(&tokens);
+        }
+
+        let input = input.trim();
+
+        // Check for field:value syntax
+        if find_unescaped(input, |c| c == ':').is_some() {
+            return self.parse_clause(input);
         }
 
-        // Check for boolean operators
-        if tokens.contains(&"AND") || tokens.contains(&"OR") || tokens.contains(&"NOT") {
+        // Several bare words: combine with the default operator
+        if tokens.len() > 1 {
             return self.parse_boolean_query(&tokens);
         }
 
-        // Check for field:value syntax
-        if let Some(pos) = input.find(':') {
-            let field = &input[..pos];
-            let value = &input[pos + 1..];
+        self.parse_clause(input)
+    }
 
-            // Check for range query
-            if value.starts_with('[') || value.starts_with('{') {
-                return self.parse_range_query(field, value);
-            }
+    /// Query for one `field:value` or bare (default field) value, with an
+    /// optional `^boost` suffix
+    fn parse_clause(&self, text: &str) -> Result<Query> {
+        if let Some(pos) = find_unescaped(text, |c| c == '^') {
+            let query = self.parse_clause(&text[..pos])?;
+            return with_boost(query, parse_boost(&text[pos + 1..])?);
+        }
 
-            if self.allow_wildcards
-                && value.ends_with('*')
-                && !value[..value.len().saturating_sub(1)].contains('*')
-                && !value.contains('?')
-            {
-                return Ok(Query::Prefix(PrefixQuery {
-                    field: field.to_string(),
-                    prefix: value.trim_end_matches('*').to_string(),
-                    boost: None,
-                }));
-            }
+        let Some(pos) = find_unescaped(text, |c| c == ':') else {
+            return self.parse_value(&self.default_field, text);
+        };
+        let field = &text[..pos];
+        let value = &text[pos + 1..];
 
-            if self.allow_wildcards && (value.contains('*') || value.contains('?')) {
-                return Ok(Query::Wildcard(WildcardQuery {
-                    field: field.to_string(),
-                    pattern: value.to_string(),
-                    boost: None,
-                }));
-            }
+        if field == "_id" {
+            return Self::parse_ids_query(value);
+        }
+
+        self.parse_value(field, value)
+    }
+
+    /// Query for a single `value` searched in `field`: a range, phrase,
+    /// fuzzy, prefix, wildcard or plain term
+    fn parse_value(&self, field: &str, value: &str) -> Result<Query> {
+        // Check for range query
+        if value.starts_with('[') || value.starts_with('{') {
+            return self.parse_range_query(field, value);
+        }
+
+        if is_quoted(value) {
+            return Ok(self.phrase_query(field, value));
+        }
 
-            return Ok(Query::Term(TermQuery {
+        // Check for fuzzy query (term~2 or term~)
+        if self.fuzzy_enabled
+            && let Some(pos) = find_unescaped(value, |c| c == '~')
+        {
+            let distance_str = &value[pos + 1..];
+            let distance = if distance_str.is_empty() {
+                1 // Default distance
+            } else {
+                distance_str.parse::<u8>().unwrap_or(2).min(2)
+            };
+            return Ok(Query::Fuzzy(FuzzyQuery {
                 field: field.to_string(),
-                value: value.to_string(),
+                term: unescape(&value[..pos]),
+                max_edits: Some(distance),
+                prefix_length: None,
                 boost: None,
             }));
         }
 
-        // Check for fuzzy query (term~2 or term~)
-        if self.fuzzy_enabled && input.contains('~') {
-            if let Some(pos) = input.find('~') {
-                let term = &input[..pos];
-                let distance_str = &input[pos + 1..];
-                let distance = if distance_str.is_empty() {
-                    1 // Default distance
-                } else {
-                    distance_str.parse::<u8>().unwrap_or(2).min(2)
-                };
-                return Ok(Query::Fuzzy(FuzzyQuery {
-                    field: self.default_field.clone(),
-                    term: term.to_string(),
-                    max_edits: Some(distance),
-                    prefix_length: None,
-                    boost: None,
-                }));
-            }
+        // Check for prefix (term*) and wildcard (te?m, t*m) queries
+        let wildcard = find_unescaped(value, |c| c == '*' || c == '?');
+        if self.allow_wildcards && wildcard.is_some_and(|pos| pos + 1 == value.len()) && value.ends_with('*') {
+            return Ok(Query::Prefix(PrefixQuery {
+                field: field.to_string(),
+                prefix: unescape(&value[..value.len() - 1]),
+                boost: None,
+            }));
         }
 
-        // Check for wildcard query (term* or term?)
-        if self.allow_wildcards && (input.contains('*') || input.contains('?')) {
+        if self.allow_wildcards && wildcard.is_some() {
             return Ok(Query::Wildcard(WildcardQuery {
-                field: self.default_field.clone(),
-                pattern: input.to_string(),
+                field: field.to_string(),
+                pattern: unescape(value),
                 boost: None,
             }));
         }
 
-        // Default to term query
         Ok(Query::Term(TermQuery {
-            field: self.default_field.clone(),
-            value: input.to_string(),
+            field: field.to_string(),
+            value: unescape(value),
             boost: None,
         }))
     }
 
-    fn parse_boolean_query(&self, tokens: &[&str]) -> Result<Query> {
-        let mut bool_query = BoolQuery::new();
-        let mut current_op = self.default_operator;
-        let mut pending_not = false;
+    /// Phrase query on `field` for a `"quoted"` value
+    fn phrase_query(&self, field: &str, quoted: &str) -> Query {
+        let phrase = unescape(&quoted[1..quoted.len() - 1]);
+        Query::Phrase(PhraseQuery {
+            field: field.to_string(),
+            phrase: phrase.split_whitespace().map(String::from).collect(),
+            slop: 0,
+            boost: None,
+        })
+    }
 
-        for token in tokens {
-            match *token {
-                "AND" => current_op = BooleanOperator::And,
-                "OR" => current_op = BooleanOperator::Or,
-                "NOT" => {
-                    // Mark the next term as must_not.
-                    pending_not = true;
-                    continue;
-                }
-                _ => {
-                    let term_query = Query::Term(TermQuery {
-                        field: self.default_field.clone(),
-                        value: token.to_string(),
-                        boost: None,
-                    });
-
-                    if pending_not {
-                        bool_query.must_not.push(term_query);
-                        pending_not = false;
-                    } else {
-                        match current_op {
-                            BooleanOperator::And => bool_query.must.push(term_query),
-                            BooleanOperator::Or => bool_query.should.push(term_query),
-                        }
-                    }
-                }
-            }
+    fn parse_boolean_query(&self, tokens: &[String]) -> Result<Query> {
+        let mut expr = BoolExprParser { parser: self, tokens, pos: 0 };
+        let clause = expr.parse_or()?;
+        if let Some(token) = expr.peek() {
+            return Err(Error::new(ErrorKind::Parse, format!("Unexpected '{}' in query", token)));
         }
 
-        Ok(Query::Bool(bool_query))
+        Ok(match clause {
+            Clause::Positive(query) => query,
+            Clause::Negated(query) => Query::Bool(BoolQuery::new().with_must_not(query)),
+        })
+    }
+
+    fn parse_ids_query(value: &str) -> Result<Query> {
+        let values = value
+            .split(',')
+            .map(|id| {
+                id.trim().parse::<u64>().map(DocId).map_err(|_| {
+                    Error::new(ErrorKind::Parse, format!("Invalid document id: '{}'", id))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Query::Ids(IdsQuery { values, boost: None }))
     }
 
     fn parse_range_query(&self, field: &str, value: &str) -> Result<Query> {
@@ -201,19 +229,27 @@ impl QueryParser {
             boost: None,
         };
 
-        let start_val = self.parse_field_value(parts[0]);
-        let end_val = self.parse_field_value(parts[1]);
+        // `*` leaves that side open; a quoted bound is taken literally
+        let bound = |part: &str| {
+            let part = part.trim();
+            if is_quoted(part) {
+                Some(self.parse_field_value(&unescape(&part[1..part.len() - 1])))
+            } else {
+                (part != "*").then(|| self.parse_field_value(&unescape(part)))
+            }
+        };
+        let (start_val, end_val) = (bound(parts[0]), bound(parts[1]));
 
         if inclusive_start {
-            range.gte = Some(start_val);
+            range.gte = start_val;
         } else {
-            range.gt = Some(start_val);
+            range.gt = start_val;
         }
 
         if inclusive_end {
-            range.lte = Some(end_val);
+            range.lte = end_val;
         } else {
-            range.lt = Some(end_val);
+            range.lt = end_val;
         }
 
         Ok(Query::Range(range))
@@ -224,12 +260,278 @@ impl QueryParser {
             FieldValue::Number(num)
         } else if let Ok(date) = DateTime::parse_from_rfc3339(s) {
             FieldValue::Date(date.with_timezone(&Utc))
+        } else if let Ok(day) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            // A bare date is midnight UTC
+            FieldValue::Date(day.and_time(NaiveTime::MIN).and_utc())
         } else {
             FieldValue::Text(s.to_string())
         }
     }
 }
 
+/// A sub-expression of a boolean query, negated when preceded by `NOT`
+enum Clause {
+    Positive(Query),
+    Negated(Query),
+}
+
+/// Recursive-descent parser over the tokens of a boolean query:
+///
+/// ```text
+/// or   := and (("OR" | <adjacent, default OR>) and)*
+/// and  := not (("AND" | <adjacent, default AND>) not)*
+/// not  := "NOT" not | atom
+/// atom := "(" or ")" | clause
+/// ```
+struct BoolExprParser<'a> {
+    parser: &'a QueryParser,
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl<'a> BoolExprParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    /// Whether the next token starts a clause that follows without an operator
+    fn at_adjacent_clause(&self, operator: BooleanOperator) -> bool {
+        let default_matches = matches!(
+            (self.parser.default_operator, operator),
+            (BooleanOperator::And, BooleanOperator::And) | (BooleanOperator::Or, BooleanOperator::Or)
+        );
+        default_matches && !matches!(self.peek(), None | Some("AND" | "OR" | ")"))
+    }
+
+    fn parse_or(&mut self) -> Result<Clause> {
+        let mut clauses = vec![self.parse_and()?];
+        loop {
+            if self.peek() == Some("OR") {
+                self.pos += 1;
+            } else if !self.at_adjacent_clause(BooleanOperator::Or) {
+                break;
+            }
+            clauses.push(self.parse_and()?);
+        }
+        Ok(combine(clauses, BooleanOperator::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Clause> {
+        let mut clauses = vec![self.parse_not()?];
+        loop {
+            if self.peek() == Some("AND") {
+                self.pos += 1;
+            } else if !self.at_adjacent_clause(BooleanOperator::And) {
+                break;
+            }
+            clauses.push(self.parse_not()?);
+        }
+        Ok(combine(clauses, BooleanOperator::And))
+    }
+
+    fn parse_not(&mut self) -> Result<Clause> {
+        if self.peek() == Some("NOT") {
+            self.pos += 1;
+            return Ok(match self.parse_not()? {
+                Clause::Positive(query) => Clause::Negated(query),
+                Clause::Negated(query) => Clause::Positive(query),
+            });
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Clause> {
+        let token = self.peek().ok_or_else(|| {
+            Error::new(ErrorKind::Parse, "Query ends where a term was expected".to_string())
+        })?;
+        self.pos += 1;
+
+        match token {
+            "(" => {
+                let clause = self.parse_or()?;
+                if self.peek() != Some(")") {
+                    return Err(Error::new(ErrorKind::Parse, "Unclosed '(' in query".to_string()));
+                }
+                self.pos += 1;
+
+                // `(...)^2` boosts the whole group
+                let Some(boost) = self.peek().and_then(|t| t.strip_prefix('^')) else {
+                    return Ok(clause);
+                };
+                self.pos += 1;
+                let boost = parse_boost(boost)?;
+                Ok(match clause {
+                    Clause::Positive(query) => Clause::Positive(with_boost(query, boost)?),
+                    Clause::Negated(query) => Clause::Negated(with_boost(query, boost)?),
+                })
+            }
+            "AND" | "OR" | ")" => Err(Error::new(
+                ErrorKind::Parse,
+                format!("Unexpected '{}' where a term was expected", token),
+            )),
+            clause => Ok(Clause::Positive(self.parser.parse_clause(clause)?)),
+        }
+    }
+}
+
+fn parse_boost(text: &str) -> Result<f32> {
+    match text.parse::<f32>() {
+        Ok(boost) if boost.is_finite() && boost >= 0.0 => Ok(boost),
+        _ => Err(Error::new(
+            ErrorKind::Parse,
+            format!("Invalid boost '{}': expected a non-negative number", text),
+        )),
+    }
+}
+
+/// Set the boost of a parsed query
+fn with_boost(mut query: Query, boost: f32) -> Result<Query> {
+    let target = match &mut query {
+        Query::Term(q) => &mut q.boost,
+        Query::Phrase(q) => &mut q.boost,
+        Query::Near(q) => &mut q.boost,
+        Query::DisMax(q) => &mut q.boost,
+        Query::Bool(q) => &mut q.boost,
+        Query::Range(q) => &mut q.boost,
+        Query::Prefix(q) => &mut q.boost,
+        Query::Wildcard(q) => &mut q.boost,
+        Query::Fuzzy(q) => &mut q.boost,
+        Query::Ids(q) => &mut q.boost,
+        Query::Boosting(_) | Query::Predicate(_) | Query::MatchAll => {
+            return Err(Error::new(ErrorKind::Parse, "This query cannot be boosted".to_string()));
+        }
+    };
+    *target = Some(boost);
+    Ok(query)
+}
+
+/// Join clauses under one operator: positives become `must` (AND) or
+/// `should` (OR) clauses and negations `must_not`. A lone clause is kept as is.
+fn combine(clauses: Vec<Clause>, operator: BooleanOperator) -> Clause {
+    if clauses.len() == 1 {
+        return clauses.into_iter().next().unwrap();
+    }
+
+    let mut bool_query = BoolQuery::new();
+    for clause in clauses {
+        let (target, query) = match (clause, operator) {
+            (Clause::Negated(query), _) => (&mut bool_query.must_not, query),
+            (Clause::Positive(query), BooleanOperator::And) => (&mut bool_query.must, query),
+            (Clause::Positive(query), BooleanOperator::Or) => (&mut bool_query.should, query),
+        };
+        // A repeated word is one term, not a double boost
+        if !target.contains(&query) {
+            target.push(query);
+        }
+    }
+    Clause::Positive(Query::Bool(bool_query))
+}
+
+/// Split `input` at whitespace that is neither escaped nor inside double
+/// quotes or a range (`[..]`/`{..}` opening a value), and around unescaped
+/// parentheses. Tokens keep their quotes, brackets and backslashes.
+fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_range = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        // A range bracket only counts where a value starts
+        let at_value_start = current.is_empty() || (current.ends_with(':') && !current.ends_with("\\:"));
+        match c {
+            '\\' => {
+                let escaped = chars.next().ok_or_else(|| {
+                    Error::new(ErrorKind::Parse, "Query ends with an unfinished escape".to_string())
+                })?;
+                current.push(c);
+                current.push(escaped);
+            }
+            '"' if !in_range => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            '[' | '{' if !in_quotes && at_value_start => {
+                in_range = true;
+                current.push(c);
+            }
+            ']' | '}' if in_range => {
+                in_range = false;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes && !in_range => {
+                if !current.is_empty() {
+                    tokens.push(mem::take(&mut current));
+                }
+            }
+            '(' | ')' if !in_quotes && !in_range => {
+                if !current.is_empty() {
+                    tokens.push(mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Byte offset of the first character matching `target` that is neither
+/// escaped nor inside double quotes
+fn find_unescaped(s: &str, target: impl Fn(char) -> bool) -> Option<usize> {
+    let mut in_quotes = false;
+    let mut chars = s.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if !in_quotes && target(c) => return Some(pos),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether `s` is a single double-quoted value
+fn is_quoted(s: &str) -> bool {
+    let Some(rest) = s.strip_prefix('"') else {
+        return false;
+    };
+    let mut chars = rest.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return pos == rest.len() - 1,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Remove escaping backslashes, keeping the characters they escape
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                out.push(escaped);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +571,307 @@ mod tests {
             _ => panic!("expected prefix query"),
         }
     }
+
+    #[test]
+    fn parse_bare_words_dedupes_repeats() {
+        let parser = QueryParser::new();
+        let Query::Bool(q) = parser.parse("rust rust programming").unwrap() else {
+            panic!("expected bool query");
+        };
+
+        let values: Vec<&str> = q
+            .should
+            .iter()
+            .map(|clause| match clause {
+                Query::Term(t) => t.value.as_str(),
+                _ => panic!("expected term query in should"),
+            })
+            .collect();
+        assert_eq!(values, vec!["rust", "programming"]);
+    }
+
+    fn term(query: Query) -> (String, String) {
+        match query {
+            Query::Term(t) => (t.field, t.value),
+            other => panic!("expected term query, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_value_containing_colons_splits_at_first_colon() {
+        let parser = QueryParser::new();
+        assert_eq!(
+            term(parser.parse("url:http://x").unwrap()),
+            ("url".to_string(), "http://x".to_string())
+        );
+        assert_eq!(
+            term(parser.parse(r"http\://x").unwrap()),
+            ("content".to_string(), "http://x".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_escaped_special_characters_as_literals() {
+        let parser = QueryParser::new();
+        assert_eq!(
+            term(parser.parse(r"title:\[draft\]").unwrap()),
+            ("title".to_string(), "[draft]".to_string())
+        );
+        assert_eq!(
+            term(parser.parse(r"title:\{x\}").unwrap()),
+            ("title".to_string(), "{x}".to_string())
+        );
+        assert_eq!(term(parser.parse(r"rust\*").unwrap()).1, "rust*");
+        assert_eq!(term(parser.parse(r"tag:c\+\+\~").unwrap()).1, "c++~");
+        assert_eq!(term(parser.parse(r"path:C\:\\temp").unwrap()).1, r"C:\temp");
+
+        let err = parser.parse(r"dangling\").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Parse));
+    }
+
+    #[test]
+    fn parse_quoted_values_keep_colons_and_spaces() {
+        let parser = QueryParser::new();
+
+        let Query::Phrase(phrase) = parser.parse(r#"title:"10:30 meeting""#).unwrap() else {
+            panic!("expected phrase query");
+        };
+        assert_eq!(phrase.field, "title");
+        assert_eq!(phrase.phrase, vec!["10:30", "meeting"]);
+
+        let Query::Phrase(phrase) = parser.parse(r#""ratio 3:1 \"exact\"""#).unwrap() else {
+            panic!("expected phrase query");
+        };
+        assert_eq!(phrase.field, "content");
+        assert_eq!(phrase.phrase, vec!["ratio", "3:1", "\"exact\""]);
+
+        let Query::Bool(q) = parser.parse(r#""a:b c" AND d"#).unwrap() else {
+            panic!("expected bool query");
+        };
+        assert!(matches!(&q.must[..], [Query::Phrase(p), Query::Term(t)] if p.phrase == ["a:b", "c"] && t.value == "d"));
+    }
+
+    #[test]
+    fn parse_fuzzy_with_and_without_field() {
+        let parser = QueryParser::new();
+        for (input, field, term, edits) in [
+            ("content:quik~1", "content", "quik", 1),
+            ("title:quik~", "title", "quik", 1),
+            ("quik~2", "content", "quik", 2),
+        ] {
+            let Query::Fuzzy(fuzzy) = parser.parse(input).unwrap() else {
+                panic!("expected fuzzy query for {}", input);
+            };
+            assert_eq!((fuzzy.field.as_str(), fuzzy.term.as_str()), (field, term));
+            assert_eq!(fuzzy.max_edits, Some(edits));
+        }
+
+        let parser = QueryParser { fuzzy_enabled: false, ..QueryParser::new() };
+        assert_eq!(term(parser.parse("content:quik~1").unwrap()).1, "quik~1");
+    }
+
+    #[test]
+    fn parse_wildcards_with_and_without_field() {
+        let parser = QueryParser::new();
+        for (input, field, pattern) in [("title:D?c*", "title", "D?c*"), ("ru?t", "content", "ru?t")] {
+            let Query::Wildcard(wildcard) = parser.parse(input).unwrap() else {
+                panic!("expected wildcard query for {}", input);
+            };
+            assert_eq!((wildcard.field.as_str(), wildcard.pattern.as_str()), (field, pattern));
+        }
+        // A lone trailing `*` is the cheaper prefix form of the same wildcard
+        for (input, field, prefix) in [("title:Doc*", "title", "Doc"), ("rus*", "content", "rus")] {
+            let Query::Prefix(p) = parser.parse(input).unwrap() else {
+                panic!("expected prefix query for {}", input);
+            };
+            assert_eq!((p.field.as_str(), p.prefix.as_str()), (field, prefix));
+        }
+
+        let parser = QueryParser { allow_wildcards: false, ..QueryParser::new() };
+        assert_eq!(term(parser.parse("title:Doc*").unwrap()).1, "Doc*");
+        assert_eq!(term(parser.parse("ru?t").unwrap()).1, "ru?t");
+    }
+
+    fn term_query(field: &str, value: &str) -> Query {
+        Query::Term(TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+            boost: None,
+        })
+    }
+
+    #[test]
+    fn parse_grouped_and_clauses_joined_by_or() {
+        let parser = QueryParser::new();
+        let parsed = parser
+            .parse("(content:quick AND content:brown) OR (content:lazy AND content:dog)")
+            .unwrap();
+
+        let expected = Query::Bool(
+            BoolQuery::new()
+                .with_should(Query::Bool(
+                    BoolQuery::new()
+                        .with_must(term_query("content", "quick"))
+                        .with_must(term_query("content", "brown")),
+                ))
+                .with_should(Query::Bool(
+                    BoolQuery::new()
+                        .with_must(term_query("content", "lazy"))
+                        .with_must(term_query("content", "dog")),
+                )),
+        );
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn parse_boolean_precedence_and_nested_groups() {
+        let parser = QueryParser::new();
+
+        // AND binds tighter than OR
+        let expected = Query::Bool(
+            BoolQuery::new()
+                .with_should(term_query("content", "a"))
+                .with_should(Query::Bool(
+                    BoolQuery::new()
+                        .with_must(term_query("content", "b"))
+                        .with_must_not(term_query("content", "c")),
+                )),
+        );
+        assert_eq!(parser.parse("a OR b AND NOT c").unwrap(), expected);
+
+        // Groups nest to any depth and keep their fields
+        let expected = Query::Bool(
+            BoolQuery::new().with_must(term_query("title", "rust")).with_must(Query::Bool(
+                BoolQuery::new().with_should(term_query("content", "async")).with_should(Query::Bool(
+                    BoolQuery::new()
+                        .with_must(term_query("content", "tokio"))
+                        .with_must(term_query("tag", "io")),
+                )),
+            )),
+        );
+        assert_eq!(
+            parser.parse("title:rust AND (async OR ((tokio AND tag:io)))").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_leading_not_group() {
+        let parser = QueryParser::new();
+        let expected = Query::Bool(
+            BoolQuery::new()
+                .with_must(term_query("content", "rust"))
+                .with_must_not(Query::Bool(
+                    BoolQuery::new()
+                        .with_should(term_query("content", "java"))
+                        .with_should(term_query("content", "go")),
+                )),
+        );
+        assert_eq!(parser.parse("NOT (java OR go) AND rust").unwrap(), expected);
+
+        let Query::Bool(alone) = parser.parse("NOT (java)").unwrap() else {
+            panic!("expected bool query");
+        };
+        assert_eq!(alone.must_not, vec![term_query("content", "java")]);
+        assert!(alone.must.is_empty() && alone.should.is_empty());
+
+        for malformed in ["(rust AND go", "rust)", "rust AND", "AND rust", "()"] {
+            let err = parser.parse(malformed).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::Parse), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn parse_boolean_keeps_each_clause_field_and_sub_expression() {
+        let parser = QueryParser::new();
+        let Query::Bool(q) = parser.parse("title:rust AND content:async").unwrap() else {
+            panic!("expected bool query");
+        };
+        assert_eq!(q.must, vec![term_query("title", "rust"), term_query("content", "async")]);
+
+        let Query::Bool(q) = parser
+            .parse(r#"price:[10 TO 100] AND (title:"quick fox" OR year:{2000 TO 2010])"#)
+            .unwrap()
+        else {
+            panic!("expected bool query");
+        };
+        let [Query::Range(price), Query::Bool(group)] = &q.must[..] else {
+            panic!("expected range and group, got {:?}", q.must);
+        };
+        assert_eq!(price.field, "price");
+        assert_eq!(price.gte, Some(FieldValue::Number(10.0)));
+        assert_eq!(price.lte, Some(FieldValue::Number(100.0)));
+        let [Query::Phrase(title), Query::Range(year)] = &group.should[..] else {
+            panic!("expected phrase and range, got {:?}", group.should);
+        };
+        assert_eq!(title.field, "title");
+        assert_eq!(title.phrase, vec!["quick", "fox"]);
+        assert_eq!(year.gt, Some(FieldValue::Number(2000.0)));
+        assert_eq!(year.lte, Some(FieldValue::Number(2010.0)));
+    }
+
+    #[test]
+    fn parse_boost_suffixes() {
+        let parser = QueryParser::new();
+        assert_eq!(
+            parser.parse("title:rust^3").unwrap(),
+            Query::Term(TermQuery {
+                field: "title".to_string(),
+                value: "rust".to_string(),
+                boost: Some(3.0),
+            })
+        );
+
+        let Query::Phrase(phrase) = parser.parse(r#""quick brown"^2.5"#).unwrap() else {
+            panic!("expected phrase query");
+        };
+        assert_eq!((phrase.phrase.len(), phrase.boost), (2, Some(2.5)));
+
+        let Query::Bool(q) = parser.parse("(rust OR go)^0.5 AND tag:new*^2").unwrap() else {
+            panic!("expected bool query");
+        };
+        assert!(matches!(&q.must[..], [Query::Bool(group), Query::Prefix(p)]
+            if group.boost == Some(0.5) && p.boost == Some(2.0)));
+
+        assert_eq!(term(parser.parse(r"c\^2").unwrap()).1, "c^2");
+    }
+
+    #[test]
+    fn parse_invalid_boost_is_an_error() {
+        let parser = QueryParser::new();
+        for input in ["rust^abc", "title:rust^", "rust^-1", "(a OR b)^x"] {
+            let err = parser.parse(input).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::Parse), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_open_ended_and_date_ranges() {
+        let parser = QueryParser::new();
+        let date = |text: &str| {
+            Some(FieldValue::Date(DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)))
+        };
+        let range = |input: &str| match parser.parse(input).unwrap() {
+            Query::Range(range) => range,
+            other => panic!("expected a range for {}, got {:?}", input, other),
+        };
+
+        let from = range("created:[2020-01-01T00:00:00Z TO *]");
+        assert_eq!(from.gte, date("2020-01-01T00:00:00Z"));
+        assert_eq!((from.gt, from.lt, from.lte), (None, None, None));
+
+        let until = range("created:{* TO 2021-06-30}");
+        assert_eq!(until.lt, date("2021-06-30T00:00:00Z"));
+        assert_eq!((until.gt, until.gte, until.lte), (None, None, None));
+
+        let spaced = range(r#"created:["2020-01-01 12:30:00+02:00" TO 2020-12-31T23:59:59Z]"#);
+        assert_eq!(spaced.gte, date("2020-01-01T10:30:00Z"));
+        assert_eq!(spaced.lte, date("2020-12-31T23:59:59Z"));
+
+        let literal = range(r#"name:["*" TO z]"#);
+        assert_eq!(literal.gte, Some(FieldValue::Text("*".to_string())));
+
+        let open = range("price:[* TO *]");
+        assert!(open.gt.is_none() && open.gte.is_none() && open.lt.is_none() && open.lte.is_none());
+    }
 }
@@ -1,16 +1,53 @@
-use crate::query::ast::{Query, TermQuery, PhraseQuery, BoolQuery, RangeQuery, PrefixQuery, WildcardQuery, FuzzyQuery};
-use crate::query::types::{IndexStatistics, SortOrder};
+use crate::query::ast::{Query, TermQuery, PhraseQuery, NearQuery, BoostingQuery, DisMaxQuery, BoolQuery, RangeQuery, PrefixQuery, WildcardQuery, FuzzyQuery, IdsQuery, PredicateQuery};
+use crate::core::types::FieldValue;
+use crate::query::types::{IndexStatistics, SortOrder, ValidationConfig};
 use crate::query::visitor::QueryVisitor;
 use crate::core::error::Result;
 
 /// Query planner creates execution plans
 pub struct QueryPlanner {
     pub statistics: IndexStatistics,
+    /// Integer ranges spanning more values than this are scanned, not expanded
+    pub max_range_terms: usize,
 }
 
 impl QueryPlanner {
     pub fn new(statistics: IndexStatistics) -> Self {
-        QueryPlanner { statistics }
+        QueryPlanner {
+            statistics,
+            max_range_terms: ValidationConfig::default().max_range_terms,
+        }
+    }
+
+    pub fn with_max_range_terms(mut self, max_range_terms: usize) -> Self {
+        self.max_range_terms = max_range_terms;
+        self
+    }
+
+    /// Terms of a bounded integer range, or `None` if the range is open,
+    /// fractional, empty or wider than `max_range_terms`.
+    /// The width is checked before anything is allocated.
+    fn range_terms(&self, q: &RangeQuery) -> Option<Vec<String>> {
+        let integral = |value: &Option<FieldValue>| match value {
+            Some(FieldValue::Number(n)) if n.is_finite() && n.fract() == 0.0 => Some(*n),
+            _ => None,
+        };
+        let low = match (&q.gte, &q.gt) {
+            (Some(_), _) => integral(&q.gte)?,
+            (None, Some(_)) => integral(&q.gt)? + 1.0,
+            (None, None) => return None,
+        };
+        let high = match (&q.lte, &q.lt) {
+            (Some(_), _) => integral(&q.lte)?,
+            (None, Some(_)) => integral(&q.lt)? - 1.0,
+            (None, None) => return None,
+        };
+
+        let width = high - low + 1.0;
+        if width < 1.0 || width > self.max_range_terms as f64 {
+            return None;
+        }
+        Some((low as i64..=high as i64).map(|n| n.to_string()).collect())
     }
 
     /// Create execution plan from query
@@ -38,6 +75,12 @@ impl QueryVisitor for QueryPlanner {
         })
     }
 
+    fn visit_near(&self, _q: &NearQuery) -> Result<LogicalPlan> {
+        Ok(LogicalPlan::Scan {
+            field: "content".to_string(),
+        })
+    }
+
     fn visit_bool(&self, q: &BoolQuery) -> Result<LogicalPlan> {
         if !q.must.is_empty() {
             let inputs = q.must
@@ -58,12 +101,36 @@ impl QueryVisitor for QueryPlanner {
         }
     }
 
-    fn visit_range(&self, _q: &RangeQuery) -> Result<LogicalPlan> {
+    fn visit_boosting(&self, _q: &BoostingQuery) -> Result<LogicalPlan> {
+        Ok(LogicalPlan::Scan {
+            field: "content".to_string(),
+        })
+    }
+
+    fn visit_dis_max(&self, _q: &DisMaxQuery) -> Result<LogicalPlan> {
         Ok(LogicalPlan::Scan {
             field: "content".to_string(),
         })
     }
 
+    fn visit_range(&self, q: &RangeQuery) -> Result<LogicalPlan> {
+        // Small integer ranges become a union of term seeks; anything else is scanned
+        match self.range_terms(q) {
+            Some(terms) => Ok(LogicalPlan::Union {
+                inputs: terms
+                    .into_iter()
+                    .map(|term| LogicalPlan::IndexSeek {
+                        field: q.field.clone(),
+                        term,
+                    })
+                    .collect(),
+            }),
+            None => Ok(LogicalPlan::Scan {
+                field: q.field.clone(),
+            }),
+        }
+    }
+
     fn visit_prefix(&self, _q: &PrefixQuery) -> Result<LogicalPlan> {
         Ok(LogicalPlan::Scan {
             field: "content".to_string(),
@@ -82,6 +149,18 @@ impl QueryVisitor for QueryPlanner {
         })
     }
 
+    fn visit_ids(&self, _q: &IdsQuery) -> Result<LogicalPlan> {
+        Ok(LogicalPlan::Scan {
+            field: "_id".to_string(),
+        })
+    }
+
+    fn visit_predicate(&self, _q: &PredicateQuery) -> Result<LogicalPlan> {
+        Ok(LogicalPlan::Scan {
+            field: "content".to_string(),
+        })
+    }
+
     fn visit_match_all(&self) -> Result<LogicalPlan> {
         Ok(LogicalPlan::Scan {
             field: "content".to_string(),
@@ -101,3 +180,41 @@ pub enum LogicalPlan {
     Intersection { inputs: Vec<LogicalPlan> },
     Difference { left: Box<LogicalPlan>, right: Box<LogicalPlan> },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(gte: f64, lte: f64) -> RangeQuery {
+        RangeQuery {
+            field: "year".to_string(),
+            gt: None,
+            gte: Some(FieldValue::Number(gte)),
+            lt: None,
+            lte: Some(FieldValue::Number(lte)),
+            boost: None,
+        }
+    }
+
+    #[test]
+    fn range_expansion_is_capped() {
+        let planner = QueryPlanner::new(IndexStatistics::from_index(&crate::index::inverted::InvertedIndex::new())).with_max_range_terms(10);
+
+        match planner.plan(&Query::Range(range(2020.0, 2022.0))) {
+            LogicalPlan::Union { inputs } => assert_eq!(inputs.len(), 3),
+            other => panic!("expected union of term seeks, got {:?}", other),
+        }
+
+        // Far past the cap: scanned without materializing any terms
+        match planner.plan(&Query::Range(range(0.0, 1e15))) {
+            LogicalPlan::Scan { field } => assert_eq!(field, "year"),
+            other => panic!("expected scan, got {:?}", other),
+        }
+
+        // Fractional bounds can't be enumerated
+        assert!(matches!(
+            planner.plan(&Query::Range(range(0.5, 2.0))),
+            LogicalPlan::Scan { .. }
+        ));
+    }
+}
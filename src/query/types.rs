@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use crate::core::error::{Error, ErrorKind, Result};
 use crate::index::inverted::InvertedIndex;
-use crate::query::ast::{Query, TermQuery, PhraseQuery, BoolQuery, RangeQuery, PrefixQuery, WildcardQuery, FuzzyQuery};
+use crate::query::ast::{Query, TermQuery, PhraseQuery, NearQuery, BoostingQuery, DisMaxQuery, BoolQuery, RangeQuery, PrefixQuery, WildcardQuery, FuzzyQuery, IdsQuery, PredicateQuery};
 use crate::query::planner::LogicalPlan;
 use crate::query::visitor::QueryVisitor;
 
@@ -30,31 +30,24 @@ pub struct FieldStatistics {
     pub unique_terms: usize,
 }
 
+#[cfg(test)]
+thread_local! {
+    /// Number of `IndexStatistics::from_index` calls on this thread (tests only)
+    pub(crate) static FROM_INDEX_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 impl IndexStatistics {
     /// Create statistics from an inverted index
     pub fn from_index(index: &InvertedIndex) -> Self {
-        let total_docs = index.doc_count;
-        let total_terms = index.dictionary.len();
-
-        // Calculate average document length (sum of all term frequencies)
-        let mut total_length = 0;
-        for posting_list in index.postings.values() {
-            if let Ok(postings) = posting_list.iter() {
-                for posting in postings {
-                    total_length += posting.term_freq;
-                }
-            }
-        }
-        let avg_doc_length = if total_docs > 0 {
-            total_length as f32 / total_docs as f32
-        } else {
-            0.0
-        };
+        #[cfg(test)]
+        FROM_INDEX_CALLS.with(|calls| calls.set(calls.get() + 1));
 
+        // Document count and total length are maintained incrementally by the
+        // index, so this is O(1) rather than a scan over every posting list
         IndexStatistics {
-            total_docs,
-            total_terms,
-            avg_doc_length,
+            total_docs: index.doc_count,
+            total_terms: index.dictionary.len(),
+            avg_doc_length: index.avg_doc_length(),
             field_stats: HashMap::new(),
         }
     }
@@ -100,7 +93,7 @@ impl CostModel {
             }
             LogicalPlan::Limit { n, input } => {
                 let input_cost = self.estimate_cost(input, stats);
-                input_cost * (*n as f32 / stats.total_docs as f32)
+                input_cost * (*n as f32 / stats.total_docs.max(1) as f32)
             }
             _ => 1.0,
         }
@@ -113,7 +106,14 @@ pub struct ValidationConfig {
     pub max_bool_clauses: usize,
     pub max_query_depth: usize,
     pub max_wildcard_terms: usize,
+    /// Most terms a numeric range is rewritten into; wider ranges are scanned
+    pub max_range_terms: usize,
     pub allow_leading_wildcard: bool,
+    /// Fields indexed without positions; phrase queries on them are rejected
+    pub positionless_fields: HashSet<String>,
+    /// Fields queries may name; queries on any other field are rejected
+    /// (None = any field, which simply matches nothing if never indexed)
+    pub known_fields: Option<HashSet<String>>,
 }
 
 impl Default for ValidationConfig {
@@ -122,7 +122,10 @@ impl Default for ValidationConfig {
             max_bool_clauses: 1024,
             max_query_depth: 10,
             max_wildcard_terms: 1000,
+            max_range_terms: 1024,
             allow_leading_wildcard: false,
+            positionless_fields: HashSet::new(),
+            known_fields: None,
         }
     }
 }
@@ -170,16 +173,55 @@ impl QueryValidator {
 
         Ok(())
     }
+
+    fn validate_field(&self, field: &str) -> Result<()> {
+        let Some(known_fields) = &self.config.known_fields else {
+            return Ok(());
+        };
+        if known_fields.contains(field) {
+            return Ok(());
+        }
+        let mut known: Vec<&str> = known_fields.iter().map(String::as_str).collect();
+        known.sort_unstable();
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Query references unknown field '{}' (schema fields: {})", field, known.join(", ")),
+        ))
+    }
 }
 
 impl QueryVisitor for QueryValidator {
     type Output = ();
 
-    fn visit_term(&self, _q: &TermQuery) -> Result<()> {
+    fn visit_term(&self, q: &TermQuery) -> Result<()> {
+        self.validate_field(&q.field)
+    }
+
+    fn visit_phrase(&self, q: &PhraseQuery) -> Result<()> {
+        self.validate_field(&q.field)?;
+        if self.config.positionless_fields.contains(&q.field) {
+            return Err(Error::new(
+                ErrorKind::UnsupportedQuery,
+                format!("Phrase query on field '{}', which is indexed without positions", q.field),
+            ));
+        }
         Ok(())
     }
 
-    fn visit_phrase(&self, _q: &PhraseQuery) -> Result<()> {
+    fn visit_near(&self, q: &NearQuery) -> Result<()> {
+        self.validate_field(&q.field)?;
+        if self.config.positionless_fields.contains(&q.field) {
+            return Err(Error::new(
+                ErrorKind::UnsupportedQuery,
+                format!("Near query on field '{}', which is indexed without positions", q.field),
+            ));
+        }
+        if q.clauses.is_empty() || !q.clauses.iter().all(|c| matches!(c, Query::Term(_))) {
+            return Err(Error::new(
+                ErrorKind::UnsupportedQuery,
+                "Near query needs at least one clause, and only term clauses".to_string(),
+            ));
+        }
         Ok(())
     }
 
@@ -202,19 +244,58 @@ impl QueryVisitor for QueryValidator {
         Ok(())
     }
 
-    fn visit_range(&self, _q: &RangeQuery) -> Result<()> {
-        Ok(())
+    fn visit_boosting(&self, q: &BoostingQuery) -> Result<()> {
+        if !(0.0..=1.0).contains(&q.negative_boost) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("negative_boost must be within [0, 1], got {}", q.negative_boost),
+            ));
+        }
+        q.positive.accept(self)?;
+        q.negative.accept(self)
     }
 
-    fn visit_prefix(&self, _q: &PrefixQuery) -> Result<()> {
+    fn visit_dis_max(&self, q: &DisMaxQuery) -> Result<()> {
+        if q.queries.len() > self.config.max_bool_clauses {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("DisMax query has {} clauses, max is {}",
+                        q.queries.len(), self.config.max_bool_clauses)
+            ));
+        }
+        if !(0.0..=1.0).contains(&q.tie_breaker) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("tie_breaker must be within [0, 1], got {}", q.tie_breaker),
+            ));
+        }
+        for query in &q.queries {
+            query.accept(self)?;
+        }
         Ok(())
     }
 
-    fn visit_wildcard(&self, _q: &WildcardQuery) -> Result<()> {
+    fn visit_range(&self, q: &RangeQuery) -> Result<()> {
+        self.validate_field(&q.field)
+    }
+
+    fn visit_prefix(&self, q: &PrefixQuery) -> Result<()> {
+        self.validate_field(&q.field)
+    }
+
+    fn visit_wildcard(&self, q: &WildcardQuery) -> Result<()> {
+        self.validate_field(&q.field)
+    }
+
+    fn visit_fuzzy(&self, q: &FuzzyQuery) -> Result<()> {
+        self.validate_field(&q.field)
+    }
+
+    fn visit_ids(&self, _q: &IdsQuery) -> Result<()> {
         Ok(())
     }
 
-    fn visit_fuzzy(&self, _q: &FuzzyQuery) -> Result<()> {
+    fn visit_predicate(&self, _q: &PredicateQuery) -> Result<()> {
         Ok(())
     }
 
@@ -1,6 +1,6 @@
 use crate::query::ast::{
-    TermQuery, PhraseQuery, BoolQuery, RangeQuery,
-    PrefixQuery, WildcardQuery, FuzzyQuery,
+    TermQuery, PhraseQuery, NearQuery, BoostingQuery, DisMaxQuery, BoolQuery, RangeQuery,
+    PrefixQuery, WildcardQuery, FuzzyQuery, IdsQuery, PredicateQuery,
 };
 use crate::core::error::Result;
 
@@ -11,10 +11,15 @@ pub trait QueryVisitor {
 
     fn visit_term(&self, query: &TermQuery)         -> Result<Self::Output>;
     fn visit_phrase(&self, query: &PhraseQuery)     -> Result<Self::Output>;
+    fn visit_near(&self, query: &NearQuery)         -> Result<Self::Output>;
     fn visit_bool(&self, query: &BoolQuery)         -> Result<Self::Output>;
+    fn visit_boosting(&self, query: &BoostingQuery) -> Result<Self::Output>;
+    fn visit_dis_max(&self, query: &DisMaxQuery)    -> Result<Self::Output>;
     fn visit_range(&self, query: &RangeQuery)       -> Result<Self::Output>;
     fn visit_prefix(&self, query: &PrefixQuery)     -> Result<Self::Output>;
     fn visit_wildcard(&self, query: &WildcardQuery) -> Result<Self::Output>;
     fn visit_fuzzy(&self, query: &FuzzyQuery)       -> Result<Self::Output>;
+    fn visit_ids(&self, query: &IdsQuery)           -> Result<Self::Output>;
+    fn visit_predicate(&self, query: &PredicateQuery) -> Result<Self::Output>;
     fn visit_match_all(&self)                       -> Result<Self::Output>;
 }
@@ -0,0 +1,77 @@
+use crate::core::types::{DocId, Document};
+use crate::query::cache::CacheStats;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// LRU cache of decompressed documents for the read path.
+///
+/// Entries remember the snapshot version they were read from and only serve
+/// readers of that version, so a document re-added and flushed into a newer
+/// snapshot is never answered from an older copy.
+pub struct DocumentCache {
+    cache: Mutex<LruCache<DocId, CachedDocument>>,
+    pub capacity: usize,
+    hit_count: AtomicUsize,
+    miss_count: AtomicUsize,
+}
+
+struct CachedDocument {
+    version: u64,
+    document: Arc<Document>,
+}
+
+impl DocumentCache {
+    pub fn new(capacity: usize) -> Self {
+        DocumentCache {
+            cache: Mutex::new(LruCache::new(NonZeroUsize::new(capacity.max(1)).unwrap())),
+            capacity,
+            hit_count: AtomicUsize::new(0),
+            miss_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Cached copy of `doc_id` as seen by snapshot `version`
+    pub fn get(&self, doc_id: DocId, version: u64) -> Option<Arc<Document>> {
+        let mut cache = self.cache.lock();
+        match cache.get(&doc_id) {
+            Some(cached) if cached.version == version => {
+                self.hit_count.fetch_add(1, Ordering::Relaxed);
+                Some(cached.document.clone())
+            }
+            _ => {
+                self.miss_count.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, document: Arc<Document>, version: u64) {
+        let mut cache = self.cache.lock();
+        // Never let a reader of an older snapshot replace a newer copy
+        if cache.peek(&document.id).is_some_and(|cached| cached.version > version) {
+            return;
+        }
+        cache.put(document.id, CachedDocument { version, document });
+    }
+
+    /// Drop the cached copy of a deleted or updated document
+    pub fn invalidate(&self, doc_id: DocId) {
+        self.cache.lock().pop(&doc_id);
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hit_count: self.hit_count.load(Ordering::Relaxed),
+            miss_count: self.miss_count.load(Ordering::Relaxed),
+            size: self.cache.lock().len(),
+            capacity: self.capacity,
+        }
+    }
+}
@@ -2,4 +2,5 @@ pub mod reader_pool;
 pub mod snapshot_reader;
 pub mod lazy;
 pub mod segment_opener;
-pub mod read_guard;
\ No newline at end of file
+pub mod read_guard;
+pub mod document_cache;
@@ -1,16 +1,21 @@
 use crate::core::error::Result;
+use crate::core::types::{DocId, Document};
 use crate::index::inverted::InvertedIndex;
 use crate::mvcc::controller::{MVCCController, Snapshot};
 use crate::query::ast::Query;
 use crate::query::matcher::{DocumentMatcher, SegmentSearch};
-use crate::search::results::{ScoredDocument, SearchResults};
+use crate::reader::document_cache::DocumentCache;
+use crate::search::results::{merge_top_k, ScoredDocument, SearchResults};
 use crate::storage::layout::StorageLayout;
+use crate::storage::segment::SegmentId;
 use crate::storage::segment_reader::SegmentReader;
 use parking_lot::RwLock;
 use roaring::RoaringBitmap;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 /// Pool of index readers with caching to prevent memory leak
 pub struct ReaderPool {
@@ -20,10 +25,22 @@ pub struct ReaderPool {
     pub storage: Arc<StorageLayout>,
     pub index: Arc<InvertedIndex>,
     /// Cache readers by snapshot version to reuse them
-    reader_cache: Arc<RwLock<HashMap<u64, Arc<IndexReader>>>>,
+    reader_cache: Arc<RwLock<HashMap<u64, PooledReader>>>,
     /// Track open segment readers for proper cleanup
     segment_reader_cache: Arc<RwLock<HashMap<(u64, usize), Arc<RwLock<SegmentReader>>>>>,
     segment_open_failures: Arc<AtomicU64>,
+    /// Cached readers unused for longer than this are evicted (`None` = never)
+    idle_timeout: Option<Duration>,
+    /// Reference point for `PooledReader::last_used`
+    epoch: Instant,
+    /// Shared by every reader of this pool (`None` = documents are always read from segments)
+    document_cache: Option<Arc<DocumentCache>>,
+}
+
+/// Cached reader plus its last access time (millis since the pool epoch)
+struct PooledReader {
+    reader: Arc<IndexReader>,
+    last_used: AtomicU64,
 }
 
 /// Index reader with snapshot
@@ -32,6 +49,7 @@ pub struct IndexReader {
     pub segments: Vec<Arc<RwLock<SegmentReader>>>,
     pub deleted_docs: Arc<RoaringBitmap>,
     pub index: Arc<InvertedIndex>,
+    pub document_cache: Option<Arc<DocumentCache>>,
 }
 
 impl ReaderPool {
@@ -50,9 +68,32 @@ impl ReaderPool {
             reader_cache: Arc::new(RwLock::new(HashMap::new())),
             segment_reader_cache: Arc::new(RwLock::new(HashMap::new())),
             segment_open_failures: Arc::new(AtomicU64::new(0)),
+            idle_timeout: None,
+            epoch: Instant::now(),
+            document_cache: None,
         }
     }
 
+    /// Cache up to `capacity` documents fetched by id
+    pub fn with_document_cache(mut self, capacity: usize) -> Self {
+        self.document_cache = Some(Arc::new(DocumentCache::new(capacity)));
+        self
+    }
+
+    pub fn document_cache(&self) -> Option<&Arc<DocumentCache>> {
+        self.document_cache.as_ref()
+    }
+
+    /// Evict cached readers that have not been used for `timeout`
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
     pub fn get_reader(&self) -> Result<Arc<IndexReader>> {
         let snapshot = self.mvcc.current_snapshot();
         let version = snapshot.version;
@@ -60,8 +101,9 @@ impl ReaderPool {
         // Check if we have a cached reader for this snapshot version
         {
             let cache = self.reader_cache.read();
-            if let Some(cached_reader) = cache.get(&version) {
-                return Ok(cached_reader.clone());
+            if let Some(cached) = cache.get(&version) {
+                cached.last_used.store(self.now_millis(), Ordering::Relaxed);
+                return Ok(cached.reader.clone());
             }
         }
 
@@ -71,9 +113,16 @@ impl ReaderPool {
         // Cache the reader for future use
         {
             let mut cache = self.reader_cache.write();
-            cache.insert(version, reader.clone());
-
-            // Clean up old cached readers if we exceed max_readers
+            cache.insert(
+                version,
+                PooledReader {
+                    reader: reader.clone(),
+                    last_used: AtomicU64::new(self.now_millis()),
+                },
+            );
+
+            // Drop readers nobody has used recently, then enforce the size cap
+            self.evict_idle_locked(&mut cache, version);
             if cache.len() > self.max_readers {
                 self.cleanup_old_readers(&mut cache);
             }
@@ -132,11 +181,60 @@ impl ReaderPool {
             segments: segment_readers,
             deleted_docs,
             index: self.index.clone(),
+            document_cache: self.document_cache.clone(),
         }))
     }
 
+    /// Evict readers idle for longer than the configured timeout, closing
+    /// their segment readers. Returns the number of readers evicted.
+    pub fn evict_idle_readers(&self) -> usize {
+        let current = self.mvcc.current_snapshot().version;
+        let mut cache = self.reader_cache.write();
+        self.evict_idle_locked(&mut cache, current)
+    }
+
+    fn evict_idle_locked(&self, cache: &mut HashMap<u64, PooledReader>, keep_version: u64) -> usize {
+        let Some(timeout) = self.idle_timeout else {
+            return 0;
+        };
+        let now = self.now_millis();
+        let timeout_ms = timeout.as_millis() as u64;
+
+        let idle: Vec<u64> = cache
+            .iter()
+            .filter(|(version, pooled)| {
+                **version != keep_version
+                    && now.saturating_sub(pooled.last_used.load(Ordering::Relaxed)) >= timeout_ms
+            })
+            .map(|(version, _)| *version)
+            .collect();
+
+        for version in &idle {
+            cache.remove(version);
+            self.cleanup_segment_readers(*version);
+        }
+        idle.len()
+    }
+
+    /// Drop every cached reader (e.g. under memory pressure).
+    /// Readers still held by callers stay valid until they are dropped.
+    pub fn clear(&self) {
+        self.reader_cache.write().clear();
+        self.segment_reader_cache.write().clear();
+    }
+
+    /// Number of readers currently cached
+    pub fn cached_reader_count(&self) -> usize {
+        self.reader_cache.read().len()
+    }
+
+    /// Number of segment readers (open segment files) held by the pool
+    pub fn open_segment_reader_count(&self) -> usize {
+        self.segment_reader_cache.read().len()
+    }
+
     /// Clean up old readers when cache is full
-    fn cleanup_old_readers(&self, cache: &mut HashMap<u64, Arc<IndexReader>>) {
+    fn cleanup_old_readers(&self, cache: &mut HashMap<u64, PooledReader>) {
         // Keep only the most recent readers
         let mut versions: Vec<u64> = cache.keys().cloned().collect();
         versions.sort();
@@ -165,60 +263,119 @@ impl ReaderPool {
 }
 
 impl IndexReader {
+    /// Fetch a live document by id, from the document cache when possible
+    pub fn get_document(&self, doc_id: DocId) -> Result<Option<Arc<Document>>> {
+        if self.deleted_docs.contains(doc_id.0 as u32) {
+            return Ok(None);
+        }
+        let version = self.snapshot.version;
+        if let Some(doc) = self.document_cache.as_ref().and_then(|c| c.get(doc_id, version)) {
+            return Ok(Some(doc));
+        }
+
+        let Some((_, doc)) = self.locate_document(doc_id)? else {
+            return Ok(None);
+        };
+        let doc = Arc::new(doc);
+        self.cache_document(doc.clone());
+        Ok(Some(doc))
+    }
+
+    /// The live copy of a document and the segment holding it, read from
+    /// storage (bypassing the document cache)
+    pub fn locate_document(&self, doc_id: DocId) -> Result<Option<(SegmentId, Document)>> {
+        if self.deleted_docs.contains(doc_id.0 as u32) {
+            return Ok(None);
+        }
+
+        // Newest segment first so a re-added document wins over its old copy
+        for reader in self.segments.iter().rev() {
+            let reader = reader.read();
+            // Skip segments whose id range rules the document out
+            let excluded = self
+                .snapshot
+                .segments
+                .iter()
+                .find(|segment| segment.id == reader.segment_id)
+                .is_some_and(|segment| !segment.metadata.may_contain(doc_id));
+            if excluded {
+                continue;
+            }
+            if let Some(doc) = reader.get_document(doc_id)? {
+                return Ok(Some((reader.segment_id, doc)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Segment readers ordered by segment creation time, newest first.
+    /// Segments missing from the snapshot sort last.
+    pub fn segments_newest_first(&self) -> Vec<Arc<RwLock<SegmentReader>>> {
+        let created_at = |reader: &Arc<RwLock<SegmentReader>>| {
+            let id = reader.read().segment_id;
+            self.snapshot
+                .segments
+                .iter()
+                .find(|segment| segment.id == id)
+                .map(|segment| segment.metadata.created_at)
+        };
+        let mut ordered: Vec<_> = self.segments.iter().map(|r| (created_at(r), r.clone())).collect();
+        ordered.sort_by_key(|(created_at, _)| std::cmp::Reverse(*created_at));
+        ordered.into_iter().map(|(_, reader)| reader).collect()
+    }
+
+    /// Offer a document read by this reader (e.g. a search hit) to the cache
+    pub fn cache_document(&self, doc: Arc<Document>) {
+        if let Some(cache) = &self.document_cache {
+            cache.insert(doc, self.snapshot.version);
+        }
+    }
+
     pub fn search(&self, query: &Query) -> Result<SearchResults> {
         self.search_with_limit(query, usize::MAX) // No limit by default
     }
 
     pub fn search_with_limit(&self, query: &Query, limit: usize) -> Result<SearchResults> {
         let matcher = DocumentMatcher::new(self.index.clone());
-        let mut all_results = Vec::new();
-
-        // Early termination optimization: if we have enough high-scoring results,
-        // we can stop searching segments early (especially useful for sorted segments)
-        let early_termination_threshold = limit * 3; // Collect 3x the limit then stop
+        let mut per_segment = Vec::with_capacity(self.segments.len());
+        let mut matched = HashSet::new();
 
-        // Search each segment using M05's extension trait
+        // Every segment contributes its own top `limit`: stopping early or
+        // keeping fewer could drop a hit that beats everything elsewhere
         for segment_reader in &self.segments {
-            // Check if we can terminate early
-            if all_results.len() >= early_termination_threshold && limit < usize::MAX {
-                // We have enough candidates, check if we should continue
-                // Sort to see if lower segments could have better scores
-                all_results.sort_by(|a: &ScoredDocument, b: &ScoredDocument| {
-                    b.score.partial_cmp(&a.score).unwrap()
-                });
-
-                // If the worst score in our top-K is good enough, we can stop
-                if all_results.len() >= limit {
-                    let kth_score = all_results[limit - 1].score;
-                    // Simple heuristic: if kth score is > 0.5, probably good enough
-                    if kth_score > 0.5 {
-                        break; // Early termination
-                    }
-                }
-            }
-
             let reader = segment_reader.read(); // Use READ lock for concurrent reads
-            let results = reader.search(query, &matcher)?;
-            all_results.extend(results);
-        }
+            let mut results = reader.search(query, &matcher)?;
+            results.retain(|doc| !self.deleted_docs.contains(doc.doc_id.0 as u32));
+            matched.extend(results.iter().map(|doc| doc.doc_id));
 
-        // Filter deleted documents
-        all_results.retain(|doc| !self.deleted_docs.contains(doc.doc_id.0 as u32));
+            if results.len() > limit {
+                results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.doc_id.0.cmp(&b.doc_id.0)));
+                results.truncate(limit);
+            }
+            per_segment.push(results);
+        }
 
-        // Sort and take top K results
-        all_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        // After a merge or compaction a doc can still sit in an old and a new
+        // segment of the same snapshot; keep its best-scoring copy only
+        let mut best: HashMap<DocId, ScoredDocument> = HashMap::new();
+        for hit in per_segment.into_iter().flatten() {
+            match best.entry(hit.doc_id) {
+                Entry::Occupied(mut entry) if entry.get().score < hit.score => {
+                    entry.insert(hit);
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(entry) => {
+                    entry.insert(hit);
+                }
+            }
+        }
 
-        let total_hits = all_results.len();
+        let all_results = merge_top_k(vec![best.into_values().collect()], limit);
         let max_score = all_results.first().map(|h| h.score).unwrap_or(0.0);
 
-        // Truncate to limit if specified
-        if limit < usize::MAX && all_results.len() > limit {
-            all_results.truncate(limit);
-        }
-
         Ok(SearchResults {
             hits: all_results,
-            total_hits,
+            total_hits: matched.len(),
             max_score,
             took_ms: 0,
         })
@@ -256,4 +413,97 @@ mod tests {
         assert!(reader.segments.is_empty());
         assert_eq!(pool.segment_open_failure_count(), 1);
     }
+
+    #[test]
+    fn idle_readers_are_evicted_and_segment_readers_closed() {
+        use crate::core::types::{Document, FieldValue};
+        use crate::memory::buffer_pool::BufferPool;
+        use crate::storage::segment_writer::SegmentWriter;
+        use crate::compression::compress::CompressionType;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+
+        let mut writer = SegmentWriter::new(
+            &storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            CompressionType::LZ4,
+        )
+        .unwrap();
+        writer
+            .write_document(&Document {
+                id: DocId(1),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("idle".to_string()),
+                )]),
+            })
+            .unwrap();
+        let segment = writer.finish(&storage).unwrap();
+        mvcc.create_snapshot(vec![Arc::new(segment.clone())]);
+
+        let pool = ReaderPool::new(mvcc.clone(), storage, Arc::new(InvertedIndex::new()), 8)
+            .with_idle_timeout(Duration::from_millis(50));
+
+        let reader = pool.get_reader().unwrap();
+        let segment_reader = Arc::downgrade(&reader.segments[0]);
+        drop(reader);
+        assert_eq!(pool.open_segment_reader_count(), 1);
+
+        // A newer snapshot makes the first reader evictable once it goes idle
+        mvcc.create_snapshot(vec![Arc::new(segment)]);
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(pool.evict_idle_readers(), 1);
+        assert_eq!(pool.cached_reader_count(), 0);
+        assert_eq!(pool.open_segment_reader_count(), 0);
+        assert!(segment_reader.upgrade().is_none(), "segment file still open");
+    }
+
+    #[test]
+    fn doc_in_two_segments_of_a_snapshot_is_returned_once() {
+        use crate::core::types::{Document, FieldValue};
+        use crate::memory::buffer_pool::BufferPool;
+        use crate::storage::segment_writer::SegmentWriter;
+        use crate::compression::compress::CompressionType;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+
+        // The pre-merge segment and the merged one both hold doc 1
+        let mut segments = Vec::new();
+        for ids in [&[1u64][..], &[1, 2]] {
+            let mut writer = SegmentWriter::new(
+                &storage,
+                SegmentId::new(),
+                Arc::new(BufferPool::new(1024 * 1024)),
+                CompressionType::LZ4,
+            )
+            .unwrap();
+            for &id in ids {
+                writer
+                    .write_document(&Document {
+                        id: DocId(id),
+                        fields: HashMap::from([(
+                            "content".to_string(),
+                            FieldValue::Text("merged".to_string()),
+                        )]),
+                    })
+                    .unwrap();
+            }
+            segments.push(Arc::new(writer.finish(&storage).unwrap()));
+        }
+        mvcc.create_snapshot(segments);
+
+        let pool = ReaderPool::new(mvcc, storage, Arc::new(InvertedIndex::new()), 8);
+        let results = pool.get_reader().unwrap().search_with_limit(&Query::MatchAll, 10).unwrap();
+
+        let mut ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(results.total_hits, 2);
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use serde::{Serialize, Deserialize};
 
 /// Field definition with analyzer
@@ -33,6 +34,12 @@ pub struct FieldDefinitionWithAnalyzer {
     pub indexed: bool,
     pub stored: bool,
     pub analyzer: Option<String>,  // Added: per-field analyzer
+    #[serde(default = "default_positions")]
+    pub positions: bool,           // Store token positions (needed for phrase queries)
+}
+
+fn default_positions() -> bool {
+    true
 }
 
 impl SchemaWithAnalyzer {
@@ -50,10 +57,34 @@ impl SchemaWithAnalyzer {
             indexed: true,
             stored: true,
             analyzer,
+            positions: true,
         });
         self
     }
 
+    /// Stop storing token positions for `field_name`, shrinking its postings.
+    /// Phrase queries on the field are rejected afterwards.
+    pub fn disable_positions(mut self, field_name: &str) -> Self {
+        if let Some(field) = self.fields.iter_mut().find(|f| f.name == field_name) {
+            field.positions = false;
+        }
+        self
+    }
+
+    /// Names of fields indexed without positions
+    pub fn positionless_fields(&self) -> HashSet<String> {
+        self.fields
+            .iter()
+            .filter(|f| !f.positions)
+            .map(|f| f.name.clone())
+            .collect()
+    }
+
+    /// Names of every field in the schema
+    pub fn field_names(&self) -> HashSet<String> {
+        self.fields.iter().map(|f| f.name.clone()).collect()
+    }
+
     pub fn get_analyzer_for_field(&self, field_name: &str) -> Option<&String> {
         self.fields
             .iter()
@@ -1,5 +1,6 @@
 use crate::index::inverted::TermInfo;
 use crate::index::posting::Posting;
+use crate::search::results::ScoredDocument;
 
 /// Read-only view passed to scorers — decouples them from InvertedIndex internals.
 pub struct ScoringContext<'a> {
@@ -28,6 +29,19 @@ pub trait Scorer: Send + Sync {
         self.score_ctx(&ctx)
     }
 
+    /// Score a phrase found `phrase_freq` times in a document, as a term
+    /// with that frequency and the statistics of the phrase's rarest term
+    /// (`rarest` is that term's posting for the document)
+    fn score_phrase(&self, phrase_freq: u32, rarest: &Posting, term_info: &TermInfo, doc_stats: &DocStats) -> f32 {
+        let posting = Posting {
+            doc_id: rarest.doc_id,
+            term_freq: phrase_freq,
+            positions: Vec::new(),
+            field_norm: rarest.field_norm,
+        };
+        self.score(&posting, term_info, doc_stats)
+    }
+
     fn name(&self) -> &str;
 
     fn requires_positions(&self) -> bool {
@@ -42,6 +56,63 @@ pub trait Scorer: Send + Sync {
     }
 }
 
+/// Second stage of two-stage ranking: scores a hit already retrieved by the
+/// first-stage scorer. Sees the hit's first-stage score and document, so it
+/// can afford features too costly to compute for every match.
+pub trait Rescorer: Send + Sync {
+    fn rescore(&self, hit: &ScoredDocument) -> f32;
+}
+
+impl<F: Fn(&ScoredDocument) -> f32 + Send + Sync> Rescorer for F {
+    fn rescore(&self, hit: &ScoredDocument) -> f32 {
+        self(hit)
+    }
+}
+
+/// Sum clause scores independently of clause order.
+///
+/// Float addition isn't associative, so summing the same scores in a
+/// different order (e.g. after the optimizer reorders clauses) can change the
+/// last bits and flip near-tied documents. Sorting first and accumulating in
+/// `f64` makes the result a function of the set of scores alone.
+pub fn stable_sum(scores: &mut [f32]) -> f32 {
+    scores.sort_by(|a, b| a.total_cmp(b));
+    scores.iter().map(|&s| s as f64).sum::<f64>() as f32
+}
+
+/// Round a score to `decimals` decimal places
+pub fn round_score(score: f32, decimals: u32) -> f32 {
+    let factor = 10f64.powi(decimals as i32);
+    ((score as f64 * factor).round() / factor) as f32
+}
+
+/// Inverse document frequency variant, computed from a term's document
+/// frequency `df` and the collection size `N`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdfFormula {
+    /// `ln(N / df)`: zero for terms in every document
+    Classic,
+    /// `ln((N + 1) / (df + 1))`: defined for df = 0 (what `TermDictionary` stores)
+    Smoothed,
+    /// `ln((N - df + 0.5) / (df + 0.5) + 1)`: Lucene-style BM25 idf, always positive
+    Bm25,
+}
+
+impl IdfFormula {
+    pub fn idf(&self, doc_freq: u32, total_docs: usize) -> f32 {
+        let n = total_docs as f64;
+        let df = doc_freq as f64;
+        let idf = match self {
+            IdfFormula::Classic if doc_freq == 0 || total_docs == 0 => 0.0,
+            IdfFormula::Classic => (n / df).ln(),
+            IdfFormula::Smoothed => ((n + 1.0) / (df + 1.0)).ln(),
+            // Clamped so stale statistics (df > N, e.g. an empty index) cannot go negative
+            IdfFormula::Bm25 => (((n - df).max(0.0) + 0.5) / (df + 0.5) + 1.0).ln(),
+        };
+        idf as f32
+    }
+}
+
 /// Document statistics for scoring
 #[derive(Debug, Clone)]
 pub struct DocStats {
@@ -50,28 +121,58 @@ pub struct DocStats {
     pub total_docs: usize,    // Total number of documents
 }
 
-/// TF-IDF Scorer
+/// How raw term frequency is weighted by `TfIdfScorer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TfWeighting {
+    Raw, // tf
+    Log, // 1 + ln(tf), dampens repeated terms without capping them
+}
+
+/// Classic TF-IDF Scorer: `tf_weight * idf`, optionally multiplied by the
+/// posting's `field_norm` (1/sqrt(doc length), i.e. cosine length
+/// normalization). Unlike BM25 the tf contribution never saturates.
 pub struct TfIdfScorer {
     pub normalize: bool,
+    pub tf_weighting: TfWeighting,
+    pub idf_formula: IdfFormula,
 }
 
 impl TfIdfScorer {
     pub fn new(normalize: bool) -> Self {
-        TfIdfScorer { normalize }
+        TfIdfScorer {
+            normalize,
+            tf_weighting: TfWeighting::Raw,
+            idf_formula: IdfFormula::Smoothed,
+        }
+    }
+
+    pub fn with_idf_formula(mut self, idf_formula: IdfFormula) -> Self {
+        self.idf_formula = idf_formula;
+        self
+    }
+
+    pub fn with_tf_weighting(mut self, tf_weighting: TfWeighting) -> Self {
+        self.tf_weighting = tf_weighting;
+        self
     }
 }
 
 impl Scorer for TfIdfScorer {
     fn score_ctx(&self, ctx: &ScoringContext<'_>) -> f32 {
-        // TF = term frequency / document length (if normalized)
-        let tf = if self.normalize {
-            ctx.posting.term_freq as f32 / ctx.doc_stats.doc_length as f32
-        } else {
-            ctx.posting.term_freq as f32
+        let raw_tf = ctx.posting.term_freq as f32;
+        let tf = match self.tf_weighting {
+            TfWeighting::Raw => raw_tf,
+            TfWeighting::Log if raw_tf > 0.0 => 1.0 + raw_tf.ln(),
+            TfWeighting::Log => 0.0,
         };
 
+        // Length normalization
+        let norm = if self.normalize { ctx.posting.field_norm } else { 1.0 };
+
+        let idf = self.idf_formula.idf(ctx.term_info.doc_freq, ctx.doc_stats.total_docs);
+
         // TF-IDF = TF * IDF
-        tf * ctx.term_info.idf * ctx.query_boost
+        tf * idf * norm * ctx.query_boost
     }
 
     fn name(&self) -> &str {
@@ -83,6 +184,7 @@ impl Scorer for TfIdfScorer {
 pub struct BM25Scorer {
     pub k1: f32,  // Term frequency saturation (default: 1.2)
     pub b: f32,   // Length normalization strength (default: 0.75)
+    pub idf_formula: IdfFormula, // Default: IdfFormula::Bm25
 }
 
 impl Default for BM25Scorer {
@@ -90,6 +192,7 @@ impl Default for BM25Scorer {
         BM25Scorer {
             k1: 1.2,
             b: 0.75,
+            idf_formula: IdfFormula::Bm25,
         }
     }
 }
@@ -101,8 +204,14 @@ impl Scorer for BM25Scorer {
         let avg_doc_len = ctx.doc_stats.avg_doc_length;
 
         // BM25 formula
-        let numerator = ctx.term_info.idf * tf * (self.k1 + 1.0);
-        let denominator = tf + self.k1 * (1.0 - self.b + self.b * (doc_len / avg_doc_len));
+        let idf = self.idf_formula.idf(ctx.term_info.doc_freq, ctx.doc_stats.total_docs);
+        let numerator = idf * tf * (self.k1 + 1.0);
+        // Without collection statistics treat the document as average length
+        let length_ratio = if avg_doc_len > 0.0 { doc_len / avg_doc_len } else { 1.0 };
+        let denominator = tf + self.k1 * (1.0 - self.b + self.b * length_ratio);
+        if denominator <= 0.0 {
+            return 0.0;
+        }
 
         (numerator / denominator) * ctx.query_boost
     }
@@ -111,3 +220,149 @@ impl Scorer for BM25Scorer {
         "bm25"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::DocId;
+    use crate::index::inverted::TermDictionary;
+
+    const TOTAL_DOCS: usize = 100;
+
+    fn score<S: Scorer>(scorer: &S, term_freq: u32, field_norm: f32, doc_freq: u32) -> f32 {
+        let posting = Posting {
+            doc_id: DocId(1),
+            term_freq,
+            positions: Vec::new(),
+            field_norm,
+        };
+        let info = TermInfo {
+            doc_freq,
+            total_freq: doc_freq as u64,
+            idf: 0.0, // Scorers derive idf from doc_freq with their own formula
+            posting_offset: 0,
+            posting_size: 0,
+        };
+        scorer.score_ctx(&ScoringContext {
+            doc_id: posting.doc_id,
+            posting: &posting,
+            term_info: &info,
+            doc_stats: DocStats {
+                doc_length: 20,
+                avg_doc_length: 20.0,
+                total_docs: TOTAL_DOCS,
+            },
+            query_boost: 1.0,
+        })
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn idf_formulas_match_known_values() {
+        assert!(close(IdfFormula::Classic.idf(10, 100), 10f32.ln()));
+        assert!(close(IdfFormula::Classic.idf(100, 100), 0.0));
+        assert_eq!(IdfFormula::Classic.idf(0, 100), 0.0);
+
+        assert!(close(IdfFormula::Smoothed.idf(10, 100), (101f32 / 11.0).ln()));
+        assert!(close(IdfFormula::Smoothed.idf(0, 100), 101f32.ln()));
+
+        assert!(close(IdfFormula::Bm25.idf(10, 100), (90.5f32 / 10.5 + 1.0).ln()));
+        // Stays positive even for a term in every document
+        assert!(IdfFormula::Bm25.idf(100, 100) > 0.0);
+
+        // The dictionary stores the smoothed variant unless told otherwise
+        let mut dictionary = TermDictionary::new();
+        let term = crate::index::inverted::Term::new("rust");
+        dictionary.add_term(&term, 10);
+        dictionary.calculate_idf(100);
+        assert!(close(dictionary.get_term_info(&term).unwrap().idf, IdfFormula::Smoothed.idf(10, 100)));
+        dictionary.calculate_idf_with(100, IdfFormula::Classic);
+        assert!(close(dictionary.get_term_info(&term).unwrap().idf, 10f32.ln()));
+    }
+
+    #[test]
+    fn bm25_uses_its_own_idf() {
+        let bm25 = BM25Scorer::default();
+        // tf = 1 with an average-length document reduces BM25 to its idf
+        assert!(close(score(&bm25, 1, 1.0, 10), IdfFormula::Bm25.idf(10, TOTAL_DOCS)));
+
+        let classic = BM25Scorer {
+            idf_formula: IdfFormula::Classic,
+            ..BM25Scorer::default()
+        };
+        assert!(close(score(&classic, 1, 1.0, 10), IdfFormula::Classic.idf(10, TOTAL_DOCS)));
+    }
+
+    #[test]
+    fn tfidf_follows_classic_formula() {
+        let idf = IdfFormula::Smoothed.idf(10, TOTAL_DOCS);
+
+        let raw = TfIdfScorer::new(false);
+        assert!(close(score(&raw, 4, 0.5, 10), 4.0 * idf));
+
+        let log = TfIdfScorer::new(false).with_tf_weighting(TfWeighting::Log);
+        assert!(close(score(&log, 4, 0.5, 10), (1.0 + 4f32.ln()) * idf));
+        assert!(close(score(&log, 1, 0.5, 10), idf));
+
+        // Cosine-style normalization scales by the posting's field norm
+        let normalized = TfIdfScorer::new(true);
+        assert!(close(score(&normalized, 4, 0.5, 10), 2.0 * idf));
+
+        let classic = TfIdfScorer::new(false).with_idf_formula(IdfFormula::Classic);
+        assert!(close(score(&classic, 4, 1.0, 10), 4.0 * 10f32.ln()));
+    }
+
+    #[test]
+    fn tfidf_does_not_saturate_like_bm25() {
+        // Doc A repeats one query term; doc B has both query terms a few times.
+        // Both terms have the same document frequency.
+        let doc_a = [20];
+        let doc_b = [3, 3];
+        let total = |scorer: &dyn Fn(u32) -> f32, doc: &[u32]| -> f32 {
+            doc.iter().map(|&tf| scorer(tf)).sum()
+        };
+
+        let bm25 = BM25Scorer::default();
+        let bm25 = |tf| score(&bm25, tf, 1.0, 10);
+        assert!(total(&bm25, &doc_b) > total(&bm25, &doc_a));
+        // BM25's contribution is bounded by idf * (k1 + 1) however large tf gets
+        assert!(bm25(1000) < IdfFormula::Bm25.idf(10, TOTAL_DOCS) * 2.2);
+
+        let tfidf = TfIdfScorer::new(false);
+        let tfidf = |tf| score(&tfidf, tf, 1.0, 10);
+        assert!(total(&tfidf, &doc_a) > total(&tfidf, &doc_b));
+        assert!(close(tfidf(1000), 1000.0 * tfidf(1)));
+    }
+
+    #[test]
+    fn scores_stay_finite_without_collection_statistics() {
+        let posting = Posting {
+            doc_id: DocId(1),
+            term_freq: 1,
+            positions: Vec::new(),
+            field_norm: 1.0,
+        };
+        let info = TermInfo {
+            doc_freq: 1,
+            total_freq: 1,
+            idf: 0.0,
+            posting_offset: 0,
+            posting_size: 0,
+        };
+        // Empty index, and a single-document index with zero-length stats
+        for (total_docs, doc_length, avg_doc_length) in [(0, 0, 0.0), (1, 1, 0.0), (1, 0, 1.0)] {
+            let doc_stats = DocStats { doc_length, avg_doc_length, total_docs };
+            let bm25 = BM25Scorer::default().score(&posting, &info, &doc_stats);
+            let tfidf = TfIdfScorer::new(true).score(&posting, &info, &doc_stats);
+            assert!(bm25.is_finite() && bm25 >= 0.0, "bm25 = {}", bm25);
+            assert!(tfidf.is_finite(), "tfidf = {}", tfidf);
+        }
+        for formula in [IdfFormula::Classic, IdfFormula::Smoothed, IdfFormula::Bm25] {
+            assert!(formula.idf(1, 0).is_finite());
+            assert!(formula.idf(0, 0).is_finite());
+        }
+    }
+}
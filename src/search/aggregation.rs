@@ -0,0 +1,176 @@
+use crate::core::types::{DocId, FieldValue};
+use crate::search::collector::{CollectDecision, Collector, IntoResults, MatchedDocument};
+use std::collections::HashMap;
+
+/// Counts matched documents per distinct value of a field.
+///
+/// With a sample fraction only a pseudo-random subset of the matches is
+/// bucketed and the counts are scaled up, trading accuracy for speed on
+/// large result sets. The subset is chosen by hashing the doc id, so the
+/// same query over the same documents always gives the same estimate.
+pub struct TermsAggregation {
+    pub field: String,
+    pub sample: Option<f32>,
+    counts: HashMap<String, u64>,
+    matched: u64,
+    sampled: u64,
+}
+
+/// One distinct field value and its (possibly estimated) document count
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermBucket {
+    pub key: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregationResult {
+    /// Buckets by descending count, ties by key
+    pub buckets: Vec<TermBucket>,
+    /// Number of matched documents (always exact)
+    pub doc_count: u64,
+    /// Number of documents actually bucketed
+    pub sample_size: u64,
+    /// Sample fraction used, `None` if counts are exact
+    pub sample: Option<f32>,
+}
+
+impl AggregationResult {
+    pub fn is_estimate(&self) -> bool {
+        self.sample.is_some()
+    }
+
+    pub fn count(&self, key: &str) -> Option<u64> {
+        self.buckets.iter().find(|b| b.key == key).map(|b| b.count)
+    }
+}
+
+impl TermsAggregation {
+    pub fn new(field: impl Into<String>) -> Self {
+        TermsAggregation {
+            field: field.into(),
+            sample: None,
+            counts: HashMap::new(),
+            matched: 0,
+            sampled: 0,
+        }
+    }
+
+    /// Only bucket roughly `fraction` of the matched documents.
+    /// Fractions of 1.0 or more keep exact counting.
+    pub fn with_sample(mut self, fraction: f32) -> Self {
+        self.sample = (fraction < 1.0).then_some(fraction.max(0.0));
+        self
+    }
+
+    /// Whether `doc_id` falls into a sample of the given fraction
+    fn in_sample(doc_id: DocId, fraction: f32) -> bool {
+        // splitmix64 finalizer: spreads sequential ids uniformly over u64
+        let mut h = doc_id.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+        (h as f64 / u64::MAX as f64) < fraction as f64
+    }
+
+    fn bucket_key(value: &FieldValue) -> String {
+        match value {
+            FieldValue::Text(s) => s.clone(),
+            FieldValue::Number(n) => n.to_string(),
+            FieldValue::Date(d) => d.to_rfc3339(),
+            FieldValue::Boolean(b) => b.to_string(),
+        }
+    }
+}
+
+impl Collector for TermsAggregation {
+    fn collect(&mut self, doc: MatchedDocument) -> CollectDecision {
+        self.matched += 1;
+        if let Some(fraction) = self.sample
+            && !Self::in_sample(doc.doc_id, fraction)
+        {
+            return CollectDecision::Continue;
+        }
+
+        self.sampled += 1;
+        if let Some(value) = doc.document.as_ref().and_then(|d| d.get_field(&self.field)) {
+            *self.counts.entry(Self::bucket_key(value)).or_insert(0) += 1;
+        }
+        CollectDecision::Continue
+    }
+}
+
+impl IntoResults for TermsAggregation {
+    type Output = AggregationResult;
+
+    fn into_results(self) -> AggregationResult {
+        let scale = |count: u64| match self.sample {
+            Some(fraction) if fraction > 0.0 => (count as f64 / fraction as f64).round() as u64,
+            Some(_) => 0,
+            None => count,
+        };
+
+        let mut buckets: Vec<TermBucket> = self
+            .counts
+            .iter()
+            .map(|(key, &count)| TermBucket {
+                key: key.clone(),
+                count: scale(count),
+            })
+            .collect();
+        buckets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+
+        AggregationResult {
+            buckets,
+            doc_count: self.matched,
+            sample_size: self.sampled,
+            sample: self.sample,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Document;
+
+    fn aggregate(mut agg: TermsAggregation, docs: u64) -> AggregationResult {
+        let categories = ["books", "tools", "art", "music"];
+        for id in 0..docs {
+            let mut doc = Document::new(DocId(id));
+            // 40% books, 30% tools, 20% art, 10% music
+            let category = categories[[0, 0, 0, 0, 1, 1, 1, 2, 2, 3][(id % 10) as usize]];
+            doc.add_field("category".to_string(), FieldValue::Text(category.to_string()));
+            agg.collect(MatchedDocument {
+                doc_id: doc.id,
+                score: 1.0,
+                document: Some(doc),
+                explanation: None,
+            });
+        }
+        agg.into_results()
+    }
+
+    #[test]
+    fn sampled_counts_approximate_exact_counts() {
+        let exact = aggregate(TermsAggregation::new("category"), 20_000);
+        assert!(!exact.is_estimate());
+        assert_eq!(exact.count("books"), Some(8_000));
+        assert_eq!(exact.sample_size, 20_000);
+
+        let sampled = aggregate(TermsAggregation::new("category").with_sample(0.1), 20_000);
+        assert!(sampled.is_estimate());
+        assert_eq!(sampled.doc_count, 20_000);
+        assert!((1_600..=2_400).contains(&sampled.sample_size));
+        for bucket in &exact.buckets {
+            let estimate = sampled.count(&bucket.key).unwrap() as f64;
+            let error = (estimate - bucket.count as f64).abs() / bucket.count as f64;
+            assert!(error < 0.2, "{}: {} vs {}", bucket.key, estimate, bucket.count);
+        }
+
+        // Same documents, same sample
+        let again = aggregate(TermsAggregation::new("category").with_sample(0.1), 20_000);
+        assert_eq!(again.buckets, sampled.buckets);
+        assert_eq!(again.sample_size, sampled.sample_size);
+    }
+}
@@ -19,8 +19,15 @@ pub enum CollectDecision {
 
 /// Trait for collecting matched documents during query execution.
 pub trait Collector: Send {
+    /// Called before the hits of each segment are collected.
+    fn begin_segment(&mut self) {}
     fn collect(&mut self, doc: MatchedDocument) -> CollectDecision;
     fn finish(&mut self) {}
+    /// Score a hit must beat to be kept, once the collector is full.
+    /// Lets the executor skip extra work for hits that would be dropped.
+    fn min_competitive_score(&self) -> Option<f32> {
+        None
+    }
 }
 
 /// Trait to convert a collector's internal state into a final result type.
@@ -28,3 +35,46 @@ pub trait IntoResults {
     type Output;
     fn into_results(self) -> Self::Output;
 }
+
+/// Drops hits scoring below `min_score` before they reach the wrapped
+/// collector, counting them so totals can still include them.
+pub struct MinScoreCollector<'a, C> {
+    inner: &'a mut C,
+    min_score: f32,
+    /// Hits dropped so far
+    pub below_min_score: usize,
+}
+
+impl<'a, C: Collector> MinScoreCollector<'a, C> {
+    pub fn new(inner: &'a mut C, min_score: f32) -> Self {
+        MinScoreCollector {
+            inner,
+            min_score,
+            below_min_score: 0,
+        }
+    }
+}
+
+impl<C: Collector> Collector for MinScoreCollector<'_, C> {
+    fn begin_segment(&mut self) {
+        self.inner.begin_segment();
+    }
+
+    fn collect(&mut self, doc: MatchedDocument) -> CollectDecision {
+        if doc.score < self.min_score {
+            self.below_min_score += 1;
+            return CollectDecision::Continue;
+        }
+        self.inner.collect(doc)
+    }
+
+    fn finish(&mut self) {
+        self.inner.finish();
+    }
+
+    fn min_competitive_score(&self) -> Option<f32> {
+        // Hits scoring exactly `min_score` are kept, so the floor sits just below it
+        let floor = self.min_score.next_down();
+        Some(self.inner.min_competitive_score().map_or(floor, |inner| inner.max(floor)))
+    }
+}
@@ -1,14 +1,22 @@
 use crate::core::error::{Error, ErrorKind, Result};
-use crate::core::types::DocId;
-use crate::index::inverted::{InvertedIndex, Term};
-use crate::query::ast::{BoolQuery, Query, TermQuery};
+use crate::core::types::{DocId, Document, FieldValue};
+use crate::index::inverted::{InvertedIndex, Term, TermInfo};
+use crate::index::posting::Posting;
+use crate::query::ast::{BoolQuery, DisMaxQuery, IdsQuery, PhraseQuery, Query, TermQuery};
 use crate::query::matcher::{DocumentMatcher, SegmentSearch};
 use crate::query::optimizer::QueryOptimizer;
 use crate::query::planner::{LogicalPlan, QueryPlanner};
 use crate::query::types::{IndexStatistics, QueryValidator, ValidationConfig};
 use crate::reader::reader_pool::IndexReader;
-use crate::scoring::scorer::{BM25Scorer, DocStats, Scorer, TfIdfScorer};
-use crate::search::results::{ScoreExplanation, ScoredDocument, SearchResults, TopKCollector};
+use crate::scoring::scorer::{
+    round_score, stable_sum, BM25Scorer, DocStats, IdfFormula, Rescorer, Scorer, TfIdfScorer,
+};
+use crate::search::collector::{Collector, IntoResults, MatchedDocument, MinScoreCollector};
+use crate::search::results::{ScoreExplanation, ScoredDocument, SearchResults, SegmentTopKCollector};
+use crate::search::sort::{SortSpec, SortedCollector};
+use crate::search::source::SourceFilter;
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 
 /// Scoring algorithm selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,6 +34,36 @@ pub struct ExecutionConfig {
     pub enable_validation: bool,
     pub collect_explanations: bool,
     pub timeout_ms: Option<u64>,
+    /// Stored fields to keep in returned documents (`None` = all fields).
+    /// Only trims the response: matching, scoring and sorting still read
+    /// and decompress whole documents from the segment.
+    pub fields: Option<Vec<String>>,
+    /// Include/exclude glob filter applied to returned documents
+    pub source: Option<SourceFilter>,
+    /// Order hits by these keys instead of by descending score
+    pub sort: Option<SortSpec>,
+    /// Round scores to this many decimals before ranking, so near-ties
+    /// fall back to doc-id order (`None` = full precision)
+    pub score_decimals: Option<u32>,
+    /// Drop term clauses found in more than this fraction of documents from
+    /// boolean queries that still have rarer clauses (`None` = keep all)
+    pub common_term_cutoff: Option<f32>,
+    /// When false, matches are not scored (every score is 0.0) and hits
+    /// come back in doc-id order, or in `sort` order if one is given
+    pub track_scores: bool,
+    /// Read segments newest first (by creation time) and let hits from newer
+    /// segments win score ties, for recency-biased search over logs
+    pub newest_segments_first: bool,
+    /// Find phrase matches by intersecting the terms' posting lists first and
+    /// checking positions only in the documents left (false = scan each
+    /// term's postings for every document, the slower original path)
+    pub phrase_intersection: bool,
+    /// Drop hits scoring below this, e.g. to hide barely relevant matches
+    /// (`None` = keep all; ignored when scores are not tracked)
+    pub min_score: Option<f32>,
+    /// Count hits dropped by `min_score` in `total_hits` (false = count
+    /// only the hits at or above it)
+    pub total_hits_before_min_score: bool,
 }
 
 impl Default for ExecutionConfig {
@@ -36,6 +74,16 @@ impl Default for ExecutionConfig {
             enable_validation: true,
             collect_explanations: false,
             timeout_ms: Some(30000), // 30 seconds default
+            fields: None,
+            source: None,
+            sort: None,
+            score_decimals: None,
+            common_term_cutoff: None,
+            track_scores: true,
+            newest_segments_first: false,
+            phrase_intersection: true,
+            min_score: None,
+            total_hits_before_min_score: false,
         }
     }
 }
@@ -47,22 +95,36 @@ impl ExecutionConfig {
             scoring: ScoringAlgorithm::None, // No scoring for speed
             enable_optimization: false,
             enable_validation: false,
-            collect_explanations: false,
             timeout_ms: Some(10000),
+            ..Self::default()
         }
     }
 
     /// Create a debug config with explanations
     pub fn debug() -> Self {
         ExecutionConfig {
-            scoring: ScoringAlgorithm::BM25,
-            enable_optimization: true,
-            enable_validation: true,
             collect_explanations: true,
             timeout_ms: None,
+            ..Self::default()
         }
     }
 
+    /// Whether results match those of the default config, so they can be
+    /// shared through the query cache. Optimization, validation and the
+    /// timeout change how a query runs, not what it returns.
+    pub fn has_default_results(&self) -> bool {
+        self.scoring == ScoringAlgorithm::BM25
+            && !self.collect_explanations
+            && self.fields.is_none()
+            && self.source.is_none()
+            && self.sort.is_none()
+            && self.score_decimals.is_none()
+            && self.common_term_cutoff.is_none()
+            && self.track_scores
+            && !self.newest_segments_first
+            && self.min_score.is_none()
+    }
+
     /// Create config with specific scoring algorithm
     pub fn with_scoring(algorithm: ScoringAlgorithm) -> Self {
         ExecutionConfig {
@@ -80,13 +142,78 @@ impl ExecutionConfig {
     pub fn tfidf() -> Self {
         Self::with_scoring(ScoringAlgorithm::TfIdf)
     }
+
+    /// Only load the given stored fields into returned documents
+    pub fn with_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Filter returned documents with include/exclude field globs
+    pub fn with_source(mut self, source: SourceFilter) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Sort hits by field values and/or score
+    pub fn with_sort(mut self, sort: SortSpec) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Round scores to `decimals` decimal places before ranking
+    pub fn with_score_decimals(mut self, decimals: u32) -> Self {
+        self.score_decimals = Some(decimals);
+        self
+    }
+
+    /// Search newer segments first and rank their hits first on score ties
+    pub fn with_newest_segments_first(mut self) -> Self {
+        self.newest_segments_first = true;
+        self
+    }
+
+    /// Filter-only execution: skip scoring and return hits in doc-id order
+    pub fn without_scores(mut self) -> Self {
+        self.track_scores = false;
+        self
+    }
+
+    /// Verify phrases by scanning every document's postings instead of
+    /// intersecting posting lists first
+    pub fn without_phrase_intersection(mut self) -> Self {
+        self.phrase_intersection = false;
+        self
+    }
+
+    /// Drop hits scoring below `min_score`
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Count hits dropped by `min_score` in `total_hits`
+    pub fn with_total_hits_before_min_score(mut self) -> Self {
+        self.total_hits_before_min_score = true;
+        self
+    }
+
+    /// Treat terms in more than `ratio` of all documents as stopwords
+    pub fn with_common_term_cutoff(mut self, ratio: f32) -> Self {
+        self.common_term_cutoff = Some(ratio);
+        self
+    }
 }
 
 // No need for SimpleScorer - when scoring is disabled, we use the score from DocumentMatcher
 
-/// Query executor service (stateless)
+/// Query executor service
 ///
-/// This executor does NOT own any data or cache. It operates on provided IndexReader instances.
+/// This executor does NOT own any data or cache; it only counts the documents it scores. It operates on provided IndexReader instances.
 ///
 /// # Example
 /// ```rust,no_run
@@ -94,11 +221,13 @@ impl ExecutionConfig {
 /// let executor = QueryExecutor::new();
 /// // let reader = reader_pool.get_reader()?;
 /// // let query = parser.parse("rust programming")?;
-/// // let results = executor.execute(&reader, &query, 10, ExecutionConfig::default())?;
+/// // let results = executor.execute(&reader, &query, 10, 0, ExecutionConfig::default())?;
 /// ```
 pub struct QueryExecutor {
     pub optimizer: QueryOptimizer,
     pub validator_config: ValidationConfig,
+    scored_docs: AtomicU64,
+    planned_queries: AtomicU64,
 }
 
 impl QueryExecutor {
@@ -107,6 +236,8 @@ impl QueryExecutor {
         QueryExecutor {
             optimizer: QueryOptimizer::new(),
             validator_config: ValidationConfig::default(),
+            scored_docs: AtomicU64::new(0),
+            planned_queries: AtomicU64::new(0),
         }
     }
 
@@ -115,15 +246,28 @@ impl QueryExecutor {
         QueryExecutor {
             optimizer: QueryOptimizer::new(),
             validator_config,
+            scored_docs: AtomicU64::new(0),
+            planned_queries: AtomicU64::new(0),
         }
     }
 
+    /// Documents scored by BM25, TF-IDF or a custom scorer so far
+    pub fn scored_docs(&self) -> u64 {
+        self.scored_docs.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Queries run through the planner so far
+    pub fn planned_queries(&self) -> u64 {
+        self.planned_queries.load(AtomicOrdering::Relaxed)
+    }
+
     /// Execute a query on the provided IndexReader
     ///
     /// # Arguments
     /// * `reader` - The IndexReader containing segments and index
     /// * `query` - The parsed query to execute
     /// * `limit` - Maximum number of results to return
+    /// * `offset` - Ranked hits to skip first, for paging
     /// * `config` - Execution configuration
     ///
     /// # Returns
@@ -133,41 +277,131 @@ impl QueryExecutor {
         reader: &IndexReader,
         query: &Query,
         limit: usize,
+        offset: usize,
+        config: ExecutionConfig,
+    ) -> Result<SearchResults> {
+        self.execute_scored(reader, query, limit, offset, config, None)
+    }
+
+    /// Execute a query with the default configuration, scoring hits with a
+    /// caller-provided `scorer` instead of `ExecutionConfig::scoring`.
+    /// The scorer sees the same postings, term info and document statistics
+    /// as the built-in BM25 and TF-IDF scorers.
+    pub fn execute_with_scorer(
+        &self,
+        reader: &IndexReader,
+        query: &Query,
+        limit: usize,
+        scorer: &dyn Scorer,
+    ) -> Result<SearchResults> {
+        self.execute_scored(reader, query, limit, 0, ExecutionConfig::default(), Some(scorer))
+    }
+
+    /// Two-stage ranking: collect the top `window` hits by the default
+    /// configuration's score, then score only those again with `rescorer`
+    /// and re-sort them. The rescorer runs at most `window` times however
+    /// many documents match, so its cost stays bounded.
+    pub fn execute_with_rescore(
+        &self,
+        reader: &IndexReader,
+        query: &Query,
+        window: usize,
+        rescorer: &dyn Rescorer,
+    ) -> Result<SearchResults> {
+        let start = std::time::Instant::now();
+        let mut results = self.execute(reader, query, window, 0, ExecutionConfig::default())?;
+        for hit in &mut results.hits {
+            hit.score = rescorer.rescore(hit);
+        }
+        // Stable, so hits the rescorer ties keep their first-stage order
+        results.hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.max_score = results.hits.first().map_or(0.0, |hit| hit.score);
+        results.took_ms = start.elapsed().as_millis() as u64;
+        Ok(results)
+    }
+
+    /// `execute`, with `scorer` (if any) overriding the configured algorithm
+    fn execute_scored(
+        &self,
+        reader: &IndexReader,
+        query: &Query,
+        limit: usize,
+        offset: usize,
         config: ExecutionConfig,
+        scorer: Option<&dyn Scorer>,
     ) -> Result<SearchResults> {
         let start = std::time::Instant::now();
 
+        // Index statistics are shared by validation, planning and scoring
+        let stats = IndexStatistics::from_index(&reader.index);
+
         // 1. Validate query if enabled
         if config.enable_validation {
-            let stats = IndexStatistics::from_index(&reader.index);
-            let validator = QueryValidator::new(self.validator_config.clone(), stats);
+            let validator = QueryValidator::new(self.validator_config.clone(), stats.clone());
             validator.validate(query)?;
         }
 
-        // 2. Optimize query if enabled
+        // 2. Drop common terms, then optimize query if enabled
+        let query = &match config.common_term_cutoff {
+            Some(cutoff) => Self::strip_common_terms(query, &reader.index, &stats, cutoff),
+            None => query.clone(),
+        };
         let optimized_query = if config.enable_optimization {
-            self.optimize_query(query, &reader.index)?
+            self.optimize_query(query, &stats)?
         } else {
             query.clone()
         };
 
-        // 3. Create collector for top-K results
-        let mut collector = TopKCollector::new(limit);
-
-        // 4. Execute on reader's segments
-        self.execute_on_segments(reader, &optimized_query, &mut collector, &config)?;
+        // 3-4. Collect top hits from the reader's segments
+        let mut below_min_score = 0;
+        let mut results = if let Some(score) = Self::match_all_score(&optimized_query, &config, scorer) {
+            self.collect_match_all(reader, &optimized_query, score, limit, offset, &config)?
+        } else {
+            match &config.sort {
+                // An empty spec orders by doc id alone
+                None if !config.track_scores => {
+                    let mut collector = SortedCollector::new(SortSpec::new(), limit).with_offset(offset);
+                    self.execute_on_segments(reader, &optimized_query, &mut collector, &config, &stats, scorer)?;
+                    collector.into_results()
+                }
+                Some(sort) => {
+                    let mut collector = SortedCollector::new(sort.clone(), limit).with_offset(offset);
+                    below_min_score = self.collect_above_min_score(
+                        reader, &optimized_query, &mut collector, &config, &stats, scorer,
+                    )?;
+                    collector.into_results()
+                }
+                None => {
+                    // Top-K per segment, then re-selected globally
+                    let mut collector = SegmentTopKCollector::new(limit).with_offset(offset);
+                    if config.newest_segments_first {
+                        collector = collector.with_segment_order_ties();
+                    }
+                    below_min_score = self.collect_above_min_score(
+                        reader, &optimized_query, &mut collector, &config, &stats, scorer,
+                    )?;
+                    collector.into_results()
+                }
+            }
+        };
+        if config.total_hits_before_min_score {
+            results.total_hits += below_min_score;
+        }
 
-        // 5. Build final results
-        let total_hits = collector.total_collected;
-        let max_score = collector.max_score();
-        let hits = collector.get_results(); // This consumes collector, must be last
+        // 5. Trim returned documents (after sorting, which may read any field)
+        for hit in &mut results.hits {
+            let document = match &config.fields {
+                Some(fields) => hit.document.take().map(|d| Self::project_fields(d, fields)),
+                None => hit.document.take(),
+            };
+            hit.document = document;
+            if let (Some(source), Some(d)) = (&config.source, hit.document.as_mut()) {
+                source.apply(d);
+            }
+        }
+        results.took_ms = start.elapsed().as_millis() as u64;
 
-        Ok(SearchResults {
-            hits,
-            total_hits,
-            max_score,
-            took_ms: start.elapsed().as_millis() as u64,
-        })
+        Ok(results)
     }
 
     /// Execute query with simple configuration (convenience method)
@@ -177,18 +411,65 @@ impl QueryExecutor {
         query: &Query,
         limit: usize,
     ) -> Result<SearchResults> {
-        self.execute(reader, query, limit, ExecutionConfig::simple())
+        self.execute(reader, query, limit, 0, ExecutionConfig::simple())
+    }
+
+    /// Remove term clauses whose document frequency exceeds `cutoff` (as a
+    /// fraction of all documents) from boolean queries, like Lucene's
+    /// `cutoff_frequency`. A clause list made only of common terms is kept,
+    /// so a query of stopwords alone still matches.
+    fn strip_common_terms(
+        query: &Query,
+        index: &InvertedIndex,
+        stats: &IndexStatistics,
+        cutoff: f32,
+    ) -> Query {
+        let is_common = |clause: &Query| match clause {
+            Query::Term(tq) if stats.total_docs > 0 => index
+                .dictionary
+                .get_term_info(&Term::new(&tq.value))
+                .is_some_and(|info| info.doc_freq as f32 / stats.total_docs as f32 > cutoff),
+            _ => false,
+        };
+
+        let Query::Bool(bool_query) = query else {
+            return query.clone();
+        };
+        let strip = |clauses: &[Query]| -> Vec<Query> {
+            clauses
+                .iter()
+                .map(|clause| Self::strip_common_terms(clause, index, stats, cutoff))
+                .collect()
+        };
+        let mut stripped = BoolQuery {
+            must: strip(&bool_query.must),
+            should: strip(&bool_query.should),
+            ..bool_query.clone()
+        };
+
+        let has_rare_clause = stripped.must.iter().chain(&stripped.should).any(|c| !is_common(c));
+        if has_rare_clause {
+            stripped.must.retain(|c| !is_common(c));
+            stripped.should.retain(|c| !is_common(c));
+            let remaining = stripped.should.len() as u32;
+            stripped.minimum_should_match = stripped
+                .minimum_should_match
+                .filter(|_| remaining > 0)
+                .map(|n| n.min(remaining));
+        }
+        Query::Bool(stripped)
     }
 
     /// Optimize a query based on index statistics
-    fn optimize_query(&self, query: &Query, index: &InvertedIndex) -> Result<Query> {
+    fn optimize_query(&self, query: &Query, stats: &IndexStatistics) -> Result<Query> {
         if !Self::is_safe_to_optimize(query) {
             return Ok(query.clone());
         }
 
         // Create planner with current index statistics
-        let stats = IndexStatistics::from_index(index);
-        let planner = QueryPlanner::new(stats);
+        self.planned_queries.fetch_add(1, AtomicOrdering::Relaxed);
+        let planner = QueryPlanner::new(stats.clone())
+            .with_max_range_terms(self.validator_config.max_range_terms);
 
         // Generate logical plan
         let plan = planner.plan(query);
@@ -295,32 +576,74 @@ impl QueryExecutor {
                     && bool_query.filter.is_empty()
                     && bool_query.must.iter().all(Self::is_safe_to_optimize)
                     && bool_query.should.iter().all(Self::is_safe_to_optimize)
+                    // The plan intersects must clauses and unions should clauses
+                    // alone, so it only keeps the default should-clause minimum
+                    && bool_query.required_should_matches()
+                        == usize::from(bool_query.must.is_empty() && !bool_query.should.is_empty())
             }
             Query::MatchAll => true,
             Query::Phrase(_)
+            | Query::Near(_)
+            | Query::Boosting(_)
+            | Query::DisMax(_)
             | Query::Range(_)
             | Query::Prefix(_)
             | Query::Wildcard(_)
-            | Query::Fuzzy(_) => false,
+            | Query::Fuzzy(_)
+            | Query::Ids(_)
+            | Query::Predicate(_) => false,
         }
     }
 
+    /// Collect the hits of `execute_on_segments`, dropping hits below
+    /// `config.min_score`.
+    /// Returns how many were dropped.
+    fn collect_above_min_score<C: Collector>(
+        &self,
+        reader: &IndexReader,
+        query: &Query,
+        collector: &mut C,
+        config: &ExecutionConfig,
+        stats: &IndexStatistics,
+        custom_scorer: Option<&dyn Scorer>,
+    ) -> Result<usize> {
+        let Some(min_score) = config.min_score.filter(|_| config.track_scores) else {
+            self.execute_on_segments(reader, query, collector, config, stats, custom_scorer)?;
+            return Ok(0);
+        };
+        let mut collector = MinScoreCollector::new(collector, min_score);
+        self.execute_on_segments(reader, query, &mut collector, config, stats, custom_scorer)?;
+        Ok(collector.below_min_score)
+    }
+
     /// Execute query on IndexReader's segments with configurable scoring
-    fn execute_on_segments(
+    fn execute_on_segments<C: Collector>(
         &self,
         reader: &IndexReader,
         query: &Query,
-        collector: &mut TopKCollector,
+        collector: &mut C,
         config: &ExecutionConfig,
+        stats: &IndexStatistics,
+        custom_scorer: Option<&dyn Scorer>,
     ) -> Result<()> {
-        // Get index statistics for scoring
-        let stats = IndexStatistics::from_index(&reader.index);
+        if let Query::Ids(ids) = query {
+            return self.collect_ids(reader, ids, collector, config);
+        }
 
         // Create document matcher for query evaluation (filtering)
-        let matcher = DocumentMatcher::new(reader.index.clone());
+        let matcher = DocumentMatcher::new(reader.index.clone())
+            .with_phrase_intersection(config.phrase_intersection);
+
+        let segments = if config.newest_segments_first {
+            reader.segments_newest_first()
+        } else {
+            reader.segments.clone()
+        };
 
         // Process each segment
-        for segment_reader in &reader.segments {
+        for segment_reader in &segments {
+            collector.begin_segment();
+
             // Get READ lock on segment reader for concurrent reads
             let seg_reader = segment_reader.read();
 
@@ -335,25 +658,43 @@ impl QueryExecutor {
                 }
 
                 // Calculate score based on selected algorithm
+                if config.track_scores
+                    && (custom_scorer.is_some() || config.scoring != ScoringAlgorithm::None)
+                {
+                    self.scored_docs.fetch_add(1, AtomicOrdering::Relaxed);
+                }
                 let final_score = match config.scoring {
+                    _ if !config.track_scores => 0.0,
+                    _ if let Some(scorer) = custom_scorer => {
+                        self.calculate_score(&doc, query, &matcher, scorer, stats)?
+                    }
                     ScoringAlgorithm::BM25 => {
                         let scorer = BM25Scorer::default();
-                        self.calculate_score(doc.doc_id, query, &reader.index, &scorer, &stats)?
+                        self.calculate_score(&doc, query, &matcher, &scorer, stats)?
                     }
                     ScoringAlgorithm::TfIdf => {
                         let scorer = TfIdfScorer::new(true); // normalized TF-IDF
-                        self.calculate_score(doc.doc_id, query, &reader.index, &scorer, &stats)?
+                        self.calculate_score(&doc, query, &matcher, &scorer, stats)?
                     }
                     ScoringAlgorithm::None => {
                         1.0 // Simple scoring
                     }
                 };
+                let final_score = match config.score_decimals {
+                    Some(decimals) => round_score(final_score, decimals),
+                    None => final_score,
+                };
 
-                let scored_doc = ScoredDocument {
+                // Hits the collector will drop only need counting
+                let competitive = collector
+                    .min_competitive_score()
+                    .is_none_or(|min_score| final_score > min_score);
+
+                let matched = MatchedDocument {
                     doc_id: doc.doc_id,
                     score: final_score,
                     document: doc.document,
-                    explanation: if config.collect_explanations {
+                    explanation: if competitive && config.collect_explanations && config.track_scores {
                         Some(self.generate_score_explanation(
                             doc.doc_id,
                             query,
@@ -366,40 +707,253 @@ impl QueryExecutor {
                 };
 
                 // Collect result
-                collector.collect(scored_doc);
+                collector.collect(matched);
             }
         }
 
         Ok(())
     }
 
-    /// Calculate score for a document given a query and scorer
-    fn calculate_score<S: Scorer>(
+    /// Resolve an ids query by direct lookup instead of scanning segments;
+    /// the reader skips deleted ids and returns the newest copy of each
+    fn collect_ids<C: Collector>(
         &self,
-        doc_id: DocId,
+        reader: &IndexReader,
+        ids: &IdsQuery,
+        collector: &mut C,
+        config: &ExecutionConfig,
+    ) -> Result<()> {
+        let score = match config.score_decimals {
+            _ if !config.track_scores => 0.0,
+            Some(decimals) => round_score(ids.boost.unwrap_or(1.0), decimals),
+            None => ids.boost.unwrap_or(1.0),
+        };
+        let mut seen = HashSet::new();
+
+        collector.begin_segment();
+        for &doc_id in &ids.values {
+            if !seen.insert(doc_id) {
+                continue;
+            }
+            let Some(document) = reader.get_document(doc_id)? else {
+                continue;
+            };
+            collector.collect(MatchedDocument {
+                doc_id,
+                score,
+                document: Some((*document).clone()),
+                explanation: if config.collect_explanations {
+                    Some(self.generate_score_explanation(
+                        doc_id,
+                        &Query::Ids(ids.clone()),
+                        score,
+                        &reader.index,
+                    )?)
+                } else {
+                    None
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// The score every hit of a match-all query (optionally with filters)
+    /// would get, or `None` when the query or config needs the generic path.
+    /// Such queries score each document the same, so their hits can be
+    /// streamed in doc-id order instead of being scored and ranked.
+    fn match_all_score(query: &Query, config: &ExecutionConfig, scorer: Option<&dyn Scorer>) -> Option<f32> {
+        if config.sort.is_some()
+            || config.collect_explanations
+            || config.newest_segments_first
+            || config.min_score.is_some()
+            || scorer.is_some()
+        {
+            return None;
+        }
+        let score = match query {
+            Query::MatchAll => 1.0,
+            Query::Bool(bool_query)
+                if bool_query.should.is_empty()
+                    && !bool_query.must.is_empty()
+                    && bool_query.must.iter().all(|clause| matches!(clause, Query::MatchAll)) =>
+            {
+                bool_query.must.len() as f32 * bool_query.boost.unwrap_or(1.0)
+            }
+            _ => return None,
+        };
+        Some(match config.score_decimals {
+            _ if !config.track_scores => 0.0,
+            _ if config.scoring == ScoringAlgorithm::None => 1.0,
+            Some(decimals) => round_score(score, decimals),
+            None => score,
+        })
+    }
+
+    /// Stream the live documents of every segment and keep the `offset +
+    /// limit` lowest ids, without scoring or ranking. Filters and `must_not`
+    /// clauses of a boolean query are still applied to each document.
+    fn collect_match_all(
+        &self,
+        reader: &IndexReader,
         query: &Query,
-        index: &InvertedIndex,
+        score: f32,
+        limit: usize,
+        offset: usize,
+        config: &ExecutionConfig,
+    ) -> Result<SearchResults> {
+        let window = offset.saturating_add(limit);
+        let matcher = match query {
+            Query::Bool(_) => Some(DocumentMatcher::new(reader.index.clone())
+                .with_phrase_intersection(config.phrase_intersection)),
+            _ => None,
+        };
+        let mut lowest: BinaryHeap<ByDocId> = BinaryHeap::with_capacity(window.min(1024));
+        let mut total_hits = 0;
+
+        for segment_reader in &reader.segments {
+            segment_reader.read().for_each_document(|doc| {
+                if reader.deleted_docs.contains(doc.id.0 as u32) {
+                    return Ok(());
+                }
+                if let Some(matcher) = &matcher
+                    && !matcher.matches(&doc, query)?
+                {
+                    return Ok(());
+                }
+                total_hits += 1;
+
+                if lowest.len() < window {
+                    lowest.push(ByDocId(doc));
+                } else if lowest.peek().is_some_and(|highest| doc.id < highest.0.id) {
+                    lowest.pop();
+                    lowest.push(ByDocId(doc));
+                }
+                Ok(())
+            })?;
+        }
+
+        let max_score = if lowest.is_empty() { 0.0 } else { score };
+        let hits = lowest
+            .into_sorted_vec()
+            .into_iter()
+            .skip(offset)
+            .map(|ByDocId(doc)| ScoredDocument {
+                doc_id: doc.id,
+                score,
+                document: Some(doc),
+                explanation: None,
+            })
+            .collect();
+
+        Ok(SearchResults {
+            hits,
+            total_hits,
+            max_score,
+            took_ms: 0,
+        })
+    }
+
+    /// Keep only the requested stored fields of a document. Runs on the
+    /// final hits, after the segment has already read the whole document.
+    fn project_fields(mut doc: Document, fields: &[String]) -> Document {
+        doc.fields.retain(|name, _| fields.iter().any(|f| f == name));
+        doc
+    }
+
+    /// Calculate score for a matched document given a query and scorer
+    fn calculate_score<S: Scorer + ?Sized>(
+        &self,
+        doc: &ScoredDocument,
+        query: &Query,
+        matcher: &DocumentMatcher,
         scorer: &S,
         stats: &IndexStatistics,
     ) -> Result<f32> {
         match query {
             Query::Term(term_query) => {
-                self.score_term_query(doc_id, term_query, index, scorer, stats)
+                self.score_term_query(doc.doc_id, term_query, matcher.index(), scorer, stats)
             }
             Query::Bool(bool_query) => {
-                self.score_bool_query(doc_id, bool_query, index, scorer, stats)
+                self.score_bool_query(doc, bool_query, matcher, scorer, stats)
+            }
+            Query::Boosting(boosting) => {
+                let score = self.calculate_score(doc, &boosting.positive, matcher, scorer, stats)?;
+                let demoted = match &doc.document {
+                    Some(document) => matcher.matches(document, &boosting.negative)?,
+                    None => false,
+                };
+                Ok(if demoted { score * boosting.negative_boost } else { score })
             }
-            Query::Phrase(_phrase_query) => {
-                // For phrase queries, use simple scoring for now
-                // Proper phrase scoring would require position-aware scoring
-                Ok(1.0)
+            Query::DisMax(dis_max) => {
+                self.score_dis_max_query(doc, dis_max, matcher, scorer, stats)
+            }
+            Query::Range(range) => {
+                // Constant score: the boost, for documents inside the range
+                let in_range = match &doc.document {
+                    Some(document) => matcher.matches(document, query)?,
+                    None => true,
+                };
+                Ok(if in_range { range.boost.unwrap_or(1.0) } else { 0.0 })
+            }
+            Query::Prefix(prefix) => Ok(Self::score_expansion(
+                doc,
+                &prefix.field,
+                |word| word.starts_with(&prefix.prefix),
+                prefix.boost,
+                matcher.index(),
+                stats,
+            )),
+            Query::Wildcard(wildcard) => {
+                let pattern = wildcard_word_regex(&wildcard.pattern);
+                Ok(Self::score_expansion(
+                    doc,
+                    &wildcard.field,
+                    |word| pattern.as_ref().is_some_and(|re| re.is_match(word)),
+                    wildcard.boost,
+                    matcher.index(),
+                    stats,
+                ))
+            }
+            Query::Ids(ids) => Ok(if ids.values.contains(&doc.doc_id) {
+                ids.boost.unwrap_or(1.0)
+            } else {
+                0.0
+            }),
+            Query::Phrase(phrase_query) => {
+                self.score_phrase_query(doc.doc_id, phrase_query, matcher, scorer, stats)
             }
             _ => Ok(1.0), // Other query types use simple scoring
         }
     }
 
+    /// Score a prefix/wildcard match by the rarest term it expanded to in
+    /// this document (highest BM25 idf), so rare expansions outrank common
+    /// ones. Falls back to a flat 1.0 when no expansion is in the index.
+    fn score_expansion(
+        doc: &ScoredDocument,
+        field: &str,
+        expands_to: impl Fn(&str) -> bool,
+        boost: Option<f32>,
+        index: &InvertedIndex,
+        stats: &IndexStatistics,
+    ) -> f32 {
+        let Some(FieldValue::Text(text)) = doc.document.as_ref().and_then(|d| d.get_field(field))
+        else {
+            return boost.unwrap_or(1.0);
+        };
+
+        let best_idf = text
+            .split_whitespace()
+            .filter(|word| expands_to(word))
+            .filter_map(|word| index.dictionary.get_term_info(&Term::new(&word.to_lowercase())))
+            .map(|info| IdfFormula::Bm25.idf(info.doc_freq, stats.total_docs))
+            .fold(None, |best: Option<f32>, idf| Some(best.map_or(idf, |b| b.max(idf))));
+
+        best_idf.unwrap_or(1.0) * boost.unwrap_or(1.0)
+    }
+
     /// Score a single term query using the provided scorer
-    fn score_term_query<S: Scorer>(
+    fn score_term_query<S: Scorer + ?Sized>(
         &self,
         doc_id: DocId,
         term_query: &TermQuery,
@@ -418,7 +972,7 @@ impl QueryExecutor {
                     if posting.doc_id == doc_id {
                         // Calculate doc stats
                         let doc_stats = DocStats {
-                            doc_length: posting.positions.len(),
+                            doc_length: posting.doc_length(),
                             avg_doc_length: stats.avg_doc_length,
                             total_docs: stats.total_docs,
                         };
@@ -434,29 +988,98 @@ impl QueryExecutor {
         Ok(0.0) // Term not found in document
     }
 
-    /// Score a boolean query (sum of term scores)
-    fn score_bool_query<S: Scorer>(
+    /// Score a phrase by how many times it occurs in the document, weighted
+    /// by the idf of its rarest term
+    fn score_phrase_query<S: Scorer + ?Sized>(
         &self,
         doc_id: DocId,
-        bool_query: &BoolQuery,
-        index: &InvertedIndex,
+        phrase_query: &PhraseQuery,
+        matcher: &DocumentMatcher,
         scorer: &S,
         stats: &IndexStatistics,
     ) -> Result<f32> {
-        let mut total_score = 0.0;
+        let index = matcher.index();
+        let mut term_positions = Vec::with_capacity(phrase_query.phrase.len());
+        let mut rarest: Option<(Posting, &TermInfo)> = None;
 
-        // Score must clauses
-        for must_clause in &bool_query.must {
-            total_score += self.calculate_score(doc_id, must_clause, index, scorer, stats)?;
+        let Some(postings) = matcher.phrase_postings(&phrase_query.phrase, doc_id)? else {
+            return Ok(0.0);
+        };
+        for (term_text, posting) in phrase_query.phrase.iter().zip(postings) {
+            let Some(term_info) = index.dictionary.get_term_info(&Term::new(term_text)) else {
+                return Ok(0.0);
+            };
+
+            term_positions.push(posting.positions.clone());
+            if rarest.as_ref().is_none_or(|(_, info)| term_info.doc_freq < info.doc_freq) {
+                rarest = Some((posting, term_info));
+            }
         }
 
-        // Score should clauses
-        for should_clause in &bool_query.should {
-            total_score += self.calculate_score(doc_id, should_clause, index, scorer, stats)?;
-        }
+        let occurrences = matcher.phrase_occurrences(&term_positions, phrase_query.slop);
+        let Some((posting, term_info)) = rarest.filter(|_| occurrences > 0) else {
+            return Ok(0.0);
+        };
+
+        let doc_stats = DocStats {
+            doc_length: posting.doc_length(),
+            avg_doc_length: stats.avg_doc_length,
+            total_docs: stats.total_docs,
+        };
+        let score = scorer.score_phrase(occurrences as u32, &posting, term_info, &doc_stats);
+        Ok(score * phrase_query.boost.unwrap_or(1.0))
+    }
+
+    /// Score a boolean query (sum of term scores)
+    fn score_bool_query<S: Scorer + ?Sized>(
+        &self,
+        doc: &ScoredDocument,
+        bool_query: &BoolQuery,
+        matcher: &DocumentMatcher,
+        scorer: &S,
+        stats: &IndexStatistics,
+    ) -> Result<f32> {
+        // Score must and should clauses
+        let mut clause_scores = bool_query
+            .must
+            .iter()
+            .chain(&bool_query.should)
+            .map(|clause| self.calculate_score(doc, clause, matcher, scorer, stats))
+            .collect::<Result<Vec<f32>>>()?;
 
         // Apply boost
-        Ok(total_score * bool_query.boost.unwrap_or(1.0))
+        Ok(stable_sum(&mut clause_scores) * bool_query.boost.unwrap_or(1.0))
+    }
+
+    /// Score a dis_max query: best matching sub-query plus a tie-breaker share of the rest
+    fn score_dis_max_query<S: Scorer + ?Sized>(
+        &self,
+        doc: &ScoredDocument,
+        dis_max: &DisMaxQuery,
+        matcher: &DocumentMatcher,
+        scorer: &S,
+        stats: &IndexStatistics,
+    ) -> Result<f32> {
+        let mut max_score = 0.0f32;
+        let mut scores = Vec::with_capacity(dis_max.queries.len());
+
+        for query in &dis_max.queries {
+            // Only sub-queries that match this document contribute
+            let matched = match &doc.document {
+                Some(document) => matcher.matches(document, query)?,
+                None => true,
+            };
+            if !matched {
+                continue;
+            }
+            let score = self.calculate_score(doc, query, matcher, scorer, stats)?;
+            max_score = max_score.max(score);
+            scores.push(score);
+        }
+
+        let sum_score = stable_sum(&mut scores);
+        let total = max_score + dis_max.tie_breaker * (sum_score - max_score);
+        Ok(total * dis_max.boost.unwrap_or(1.0))
     }
 
     /// Generate detailed score explanation
@@ -497,11 +1120,47 @@ impl Default for QueryExecutor {
     }
 }
 
+/// A document ordered by its id alone, for picking the lowest ids of a scan
+struct ByDocId(Document);
+
+impl PartialEq for ByDocId {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for ByDocId {}
+
+impl PartialOrd for ByDocId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByDocId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.id.cmp(&other.0.id)
+    }
+}
+
+/// Anchored regex matching one whole word against a `*`/`?` pattern
+fn wildcard_word_regex(pattern: &str) -> Option<regex::Regex> {
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex::Regex::new(&regex).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::types::FieldValue;
-    use crate::query::ast::{FuzzyQuery, PhraseQuery, RangeQuery, WildcardQuery};
+    use crate::query::ast::{FuzzyQuery, PhraseQuery, PrefixQuery, RangeQuery, WildcardQuery};
 
     #[test]
     fn test_execution_config() {
@@ -535,7 +1194,7 @@ mod tests {
     #[test]
     fn optimize_query_preserves_non_term_query_semantics() {
         let executor = QueryExecutor::new();
-        let index = InvertedIndex::new();
+        let stats = IndexStatistics::from_index(&InvertedIndex::new());
 
         let phrase = Query::Phrase(PhraseQuery {
             field: "content".to_string(),
@@ -564,10 +1223,10 @@ mod tests {
             boost: None,
         });
 
-        let optimized_phrase = executor.optimize_query(&phrase, &index).unwrap();
-        let optimized_wildcard = executor.optimize_query(&wildcard, &index).unwrap();
-        let optimized_fuzzy = executor.optimize_query(&fuzzy, &index).unwrap();
-        let optimized_range = executor.optimize_query(&range, &index).unwrap();
+        let optimized_phrase = executor.optimize_query(&phrase, &stats).unwrap();
+        let optimized_wildcard = executor.optimize_query(&wildcard, &stats).unwrap();
+        let optimized_fuzzy = executor.optimize_query(&fuzzy, &stats).unwrap();
+        let optimized_range = executor.optimize_query(&range, &stats).unwrap();
 
         assert!(matches!(optimized_phrase, Query::Phrase(_)));
         assert!(matches!(optimized_wildcard, Query::Wildcard(_)));
@@ -578,7 +1237,7 @@ mod tests {
     #[test]
     fn optimize_query_keeps_bool_with_must_not() {
         let executor = QueryExecutor::new();
-        let index = InvertedIndex::new();
+        let stats = IndexStatistics::from_index(&InvertedIndex::new());
 
         let bool_query = Query::Bool(BoolQuery {
             must: vec![],
@@ -597,7 +1256,7 @@ mod tests {
             boost: None,
         });
 
-        let optimized = executor.optimize_query(&bool_query, &index).unwrap();
+        let optimized = executor.optimize_query(&bool_query, &stats).unwrap();
         let Query::Bool(q) = optimized else {
             panic!("expected bool query");
         };
@@ -608,4 +1267,814 @@ mod tests {
             _ => panic!("expected term in must_not"),
         }
     }
+
+    #[test]
+    fn execute_computes_index_statistics_once() {
+        use crate::mvcc::controller::MVCCController;
+        use crate::query::types::FROM_INDEX_CALLS;
+        use crate::reader::reader_pool::ReaderPool;
+        use crate::storage::layout::StorageLayout;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let pool = ReaderPool::new(
+            Arc::new(MVCCController::new()),
+            storage,
+            Arc::new(InvertedIndex::new()),
+            4,
+        );
+        let reader = pool.get_reader().unwrap();
+        let query = Query::Term(TermQuery {
+            field: "content".to_string(),
+            value: "rust".to_string(),
+            boost: None,
+        });
+
+        FROM_INDEX_CALLS.with(|calls| calls.set(0));
+        QueryExecutor::new()
+            .execute(&reader, &query, 10, 0, ExecutionConfig::default())
+            .unwrap();
+        assert_eq!(FROM_INDEX_CALLS.with(|calls| calls.get()), 1);
+    }
+
+    /// Write `docs` to one segment and index their text fields into a fresh
+    /// shared index, returning a pool whose readers see both.
+    fn indexed_pool(
+        storage_dir: &std::path::Path,
+        docs: &[Document],
+    ) -> crate::reader::reader_pool::ReaderPool {
+        segmented_pool(storage_dir, &[docs])
+    }
+
+    /// One segment per slice, created one minute apart in slice order
+    fn segmented_pool(
+        storage_dir: &std::path::Path,
+        segments: &[&[Document]],
+    ) -> crate::reader::reader_pool::ReaderPool {
+        use crate::analysis::token::Token;
+        use crate::compression::compress::CompressionType;
+        use crate::memory::buffer_pool::BufferPool;
+        use crate::mvcc::controller::MVCCController;
+        use crate::reader::reader_pool::ReaderPool;
+        use crate::storage::layout::StorageLayout;
+        use crate::storage::segment::SegmentId;
+        use crate::storage::segment_writer::SegmentWriter;
+        use std::sync::Arc;
+
+        let storage = Arc::new(StorageLayout::new(storage_dir.to_path_buf()).unwrap());
+        let mut index = InvertedIndex::new();
+        let mut published = Vec::new();
+        let created = chrono::Utc::now();
+        for (age, docs) in segments.iter().enumerate() {
+            let mut writer = SegmentWriter::new(
+                &storage,
+                SegmentId::new(),
+                Arc::new(BufferPool::new(1024 * 1024)),
+                CompressionType::None,
+            )
+            .unwrap();
+            for doc in docs.iter() {
+                let tokens: Vec<Token> = doc
+                    .fields
+                    .values()
+                    .filter_map(|value| match value {
+                        FieldValue::Text(text) => Some(text.split_whitespace()),
+                        _ => None,
+                    })
+                    .flatten()
+                    .enumerate()
+                    .map(|(i, word)| Token::new(word.to_string(), i as u32, 0))
+                    .collect();
+                index.add_document(doc.id, &tokens).unwrap();
+                writer.write_document(doc).unwrap();
+            }
+            let mut segment = writer.finish(&storage).unwrap();
+            segment.metadata.created_at = created + chrono::Duration::minutes(age as i64);
+            published.push(Arc::new(segment));
+        }
+
+        let mvcc = Arc::new(MVCCController::new());
+        mvcc.create_snapshot(published);
+        ReaderPool::new(mvcc, storage, Arc::new(index), 4)
+    }
+
+    fn text_doc(id: u64, fields: &[(&str, &str)]) -> Document {
+        Document {
+            id: DocId(id),
+            fields: fields
+                .iter()
+                .map(|(name, text)| (name.to_string(), FieldValue::Text(text.to_string())))
+                .collect(),
+        }
+    }
+
+    fn term(field: &str, value: &str) -> Query {
+        Query::Term(TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+            boost: None,
+        })
+    }
+
+    #[test]
+    fn match_all_returns_first_live_docs_in_id_order() {
+        use crate::reader::reader_pool::IndexReader;
+        use roaring::RoaringBitmap;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let doc = |id| text_doc(id, &[("content", if id % 2 == 0 { "even" } else { "odd" })]);
+        let pool = segmented_pool(
+            temp_dir.path(),
+            &[&[doc(5), doc(1), doc(3)], &[doc(2), doc(6), doc(4)]],
+        );
+        let pooled = pool.get_reader().unwrap();
+        let reader = IndexReader {
+            snapshot: pooled.snapshot.clone(),
+            segments: pooled.segments.clone(),
+            deleted_docs: Arc::new(RoaringBitmap::from_iter([2u32])),
+            index: pooled.index.clone(),
+            document_cache: None,
+        };
+        let executor = QueryExecutor::new();
+        let ids = |results: &SearchResults| results.hits.iter().map(|h| h.doc_id.0).collect::<Vec<_>>();
+
+        let results = executor
+            .execute(&reader, &Query::MatchAll, 3, 0, ExecutionConfig::default())
+            .unwrap();
+        assert_eq!(ids(&results), vec![1, 3, 4]);
+        assert_eq!(results.total_hits, 5);
+        assert!(results.hits.iter().all(|h| h.score == 1.0 && h.document.is_some()));
+
+        let page = executor
+            .execute(&reader, &Query::MatchAll, 3, 2, ExecutionConfig::default())
+            .unwrap();
+        assert_eq!(ids(&page), vec![4, 5, 6]);
+
+        // Same hits as the scored path, which ranks equal scores by doc id
+        let scored = Query::Bool(BoolQuery {
+            must: vec![],
+            should: vec![Query::MatchAll],
+            must_not: vec![],
+            filter: vec![],
+            boost: None,
+            minimum_should_match: None,
+        });
+        let generic = executor.execute(&reader, &scored, 3, 0, ExecutionConfig::default()).unwrap();
+        assert_eq!(ids(&generic), ids(&results));
+
+        let filtered = Query::Bool(BoolQuery {
+            must: vec![Query::MatchAll],
+            should: vec![],
+            must_not: vec![],
+            filter: vec![term("content", "even")],
+            boost: None,
+            minimum_should_match: None,
+        });
+        let results = executor.execute(&reader, &filtered, 10, 0, ExecutionConfig::default()).unwrap();
+        assert_eq!(ids(&results), vec![4, 6]);
+        assert_eq!(results.total_hits, 2);
+    }
+
+    #[test]
+    fn boosting_query_demotes_negative_matches_without_dropping_them() {
+        use crate::query::ast::BoostingQuery;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(
+            temp_dir.path(),
+            &[
+                text_doc(1, &[("content", "rust deprecated api")]),
+                text_doc(2, &[("content", "rust modern api")]),
+                text_doc(3, &[("content", "rust guide book")]),
+                text_doc(4, &[("content", "python guide book")]),
+            ],
+        );
+        let reader = pool.get_reader().unwrap();
+
+        let query = Query::Boosting(BoostingQuery {
+            positive: Box::new(term("content", "rust")),
+            negative: Box::new(term("content", "deprecated")),
+            negative_boost: 0.2,
+        });
+
+        let results = QueryExecutor::new()
+            .execute(&reader, &query, 10, 0, ExecutionConfig::default())
+            .unwrap();
+        let ranked: Vec<DocId> = results.hits.iter().map(|hit| hit.doc_id).collect();
+
+        assert_eq!(ranked.len(), 3, "{:?}", ranked);
+        assert_eq!(ranked[2], DocId(1));
+        let top = results.hits[0].score;
+        assert!(top > 0.0);
+        assert!(results.hits[2].score < top * 0.5);
+    }
+
+    #[test]
+    fn dis_max_takes_best_field_instead_of_summing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(
+            temp_dir.path(),
+            &[
+                text_doc(1, &[("title", "rust"), ("body", "rust")]),
+                text_doc(2, &[("title", "guide"), ("body", "rust")]),
+                text_doc(3, &[("title", "python"), ("body", "snakes")]),
+            ],
+        );
+        let reader = pool.get_reader().unwrap();
+        let executor = QueryExecutor::new();
+        let score_of = |query: &Query, id: u64| {
+            executor
+                .execute(&reader, query, 10, 0, ExecutionConfig::default())
+                .unwrap()
+                .hits
+                .into_iter()
+                .find(|hit| hit.doc_id == DocId(id))
+                .map(|hit| hit.score)
+                .unwrap()
+        };
+
+        let clauses = vec![term("title", "rust"), term("body", "rust")];
+        let should = Query::Bool(BoolQuery {
+            should: clauses.clone(),
+            ..BoolQuery::new()
+        });
+        let dis_max = |tie_breaker| {
+            Query::DisMax(DisMaxQuery {
+                queries: clauses.clone(),
+                tie_breaker,
+                boost: None,
+            })
+        };
+
+        // Doc 1 matches in both fields: bool sums, dis_max keeps the best one
+        let single = score_of(&dis_max(0.0), 1);
+        assert!(single > 0.0);
+        assert!((score_of(&should, 1) - 2.0 * single).abs() < 1e-5);
+        assert!((score_of(&dis_max(0.3), 1) - 1.3 * single).abs() < 1e-5);
+
+        // Doc 2 matches in one field only: no tie-breaker bonus
+        let body_only = score_of(&dis_max(0.3), 2);
+        assert!((body_only - score_of(&dis_max(0.0), 2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sort_uses_fields_that_are_projected_away() {
+        use crate::query::types::SortOrder;
+        use crate::search::sort::SortField;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(
+            temp_dir.path(),
+            &[
+                text_doc(1, &[("content", "rust"), ("category", "tools")]),
+                text_doc(2, &[("content", "rust"), ("category", "books")]),
+                text_doc(3, &[("content", "rust")]),
+                text_doc(4, &[("content", "rust"), ("category", "art")]),
+            ],
+        );
+        let reader = pool.get_reader().unwrap();
+
+        let config = ExecutionConfig::default()
+            .with_sort(SortSpec::new().then(SortField::field("category", SortOrder::Asc)))
+            .with_fields(["content"]);
+        let results = QueryExecutor::new()
+            .execute(&reader, &term("content", "rust"), 3, 0, config)
+            .unwrap();
+
+        let ranked: Vec<DocId> = results.hits.iter().map(|hit| hit.doc_id).collect();
+        assert_eq!(ranked, vec![DocId(4), DocId(2), DocId(1)]);
+        assert_eq!(results.total_hits, 4);
+        assert!(results
+            .hits
+            .iter()
+            .all(|hit| !hit.document.as_ref().unwrap().fields.contains_key("category")));
+    }
+
+    #[test]
+    fn parallel_repeated_queries_rank_identically() {
+        use std::sync::Arc;
+
+        let words = ["rust", "search", "engine", "index", "query"];
+        let docs: Vec<Document> = (0..40u64)
+            .map(|id| {
+                let text: Vec<&str> = (0..1 + id as usize % 7)
+                    .map(|i| words[(id as usize + i * 3) % words.len()])
+                    .collect();
+                text_doc(id, &[("content", &text.join(" "))])
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(indexed_pool(temp_dir.path(), &docs));
+
+        // Same clauses in every rotation: the score must not depend on order
+        let clauses: Vec<Query> = words[..3].iter().map(|w| term("content", w)).collect();
+        let queries: Vec<Query> = (0..clauses.len())
+            .map(|shift| {
+                let mut should = clauses.clone();
+                should.rotate_left(shift);
+                Query::Bool(BoolQuery { should, ..BoolQuery::new() })
+            })
+            .collect();
+
+        let run = |query: &Query| -> Vec<(DocId, f32)> {
+            let reader = pool.get_reader().unwrap();
+            let config = ExecutionConfig::default().with_score_decimals(4);
+            QueryExecutor::new()
+                .execute(&reader, query, 15, 0, config)
+                .unwrap()
+                .hits
+                .iter()
+                .map(|hit| (hit.doc_id, hit.score))
+                .collect()
+        };
+        let expected = run(&queries[0]);
+        assert_eq!(expected.len(), 15);
+        for &(_, score) in &expected {
+            assert_eq!(score, crate::scoring::scorer::round_score(score, 4));
+        }
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|t| {
+                    let (queries, run) = (&queries, &run);
+                    scope.spawn(move || {
+                        (0..10)
+                            .map(|i| run(&queries[(t + i) % queries.len()]))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for handle in handles {
+                for ranking in handle.join().unwrap() {
+                    assert_eq!(ranking, expected);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn common_terms_above_cutoff_are_skipped() {
+        let docs: Vec<Document> = (0..10u64)
+            .map(|id| match id {
+                0 | 1 => text_doc(id, &[("content", "the rust book")]),
+                9 => text_doc(id, &[("content", "a rust guide")]),
+                _ => text_doc(id, &[("content", "the other book")]),
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+
+        let ids = |query: &Query, config: ExecutionConfig| {
+            let mut ids: Vec<u64> = QueryExecutor::new()
+                .execute(&reader, query, 20, 0, config)
+                .unwrap()
+                .hits
+                .iter()
+                .map(|hit| hit.doc_id.0)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let cutoff = || ExecutionConfig::default().with_common_term_cutoff(0.5);
+        let the_rust = Query::Bool(BoolQuery {
+            should: vec![term("content", "the"), term("content", "rust")],
+            ..BoolQuery::new()
+        });
+
+        // "the" is in 9 of 10 documents: only "rust" decides what matches
+        assert_eq!(ids(&the_rust, ExecutionConfig::default()).len(), 10);
+        assert_eq!(ids(&the_rust, cutoff()), vec![0, 1, 9]);
+
+        // Required rare terms still filter; the common one no longer does
+        let must = Query::Bool(BoolQuery {
+            must: vec![term("content", "the"), term("content", "rust")],
+            ..BoolQuery::new()
+        });
+        assert_eq!(ids(&must, ExecutionConfig::default()), vec![0, 1]);
+        assert_eq!(ids(&must, cutoff()), vec![0, 1, 9]);
+
+        // Nothing but common terms: kept so the query still matches
+        let only_common = Query::Bool(BoolQuery {
+            should: vec![term("content", "the")],
+            ..BoolQuery::new()
+        });
+        assert_eq!(ids(&only_common, cutoff()).len(), 9);
+    }
+
+    #[test]
+    fn repeated_query_terms_score_once() {
+        let docs = vec![
+            text_doc(1, &[("content", "rust programming")]),
+            text_doc(2, &[("content", "rust rust rust")]),
+            text_doc(3, &[("content", "go programming")]),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let parser = crate::query::parser::QueryParser::new();
+
+        let scores = |input: &str| -> Vec<(u64, f32)> {
+            let query = parser.parse(input).unwrap();
+            QueryExecutor::new()
+                .execute(&reader, &query, 10, 0, ExecutionConfig::default())
+                .unwrap()
+                .hits
+                .iter()
+                .map(|hit| (hit.doc_id.0, hit.score))
+                .collect()
+        };
+
+        let single = scores("rust");
+        assert_eq!(single.len(), 2);
+        assert_eq!(scores("rust rust"), single);
+        assert_eq!(scores("rust OR rust"), single);
+    }
+
+    #[test]
+    fn boosted_range_adds_its_boost_to_bool_score() {
+        let docs: Vec<Document> = [(1, 2019.0), (2, 2023.0)]
+            .into_iter()
+            .map(|(id, year)| {
+                let mut doc = text_doc(id, &[("content", "rust guide")]);
+                doc.add_field("year".to_string(), FieldValue::Number(year));
+                doc
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+
+        let recent = Query::Range(RangeQuery {
+            field: "year".to_string(),
+            gt: None,
+            gte: Some(FieldValue::Number(2020.0)),
+            lt: None,
+            lte: None,
+            boost: Some(3.0),
+        });
+        let scores = |query: Query| -> Vec<(u64, f32)> {
+            let mut hits: Vec<(u64, f32)> = QueryExecutor::new()
+                .execute(&reader, &query, 10, 0, ExecutionConfig::default())
+                .unwrap()
+                .hits
+                .iter()
+                .map(|hit| (hit.doc_id.0, hit.score))
+                .collect();
+            hits.sort_by_key(|(id, _)| *id);
+            hits
+        };
+
+        let plain = scores(term("content", "guide"));
+        let boosted = scores(Query::Bool(BoolQuery {
+            should: vec![term("content", "guide"), recent],
+            ..BoolQuery::default()
+        }));
+
+        // Only the document inside the range gains the boost
+        assert_eq!(boosted.len(), 2);
+        assert!((boosted[0].1 - plain[0].1).abs() < 1e-4);
+        assert!((boosted[1].1 - plain[1].1 - 3.0).abs() < 1e-4, "{:?} vs {:?}", boosted, plain);
+    }
+
+    #[test]
+    fn prefix_and_wildcard_rank_rare_expansions_higher() {
+        let mut docs = vec![
+            text_doc(1, &[("content", "searching")]),
+            text_doc(2, &[("content", "seahorse")]),
+        ];
+        // Make "searching" common
+        docs.extend((3..8).map(|id| text_doc(id, &[("content", "searching again")])));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+
+        let top = |query: Query| {
+            let results = QueryExecutor::new()
+                .execute(&reader, &query, 10, 0, ExecutionConfig::default())
+                .unwrap();
+            assert_eq!(results.hits.len(), 7);
+            assert!(results.hits[0].score > results.hits[1].score);
+            results.hits[0].doc_id
+        };
+
+        assert_eq!(
+            top(Query::Prefix(PrefixQuery {
+                field: "content".to_string(),
+                prefix: "sea".to_string(),
+                boost: None,
+            })),
+            DocId(2)
+        );
+        assert_eq!(
+            top(Query::Wildcard(WildcardQuery {
+                field: "content".to_string(),
+                pattern: "sea*".to_string(),
+                boost: None,
+            })),
+            DocId(2)
+        );
+    }
+
+    #[test]
+    fn filter_only_execution_skips_scoring_and_returns_doc_id_order() {
+        let docs: Vec<Document> = [
+            (1, "rust"),
+            (2, "rust rust rust and more rust"),
+            (3, "go"),
+            (4, "rust book"),
+            (5, "rust rust"),
+        ]
+        .into_iter()
+        .map(|(id, text)| text_doc(id, &[("content", text)]))
+        .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let executor = QueryExecutor::new();
+
+        let filtered = executor
+            .execute(&reader, &term("content", "rust"), 3, 0, ExecutionConfig::default().without_scores())
+            .unwrap();
+        let ids: Vec<u64> = filtered.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!(filtered.total_hits, 4);
+        assert!(filtered.hits.iter().all(|hit| hit.score == 0.0));
+        assert_eq!(executor.scored_docs(), 0);
+
+        let ranked = executor
+            .execute(&reader, &term("content", "rust"), 3, 0, ExecutionConfig::default())
+            .unwrap();
+        assert_eq!(executor.scored_docs(), 4);
+        assert_ne!(ranked.hits.iter().map(|hit| hit.doc_id.0).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn newest_segments_first_ranks_recent_hits_first_on_ties() {
+        let logs = |ids: std::ops::RangeInclusive<u64>| -> Vec<Document> {
+            ids.map(|id| text_doc(id, &[("content", "disk error")])).collect()
+        };
+        let (oldest, middle, newest) = (logs(1..=3), logs(4..=6), logs(7..=9));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = segmented_pool(temp_dir.path(), &[&oldest, &middle, &newest]);
+        let reader = pool.get_reader().unwrap();
+        let ids = |config: ExecutionConfig| -> Vec<u64> {
+            QueryExecutor::new()
+                .execute(&reader, &term("content", "error"), 4, 0, config)
+                .unwrap()
+                .hits
+                .iter()
+                .map(|hit| hit.doc_id.0)
+                .collect()
+        };
+
+        assert_eq!(ids(ExecutionConfig::default()), vec![1, 2, 3, 4]);
+        assert_eq!(ids(ExecutionConfig::default().with_newest_segments_first()), vec![7, 8, 9, 4]);
+
+        let order: Vec<_> = reader
+            .segments_newest_first()
+            .iter()
+            .map(|segment| segment.read().segment_id)
+            .collect();
+        let expected: Vec<_> = reader.snapshot.segments.iter().rev().map(|s| s.id).collect();
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn phrase_found_more_often_scores_higher() {
+        let docs = vec![
+            text_doc(1, &[("content", "the quick fox ran past a slow dog")]),
+            text_doc(2, &[("content", "quick fox and quick fox and quick fox")]),
+            text_doc(3, &[("content", "fox before quick is no phrase")]),
+            text_doc(4, &[("content", "a slow dog sleeps")]),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let phrase = Query::Phrase(PhraseQuery {
+            field: "content".to_string(),
+            phrase: vec!["quick".to_string(), "fox".to_string()],
+            slop: 0,
+            boost: None,
+        });
+
+        for config in [ExecutionConfig::bm25(), ExecutionConfig::tfidf()] {
+            let results = QueryExecutor::new().execute(&reader, &phrase, 10, 0, config).unwrap();
+            let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+            assert_eq!(ids, vec![2, 1]);
+            assert!(results.hits[0].score > results.hits[1].score);
+            assert!(results.hits[1].score > 0.0);
+        }
+    }
+
+    #[test]
+    fn phrase_intersection_matches_per_document_scan() {
+        let words = ["quick", "fox", "slow", "dog", "the"];
+        let docs: Vec<Document> = (0..200u64)
+            .map(|id| {
+                let text: Vec<&str> = (0..12).map(|i| words[((id * 7 + i * i * 3) % 5) as usize]).collect();
+                text_doc(id, &[("content", &text.join(" "))])
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let phrase = |terms: &[&str], slop: u32| {
+            Query::Phrase(PhraseQuery {
+                field: "content".to_string(),
+                phrase: terms.iter().map(|t| t.to_string()).collect(),
+                slop,
+                boost: None,
+            })
+        };
+        let executor = QueryExecutor::new();
+
+        let queries = [
+            phrase(&["quick", "fox"], 0),
+            phrase(&["fox", "quick"], 1),
+            phrase(&["the", "the"], 0),
+            phrase(&["slow", "dog", "the"], 2),
+            phrase(&["quick", "missing"], 0),
+            Query::Bool(BoolQuery {
+                must: vec![term("content", "dog")],
+                should: vec![phrase(&["dog", "fox"], 0)],
+                must_not: vec![phrase(&["the", "slow"], 0)],
+                filter: vec![],
+                boost: None,
+                minimum_should_match: None,
+            }),
+        ];
+        let matched = executor.execute(&reader, &queries[0], 1000, 0, ExecutionConfig::default()).unwrap();
+        assert!(matched.total_hits > 10 && matched.total_hits < docs.len());
+
+        for query in &queries {
+            let hits = |config: ExecutionConfig| -> Vec<(u64, f32)> {
+                let results = executor.execute(&reader, query, 1000, 0, config).unwrap();
+                results.hits.iter().map(|hit| (hit.doc_id.0, hit.score)).collect()
+            };
+            let intersected = hits(ExecutionConfig::default());
+            assert_eq!(intersected, hits(ExecutionConfig::default().without_phrase_intersection()), "{:?}", query);
+            assert_eq!(
+                hits(ExecutionConfig::simple()),
+                hits(ExecutionConfig::simple().without_phrase_intersection()),
+                "{:?}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn bm25_scores_shorter_document_higher_for_same_term_freq() {
+        let docs = vec![
+            text_doc(1, &[("content", "rust compiler guide")]),
+            text_doc(2, &[("content", "rust notes on gardening cooking travel music and many other long topics")]),
+            text_doc(3, &[("content", "unrelated words only")]),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let query = Query::Term(TermQuery {
+            field: "content".to_string(),
+            value: "rust".to_string(),
+            boost: None,
+        });
+
+        let results = QueryExecutor::new().execute(&reader, &query, 10, 0, ExecutionConfig::bm25()).unwrap();
+        let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(results.hits[0].score > results.hits[1].score);
+    }
+
+    #[test]
+    fn custom_scorer_scores_every_hit() {
+        use crate::scoring::scorer::ScoringContext;
+        use std::sync::atomic::AtomicUsize;
+
+        struct ConstantScorer {
+            calls: AtomicUsize,
+        }
+        impl Scorer for ConstantScorer {
+            fn score_ctx(&self, ctx: &ScoringContext<'_>) -> f32 {
+                assert!(ctx.term_info.doc_freq > 0);
+                assert_eq!(ctx.posting.doc_length(), ctx.doc_stats.doc_length);
+                self.calls.fetch_add(1, AtomicOrdering::Relaxed);
+                7.0
+            }
+            fn name(&self) -> &str {
+                "constant"
+            }
+        }
+
+        let docs = vec![
+            text_doc(1, &[("content", "rust is fast")]),
+            text_doc(2, &[("content", "rust and more rust")]),
+            text_doc(3, &[("content", "python is slow")]),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let scorer = ConstantScorer { calls: AtomicUsize::new(0) };
+
+        let executor = QueryExecutor::new();
+        let results = executor
+            .execute_with_scorer(&reader, &term("content", "rust"), 10, &scorer)
+            .unwrap();
+        assert_eq!(results.hits.len(), 2);
+        assert!(results.hits.iter().all(|hit| hit.score == 7.0));
+        assert_eq!(scorer.calls.load(AtomicOrdering::Relaxed), 2);
+        assert_eq!(executor.scored_docs(), 2);
+    }
+
+    #[test]
+    fn rescorer_runs_only_on_the_first_stage_window() {
+        use std::sync::atomic::AtomicUsize;
+
+        // More "rust" ranks higher in the first stage
+        let docs: Vec<Document> = (1..=6)
+            .map(|i| {
+                let text = vec!["rust"; i as usize].join(" ") + " language";
+                text_doc(i, &[("content", text.as_str())])
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let query = term("content", "rust");
+        let executor = QueryExecutor::new();
+
+        let first_stage: Vec<u64> = executor
+            .execute(&reader, &query, 3, 0, ExecutionConfig::default())
+            .unwrap()
+            .hits
+            .iter()
+            .map(|hit| hit.doc_id.0)
+            .collect();
+        assert_eq!(first_stage, vec![6, 5, 4]);
+
+        // Prefers low doc ids, reversing the first-stage order
+        let calls = AtomicUsize::new(0);
+        let rescorer = |hit: &ScoredDocument| {
+            calls.fetch_add(1, AtomicOrdering::Relaxed);
+            assert!(hit.score > 0.0 && hit.document.is_some());
+            100.0 - hit.doc_id.0 as f32
+        };
+        let results = executor.execute_with_rescore(&reader, &query, 3, &rescorer).unwrap();
+
+        assert_eq!(calls.load(AtomicOrdering::Relaxed), 3);
+        assert_eq!(results.total_hits, 6);
+        let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, vec![4, 5, 6]);
+        assert_eq!(results.max_score, 96.0);
+    }
+
+    #[test]
+    fn hits_below_min_score_are_dropped() {
+        let docs: Vec<Document> = (1..=6)
+            .map(|i| {
+                let text = vec!["rust"; i as usize].join(" ") + " language";
+                text_doc(i, &[("content", text.as_str())])
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = indexed_pool(temp_dir.path(), &docs);
+        let reader = pool.get_reader().unwrap();
+        let query = term("content", "rust");
+        let executor = QueryExecutor::new();
+
+        let all = executor.execute(&reader, &query, 10, 0, ExecutionConfig::default()).unwrap();
+        assert_eq!(all.total_hits, 6);
+        // Between the third and fourth best scores
+        let threshold = (all.hits[2].score + all.hits[3].score) / 2.0;
+
+        let config = ExecutionConfig::default().with_min_score(threshold);
+        let results = executor.execute(&reader, &query, 2, 0, config.clone()).unwrap();
+        let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, vec![6, 5]);
+        assert_eq!(results.total_hits, 3);
+
+        let above: Vec<u64> = executor
+            .execute(&reader, &query, 10, 0, config.clone())
+            .unwrap()
+            .hits
+            .iter()
+            .map(|hit| hit.doc_id.0)
+            .collect();
+        assert_eq!(above, vec![6, 5, 4]);
+
+        let results = executor
+            .execute(&reader, &query, 10, 0, config.with_total_hits_before_min_score())
+            .unwrap();
+        assert_eq!(results.hits.len(), 3);
+        assert_eq!(results.total_hits, 6);
+
+        // Match-all scores are constant: all or nothing clears the bar
+        let match_all = |min_score| {
+            let config = ExecutionConfig::default().with_min_score(min_score);
+            executor.execute(&reader, &Query::MatchAll, 10, 0, config).unwrap().hits.len()
+        };
+        assert_eq!(match_all(1.0), 6);
+        assert_eq!(match_all(1.5), 0);
+    }
 }
@@ -4,4 +4,7 @@ pub mod prefix;
 pub mod fuzzy;
 mod streaming;
 pub mod collector;
-pub mod pipeline;
\ No newline at end of file
+pub mod pipeline;
+pub mod source;
+pub mod sort;
+pub mod aggregation;
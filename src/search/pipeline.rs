@@ -6,7 +6,7 @@ use crate::query::optimizer::QueryOptimizer;
 use crate::query::planner::{LogicalPlan, QueryPlanner};
 use crate::query::types::{IndexStatistics, QueryValidator, ValidationConfig};
 use crate::reader::reader_pool::IndexReader;
-use crate::scoring::scorer::{BM25Scorer, DocStats, Scorer, ScoringContext};
+use crate::scoring::scorer::{stable_sum, BM25Scorer, DocStats, Scorer, ScoringContext};
 use crate::search::collector::{CollectDecision, Collector, IntoResults, MatchedDocument};
 use crate::search::results::{ScoreExplanation, TopKCollector};
 use std::sync::Arc;
@@ -78,6 +78,7 @@ impl<S: Scorer, C: Collector + IntoResults> SearchPipeline<S, C> {
         let stats = IndexStatistics::from_index(&reader.index);
 
         'segments: for segment_reader in &reader.segments {
+            self.collector.begin_segment();
             let seg = segment_reader.read();
             let matches = seg.search(&optimized_query, &matcher)?;
 
@@ -260,13 +261,22 @@ fn is_safe_to_optimize(query: &Query) -> bool {
                 && bool_query.filter.is_empty()
                 && bool_query.must.iter().all(is_safe_to_optimize)
                 && bool_query.should.iter().all(is_safe_to_optimize)
+                // The plan intersects must clauses and unions should clauses
+                // alone, so it only keeps the default should-clause minimum
+                && bool_query.required_should_matches()
+                    == usize::from(bool_query.must.is_empty() && !bool_query.should.is_empty())
         }
         Query::MatchAll => true,
         Query::Phrase(_)
+        | Query::Near(_)
+        | Query::Boosting(_)
+        | Query::DisMax(_)
         | Query::Range(_)
         | Query::Prefix(_)
         | Query::Wildcard(_)
-        | Query::Fuzzy(_) => false,
+        | Query::Fuzzy(_)
+        | Query::Ids(_)
+        | Query::Predicate(_) => false,
     }
 }
 
@@ -285,7 +295,7 @@ fn calculate_score_with<S: Scorer>(
                     for posting in &posting_list.iter()? {
                         if posting.doc_id == doc_id {
                             let doc_stats = DocStats {
-                                doc_length: posting.positions.len(),
+                                doc_length: posting.doc_length(),
                                 avg_doc_length: stats.avg_doc_length,
                                 total_docs: stats.total_docs,
                             };
@@ -304,15 +314,17 @@ fn calculate_score_with<S: Scorer>(
             Ok(0.0)
         }
         Query::Bool(bq) => {
-            let mut total = 0.0;
-            for must in &bq.must {
-                total += calculate_score_with(doc_id, must, index, scorer, stats)?;
-            }
-            for should in &bq.should {
-                total += calculate_score_with(doc_id, should, index, scorer, stats)?;
-            }
-            Ok(total * bq.boost.unwrap_or(1.0))
+            let mut clause_scores = bq
+                .must
+                .iter()
+                .chain(&bq.should)
+                .map(|clause| calculate_score_with(doc_id, clause, index, scorer, stats))
+                .collect::<Result<Vec<f32>>>()?;
+            Ok(stable_sum(&mut clause_scores) * bq.boost.unwrap_or(1.0))
         }
+        Query::Range(rq) => Ok(rq.boost.unwrap_or(1.0)),
+        Query::Ids(iq) if iq.values.contains(&doc_id) => Ok(iq.boost.unwrap_or(1.0)),
+        Query::Ids(_) => Ok(0.0),
         _ => Ok(1.0),
     }
 }
@@ -332,6 +344,7 @@ mod tests {
             segments: Vec::new(),
             deleted_docs: Arc::new(RoaringBitmap::new()),
             index,
+            document_cache: None,
         };
 
         let result = pipeline.execute(&reader, &Query::MatchAll);
@@ -51,10 +51,18 @@ pub struct ScoreExplanation {
     pub details: Vec<ScoreExplanation>,
 }
 
-/// Top-K collector for efficient result collection
+/// Top-K collector for efficient result collection. With an `offset` it
+/// keeps the top `offset + k` and returns ranks `[offset, offset + k)`.
+///
+/// `heap` is a min-heap (see `ScoredDocument`'s reversed ordering) that
+/// never grows past the window: once full, a new hit only enters by
+/// evicting the lowest-scoring one.
 pub struct TopKCollector {
     pub heap: BinaryHeap<ScoredDocument>,
     pub k: usize,
+    pub offset: usize,
+    /// Lowest kept score once the heap is full; hits scoring at or below it
+    /// can't enter. `NEG_INFINITY` until then.
     pub min_score: f32,
     pub total_collected: usize,  // Track total documents processed
 }
@@ -64,61 +72,200 @@ impl TopKCollector {
         TopKCollector {
             heap: BinaryHeap::with_capacity(k + 1),
             k,
-            min_score: 0.0,
+            offset: 0,
+            min_score: f32::NEG_INFINITY,
             total_collected: 0,
         }
     }
 
+    /// Skip the `offset` best hits, returning the page after them
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Hits kept while collecting: the skipped ones plus the page itself
+    fn window(&self) -> usize {
+        self.k.saturating_add(self.offset)
+    }
+
     pub fn collect(&mut self, scored_doc: ScoredDocument) {
         self.total_collected += 1;  // Increment count
 
-        if scored_doc.score > self.min_score || self.heap.len() < self.k {
-            self.heap.push(scored_doc);
+        let window = self.window();
+        if window == 0 || !self.is_competitive(scored_doc.score) {
+            return;
+        }
 
-            if self.heap.len() > self.k {
-                self.heap.pop();
-                if let Some(min_doc) = self.heap.peek() {
-                    self.min_score = min_doc.score;
-                }
-            }
+        self.heap.push(scored_doc);
+        if self.heap.len() > window {
+            self.heap.pop();
         }
+        if self.heap.len() == window
+            && let Some(min_doc) = self.heap.peek()
+        {
+            self.min_score = min_doc.score;
+        }
+    }
+
+    /// Whether a hit with `score` would be kept if collected now
+    pub fn is_competitive(&self, score: f32) -> bool {
+        self.heap.len() < self.window() || score > self.min_score
     }
 
     pub fn get_results(self) -> Vec<ScoredDocument> {
+        let offset = self.offset;
         let mut results: Vec<_> = self.heap.into_iter().collect();
         results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        results
+        results.split_off(offset.min(results.len()))
     }
 
     pub fn max_score(&self) -> f32 {
-        self.heap.peek().map(|doc| doc.score).unwrap_or(0.0)
+        // `peek` is the lowest kept score, so scan for the highest
+        self.heap.iter().map(|doc| doc.score).reduce(f32::max).unwrap_or(0.0)
+    }
+}
+
+/// Collector that keeps a separate top-K per segment and re-selects the
+/// global top-K when finished.
+///
+/// Each segment must contribute its own top `k` (not fewer): a segment may
+/// hold every one of the global top-K, so anything less could drop a hit
+/// that outranks everything in the other segments.
+pub struct SegmentTopKCollector {
+    pub k: usize,
+    /// Global ranks skipped before the returned `k`; each segment then
+    /// keeps its top `offset + k`
+    pub offset: usize,
+    current: TopKCollector,
+    segments: Vec<Vec<ScoredDocument>>,
+    pub total_collected: usize,
+    /// Break score ties by segment order (earlier-collected segments win)
+    /// before doc id, so hits from segments read first rank first
+    pub segment_order_ties: bool,
+}
+
+impl SegmentTopKCollector {
+    pub fn new(k: usize) -> Self {
+        SegmentTopKCollector {
+            k,
+            offset: 0,
+            current: TopKCollector::new(k),
+            segments: Vec::new(),
+            total_collected: 0,
+            segment_order_ties: false,
+        }
+    }
+
+    /// Return global ranks `[offset, offset + k)`
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self.current = TopKCollector::new(self.window());
+        self
+    }
+
+    fn window(&self) -> usize {
+        self.k.saturating_add(self.offset)
+    }
+
+    /// Rank tied hits by the order their segments were collected in
+    pub fn with_segment_order_ties(mut self) -> Self {
+        self.segment_order_ties = true;
+        self
+    }
+
+    /// Close the current segment and stash its top-K
+    fn seal_segment(&mut self) {
+        if self.current.heap.is_empty() {
+            return;
+        }
+        let fresh = TopKCollector::new(self.window());
+        let sealed = std::mem::replace(&mut self.current, fresh);
+        self.segments.push(sealed.get_results());
+    }
+}
+
+impl Collector for SegmentTopKCollector {
+    fn begin_segment(&mut self) {
+        self.seal_segment();
+    }
+
+    fn collect(&mut self, doc: MatchedDocument) -> CollectDecision {
+        self.total_collected += 1;
+        Collector::collect(&mut self.current, doc)
+    }
+
+    fn min_competitive_score(&self) -> Option<f32> {
+        self.current.min_competitive_score()
+    }
+
+    fn finish(&mut self) {
+        self.seal_segment();
     }
 }
 
+impl IntoResults for SegmentTopKCollector {
+    type Output = SearchResults;
+
+    fn into_results(mut self) -> SearchResults {
+        self.finish();
+        let window = self.window();
+        let mut hits = if self.segment_order_ties {
+            merge_top_k_by_segment_order(self.segments, window)
+        } else {
+            merge_top_k(self.segments, window)
+        };
+        SearchResults {
+            max_score: hits.first().map(|d| d.score).unwrap_or(0.0),
+            hits: hits.split_off(self.offset.min(hits.len())),
+            total_hits: self.total_collected,
+            took_ms: 0,
+        }
+    }
+}
+
+/// Merge per-segment top-K lists into the global top `k` (highest score
+/// first, ties broken by doc id)
+pub fn merge_top_k(segments: Vec<Vec<ScoredDocument>>, k: usize) -> Vec<ScoredDocument> {
+    let mut all: Vec<ScoredDocument> = segments.into_iter().flatten().collect();
+    all.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.doc_id.0.cmp(&b.doc_id.0)));
+    all.truncate(k);
+    all
+}
+
+/// Like `merge_top_k`, but ties go to the segment that comes first in
+/// `segments`, then to the lower doc id
+pub fn merge_top_k_by_segment_order(segments: Vec<Vec<ScoredDocument>>, k: usize) -> Vec<ScoredDocument> {
+    let mut all: Vec<(usize, ScoredDocument)> = segments
+        .into_iter()
+        .enumerate()
+        .flat_map(|(order, hits)| hits.into_iter().map(move |hit| (order, hit)))
+        .collect();
+    all.sort_by(|(a_order, a), (b_order, b)| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a_order.cmp(b_order))
+            .then(a.doc_id.0.cmp(&b.doc_id.0))
+    });
+    all.into_iter().take(k).map(|(_, hit)| hit).collect()
+}
+
 // Implement the new Collector trait for TopKCollector
 impl Collector for TopKCollector {
     fn collect(&mut self, doc: MatchedDocument) -> CollectDecision {
-        let scored_doc = ScoredDocument {
+        TopKCollector::collect(self, ScoredDocument {
             doc_id: doc.doc_id,
             score: doc.score,
             document: doc.document,
             explanation: doc.explanation,
-        };
-        self.total_collected += 1;
-
-        if scored_doc.score > self.min_score || self.heap.len() < self.k {
-            self.heap.push(scored_doc);
-
-            if self.heap.len() > self.k {
-                self.heap.pop();
-                if let Some(min_doc) = self.heap.peek() {
-                    self.min_score = min_doc.score;
-                }
-            }
-        }
+        });
 
         CollectDecision::Continue
     }
+
+    fn min_competitive_score(&self) -> Option<f32> {
+        (self.heap.len() >= self.window()).then_some(self.min_score)
+    }
 }
 
 impl IntoResults for TopKCollector {
@@ -126,11 +273,11 @@ impl IntoResults for TopKCollector {
 
     fn into_results(self) -> SearchResults {
         let total_hits = self.total_collected;
-        let max_score = self.heap.peek().map(|d| d.score).unwrap_or(0.0);
+        let max_score = self.max_score();
         let hits = {
             let mut results: Vec<_> = self.heap.into_iter().collect();
             results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-            results
+            results.split_off(self.offset.min(results.len()))
         };
 
         SearchResults {
@@ -141,3 +288,106 @@ impl IntoResults for TopKCollector {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(id: u64, score: f32) -> MatchedDocument {
+        MatchedDocument {
+            doc_id: DocId(id),
+            score,
+            document: None,
+            explanation: None,
+        }
+    }
+
+    #[test]
+    fn global_top_hit_survives_crowded_segment() {
+        // Segment 0 holds the best hit plus many near-top ones; segment 1
+        // holds hits that beat the near-top ones of segment 0 but not its best
+        let segments: Vec<Vec<(u64, f32)>> = vec![
+            std::iter::once((0, 10.0))
+                .chain((1..50).map(|id| (id, 9.0 - id as f32 * 0.01)))
+                .collect(),
+            (100..150).map(|id| (id, 9.5 - (id - 100) as f32 * 0.001)).collect(),
+            vec![(200, 1.0)],
+        ];
+        let k = 5;
+
+        let mut collector = SegmentTopKCollector::new(k);
+        for segment in &segments {
+            collector.begin_segment();
+            for &(id, score) in segment {
+                collector.collect(matched(id, score));
+            }
+        }
+        let results = collector.into_results();
+
+        let mut expected: Vec<(u64, f32)> = segments.concat();
+        expected.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let expected: Vec<u64> = expected.iter().take(k).map(|&(id, _)| id).collect();
+
+        let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(ids[0], 0);
+        assert_eq!(results.max_score, 10.0);
+        assert_eq!(results.total_hits, 101);
+    }
+
+    #[test]
+    fn min_score_raises_the_competitive_floor_of_the_wrapped_collector() {
+        use crate::search::collector::MinScoreCollector;
+
+        let mut top_k = TopKCollector::new(2);
+        let mut collector = MinScoreCollector::new(&mut top_k, 5.0);
+        let floor = collector.min_competitive_score().unwrap();
+        // Hits at `min_score` are kept, so they must still count as competitive
+        assert!(floor < 5.0 && floor > 4.99);
+
+        for (id, score) in [(1, 3.0), (2, 8.0), (3, 9.0)] {
+            Collector::collect(&mut collector, matched(id, score));
+        }
+        assert_eq!(collector.min_competitive_score(), Some(8.0));
+        assert_eq!(collector.below_min_score, 1);
+    }
+
+    #[test]
+    fn top_k_heap_stays_bounded() {
+        let mut collector = TopKCollector::new(10);
+        // Scores are a permutation of 0..10_000, in scattered order
+        for i in 0..10_000u64 {
+            let score = ((i * 7919) % 10_000) as f32;
+            Collector::collect(&mut collector, matched(i, score));
+            assert!(collector.heap.len() <= 10);
+        }
+        assert_eq!(collector.min_competitive_score(), Some(9990.0));
+        let results = collector.into_results();
+
+        let scores: Vec<f32> = results.hits.iter().map(|hit| hit.score).collect();
+        let expected: Vec<f32> = (9990..10_000).rev().map(|s| s as f32).collect();
+        assert_eq!(scores, expected);
+        for hit in &results.hits {
+            assert_eq!(((hit.doc_id.0 * 7919) % 10_000) as f32, hit.score);
+        }
+        assert_eq!(results.max_score, 9999.0);
+        assert_eq!(results.total_hits, 10_000);
+    }
+
+    #[test]
+    fn offset_skips_leading_global_ranks() {
+        let mut collector = SegmentTopKCollector::new(3).with_offset(2);
+        for segment in [[(1, 9.0), (2, 5.0), (3, 1.0)], [(4, 8.0), (5, 7.0), (6, 2.0)]] {
+            collector.begin_segment();
+            for (id, score) in segment {
+                collector.collect(matched(id, score));
+            }
+        }
+        let results = collector.into_results();
+
+        let ids: Vec<u64> = results.hits.iter().map(|hit| hit.doc_id.0).collect();
+        assert_eq!(ids, vec![5, 2, 6]);
+        assert_eq!(results.max_score, 9.0);
+        assert_eq!(results.total_hits, 6);
+    }
+}
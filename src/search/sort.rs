@@ -0,0 +1,313 @@
+use std::cmp::Ordering;
+use crate::core::types::FieldValue;
+use crate::query::types::SortOrder;
+use crate::search::collector::{CollectDecision, Collector, IntoResults, MatchedDocument};
+use crate::search::results::{ScoredDocument, SearchResults};
+
+/// What a sort key reads from each hit
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    /// Relevance score
+    Score,
+    /// Stored field value of the hit's document
+    Field(String),
+}
+
+/// Where hits without a value for a sort field end up
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum MissingValue {
+    /// Before every hit that has a value, regardless of sort order
+    First,
+    /// After every hit that has a value, regardless of sort order
+    #[default]
+    Last,
+    /// Sort as if the document had this value
+    Default(FieldValue),
+}
+
+/// One key of a multi-key sort
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortField {
+    pub key: SortKey,
+    pub order: SortOrder,
+    pub missing: MissingValue,
+}
+
+impl SortField {
+    pub fn field(name: impl Into<String>, order: SortOrder) -> Self {
+        SortField {
+            key: SortKey::Field(name.into()),
+            order,
+            missing: MissingValue::default(),
+        }
+    }
+
+    pub fn score(order: SortOrder) -> Self {
+        SortField {
+            key: SortKey::Score,
+            order,
+            missing: MissingValue::default(),
+        }
+    }
+
+    pub fn missing(mut self, missing: MissingValue) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    fn value(&self, hit: &ScoredDocument) -> Option<FieldValue> {
+        match &self.key {
+            SortKey::Score => Some(FieldValue::Number(hit.score as f64)),
+            SortKey::Field(name) => hit
+                .document
+                .as_ref()
+                .and_then(|doc| doc.fields.get(name))
+                .cloned(),
+        }
+    }
+
+    fn compare(&self, a: &ScoredDocument, b: &ScoredDocument) -> Ordering {
+        let (a, b) = match &self.missing {
+            MissingValue::Default(default) => (
+                Some(self.value(a).unwrap_or_else(|| default.clone())),
+                Some(self.value(b).unwrap_or_else(|| default.clone())),
+            ),
+            _ => (self.value(a), self.value(b)),
+        };
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let ordering = compare_values(&a, &b);
+                match self.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            }
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if self.missing == MissingValue::First => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) if self.missing == MissingValue::First => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+        }
+    }
+}
+
+/// Composite comparator: hits are ordered by the first key, ties are broken
+/// by the next one, and remaining ties by ascending doc id.
+///
+/// Field keys read the hit's stored document, so documents must still carry
+/// the sort fields when they are compared.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SortSpec {
+    pub fields: Vec<SortField>,
+}
+
+impl SortSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a key with lower priority than the existing ones
+    pub fn then(mut self, field: SortField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn compare(&self, a: &ScoredDocument, b: &ScoredDocument) -> Ordering {
+        self.fields
+            .iter()
+            .map(|field| field.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.doc_id.0.cmp(&b.doc_id.0))
+    }
+
+    pub fn sort(&self, hits: &mut [ScoredDocument]) {
+        hits.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+/// Values of the same type compare naturally; mixed types are grouped by type
+fn compare_values(a: &FieldValue, b: &FieldValue) -> Ordering {
+    fn rank(value: &FieldValue) -> u8 {
+        match value {
+            FieldValue::Number(_) => 0,
+            FieldValue::Date(_) => 1,
+            FieldValue::Text(_) => 2,
+            FieldValue::Boolean(_) => 3,
+        }
+    }
+    match (a, b) {
+        (FieldValue::Number(a), FieldValue::Number(b)) => a.total_cmp(b),
+        (FieldValue::Date(a), FieldValue::Date(b)) => a.cmp(b),
+        (FieldValue::Text(a), FieldValue::Text(b)) => a.cmp(b),
+        (FieldValue::Boolean(a), FieldValue::Boolean(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Collector keeping the first `limit` hits in `SortSpec` order, after
+/// skipping `offset` of them
+pub struct SortedCollector {
+    spec: SortSpec,
+    limit: usize,
+    offset: usize,
+    hits: Vec<ScoredDocument>,
+    total_collected: usize,
+    max_score: f32,
+}
+
+impl SortedCollector {
+    pub fn new(spec: SortSpec, limit: usize) -> Self {
+        SortedCollector {
+            spec,
+            limit,
+            offset: 0,
+            hits: Vec::new(),
+            total_collected: 0,
+            max_score: 0.0,
+        }
+    }
+
+    /// Skip the first `offset` sorted hits
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Hits kept while collecting: the skipped ones plus the page itself
+    fn window(&self) -> usize {
+        self.limit.saturating_add(self.offset)
+    }
+
+    fn truncate(&mut self) {
+        self.spec.sort(&mut self.hits);
+        self.hits.truncate(self.window());
+    }
+}
+
+impl Collector for SortedCollector {
+    fn collect(&mut self, doc: MatchedDocument) -> CollectDecision {
+        self.total_collected += 1;
+        self.max_score = self.max_score.max(doc.score);
+        self.hits.push(ScoredDocument {
+            doc_id: doc.doc_id,
+            score: doc.score,
+            document: doc.document,
+            explanation: doc.explanation,
+        });
+        // Sort in batches so memory stays proportional to `limit + offset`
+        if self.hits.len() >= self.window().saturating_mul(2).max(64) {
+            self.truncate();
+        }
+        CollectDecision::Continue
+    }
+
+    fn finish(&mut self) {
+        self.truncate();
+    }
+}
+
+impl IntoResults for SortedCollector {
+    type Output = SearchResults;
+
+    fn into_results(mut self) -> SearchResults {
+        self.finish();
+        SearchResults {
+            hits: self.hits.split_off(self.offset.min(self.hits.len())),
+            total_hits: self.total_collected,
+            max_score: self.max_score,
+            took_ms: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{DocId, Document};
+    use std::collections::HashMap;
+
+    fn hit(id: u64, score: f32, fields: &[(&str, FieldValue)]) -> ScoredDocument {
+        ScoredDocument {
+            doc_id: DocId(id),
+            score,
+            document: Some(Document {
+                id: DocId(id),
+                fields: fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect::<HashMap<_, _>>(),
+            }),
+            explanation: None,
+        }
+    }
+
+    fn text(s: &str) -> FieldValue {
+        FieldValue::Text(s.to_string())
+    }
+
+    fn ids(hits: &[ScoredDocument]) -> Vec<u64> {
+        hits.iter().map(|hit| hit.doc_id.0).collect()
+    }
+
+    #[test]
+    fn sorts_by_category_then_score_descending() {
+        let mut hits = vec![
+            hit(1, 0.5, &[("category", text("books"))]),
+            hit(2, 2.0, &[("category", text("tools"))]),
+            hit(3, 1.5, &[("category", text("books"))]),
+            hit(4, 0.1, &[("category", text("art"))]),
+            hit(5, 1.5, &[("category", text("books"))]),
+        ];
+        let spec = SortSpec::new()
+            .then(SortField::field("category", SortOrder::Asc))
+            .then(SortField::score(SortOrder::Desc));
+
+        spec.sort(&mut hits);
+        // 3 and 5 tie on both keys and fall back to doc id
+        assert_eq!(ids(&hits), vec![4, 3, 5, 1, 2]);
+    }
+
+    #[test]
+    fn missing_values_follow_policy() {
+        let hits = vec![
+            hit(1, 1.0, &[("price", FieldValue::Number(30.0))]),
+            hit(2, 1.0, &[]),
+            hit(3, 1.0, &[("price", FieldValue::Number(10.0))]),
+            hit(4, 1.0, &[("price", FieldValue::Number(20.0))]),
+        ];
+        let sorted = |order: SortOrder, missing: MissingValue| {
+            let mut hits = hits.clone();
+            SortSpec::new()
+                .then(SortField::field("price", order).missing(missing))
+                .sort(&mut hits);
+            ids(&hits)
+        };
+
+        assert_eq!(sorted(SortOrder::Asc, MissingValue::Last), vec![3, 4, 1, 2]);
+        assert_eq!(sorted(SortOrder::Desc, MissingValue::Last), vec![1, 4, 3, 2]);
+        assert_eq!(sorted(SortOrder::Asc, MissingValue::First), vec![2, 3, 4, 1]);
+        assert_eq!(sorted(SortOrder::Desc, MissingValue::First), vec![2, 1, 4, 3]);
+        let default = MissingValue::Default(FieldValue::Number(25.0));
+        assert_eq!(sorted(SortOrder::Asc, default.clone()), vec![3, 4, 2, 1]);
+        assert_eq!(sorted(SortOrder::Desc, default), vec![1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn collector_keeps_first_hits_in_sort_order() {
+        let spec = SortSpec::new().then(SortField::field("rank", SortOrder::Asc));
+        let mut collector = SortedCollector::new(spec, 3);
+        for id in (0..200u64).rev() {
+            collector.collect(MatchedDocument {
+                doc_id: DocId(id),
+                score: id as f32,
+                document: hit(id, 0.0, &[("rank", FieldValue::Number(id as f64))]).document,
+                explanation: None,
+            });
+        }
+
+        let results = collector.into_results();
+        assert_eq!(ids(&results.hits), vec![0, 1, 2]);
+        assert_eq!(results.total_hits, 200);
+        assert_eq!(results.max_score, 199.0);
+    }
+}
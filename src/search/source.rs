@@ -0,0 +1,130 @@
+use crate::core::types::Document;
+
+/// Include/exclude filter over stored fields (like `_source` filtering)
+///
+/// Patterns are field-name globs: `*` matches any run of characters and
+/// `?` matches a single character. A field is kept when it matches at least
+/// one include pattern (or no includes are given) and no exclude pattern.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceFilter {
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+}
+
+impl SourceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an include pattern
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.includes.push(pattern.into());
+        self
+    }
+
+    /// Add an exclude pattern
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.excludes.push(pattern.into());
+        self
+    }
+
+    /// Check whether a field name passes the filter
+    pub fn allows(&self, field: &str) -> bool {
+        let included = self.includes.is_empty()
+            || self.includes.iter().any(|p| glob_match(p, field));
+        included && !self.excludes.iter().any(|p| glob_match(p, field))
+    }
+
+    /// Drop every field of the document that does not pass the filter
+    pub fn apply(&self, doc: &mut Document) {
+        doc.fields.retain(|name, _| self.allows(name));
+    }
+}
+
+/// Match `text` against a glob `pattern` supporting `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{DocId, FieldValue};
+    use std::collections::HashMap;
+
+    fn doc() -> Document {
+        Document {
+            id: DocId(1),
+            fields: HashMap::from([
+                ("title".to_string(), FieldValue::Text("rust".to_string())),
+                ("body".to_string(), FieldValue::Text("a very long body".to_string())),
+                ("meta_author".to_string(), FieldValue::Text("alice".to_string())),
+                ("meta_year".to_string(), FieldValue::Number(2024.0)),
+            ]),
+        }
+    }
+
+    fn field_names(doc: &Document) -> Vec<&str> {
+        let mut names: Vec<&str> = doc.fields.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn include_only_keeps_matching_fields() {
+        let mut d = doc();
+        SourceFilter::new().include("meta_*").include("title").apply(&mut d);
+        assert_eq!(field_names(&d), vec!["meta_author", "meta_year", "title"]);
+    }
+
+    #[test]
+    fn exclude_only_drops_matching_fields() {
+        let mut d = doc();
+        SourceFilter::new().exclude("body").apply(&mut d);
+        assert_eq!(field_names(&d), vec!["meta_author", "meta_year", "title"]);
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let mut d = doc();
+        SourceFilter::new()
+            .include("meta_*")
+            .exclude("meta_y??r")
+            .apply(&mut d);
+        assert_eq!(field_names(&d), vec!["meta_author"]);
+    }
+
+    #[test]
+    fn glob_match_handles_stars_and_question_marks() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*c", "abbbc"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "abbc"));
+        assert!(glob_match("*_*", "meta_year"));
+        assert!(!glob_match("title", "titles"));
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use parking_lot::Mutex;
+use crate::core::error::Result;
+
+/// An open file handed out by a [`StorageBackend`] backend
+pub trait StorageFile: Read + Write + Seek + Send + Sync {
+    /// Make everything written so far durable
+    fn sync_all(&self) -> io::Result<()>;
+
+    /// Current length in bytes
+    fn size(&self) -> io::Result<u64>;
+}
+
+impl StorageFile for File {
+    fn sync_all(&self) -> io::Result<()> {
+        File::sync_all(self)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+/// File operations behind [`StorageLayout`](crate::storage::layout::StorageLayout).
+///
+/// Every segment, index, WAL and metadata file is reached through this
+/// trait, so swapping the backend decides where the data lives. Besides the
+/// filesystem and RAM backends below, users can plug in their own (e.g. an
+/// object store) through `Config::storage_backend`.
+pub trait StorageBackend: Send + Sync + Debug {
+    /// Create (or truncate) a file for writing
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageFile>>;
+    /// Open an existing file for reading
+    fn open(&self, path: &Path) -> Result<Box<dyn StorageFile>>;
+    /// Open a file for reading and appending, creating it if missing
+    fn open_append(&self, path: &Path) -> Result<Box<dyn StorageFile>>;
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+    fn remove(&self, path: &Path) -> Result<()>;
+    fn create_dir_all(&self, path: &Path) -> Result<()>;
+    /// Files and directories directly inside `dir`
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>>;
+    /// Persist renames inside `dir`; best effort
+    fn sync_dir(&self, _dir: &Path) {}
+}
+
+/// The local filesystem (the default backend)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileStorage;
+
+impl StorageBackend for FileStorage {
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        Ok(Box::new(File::create(path)?))
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn open_append(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        let file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(fs::read(path)?)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        Ok(fs::write(path, data)?)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        Ok(fs::rename(from, to)?)
+    }
+
+    fn remove(&self, path: &Path) -> Result<()> {
+        Ok(fs::remove_file(path)?)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        Ok(fs::create_dir_all(path)?)
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect()
+    }
+
+    fn sync_dir(&self, dir: &Path) {
+        // Directories can't be opened for sync on every platform
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+/// Files kept in RAM; nothing ever touches the disk. Data is lost when the
+/// last handle to the storage is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    files: Mutex<HashMap<PathBuf, Arc<Mutex<Vec<u8>>>>>,
+    dirs: Mutex<HashSet<PathBuf>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn file(&self, path: &Path) -> io::Result<Arc<Mutex<Vec<u8>>>> {
+        self.files.lock().get(path).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+        })
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn create(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        let data = Arc::new(Mutex::new(Vec::new()));
+        self.files.lock().insert(path.to_path_buf(), data.clone());
+        Ok(Box::new(MemoryFile { data, position: 0, append: false }))
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        Ok(Box::new(MemoryFile { data: self.file(path)?, position: 0, append: false }))
+    }
+
+    fn open_append(&self, path: &Path) -> Result<Box<dyn StorageFile>> {
+        let data = self.files.lock().entry(path.to_path_buf()).or_default().clone();
+        Ok(Box::new(MemoryFile { data, position: 0, append: true }))
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(self.file(path)?.lock().clone())
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.files.lock().insert(path.to_path_buf(), Arc::new(Mutex::new(data.to_vec())));
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.lock().contains_key(path) || self.is_dir(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.lock().contains(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut files = self.files.lock();
+        let data = files.remove(from).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} not found", from.display()))
+        })?;
+        files.insert(to.to_path_buf(), data);
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> Result<()> {
+        self.file(path)?;
+        self.files.lock().remove(path);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let mut dirs = self.dirs.lock();
+        dirs.extend(path.ancestors().filter(|dir| !dir.as_os_str().is_empty()).map(Path::to_path_buf));
+        Ok(())
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        if !self.is_dir(dir) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", dir.display())).into());
+        }
+        let in_dir = |path: &&PathBuf| path.parent() == Some(dir);
+        let mut entries: Vec<PathBuf> = self.files.lock().keys().filter(in_dir).cloned().collect();
+        entries.extend(self.dirs.lock().iter().filter(in_dir).cloned());
+        Ok(entries)
+    }
+}
+
+/// Handle to a [`MemoryStorage`] file. Handles share the file's bytes, so a
+/// renamed or reopened file sees every write.
+struct MemoryFile {
+    data: Arc<Mutex<Vec<u8>>>,
+    position: u64,
+    append: bool,
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.data.lock();
+        let start = (self.position as usize).min(data.len());
+        let n = buf.len().min(data.len() - start);
+        buf[..n].copy_from_slice(&data[start..start + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut data = self.data.lock();
+        if self.append {
+            self.position = data.len() as u64;
+        }
+        let start = self.position as usize;
+        if data.len() < start + buf.len() {
+            data.resize(start + buf.len(), 0);
+        }
+        data[start..start + buf.len()].copy_from_slice(buf);
+        self.position += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let len = self.data.lock().len() as i64;
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => len + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+        if position < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before start of file"));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+impl StorageFile for MemoryFile {
+    fn sync_all(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.data.lock().len() as u64)
+    }
+}
@@ -1,5 +1,3 @@
-use std::fs;
-use std::fs::File;
 use std::io::Read;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -20,11 +18,11 @@ impl Checkpoint {
     /// Load checkpoint from disk
     pub fn load(storage: &StorageLayout) -> Result<Option<Self>> {
         let path = storage.checkpoint_path();
-        if !path.exists() {
+        if !storage.backend().exists(&path) {
             return Ok(None);
         }
 
-        let data = fs::read(path)?;
+        let data = storage.backend().read(&path)?;
         let checkpoint = bincode::deserialize(&data)?;
         Ok(Some(checkpoint))
     }
@@ -32,7 +30,7 @@ impl Checkpoint {
     /// Save checkpoint to disk
     pub fn save(&self, storage: &StorageLayout) -> Result<()> {
         let data = bincode::serialize(self)?;
-        fs::write(storage.checkpoint_path(), data)?;
+        storage.backend().write(&storage.checkpoint_path(), &data)?;
         Ok(())
     }
 }
@@ -77,7 +75,7 @@ impl RecoveryManager {
 
     fn replay_wal_from(&self, position: u64) -> Result<Vec<Operation>> {
         let mut operations = Vec::new();
-        let mut file = File::open(self.storage.wal_path(position))?;
+        let mut file = self.storage.backend().open(&self.storage.wal_path(position))?;
 
         loop {
             // Try to read entry
@@ -106,7 +104,7 @@ impl RecoveryManager {
         };
 
         let data = bincode::serialize(&checkpoint)?;
-        fs::write(self.storage.checkpoint_path(), data)?;
+        self.storage.backend().write(&self.storage.checkpoint_path(), &data)?;
 
         self.checkpoint = Some(checkpoint);
         Ok(())
@@ -114,11 +112,11 @@ impl RecoveryManager {
 
     fn load_checkpoint(storage: &StorageLayout) -> Result<Option<Checkpoint>> {
         let path = storage.checkpoint_path();
-        if !path.exists() {
+        if !storage.backend().exists(&path) {
             return Ok(None);
         }
 
-        let data = fs::read(path)?;
+        let data = storage.backend().read(&path)?;
         let checkpoint = bincode::deserialize(&data)?;
         Ok(Some(checkpoint))
     }
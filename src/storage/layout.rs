@@ -1,6 +1,7 @@
-use std::path::PathBuf;
-use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use crate::core::error::Result;
+use crate::storage::backend::{FileStorage, StorageBackend, StorageFile};
 use crate::storage::segment::SegmentId;
 
 /// Directory structure for data files
@@ -11,20 +12,26 @@ pub struct StorageLayout {
     pub idx_dir: PathBuf,       // Inverted index files (.idx files)
     pub wal_dir: PathBuf,       // Write-ahead log location
     pub meta_dir: PathBuf,      // Metadata files location
+    pub shard_prefix_len: usize, // Hex chars of the segment id used as sub-directory (0 = flat)
+    pub verify_checksums: bool, // Check segment CRC32 in `SegmentReader::open`
+    backend: Arc<dyn StorageBackend>,  // Where the files actually live
 }
 
 impl StorageLayout {
     pub fn new(base_dir: PathBuf) -> Result<Self> {
+        Self::with_backend(base_dir, Arc::new(FileStorage))
+    }
+
+    pub fn with_backend(base_dir: PathBuf, backend: Arc<dyn StorageBackend>) -> Result<Self> {
         let segments_dir = base_dir.join("segments");
         let idx_dir = base_dir.join("idx");
         let wal_dir = base_dir.join("wal");
         let meta_dir = base_dir.join("meta");
 
         // Create directories
-        fs::create_dir_all(&segments_dir)?;
-        fs::create_dir_all(&idx_dir)?;
-        fs::create_dir_all(&wal_dir)?;
-        fs::create_dir_all(&meta_dir)?;
+        for dir in [&segments_dir, &idx_dir, &wal_dir, &meta_dir] {
+            backend.create_dir_all(dir)?;
+        }
 
         Ok(StorageLayout {
             base_dir,
@@ -32,15 +39,124 @@ impl StorageLayout {
             idx_dir,
             wal_dir,
             meta_dir,
+            shard_prefix_len: Self::DEFAULT_SHARD_PREFIX_LEN,
+            verify_checksums: false,
+            backend,
         })
     }
 
+    /// File operations for every path in this layout
+    pub fn backend(&self) -> &dyn StorageBackend {
+        self.backend.as_ref()
+    }
+
+    /// 2 hex chars = up to 256 shard directories per file kind
+    pub const DEFAULT_SHARD_PREFIX_LEN: usize = 2;
+
+    /// Set how many leading hex chars of the segment id name the shard
+    /// directory; 0 keeps every file directly in `segments_dir`/`idx_dir`.
+    /// Capped at 8 (the first UUID group).
+    pub fn with_shard_prefix_len(mut self, len: usize) -> Self {
+        self.shard_prefix_len = len.min(8);
+        self
+    }
+
+    /// Verify each segment's checksum when it is opened (costs a full read of the file)
+    pub fn with_checksum_verification(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
     pub fn segment_path(&self, id: &SegmentId) -> PathBuf {
-        self.segments_dir.join(format!("{}.seg", id.0))
+        self.sharded_path(&self.segments_dir, id, "seg")
     }
 
     pub fn index_path(&self, id: &SegmentId) -> PathBuf {
-        self.idx_dir.join(format!("{}.idx", id.0))
+        self.sharded_path(&self.idx_dir, id, "idx")
+    }
+
+    /// Deleted doc ids of a segment, kept next to its `.seg` file
+    pub fn tombstone_path(&self, id: &SegmentId) -> PathBuf {
+        self.sharded_path(&self.segments_dir, id, "del")
+    }
+
+    /// File offset of each document of a segment, kept next to its `.seg` file
+    pub fn doc_index_path(&self, id: &SegmentId) -> PathBuf {
+        self.sharded_path(&self.segments_dir, id, "dix")
+    }
+
+    /// Resolve `<dir>/<shard>/<id>.<ext>` without creating anything; the shard
+    /// directory is made by `create_temp` when the file is written.
+    /// Files written before sharding was enabled are still found at `<dir>/<id>.<ext>`.
+    fn sharded_path(&self, dir: &Path, id: &SegmentId, ext: &str) -> PathBuf {
+        let file_name = format!("{}.{}", id.0, ext);
+        if self.shard_prefix_len == 0 {
+            return dir.join(file_name);
+        }
+
+        let flat = dir.join(&file_name);
+        if self.backend.exists(&flat) {
+            return flat;
+        }
+
+        dir.join(&file_name[..self.shard_prefix_len]).join(file_name)
+    }
+
+    /// Path a file is written under until it is complete; see [`StorageLayout::publish`].
+    /// Readers only ever resolve final names, so temp files are never opened.
+    pub fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(Self::TEMP_EXTENSION);
+        PathBuf::from(name)
+    }
+
+    pub const TEMP_EXTENSION: &'static str = "tmp";
+
+    pub fn is_temp_file(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == Self::TEMP_EXTENSION)
+    }
+
+    /// Create the temp file `dest` is written through, along with its
+    /// (shard) directory; finish with [`StorageLayout::publish`].
+    pub fn create_temp(&self, dest: &Path) -> Result<Box<dyn StorageFile>> {
+        if let Some(parent) = dest.parent() {
+            self.backend.create_dir_all(parent)?;
+        }
+        self.backend.create(&Self::temp_path(dest))
+    }
+
+    /// Atomically move a fully written (and synced) temp file to its final name
+    pub fn publish(&self, temp: &Path, dest: &Path) -> Result<()> {
+        self.backend.rename(temp, dest)?;
+        // Persist the rename itself
+        if let Some(parent) = dest.parent() {
+            self.backend.sync_dir(parent);
+        }
+        Ok(())
+    }
+
+    /// Delete temp files left behind by writes interrupted by a crash.
+    /// Returns how many were removed.
+    pub fn remove_temp_files(&self) -> Result<usize> {
+        let mut removed = 0;
+        for dir in [&self.segments_dir, &self.idx_dir, &self.meta_dir] {
+            removed += self.remove_temp_files_in(dir)?;
+        }
+        Ok(removed)
+    }
+
+    fn remove_temp_files_in(&self, dir: &Path) -> Result<usize> {
+        let mut removed = 0;
+        for path in self.backend.list(dir)? {
+            if self.backend.is_dir(&path) {
+                removed += self.remove_temp_files_in(&path)?;
+            } else if Self::is_temp_file(&path) {
+                self.backend.remove(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
     }
 
     pub fn wal_path(&self, sequence: u64) -> PathBuf {
@@ -50,8 +166,112 @@ impl StorageLayout {
     pub fn checkpoint_path(&self) -> PathBuf {
         self.meta_dir.join("checkpoint.bin")
     }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.meta_dir.join("manifest.bin")
+    }
+
+    pub fn term_dictionary_path(&self) -> PathBuf {
+        self.meta_dir.join("terms.bin")
+    }
+
+    pub fn named_snapshot_path(&self, name: &str) -> PathBuf {
+        self.meta_dir.join(format!("snapshot_{}.bin", name))
+    }
     
     pub fn wal_dir(&self) -> &PathBuf {
         &self.wal_dir
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::compress::CompressionType;
+    use crate::core::types::{DocId, Document, FieldValue};
+    use crate::memory::buffer_pool::BufferPool;
+    use crate::storage::segment_reader::SegmentReader;
+    use crate::storage::segment_writer::SegmentWriter;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    #[test]
+    fn segments_spread_across_shard_dirs_and_stay_readable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let buffer_pool = Arc::new(BufferPool::new(1024 * 1024));
+
+        let mut ids = Vec::new();
+        for i in 0..64 {
+            let mut writer = SegmentWriter::new(
+                &storage,
+                SegmentId::new(),
+                buffer_pool.clone(),
+                CompressionType::None,
+            )
+            .unwrap();
+            writer
+                .write_document(&Document {
+                    id: DocId(i),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(format!("doc {}", i)),
+                    )]),
+                })
+                .unwrap();
+            ids.push(writer.finish(&storage).unwrap().id);
+        }
+
+        let shards: HashSet<PathBuf> = ids
+            .iter()
+            .map(|id| {
+                let path = storage.segment_path(id);
+                assert!(path.exists());
+                assert_eq!(path.parent().unwrap().parent().unwrap(), storage.segments_dir);
+                path.parent().unwrap().to_path_buf()
+            })
+            .collect();
+        // 64 random ids over 256 shards: all landing in a handful would mean no sharding
+        assert!(shards.len() > 16, "only {} shard dirs used", shards.len());
+        assert!(std::fs::read_dir(&storage.segments_dir).unwrap().count() <= 256);
+
+        for (i, id) in ids.iter().enumerate() {
+            let mut reader = SegmentReader::open(&storage, *id).unwrap();
+            let docs: Vec<Document> = reader
+                .iter_documents()
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(docs.len(), 1);
+            assert_eq!(docs[0].id, DocId(i as u64));
+        }
+    }
+
+    #[test]
+    fn resolving_a_path_creates_no_shard_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let id = SegmentId::new();
+
+        let path = storage.segment_path(&id);
+        assert!(!path.parent().unwrap().exists());
+        assert_eq!(std::fs::read_dir(&storage.segments_dir).unwrap().count(), 0);
+
+        storage.create_temp(&path).unwrap();
+        assert!(StorageLayout::temp_path(&path).exists());
+    }
+
+    #[test]
+    fn flat_files_from_before_sharding_are_still_resolved() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let id = SegmentId::new();
+
+        let flat = storage.segments_dir.join(format!("{}.seg", id.0));
+        std::fs::write(&flat, b"legacy").unwrap();
+        assert_eq!(storage.segment_path(&id), flat);
+
+        let unsharded = storage.clone().with_shard_prefix_len(0);
+        assert_eq!(unsharded.index_path(&id), storage.idx_dir.join(format!("{}.idx", id.0)));
+    }
+}
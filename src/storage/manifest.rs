@@ -0,0 +1,122 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use crate::core::error::{Error, ErrorKind, Result};
+use crate::mvcc::controller::Snapshot;
+use crate::storage::layout::StorageLayout;
+use crate::storage::segment::Segment;
+
+/// Segments and deletes of the last flushed snapshot.
+///
+/// Written on every flush so a reopened index starts from the segments
+/// already on disk instead of an empty snapshot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub segments: Vec<Segment>,
+    pub deleted_docs: Vec<u32>,
+}
+
+impl Manifest {
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        Manifest {
+            segments: snapshot.segments.iter().map(|s| (**s).clone()).collect(),
+            deleted_docs: snapshot.deleted_docs.iter().collect(),
+        }
+    }
+
+    /// Load the manifest, or `None` for an index that was never flushed
+    pub fn load(storage: &StorageLayout) -> Result<Option<Self>> {
+        let path = storage.manifest_path();
+        if !storage.backend().exists(&path) {
+            return Ok(None);
+        }
+        let data = storage.backend().read(&path)?;
+        Ok(Some(bincode::deserialize(&data)?))
+    }
+
+    /// Durably replace the manifest (temp file, fsync, rename)
+    pub fn save(&self, storage: &StorageLayout) -> Result<()> {
+        write_durably(storage, &storage.manifest_path(), &bincode::serialize(self)?)
+    }
+
+    pub fn into_parts(self) -> (Vec<Arc<Segment>>, Arc<RoaringBitmap>) {
+        (
+            self.segments.into_iter().map(Arc::new).collect(),
+            Arc::new(self.deleted_docs.into_iter().collect()),
+        )
+    }
+}
+
+/// A snapshot saved under a name, so the exact segment set and deletes it
+/// saw can be read again after a restart.
+///
+/// Segment files are never removed by merges, so the segments it lists stay
+/// on disk for as long as the named snapshot exists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedSnapshot {
+    pub name: String,
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+    pub manifest: Manifest,
+}
+
+impl NamedSnapshot {
+    pub fn from_snapshot(name: &str, snapshot: &Snapshot) -> Result<Self> {
+        Self::check_name(name)?;
+        Ok(NamedSnapshot {
+            name: name.to_string(),
+            version: snapshot.version,
+            timestamp: snapshot.timestamp,
+            manifest: Manifest::from_snapshot(snapshot),
+        })
+    }
+
+    /// Load a named snapshot, or `None` if none was saved under `name`
+    pub fn load(storage: &StorageLayout, name: &str) -> Result<Option<Self>> {
+        Self::check_name(name)?;
+        let path = storage.named_snapshot_path(name);
+        if !storage.backend().exists(&path) {
+            return Ok(None);
+        }
+        let data = storage.backend().read(&path)?;
+        Ok(Some(bincode::deserialize(&data)?))
+    }
+
+    /// Durably write the snapshot, replacing any saved under the same name
+    pub fn save(&self, storage: &StorageLayout) -> Result<()> {
+        write_durably(storage, &storage.named_snapshot_path(&self.name), &bincode::serialize(self)?)
+    }
+
+    /// Rebuild the snapshot; it is detached from the live MVCC controller
+    pub fn into_snapshot(self) -> Snapshot {
+        let (segments, deleted_docs) = self.manifest.into_parts();
+        Snapshot::detached(self.version, self.timestamp, segments, deleted_docs)
+    }
+
+    /// Names become file names, so keep them to a safe character set
+    fn check_name(name: &str) -> Result<()> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                format!("Invalid snapshot name '{}': use letters, digits, '-' and '_'", name),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Replace `path` atomically (temp file, fsync, rename)
+pub(crate) fn write_durably(storage: &StorageLayout, path: &Path, data: &[u8]) -> Result<()> {
+    let temp = StorageLayout::temp_path(path);
+    let mut file = storage.create_temp(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    storage.publish(&temp, path)
+}
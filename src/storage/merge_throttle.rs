@@ -0,0 +1,82 @@
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Rate limiter for merge I/O, shared by all background merges
+///
+/// Every merged byte (read or written) is charged against a global budget of
+/// `bytes_per_sec`; callers sleep until their bytes fit in the schedule.
+/// `None` disables throttling but still tracks throughput.
+pub struct MergeThrottle {
+    bytes_per_sec: Option<u64>,
+    /// Point in time at which all bytes charged so far are "paid for"
+    next_free: Mutex<Instant>,
+    bytes_merged: AtomicU64,
+    last_throughput: Mutex<f64>,
+}
+
+impl MergeThrottle {
+    pub fn new(bytes_per_sec: Option<u64>) -> Self {
+        MergeThrottle {
+            bytes_per_sec: bytes_per_sec.filter(|&rate| rate > 0),
+            next_free: Mutex::new(Instant::now()),
+            bytes_merged: AtomicU64::new(0),
+            last_throughput: Mutex::new(0.0),
+        }
+    }
+
+    /// Throttle disabled
+    pub fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        self.bytes_per_sec
+    }
+
+    /// Charge `bytes` of merge I/O, blocking if over the configured rate
+    pub fn consume(&self, bytes: u64) {
+        self.bytes_merged.fetch_add(bytes, Ordering::Relaxed);
+
+        let Some(rate) = self.bytes_per_sec else {
+            return;
+        };
+
+        let cost = Duration::from_secs_f64(bytes as f64 / rate as f64);
+        let deadline = {
+            let mut next_free = self.next_free.lock();
+            let start = (*next_free).max(Instant::now());
+            *next_free = start + cost;
+            *next_free
+        };
+
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+    }
+
+    /// Record a finished merge so its throughput shows up in stats
+    pub fn record_merge(&self, bytes: u64, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            *self.last_throughput.lock() = bytes as f64 / secs;
+        }
+    }
+
+    /// Throughput of the most recent merge in bytes/sec
+    pub fn throughput(&self) -> f64 {
+        *self.last_throughput.lock()
+    }
+
+    /// Total bytes read and written by merges
+    pub fn bytes_merged(&self) -> u64 {
+        self.bytes_merged.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for MergeThrottle {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
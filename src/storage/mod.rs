@@ -1,3 +1,4 @@
+pub mod backend;
 pub mod layout;
 pub mod segment;
 pub mod wal;
@@ -5,6 +6,10 @@ pub mod file_lock;
 pub mod segment_writer;
 pub mod segment_reader;
 pub mod checkpoint;
+pub mod manifest;
+pub mod tombstones;
 pub mod merge_policy;
+pub mod merge_throttle;
 pub mod disk_segment_opener;
-pub mod disk_segment_store;
\ No newline at end of file
+pub mod disk_segment_store;
+pub mod validation;
\ No newline at end of file
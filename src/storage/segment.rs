@@ -36,6 +36,38 @@ pub struct SegmentMetadata {
     pub max_doc_id: DocId,
 }
 
+impl Segment {
+    /// A segment without documents (e.g. everything was compacted away)
+    pub fn is_empty(&self) -> bool {
+        self.doc_count == 0
+    }
+}
+
+impl SegmentMetadata {
+    /// Metadata for a segment that has no documents yet.
+    /// The id range starts inverted (min > max) and is widened by each write;
+    /// use `doc_id_range` rather than reading the bounds directly.
+    pub fn empty() -> Self {
+        SegmentMetadata {
+            created_at: Utc::now(),
+            size_bytes: 0,
+            min_doc_id: DocId(u64::MAX),
+            max_doc_id: DocId(0),
+        }
+    }
+
+    /// Inclusive doc id range, or `None` if the segment holds no documents
+    pub fn doc_id_range(&self) -> Option<(DocId, DocId)> {
+        (self.min_doc_id <= self.max_doc_id).then_some((self.min_doc_id, self.max_doc_id))
+    }
+
+    /// Whether `doc_id` can be stored in this segment (always false when empty)
+    pub fn may_contain(&self, doc_id: DocId) -> bool {
+        self.doc_id_range()
+            .is_some_and(|(min, max)| min <= doc_id && doc_id <= max)
+    }
+}
+
 /// Segment file header
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SegmentHeader {
@@ -51,11 +83,12 @@ pub enum CompressionType {
     Lz4,
     Zstd,
     Snappy,
+    ZstdDict, // Dictionary stored between the header and the first document
 }
 
 impl SegmentHeader {
     pub const VERSION: u32 = 1;
-    pub const SIZE: usize = 24; // Fixed header size (not used with deserialize_from)
+    pub const SIZE: usize = 16; // bincode size: 3 x u32 + u32 enum tag
 
     pub fn new(doc_count: u32) -> Self {
         SegmentHeader {
@@ -66,3 +99,47 @@ impl SegmentHeader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::compress::CompressionType as BlockCompressionType;
+    use crate::core::types::{Document, FieldValue};
+    use crate::memory::buffer_pool::BufferPool;
+    use crate::storage::layout::StorageLayout;
+    use crate::storage::segment_writer::SegmentWriter;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn header_read_back_from_a_segment_file_spans_exactly_size_bytes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut writer = SegmentWriter::new(
+            &storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            BlockCompressionType::None,
+        )
+        .unwrap();
+        for i in 0..3 {
+            writer
+                .write_document(&Document {
+                    id: DocId(i),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(format!("doc {}", i)),
+                    )]),
+                })
+                .unwrap();
+        }
+        let segment = writer.finish(&storage).unwrap();
+
+        // Documents start right after the header, at `SIZE`
+        let bytes = std::fs::read(storage.segment_path(&segment.id)).unwrap();
+        let header: SegmentHeader = bincode::deserialize(&bytes[..SegmentHeader::SIZE]).unwrap();
+        assert_eq!(bincode::serialized_size(&header).unwrap(), SegmentHeader::SIZE as u64);
+        assert_eq!(header.version, SegmentHeader::VERSION);
+        assert_eq!(header.doc_count, 3);
+    }
+}
@@ -1,16 +1,26 @@
-use std::fs::File;
+use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
-use std::sync::Mutex;
+use std::sync::{Mutex, OnceLock};
 use crate::core::error::{Error, ErrorKind, Result};
 use crate::core::types::{DocId, Document};
+use crate::storage::backend::StorageFile;
 use crate::storage::layout::StorageLayout;
-use crate::storage::segment::{SegmentHeader, SegmentId};
+use crate::storage::segment::{CompressionType, SegmentHeader, SegmentId};
 use crate::compression::compress::CompressedBlock;
 
 pub struct SegmentReader {
     pub segment_id: SegmentId,
     pub header: SegmentHeader,
-    pub file: Mutex<File>,  // Wrapped in Mutex for interior mutability
+    pub file: Mutex<Box<dyn StorageFile>>,  // Wrapped in Mutex for interior mutability
+    /// File offset of each document's length prefix, built on first ranged read
+    offsets: OnceLock<Vec<u64>>,
+    /// Zstd dictionary of a ZstdDict segment
+    dictionary: Option<Vec<u8>>,
+    /// File offset of the first document
+    data_start: u64,
+    /// File offset of each document by id, from the `.dix` file
+    /// (None for segments written before it existed)
+    doc_index: Option<HashMap<DocId, u64>>,
 }
 
 /// Iterator for lazy loading documents
@@ -23,7 +33,7 @@ pub struct DocumentIterator<'a> {
 impl SegmentReader {
     pub fn open(storage: &StorageLayout, segment_id: SegmentId) -> Result<Self> {
         let path = storage.segment_path(&segment_id);
-        let mut file = File::open(&path)?;
+        let mut file = storage.backend().open(&path)?;
 
         // Read header using bincode directly (variable length)
         let header: SegmentHeader = bincode::deserialize_from(&mut file)
@@ -37,18 +47,80 @@ impl SegmentReader {
             });
         }
 
-        Ok(SegmentReader {
+        // ZstdDict segments store their dictionary ahead of the documents
+        let mut data_start = SegmentHeader::SIZE as u64;
+        let dictionary = match header.compression {
+            CompressionType::ZstdDict => {
+                let mut len_buf = [0u8; 4];
+                file.read_exact(&mut len_buf)?;
+                let mut dictionary = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+                file.read_exact(&mut dictionary)?;
+                data_start += 4 + dictionary.len() as u64;
+                Some(dictionary)
+            }
+            _ => None,
+        };
+
+        let doc_index_path = storage.doc_index_path(&segment_id);
+        let doc_index = if storage.backend().exists(&doc_index_path) {
+            let offsets: Vec<(DocId, u64)> = bincode::deserialize(&storage.backend().read(&doc_index_path)?)?;
+            Some(offsets.into_iter().collect())
+        } else {
+            None
+        };
+
+        let reader = SegmentReader {
             segment_id,
             header,
             file: Mutex::new(file),
-        })
+            offsets: OnceLock::new(),
+            dictionary,
+            data_start,
+            doc_index,
+        };
+
+        if storage.verify_checksums {
+            reader.verify_checksum()?;
+        }
+
+        Ok(reader)
+    }
+
+    /// Recompute the CRC32 over all document bytes and compare it with the header.
+    /// Fails with `ErrorKind::Io` on mismatch.
+    pub fn verify_checksum(&self) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(SegmentHeader::SIZE as u64))?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&chunk[..n]);
+        }
+        file.seek(SeekFrom::Start(SegmentHeader::SIZE as u64))?;
+
+        let actual = hasher.finalize();
+        if actual != self.header.checksum {
+            return Err(Error::new(
+                ErrorKind::Io,
+                format!(
+                    "Checksum mismatch in segment {}: expected {:08x}, got {:08x}",
+                    self.segment_id.0, self.header.checksum, actual
+                ),
+            ));
+        }
+        Ok(())
     }
 
     /// NEW: Lazy iterator - doesn't load everything into RAM
     /// Use this instead of read_all_documents()
     pub fn iter_documents(&mut self) -> Result<DocumentIterator<'_>> {
         // Seek to start of documents (after header)
-        self.file.lock().unwrap().seek(SeekFrom::Start(SegmentHeader::SIZE as u64))?;
+        self.file.lock().unwrap().seek(SeekFrom::Start(self.data_start))?;
         
         // Extract doc_count before borrowing self
         let total_docs = self.header.doc_count;
@@ -75,24 +147,134 @@ impl SegmentReader {
         // Read serialized CompressedBlock
         let mut block_buf = vec![0u8; len];
         file.read_exact(&mut block_buf)?;
-        
+        drop(file);
+
+        Ok(Some(self.decode_document(&block_buf)?))
+    }
+
+    /// Decompress and deserialize one stored document block
+    pub fn decode_document(&self, block: &[u8]) -> Result<Document> {
         // Deserialize CompressedBlock (includes original_size metadata)
-        let compressed_block: CompressedBlock = bincode::deserialize(&block_buf)?;
-        let decompressed = compressed_block.decompress()?;
-        
+        let compressed_block: CompressedBlock = bincode::deserialize(block)?;
+        let decompressed = compressed_block.decompress_with_dictionary(self.dictionary.as_deref())?;
+
         // Deserialize document
-        let doc: Document = bincode::deserialize(&decompressed)?;
+        Ok(bincode::deserialize(&decompressed)?)
+    }
 
-        Ok(Some(doc))
+    /// File offset of the first document (after the header and any dictionary)
+    pub fn data_start(&self) -> u64 {
+        self.data_start
+    }
+
+    /// Read `count` documents starting at ordinal `start`, clamped to the segment.
+    /// Seeks through the offset index, so threads can read disjoint ranges of
+    /// one segment without scanning from the beginning.
+    pub fn read_documents_range(&self, start: u32, count: u32) -> Result<Vec<Document>> {
+        let offsets = self.offsets()?;
+        let start = (start as usize).min(offsets.len());
+        let end = start.saturating_add(count as usize).min(offsets.len());
+
+        let mut docs = Vec::with_capacity(end - start);
+        for &offset in &offsets[start..end] {
+            // Lock per document so concurrent ranges interleave
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(offset))?;
+
+            let mut len_buf = [0u8; 4];
+            file.read_exact(&mut len_buf)?;
+            let mut block_buf = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+            file.read_exact(&mut block_buf)?;
+            drop(file);
+
+            docs.push(self.decode_document(&block_buf)?);
+        }
+        Ok(docs)
     }
 
-    /// Get specific document by ID
-    /// Scans through segment to find document
+    /// Offset index: walks the length prefixes once, skipping document bodies
+    fn offsets(&self) -> Result<&[u64]> {
+        if let Some(offsets) = self.offsets.get() {
+            return Ok(offsets);
+        }
+
+        let mut file = self.file.lock().unwrap();
+        let mut offsets = Vec::with_capacity(self.header.doc_count as usize);
+        let mut pos = self.data_start;
+        for _ in 0..self.header.doc_count {
+            file.seek(SeekFrom::Start(pos))?;
+            let mut len_buf = [0u8; 4];
+            file.read_exact(&mut len_buf)?;
+            offsets.push(pos);
+            pos += 4 + u32::from_le_bytes(len_buf) as u64;
+        }
+        drop(file);
+
+        Ok(self.offsets.get_or_init(|| offsets))
+    }
+
+    /// Decode every document in file order and hand it to `visit`, holding
+    /// the file lock for one sequential pass instead of seeking per document
+    pub fn for_each_document<F>(&self, mut visit: F) -> Result<()>
+    where
+        F: FnMut(Document) -> Result<()>,
+    {
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(self.data_start))?;
+
+        for _ in 0..self.header.doc_count {
+            let mut len_buf = [0u8; 4];
+            file.read_exact(&mut len_buf)?;
+            let mut block_buf = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+            file.read_exact(&mut block_buf)?;
+            visit(self.decode_document(&block_buf)?)?;
+        }
+        Ok(())
+    }
+
+    /// Get specific document by ID: one seek through the `.dix` offsets,
+    /// or a scan of the segment if it has none
     pub fn get_document(&self, doc_id: DocId) -> Result<Option<Document>> {
+        match self.doc_index {
+            Some(_) => self.seek_document(doc_id),
+            None => self.scan_for_document(doc_id),
+        }
+    }
+
+    /// Read a document at its offset in the `.dix` file, without scanning.
+    /// `None` if the segment doesn't hold it or has no `.dix` file.
+    pub fn seek_document(&self, doc_id: DocId) -> Result<Option<Document>> {
+        let Some(&offset) = self.doc_index.as_ref().and_then(|index| index.get(&doc_id)) else {
+            return Ok(None);
+        };
+
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut len_buf = [0u8; 4];
+        file.read_exact(&mut len_buf)?;
+        let mut block_buf = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+        file.read_exact(&mut block_buf)?;
+        drop(file);
+
+        let doc = self.decode_document(&block_buf)?;
+        if doc.id != doc_id {
+            return Err(Error::new(
+                ErrorKind::Parse,
+                format!(
+                    "Document index of segment {} points document {} at document {}",
+                    self.segment_id.0, doc_id.0, doc.id.0
+                ),
+            ));
+        }
+        Ok(Some(doc))
+    }
+
+    /// Scan through the segment for a document
+    fn scan_for_document(&self, doc_id: DocId) -> Result<Option<Document>> {
         let mut file = self.file.lock().unwrap();
         
-        // Skip header
-        file.seek(SeekFrom::Start(SegmentHeader::SIZE as u64))?;
+        // Skip header (and dictionary)
+        file.seek(SeekFrom::Start(self.data_start))?;
 
         for _ in 0..self.header.doc_count {
             // Read length (serialized CompressedBlock size)
@@ -103,13 +285,7 @@ impl SegmentReader {
             // Read serialized CompressedBlock
             let mut block_buf = vec![0u8; len];
             file.read_exact(&mut block_buf)?;
-            
-            // Deserialize CompressedBlock (includes original_size metadata)
-            let compressed_block: CompressedBlock = bincode::deserialize(&block_buf)?;
-            let decompressed = compressed_block.decompress()?;
-            
-            // Deserialize document
-            let doc: Document = bincode::deserialize(&decompressed)?;
+            let doc = self.decode_document(&block_buf)?;
 
             if doc.id == doc_id {
                 return Ok(Some(doc));
@@ -148,4 +324,140 @@ impl<'a> ExactSizeIterator for DocumentIterator<'a> {
     fn len(&self) -> usize {
         (self.total_docs - self.current_index) as usize
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::compress::CompressionType;
+    use crate::core::types::FieldValue;
+    use crate::memory::buffer_pool::BufferPool;
+    use crate::storage::segment_writer::SegmentWriter;
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn write_segment(storage: &StorageLayout) -> SegmentId {
+        let mut writer = SegmentWriter::new(
+            storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            CompressionType::None,
+        )
+        .unwrap();
+        for id in 0..3 {
+            writer
+                .write_document(&Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(format!("document number {}", id)),
+                    )]),
+                })
+                .unwrap();
+        }
+        writer.finish(storage).unwrap().id
+    }
+
+    #[test]
+    fn verify_on_open_detects_corrupted_byte() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_checksum_verification(true);
+        let id = write_segment(&storage);
+
+        let reader = SegmentReader::open(&storage, id).unwrap();
+        assert!(reader.get_document(DocId(2)).unwrap().is_some());
+        drop(reader);
+
+        // Flip one byte inside the document area
+        let path = storage.segment_path(&id);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::File::create(&path).unwrap().write_all(&bytes).unwrap();
+
+        let err = SegmentReader::open(&storage, id).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::Io));
+        assert!(err.context.contains("Checksum mismatch"));
+
+        // Without verification the corruption goes unnoticed at open time
+        let unverified = storage.clone().with_checksum_verification(false);
+        assert!(SegmentReader::open(&unverified, id).is_ok());
+    }
+
+    #[test]
+    fn document_ranges_concatenate_to_full_scan() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let id = write_segment(&storage);
+
+        let mut reader = SegmentReader::open(&storage, id).unwrap();
+        let full: Vec<DocId> = reader
+            .iter_documents()
+            .unwrap()
+            .map(|doc| doc.unwrap().id)
+            .collect();
+
+        let ranged: Vec<DocId> = [(0, 2), (2, 2), (4, 1)]
+            .into_iter()
+            .flat_map(|(start, count)| reader.read_documents_range(start, count).unwrap())
+            .map(|doc| doc.id)
+            .collect();
+        assert_eq!(ranged, full);
+        assert_eq!(full.len(), 3);
+    }
+
+    #[test]
+    fn seeking_through_the_doc_index_matches_a_scan() {
+        for compression in [CompressionType::LZ4, CompressionType::ZstdDict] {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+            let mut writer = SegmentWriter::new(
+                &storage,
+                SegmentId::new(),
+                Arc::new(BufferPool::new(1024 * 1024)),
+                compression,
+            )
+            .unwrap();
+            // Ids out of order, with bodies large enough to span buffer flushes
+            let ids: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000 + 10).collect();
+            for &id in &ids {
+                writer
+                    .write_document(&Document {
+                        id: DocId(id),
+                        fields: HashMap::from([(
+                            "content".to_string(),
+                            FieldValue::Text(format!("document {} {}", id, "x".repeat(id as usize * 3))),
+                        )]),
+                    })
+                    .unwrap();
+            }
+            let segment_id = writer.finish(&storage).unwrap().id;
+
+            let reader = SegmentReader::open(&storage, segment_id).unwrap();
+            assert_eq!(reader.doc_index.as_ref().unwrap().len(), 1000);
+            let mut scanned = HashMap::new();
+            reader
+                .for_each_document(|doc| {
+                    scanned.insert(doc.id, doc);
+                    Ok(())
+                })
+                .unwrap();
+            for &id in &ids {
+                let sought = reader.seek_document(DocId(id)).unwrap().unwrap();
+                assert_eq!(sought.fields, scanned[&DocId(id)].fields);
+            }
+            let sought = reader.seek_document(DocId(777)).unwrap();
+            assert_eq!(sought.unwrap().fields, reader.scan_for_document(DocId(777)).unwrap().unwrap().fields);
+            assert!(reader.seek_document(DocId(5)).unwrap().is_none());
+
+            // Segments without a `.dix` file are scanned
+            std::fs::remove_file(storage.doc_index_path(&segment_id)).unwrap();
+            let reader = SegmentReader::open(&storage, segment_id).unwrap();
+            assert!(reader.seek_document(DocId(500)).unwrap().is_none());
+            assert_eq!(reader.get_document(DocId(500)).unwrap().unwrap().id, DocId(500));
+        }
+    }
+}
@@ -1,29 +1,45 @@
-use crate::compression::compress::{CompressedBlock, CompressionType as BlockCompressionType};
+use crate::compression::compress::{
+    train_zstd_dictionary, CompressedBlock, CompressionType as BlockCompressionType,
+};
 use crate::core::error::Result;
 use crate::core::types::{DocId, Document};
+use crate::index::index_file;
 use crate::index::inverted::Term;
 use crate::index::posting::Posting;
 use crate::memory::buffer_pool::BufferPool;
+use crate::storage::backend::StorageFile;
 use crate::storage::layout::StorageLayout;
 use crate::storage::segment::{
     CompressionType as SegmentCompressionType, Segment, SegmentHeader, SegmentId, SegmentMetadata,
 };
-use chrono::Utc;
 use crc32fast::Hasher;
 use std::cmp;
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::{Seek, SeekFrom, Write};
+use std::mem;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Builds a segment in `<path>.tmp`; `finish` renames it to `path` once
+/// complete, so a crash mid-write never leaves a partial segment visible.
 pub struct SegmentWriter {
     pub segment: Segment,
     pub buffer: Vec<u8>,
-    pub file: File,
+    pub file: Box<dyn StorageFile>,
+    pub path: PathBuf,
     pub hasher: Hasher,
     pub buffer_pool: Arc<BufferPool>,
     pub inverted_index: HashMap<Term, Vec<Posting>>, // In-memory index buffer
     pub compression: BlockCompressionType,
+    pub compression_level: Option<i32>, // Codec level (`None` = codec default)
+    /// ZstdDict: serialized documents held back until the dictionary is trained
+    pending: Vec<(DocId, Vec<u8>)>,
+    /// ZstdDict: the trained dictionary (empty if training failed)
+    dictionary: Option<Vec<u8>>,
+    /// File offset of each document's length prefix, written to the `.dix` file
+    doc_offsets: HashMap<DocId, u64>,
+    /// Bytes written to the file after the header
+    written: u64,
 }
 
 impl SegmentWriter {
@@ -34,7 +50,7 @@ impl SegmentWriter {
         compression: BlockCompressionType,
     ) -> Result<Self> {
         let path = storage.segment_path(&segment_id);
-        let mut file = File::create(path)?;
+        let mut file = storage.create_temp(&path)?;
 
         // Write placeholder header to reserve space (will be updated in finish())
         let placeholder_header = SegmentHeader::new(0);
@@ -46,22 +62,28 @@ impl SegmentWriter {
             segment: Segment {
                 id: segment_id,
                 doc_count: 0,
-                metadata: SegmentMetadata {
-                    created_at: Utc::now(),
-                    size_bytes: 0,
-                    min_doc_id: DocId(u64::MAX),
-                    max_doc_id: DocId(0),
-                },
+                metadata: SegmentMetadata::empty(),
             },
             buffer: Vec::with_capacity(1024 * 1024), // 1MB buffer
             file,
+            path,
             hasher: Hasher::new(),
             buffer_pool,
             inverted_index: HashMap::new(),
             compression,
+            compression_level: None,
+            pending: Vec::new(),
+            dictionary: None,
+            doc_offsets: HashMap::new(),
+            written: 0,
         })
     }
 
+    pub fn with_compression_level(mut self, level: Option<i32>) -> Self {
+        self.compression_level = level;
+        self
+    }
+
     /// Add inverted index entry
     pub fn add_index_entry(&mut self, term: Term, posting: Posting) {
         self.inverted_index
@@ -70,17 +92,60 @@ impl SegmentWriter {
             .push(posting);
     }
 
+    /// Documents sampled to train a ZstdDict segment's dictionary
+    pub const DICTIONARY_SAMPLE_DOCS: usize = 1000;
+    /// Upper bound on the trained dictionary size
+    pub const DICTIONARY_MAX_SIZE: usize = 16 * 1024;
+
     /// Write document with compression (M08 optimization)
     pub fn write_document(&mut self, doc: &Document) -> Result<u64> {
         // Serialize document
         let data = bincode::serialize(doc)?;
+        let offset = self.buffer.len() as u64;
+
+        // Update metadata
+        self.segment.doc_count += 1;
+        self.segment.metadata.min_doc_id =
+            DocId(cmp::min(self.segment.metadata.min_doc_id.0, doc.id.0));
+        self.segment.metadata.max_doc_id =
+            DocId(cmp::max(self.segment.metadata.max_doc_id.0, doc.id.0));
+
+        // Dictionary segments hold documents back until there are enough samples
+        if matches!(self.compression, BlockCompressionType::ZstdDict) && self.dictionary.is_none() {
+            self.pending.push((doc.id, data));
+            if self.pending.len() >= Self::DICTIONARY_SAMPLE_DOCS {
+                self.train_dictionary()?;
+            }
+            return Ok(offset);
+        }
+
+        self.append_block(doc.id, &data)?;
+
+        // Flush if buffer is large
+        if self.buffer.len() > 1024 * 1024 {
+            self.flush()?;
+        }
+
+        Ok(offset)
+    }
 
-        let compressed = CompressedBlock::compress(&data, self.compression)?;
+    /// Compress one serialized document and append it, length-prefixed, to the buffer
+    fn append_block(&mut self, doc_id: DocId, data: &[u8]) -> Result<()> {
+        // A document written twice is found at its first copy, as by a scan
+        let offset = SegmentHeader::SIZE as u64 + self.written + self.buffer.len() as u64;
+        self.doc_offsets.entry(doc_id).or_insert(offset);
+
+        let compressed = match self.dictionary.as_deref() {
+            Some(dictionary) if !dictionary.is_empty() => {
+                CompressedBlock::compress_with_dictionary(data, dictionary, self.compression_level)?
+            }
+            _ => CompressedBlock::compress_with_level(data, self.compression, self.compression_level)?,
+        };
 
         // Serialize the entire CompressedBlock (includes original_size metadata)
         let compressed_block_data = bincode::serialize(&compressed)?;
 
-        let mut pooled_buffer = self.buffer_pool.get(compressed_block_data.len());
+        let mut pooled_buffer = self.buffer_pool.get(4 + compressed_block_data.len());
         pooled_buffer.clear(); // CRITICAL: Clear the pooled buffer before use!
 
         // Write length prefix (serialized CompressedBlock size)
@@ -89,40 +154,52 @@ impl SegmentWriter {
         pooled_buffer.extend_from_slice(&compressed_block_data);
 
         // Add to internal buffer
-        let offset = self.buffer.len() as u64;
         self.buffer.extend_from_slice(&pooled_buffer);
 
         self.buffer_pool.return_buffer(pooled_buffer);
+        Ok(())
+    }
 
-        // Update metadata
-        self.segment.doc_count += 1;
-        self.segment.metadata.min_doc_id =
-            DocId(cmp::min(self.segment.metadata.min_doc_id.0, doc.id.0));
-        self.segment.metadata.max_doc_id =
-            DocId(cmp::max(self.segment.metadata.max_doc_id.0, doc.id.0));
+    /// Train the dictionary on the held-back documents, write it ahead of
+    /// them and compress them with it. Too few or too small samples make
+    /// training fail; the segment then stores an empty dictionary and plain
+    /// Zstd blocks.
+    fn train_dictionary(&mut self) -> Result<()> {
+        let pending = mem::take(&mut self.pending);
+        let samples: Vec<&[u8]> = pending.iter().map(|(_, data)| data.as_slice()).collect();
+        let dictionary =
+            train_zstd_dictionary(&samples, Self::DICTIONARY_MAX_SIZE).unwrap_or_default();
 
-        // Flush if buffer is large
-        if self.buffer.len() > 1024 * 1024 {
-            self.flush()?;
-        }
+        // Nothing has been written yet, so the dictionary directly follows the header
+        self.buffer.extend_from_slice(&(dictionary.len() as u32).to_le_bytes());
+        self.buffer.extend_from_slice(&dictionary);
+        self.dictionary = Some(dictionary);
 
-        Ok(offset)
+        for (doc_id, data) in &pending {
+            self.append_block(*doc_id, data)?;
+        }
+        Ok(())
     }
 
     pub fn flush(&mut self) -> Result<()> {
         if !self.buffer.is_empty() {
             self.hasher.update(&self.buffer);
             self.file.write_all(&self.buffer)?;
+            self.written += self.buffer.len() as u64;
             self.buffer.clear();
         }
         Ok(())
     }
 
     // [ HEADER (doc_count, checksum, metadata) ] <- byte 0
+    // [ DICTIONARY LENGTH + BYTES ]              <- ZstdDict only
     // [ DOCUMENT 1 ]
     // [ DOCUMENT 2 ]
     // [ DOCUMENT 3 ]
     pub fn finish(mut self, storage: &StorageLayout) -> Result<Segment> {
+        if matches!(self.compression, BlockCompressionType::ZstdDict) && self.dictionary.is_none() {
+            self.train_dictionary()?;
+        }
         self.flush()?;
 
         // Calculate checksum before consuming hasher
@@ -140,12 +217,16 @@ impl SegmentWriter {
         self.file.sync_all()?;
 
         // Update size
-        self.segment.metadata.size_bytes = self.file.metadata()?.len() as usize;
+        self.segment.metadata.size_bytes = self.file.size()? as usize;
 
-        // Write inverted index to separate file (.idx)
+        // Write inverted index to separate file (.idx) before the segment
+        // becomes visible, so a published segment always has its index
         if !self.inverted_index.is_empty() {
             self.write_inverted_index(storage)?;
         }
+        self.write_doc_index(storage)?;
+
+        storage.publish(&StorageLayout::temp_path(&self.path), &self.path)?;
 
         Ok(self.segment)
     }
@@ -154,7 +235,8 @@ impl SegmentWriter {
     fn write_inverted_index(&self, storage: &StorageLayout) -> Result<()> {
         // Create index file path in idx/ folder
         let index_path = storage.index_path(&self.segment.id);
-        let mut index_file = File::create(index_path)?;
+        let temp_path = StorageLayout::temp_path(&index_path);
+        let mut index_file = storage.create_temp(&index_path)?;
 
         // Sort postings by doc_id for each term
         let mut sorted_index = self.inverted_index.clone();
@@ -162,16 +244,25 @@ impl SegmentWriter {
             postings.sort_by_key(|p| p.doc_id);
         }
 
-        // Serialize and compress inverted index
-        let index_data = bincode::serialize(&sorted_index)?;
-        let compressed = CompressedBlock::compress(&index_data, self.compression)?;
-
-        // Write the entire CompressedBlock (including metadata) to file
-        let compressed_block_data = bincode::serialize(&compressed)?;
-        index_file.write_all(&compressed_block_data)?;
+        // One compressed block per term, then the dictionary blocks and their index
+        index_file::write_index(&mut index_file, &sorted_index, self.compression, self.compression_level)?;
         index_file.sync_all()?;
 
-        Ok(())
+        storage.publish(&temp_path, &index_path)
+    }
+
+    /// Write the document offsets, sorted by doc id, to disk (.dix file)
+    fn write_doc_index(&self, storage: &StorageLayout) -> Result<()> {
+        let path = storage.doc_index_path(&self.segment.id);
+        let temp_path = StorageLayout::temp_path(&path);
+        let mut file = storage.create_temp(&path)?;
+
+        let mut offsets: Vec<(DocId, u64)> = self.doc_offsets.iter().map(|(&id, &offset)| (id, offset)).collect();
+        offsets.sort_unstable();
+        file.write_all(&bincode::serialize(&offsets)?)?;
+        file.sync_all()?;
+
+        storage.publish(&temp_path, &path)
     }
 
     fn segment_header_compression(compression: BlockCompressionType) -> SegmentCompressionType {
@@ -180,6 +271,166 @@ impl SegmentWriter {
             BlockCompressionType::LZ4 => SegmentCompressionType::Lz4,
             BlockCompressionType::Zstd => SegmentCompressionType::Zstd,
             BlockCompressionType::Snappy => SegmentCompressionType::Snappy,
+            BlockCompressionType::ZstdDict => SegmentCompressionType::ZstdDict,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::FieldValue;
+    use crate::storage::segment_reader::SegmentReader;
+
+    fn writer_with_doc(storage: &StorageLayout) -> SegmentWriter {
+        let mut writer = SegmentWriter::new(
+            storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            BlockCompressionType::None,
+        )
+        .unwrap();
+        writer
+            .write_document(&Document {
+                id: DocId(1),
+                fields: HashMap::from([(
+                    "content".to_string(),
+                    FieldValue::Text("hello".to_string()),
+                )]),
+            })
+            .unwrap();
+        writer.add_index_entry(
+            Term::new("hello"),
+            Posting {
+                doc_id: DocId(1),
+                term_freq: 1,
+                positions: vec![0],
+                field_norm: 1.0,
+            },
+        );
+        writer
+    }
+
+    #[test]
+    fn crash_before_rename_leaves_no_visible_segment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+
+        // "Crash": the writer goes away without finishing
+        let writer = writer_with_doc(&storage);
+        let id = writer.segment.id;
+        let path = writer.path.clone();
+        drop(writer);
+
+        assert!(!path.exists());
+        assert!(StorageLayout::temp_path(&path).exists());
+        assert!(!storage.index_path(&id).exists());
+        assert!(SegmentReader::open(&storage, id).is_err());
+
+        // Reopen sweeps the leftovers
+        assert_eq!(storage.remove_temp_files().unwrap(), 1);
+        assert!(!StorageLayout::temp_path(&path).exists());
+    }
+
+    #[test]
+    fn finish_publishes_segment_and_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+
+        let writer = writer_with_doc(&storage);
+        let path = writer.path.clone();
+        let segment = writer.finish(&storage).unwrap();
+
+        assert!(path.exists());
+        assert!(!StorageLayout::temp_path(&path).exists());
+        let index_path = storage.index_path(&segment.id);
+        assert!(index_path.exists());
+        assert!(!StorageLayout::temp_path(&index_path).exists());
+        assert_eq!(storage.remove_temp_files().unwrap(), 0);
+
+        let reader = SegmentReader::open(&storage, segment.id).unwrap();
+        assert!(reader.get_document(DocId(1)).unwrap().is_some());
+    }
+
+    #[test]
+    fn dictionary_shrinks_small_similar_documents() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_checksum_verification(true);
+        let cities = ["Hanoi", "Da Nang", "Hue", "Can Tho", "Hai Phong"];
+        let docs: Vec<Document> = (0..2_000u64)
+            .map(|id| Document {
+                id: DocId(id),
+                fields: HashMap::from([
+                    (
+                        "content".to_string(),
+                        FieldValue::Text(format!(
+                            "order {} shipped from the {} warehouse, status delivered",
+                            id,
+                            cities[(id % 5) as usize]
+                        )),
+                    ),
+                    ("category".to_string(), FieldValue::Text("shipping".to_string())),
+                    ("quantity".to_string(), FieldValue::Number((id % 7) as f64)),
+                ]),
+            })
+            .collect();
+
+        let write = |compression: BlockCompressionType| {
+            let mut writer = SegmentWriter::new(
+                &storage,
+                SegmentId::new(),
+                Arc::new(BufferPool::new(1024 * 1024)),
+                compression,
+            )
+            .unwrap();
+            for doc in &docs {
+                writer.write_document(doc).unwrap();
+            }
+            writer.finish(&storage).unwrap()
+        };
+
+        let plain = write(BlockCompressionType::Zstd);
+        let with_dictionary = write(BlockCompressionType::ZstdDict);
+        assert!(
+            with_dictionary.metadata.size_bytes * 2 < plain.metadata.size_bytes,
+            "dictionary {} vs plain {} bytes",
+            with_dictionary.metadata.size_bytes,
+            plain.metadata.size_bytes
+        );
+
+        // Documents read back intact, including past the training sample
+        let mut reader = SegmentReader::open(&storage, with_dictionary.id).unwrap();
+        let read: Vec<Document> = reader.iter_documents().unwrap().map(|d| d.unwrap()).collect();
+        assert_eq!(read.len(), docs.len());
+        assert_eq!(read[1_500].fields, docs[1_500].fields);
+        assert_eq!(
+            reader.get_document(DocId(42)).unwrap().unwrap().fields,
+            docs[42].fields
+        );
+    }
+
+    #[test]
+    fn dictionary_segment_with_few_documents_falls_back_to_plain_zstd() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut writer = SegmentWriter::new(
+            &storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            BlockCompressionType::ZstdDict,
+        )
+        .unwrap();
+        writer
+            .write_document(&Document {
+                id: DocId(1),
+                fields: HashMap::from([("content".to_string(), FieldValue::Text("hello".to_string()))]),
+            })
+            .unwrap();
+        let segment = writer.finish(&storage).unwrap();
+
+        let reader = SegmentReader::open(&storage, segment.id).unwrap();
+        assert!(reader.get_document(DocId(1)).unwrap().is_some());
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use roaring::RoaringBitmap;
+use crate::core::error::Result;
+use crate::storage::layout::StorageLayout;
+use crate::storage::manifest::write_durably;
+use crate::storage::segment::{Segment, SegmentId};
+
+/// Deleted doc ids of each segment, persisted as a `.del` file next to the
+/// segment so deletes survive a restart without replaying the WAL.
+///
+/// The snapshot tracks deletes in one bitmap; a segment's file holds the
+/// part of it that falls inside the segment's doc id range.
+#[derive(Debug, Default)]
+pub struct SegmentTombstones {
+    saved: HashMap<SegmentId, RoaringBitmap>,  // What each `.del` file currently holds
+}
+
+impl SegmentTombstones {
+    /// Read the `.del` files of `segments`; segments without one have no deletes
+    pub fn load(storage: &StorageLayout, segments: &[Arc<Segment>]) -> Result<Self> {
+        let mut saved = HashMap::new();
+        for segment in segments {
+            let path = storage.tombstone_path(&segment.id);
+            if !storage.backend().exists(&path) {
+                continue;
+            }
+            let data = storage.backend().read(&path)?;
+            saved.insert(segment.id, RoaringBitmap::deserialize_from(data.as_slice())?);
+        }
+        Ok(SegmentTombstones { saved })
+    }
+
+    /// Every deleted doc id across the loaded segments
+    pub fn deleted_docs(&self) -> RoaringBitmap {
+        self.saved.values().fold(RoaringBitmap::new(), |all, deletes| all | deletes)
+    }
+
+    /// Rewrite the `.del` file of each segment whose deletes changed since
+    /// the last save, and forget segments that are no longer live
+    pub fn save(
+        &mut self,
+        storage: &StorageLayout,
+        segments: &[Arc<Segment>],
+        deleted: &RoaringBitmap,
+    ) -> Result<()> {
+        let mut saved = HashMap::with_capacity(segments.len());
+        for segment in segments {
+            let deletes = Self::segment_deletes(segment, deleted);
+            let previous = self.saved.remove(&segment.id);
+            if previous.as_ref() != Some(&deletes) {
+                let path = storage.tombstone_path(&segment.id);
+                if deletes.is_empty() {
+                    if storage.backend().exists(&path) {
+                        storage.backend().remove(&path)?;
+                    }
+                } else {
+                    let mut data = Vec::with_capacity(deletes.serialized_size());
+                    deletes.serialize_into(&mut data)?;
+                    write_durably(storage, &path, &data)?;
+                }
+            }
+            if !deletes.is_empty() {
+                saved.insert(segment.id, deletes);
+            }
+        }
+        self.saved = saved;
+        Ok(())
+    }
+
+    /// The ids of `deleted` that may belong to `segment`
+    fn segment_deletes(segment: &Segment, deleted: &RoaringBitmap) -> RoaringBitmap {
+        let Some((min, max)) = segment.metadata.doc_id_range() else {
+            return RoaringBitmap::new();
+        };
+        let to_u32 = |id: u64| u32::try_from(id).unwrap_or(u32::MAX);
+        let mut range = RoaringBitmap::new();
+        range.insert_range(to_u32(min.0)..=to_u32(max.0));
+        deleted & range
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+use std::path::Path;
+use uuid::Uuid;
+use crate::core::error::Result;
+use crate::storage::layout::StorageLayout;
+use crate::storage::segment::SegmentId;
+use crate::storage::segment_reader::SegmentReader;
+
+/// Outcome of a read-only scan of the index (see `SearchIndex::validate`)
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub segments_checked: usize,
+    pub documents_checked: usize,
+    /// In the snapshot but no file on disk
+    pub missing_segments: Vec<SegmentId>,
+    /// On disk but not referenced by the snapshot
+    pub orphaned_segments: Vec<SegmentId>,
+    /// Unreadable header, checksum mismatch, truncation or bad documents
+    pub corrupt_segments: Vec<SegmentIssue>,
+    pub corrupt_documents: Vec<DocumentIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentIssue {
+    pub segment_id: SegmentId,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentIssue {
+    pub segment_id: SegmentId,
+    /// Position of the document within the segment (0-based)
+    pub position: u32,
+    pub reason: String,
+}
+
+impl ValidationReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing_segments.is_empty()
+            && self.orphaned_segments.is_empty()
+            && self.corrupt_segments.is_empty()
+            && self.corrupt_documents.is_empty()
+    }
+
+    /// Segments whose data can't be served: missing or corrupt
+    pub fn unrecoverable_segments(&self) -> HashSet<SegmentId> {
+        self.missing_segments
+            .iter()
+            .copied()
+            .chain(self.corrupt_segments.iter().map(|issue| issue.segment_id))
+            .collect()
+    }
+}
+
+/// Check every segment in `manifest` against the files under `storage`.
+/// Never modifies anything on disk.
+pub fn validate_segments(storage: &StorageLayout, manifest: &[SegmentId]) -> Result<ValidationReport> {
+    let mut report = ValidationReport::default();
+    // Verify explicitly below so a mismatch is reported instead of failing the open
+    let storage = storage.clone().with_checksum_verification(false);
+
+    for &segment_id in manifest {
+        if !storage.backend().exists(&storage.segment_path(&segment_id)) {
+            report.missing_segments.push(segment_id);
+            continue;
+        }
+        report.segments_checked += 1;
+        validate_segment(&storage, segment_id, &mut report);
+    }
+
+    let referenced: HashSet<SegmentId> = manifest.iter().copied().collect();
+    let mut on_disk = Vec::new();
+    list_segment_files(&storage, &storage.segments_dir, &mut on_disk)?;
+    report.orphaned_segments = on_disk
+        .into_iter()
+        .filter(|id| !referenced.contains(id))
+        .collect();
+
+    Ok(report)
+}
+
+fn validate_segment(storage: &StorageLayout, segment_id: SegmentId, report: &mut ValidationReport) {
+    let mut problems = Vec::new();
+    check_segment(storage, segment_id, report, &mut problems);
+    if !problems.is_empty() {
+        report.corrupt_segments.push(SegmentIssue {
+            segment_id,
+            reason: problems.join("; "),
+        });
+    }
+}
+
+fn check_segment(
+    storage: &StorageLayout,
+    segment_id: SegmentId,
+    report: &mut ValidationReport,
+    problems: &mut Vec<String>,
+) {
+    let mut reader = match SegmentReader::open(storage, segment_id) {
+        Ok(reader) => reader,
+        Err(e) => return problems.push(format!("cannot open: {}", e)),
+    };
+
+    if let Err(e) = reader.verify_checksum() {
+        problems.push(e.context);
+    }
+
+    let expected = reader.header.doc_count;
+    let mut read = 0u32;
+    let documents = match reader.iter_documents() {
+        Ok(documents) => documents,
+        Err(e) => return problems.push(format!("cannot read documents: {}", e)),
+    };
+    for doc in documents {
+        match doc {
+            Ok(_) => read += 1,
+            Err(e) => {
+                report.corrupt_documents.push(DocumentIssue {
+                    segment_id,
+                    position: read,
+                    reason: e.to_string(),
+                });
+                // Length-prefixed framing: nothing after a bad record can be trusted
+                break;
+            }
+        }
+    }
+    report.documents_checked += read as usize;
+
+    if read < expected {
+        problems.push(format!("{} of {} documents readable", read, expected));
+    }
+}
+
+/// Collect ids of published segment files (temp files are skipped)
+fn list_segment_files(storage: &StorageLayout, dir: &Path, out: &mut Vec<SegmentId>) -> Result<()> {
+    for path in storage.backend().list(dir)? {
+        if storage.backend().is_dir(&path) {
+            list_segment_files(storage, &path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "seg")
+            && let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Uuid::parse_str(stem).ok())
+        {
+            out.push(SegmentId(id));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::compression::compress::CompressionType;
+    use crate::core::types::{DocId, Document, FieldValue};
+    use crate::memory::buffer_pool::BufferPool;
+    use crate::storage::segment_writer::SegmentWriter;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn write_segment(storage: &StorageLayout, docs: u64) -> SegmentId {
+        let mut writer = SegmentWriter::new(
+            storage,
+            SegmentId::new(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            CompressionType::None,
+        )
+        .unwrap();
+        for id in 0..docs {
+            writer
+                .write_document(&Document {
+                    id: DocId(id),
+                    fields: HashMap::from([(
+                        "content".to_string(),
+                        FieldValue::Text(format!("document {}", id)),
+                    )]),
+                })
+                .unwrap();
+        }
+        writer.finish(storage).unwrap().id
+    }
+
+    #[test]
+    fn report_identifies_missing_orphaned_and_corrupt_segments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+
+        let healthy = write_segment(&storage, 3);
+        let flipped = write_segment(&storage, 3);
+        let truncated = write_segment(&storage, 3);
+        let orphan = write_segment(&storage, 1);
+        let missing = SegmentId::new();
+
+        // Flip a byte in the last document's block trailer
+        let path = storage.segment_path(&flipped);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&path, &bytes).unwrap();
+
+        // Cut the file inside the second document
+        let path = storage.segment_path(&truncated);
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() * 2 / 3]).unwrap();
+
+        let manifest = [healthy, flipped, truncated, missing];
+        let report = validate_segments(&storage, &manifest).unwrap();
+
+        assert!(!report.is_clean());
+        assert_eq!(report.segments_checked, 3);
+        assert_eq!(report.missing_segments, vec![missing]);
+        assert_eq!(report.orphaned_segments, vec![orphan]);
+
+        let corrupt: HashSet<SegmentId> =
+            report.corrupt_segments.iter().map(|i| i.segment_id).collect();
+        assert_eq!(corrupt, HashSet::from([flipped, truncated]));
+
+        // First unreadable document of each damaged segment
+        let bad_docs: HashSet<(SegmentId, u32)> = report
+            .corrupt_documents
+            .iter()
+            .map(|d| (d.segment_id, d.position))
+            .collect();
+        assert_eq!(bad_docs, HashSet::from([(flipped, 2), (truncated, 1)]));
+        assert_eq!(report.documents_checked, 3 + 2 + 1);
+
+        assert_eq!(
+            report.unrecoverable_segments(),
+            HashSet::from([flipped, truncated, missing])
+        );
+
+        // Nothing was touched
+        assert!(storage.segment_path(&orphan).exists());
+        assert_eq!(fs::read(storage.segment_path(&truncated)).unwrap().len(), bytes.len() * 2 / 3);
+    }
+
+    #[test]
+    fn clean_index_produces_clean_report() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = StorageLayout::new(temp_dir.path().to_path_buf()).unwrap();
+        let ids = [write_segment(&storage, 2), write_segment(&storage, 4)];
+
+        let report = validate_segments(&storage, &ids).unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(report.documents_checked, 6);
+    }
+}
@@ -1,14 +1,24 @@
-use std::fs::{File, OpenOptions};
 use std::io::{Write, Read, Seek, SeekFrom};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::core::types::{DocId, Document};
+use crate::storage::backend::StorageFile;
 use crate::storage::layout::StorageLayout;
 use crate::core::error::{Result, Error, ErrorKind};
 
-/// Write-ahead log for durability
+/// Marks the start of every WAL frame, so a reader can find the next entry
+/// after a corrupt one
+const FRAME_MAGIC: [u8; 4] = *b"DWAL";
+/// Magic, payload length and payload CRC32
+const FRAME_HEADER_LEN: usize = 12;
+/// Larger lengths can only come from a corrupt header
+const MAX_ENTRY_LEN: usize = 10_000_000;
+
+/// Write-ahead log for durability.
+///
+/// Each entry is framed as `magic | len (u32 LE) | crc32 (u32 LE) | payload`.
 pub struct WAL {
-    pub file: File,
+    pub file: Box<dyn StorageFile>,
     pub position: u64,
     pub sync_mode: SyncMode,
     pub sequence: u64,
@@ -34,15 +44,33 @@ pub enum Operation {
     UpdateDocument(Document),
     DeleteDocument(DocId),
     Commit,
+    /// Operations that must be applied together (a committed transaction)
+    Batch(Vec<Operation>),
+}
+
+/// How far point-in-time recovery replays the WAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryTarget {
+    /// Entries up to and including this sequence number
+    Sequence(u64),
+    /// Entries written at or before this time
+    Timestamp(DateTime<Utc>),
+}
+
+impl RecoveryTarget {
+    pub fn includes(&self, entry: &WALEntry) -> bool {
+        match self {
+            RecoveryTarget::Sequence(sequence) => entry.sequence <= *sequence,
+            RecoveryTarget::Timestamp(timestamp) => entry.timestamp <= *timestamp,
+        }
+    }
 }
 
 impl WAL {
     pub fn open(storage: &StorageLayout, sequence: u64) -> Result<Self> {
         let path = storage.wal_path(sequence);
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)?;
+        // Readable too, so recovery can replay the log through the same handle
+        let file = storage.backend().open_append(&path)?;
 
         Ok(WAL {
             file,
@@ -60,14 +88,17 @@ impl WAL {
         };
 
         let data = bincode::serialize(&entry)?;
-        let len = data.len() as u32;
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+        frame.extend_from_slice(&FRAME_MAGIC);
+        frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+        frame.extend_from_slice(&data);
 
-        // Write length + data
-        self.file.write_all(&len.to_le_bytes())?;
-        self.file.write_all(&data)?;
+        // One write per frame, so a torn write only ever damages this entry
+        self.file.write_all(&frame)?;
 
         self.sequence += 1;
-        self.position += 4 + data.len() as u64;
+        self.position += frame.len() as u64;
 
         // Sync based on mode
         match self.sync_mode {
@@ -96,63 +127,74 @@ impl WAL {
         Ok(())
     }
     
-    /// Read all entries from WAL for recovery
+    /// Read all entries from WAL for recovery.
+    ///
+    /// A frame whose header or checksum doesn't hold up is skipped: reading
+    /// resumes at the next frame marker, so one corrupt entry doesn't take
+    /// the valid entries after it down too. A truncated final frame (a write
+    /// cut short by a crash) ends the log.
+    ///
+    /// Logs written before framing (bare `len | payload` records) are read
+    /// in that format up to the first frame marker, which is where entries
+    /// appended since the upgrade begin.
     pub fn read_entries(&mut self) -> Result<Vec<WALEntry>> {
         let mut entries = Vec::new();
-        
+
         // Seek to beginning of file
         self.file.seek(SeekFrom::Start(0))?;
-        
-        loop {
-            // Try to read length
-            let mut len_buf = [0u8; 4];
-            match self.file.read_exact(&mut len_buf) {
-                Ok(_) => {},
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    // Reached end of file
-                    break;
-                },
-                Err(e) => return Err(Error::new(ErrorKind::Io, format!("Failed to read WAL: {}", e))),
+        let mut bytes = Vec::new();
+        self.file
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::new(ErrorKind::Io, format!("Failed to read WAL: {}", e)))?;
+
+        let mut pos = read_legacy_entries(&bytes, &mut entries);
+        while let Some(start) = find_frame(&bytes, pos) {
+            let header = &bytes[start..];
+            if header.len() < FRAME_HEADER_LEN {
+                break;
             }
-            
-            let len = u32::from_le_bytes(len_buf) as usize;
-            
-            // Sanity check - entry shouldn't be too large
-            if len > 10_000_000 {  // 10MB max per entry
-                return Err(Error::new(ErrorKind::InvalidInput, "WAL entry too large, possibly corrupted".to_string()));
+            let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+            let crc = u32::from_le_bytes(header[8..12].try_into().unwrap());
+            let data_end = start + FRAME_HEADER_LEN + len;
+
+            let checksum_fails = data_end <= bytes.len()
+                && crc32fast::hash(&bytes[start + FRAME_HEADER_LEN..data_end]) != crc;
+            if len > MAX_ENTRY_LEN || checksum_fails {
+                // Corrupt header or payload: resynchronize on the next marker
+                eprintln!("Warning: Skipping corrupt WAL entry at offset {}", start);
+                pos = start + 1;
+                continue;
             }
-            
-            // Read entry data
-            let mut data = vec![0u8; len];
-            self.file.read_exact(&mut data)?;
-            
-            // Deserialize entry
-            match bincode::deserialize::<WALEntry>(&data) {
-                Ok(entry) => entries.push(entry),
-                Err(e) => {
-                    // Log warning but continue - partial recovery is better than none
-                    eprintln!("Warning: Failed to deserialize WAL entry: {}", e);
-                    // Try to continue reading from next position
+            if data_end > bytes.len() {
+                // A later marker means this length was damaged, not torn off
+                if find_frame(&bytes, start + 1).is_some() {
+                    eprintln!("Warning: Skipping corrupt WAL entry at offset {}", start);
+                    pos = start + 1;
+                    continue;
                 }
+                break;
+            }
+
+            match bincode::deserialize::<WALEntry>(&bytes[start + FRAME_HEADER_LEN..data_end]) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => eprintln!("Warning: Failed to deserialize WAL entry: {}", e),
             }
+            pos = data_end;
         }
-        
+
         // Reset file position for future appends
         self.position = self.file.seek(SeekFrom::End(0))?;
-        
+
         Ok(entries)
     }
-    
+
     /// Find all WAL files for recovery
     pub fn find_wal_files(storage: &StorageLayout) -> Result<Vec<u64>> {
         let mut sequences = Vec::new();
         let wal_dir = storage.wal_dir();
         
-        if wal_dir.exists() {
-            for entry in std::fs::read_dir(wal_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                
+        if storage.backend().exists(wal_dir) {
+            for path in storage.backend().list(wal_dir)? {
                 if path.extension().and_then(|s| s.to_str()) == Some("log") {
                     // Extract sequence number from filename (format: wal_00000000.log)
                     if let Some(stem) = path.file_stem() {
@@ -173,4 +215,111 @@ impl WAL {
         sequences.sort();
         Ok(sequences)
     }
-}
\ No newline at end of file
+}
+
+/// Read the unframed `len (u32 LE) | payload` records that lead a log
+/// written before framing, returning the offset framed reading resumes at.
+///
+/// A log that starts with a frame marker has none. The marker read as a
+/// length exceeds `MAX_ENTRY_LEN`, so it can't be mistaken for a record.
+fn read_legacy_entries(bytes: &[u8], entries: &mut Vec<WALEntry>) -> usize {
+    let mut pos = 0;
+    while bytes.len() - pos >= 4 && !bytes[pos..].starts_with(&FRAME_MAGIC) {
+        let len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        let data_end = pos + 4 + len;
+        if len > MAX_ENTRY_LEN || data_end > bytes.len() {
+            // Corrupt or torn legacy record: nothing after it can be trusted
+            // until the framed entries begin
+            eprintln!("Warning: Skipping corrupt WAL entry at offset {}", pos);
+            return find_frame(bytes, pos).unwrap_or(bytes.len());
+        }
+
+        match bincode::deserialize::<WALEntry>(&bytes[pos + 4..data_end]) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!("Warning: Failed to deserialize WAL entry: {}", e),
+        }
+        pos = data_end;
+    }
+    pos
+}
+
+/// Offset of the first frame marker at or after `from`
+fn find_frame(bytes: &[u8], from: usize) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(FRAME_MAGIC.len())
+        .position(|window| window == FRAME_MAGIC)
+        .map(|offset| from + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::backend::MemoryStorage;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[test]
+    fn corrupt_entries_are_skipped_and_later_ones_recovered() {
+        let storage = StorageLayout::with_backend(PathBuf::from("wal_test"), Arc::new(MemoryStorage::new())).unwrap();
+        let mut wal = WAL::open(&storage, 0).unwrap();
+        let mut frame_starts = Vec::new();
+        for id in 0..5 {
+            frame_starts.push(wal.position as usize);
+            wal.append(Operation::DeleteDocument(DocId(id))).unwrap();
+        }
+
+        let path = storage.wal_path(0);
+        let mut bytes = storage.backend().read(&path).unwrap();
+        // Entry 1 claims an absurd length; entry 3's payload has a flipped bit
+        bytes[frame_starts[1] + 4..frame_starts[1] + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[frame_starts[3] + FRAME_HEADER_LEN + 2] ^= 0x40;
+        // And a crash tore the tail off a sixth entry
+        bytes.extend_from_slice(&FRAME_MAGIC);
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        storage.backend().write(&path, &bytes).unwrap();
+
+        let entries = WAL::open(&storage, 0).unwrap().read_entries().unwrap();
+        let recovered: Vec<u64> = entries
+            .iter()
+            .map(|entry| match entry.operation {
+                Operation::DeleteDocument(DocId(id)) => id,
+                _ => panic!("unexpected operation"),
+            })
+            .collect();
+        assert_eq!(recovered, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn unframed_entries_from_before_framing_are_recovered() {
+        let storage = StorageLayout::with_backend(PathBuf::from("wal_test"), Arc::new(MemoryStorage::new())).unwrap();
+        let path = storage.wal_path(0);
+        // A log in the old `len | payload` format
+        let mut bytes = Vec::new();
+        for id in 0..3 {
+            let entry = WALEntry {
+                sequence: id,
+                operation: Operation::DeleteDocument(DocId(id)),
+                timestamp: Utc::now(),
+            };
+            let data = bincode::serialize(&entry).unwrap();
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&data);
+        }
+        storage.backend().write(&path, &bytes).unwrap();
+
+        // Appended to after the upgrade
+        let mut wal = WAL::open(&storage, 0).unwrap();
+        wal.append(Operation::DeleteDocument(DocId(3))).unwrap();
+
+        let entries = WAL::open(&storage, 0).unwrap().read_entries().unwrap();
+        let recovered: Vec<u64> = entries
+            .iter()
+            .map(|entry| match entry.operation {
+                Operation::DeleteDocument(DocId(id)) => id,
+                _ => panic!("unexpected operation"),
+            })
+            .collect();
+        assert_eq!(recovered, vec![0, 1, 2, 3]);
+    }
+}
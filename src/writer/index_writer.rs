@@ -6,15 +6,26 @@ use crate::core::types::{DocId, Document};
 use crate::memory::buffer_pool::BufferPool;
 use crate::memory::pool::MemoryPool;
 use crate::mvcc::controller::MVCCController;
-use crate::parallel::indexer::ParallelIndexer;
+use crate::index::global_dictionary::GlobalTermDictionary;
+use crate::index::index_reader::IndexReader;
+use crate::index::inverted::Term;
+use crate::index::posting::Posting;
+use crate::parallel::indexer::{IndexedDoc, ParallelIndexer};
 use crate::storage::layout::StorageLayout;
+use crate::storage::manifest::Manifest;
 use crate::storage::merge_policy::{LogStructuredMergePolicy, MergePolicy, TieredMergePolicy};
+use crate::storage::merge_throttle::MergeThrottle;
 use crate::storage::segment::Segment;
 use crate::storage::segment::SegmentId;
+use crate::storage::segment_reader::SegmentReader;
 use crate::storage::segment_writer::SegmentWriter;
+use crate::storage::tombstones::SegmentTombstones;
 use crate::storage::wal::{Operation, WAL};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
+use parking_lot::RwLock;
+use rayon::prelude::*;
+use roaring::RoaringBitmap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -31,6 +42,12 @@ pub struct IndexWriter {
     pub parallel_indexer: Arc<ParallelIndexer>, // Parallel document processing
     pub analyzer: Arc<Analyzer>,
     pub merge_policy: Box<dyn MergePolicy>,
+    pub merge_throttle: Arc<MergeThrottle>, // Rate limit for background merge I/O
+    pub positionless_fields: HashSet<String>, // Fields whose postings carry no positions
+    pub term_dictionary: Arc<RwLock<GlobalTermDictionary>>, // Term -> segments, across all segments
+    tombstones: RwLock<SegmentTombstones>, // Per-segment deletes as last persisted to `.del` files
+    bulk_load: bool, // WAL writes and manifest updates deferred to the next `commit`
+    unpublished_transactions: bool, // Committed transactions still only in the segment buffer
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +56,16 @@ pub struct WriterConfig {
     pub commit_interval: Duration,
     pub max_segment_size: usize,
     pub compression: CompressionType,
+    /// Codec level for new segments, e.g. Zstd 1–19 (`None` = codec default)
+    pub compression_level: Option<i32>,
+    /// `add_documents_batch` tokenizes in parallel above this many documents...
+    pub parallel_batch_min_docs: usize,
+    /// ...or above this many (estimated) bytes, whichever is reached first
+    pub parallel_batch_min_bytes: usize,
+    /// Commit (flush + WAL commit marker) at the end of every `add_documents_batch`
+    pub flush_on_batch_complete: bool,
+    /// Segments `compact` rewrites concurrently (1 = one at a time)
+    pub compaction_threads: usize,
 }
 
 impl IndexWriter {
@@ -76,6 +103,10 @@ impl IndexWriter {
             SegmentWriter::new(&storage, SegmentId::new(), buffer_pool.clone(), compression)?;
 
         let wal = WAL::open(&storage, 0)?;
+        let snapshot = mvcc.current_snapshot();
+        let live_segments = snapshot.segments.iter().map(|s| s.id).collect();
+        let term_dictionary = GlobalTermDictionary::load(&storage, &live_segments)?;
+        let tombstones = SegmentTombstones::load(&storage, &snapshot.segments)?;
 
         let merge_policy: Box<dyn MergePolicy> = match merge_policy_type {
             MergePolicyType::Tiered => Box::new(TieredMergePolicy::default()),
@@ -97,6 +128,12 @@ impl IndexWriter {
             parallel_indexer,
             analyzer,
             merge_policy,
+            merge_throttle: Arc::new(MergeThrottle::unlimited()),
+            positionless_fields: HashSet::new(),
+            term_dictionary: Arc::new(RwLock::new(term_dictionary)),
+            tombstones: RwLock::new(tombstones),
+            bulk_load: false,
+            unpublished_transactions: false,
         })
     }
     pub fn add_document(&mut self, doc: Document) -> Result<()> {
@@ -113,7 +150,7 @@ impl IndexWriter {
             .index_batch(vec![doc.clone()], &self.analyzer)?;
 
         // Write to WAL first
-        if write_wal {
+        if write_wal && !self.bulk_load {
             self.wal.append(Operation::AddDocument(doc.clone()))?;
         }
 
@@ -122,58 +159,152 @@ impl IndexWriter {
 
         // Add to inverted index (INDEX)
         if let Some(indexed_doc) = indexed_docs.first() {
-            // Create posting for this document
-            use crate::index::posting::Posting;
-            let mut term_positions: HashMap<_, Vec<usize>> = HashMap::new();
-
-            for (pos, token) in indexed_doc.tokens.iter().enumerate() {
-                term_positions
-                    .entry(token.text.clone())
-                    .or_insert_with(Vec::new)
-                    .push(pos);
-            }
-
-            for (term_text, positions) in term_positions {
-                let term = crate::index::inverted::Term::new(&term_text);
-                let posting = Posting {
-                    doc_id: doc.id,
-                    term_freq: positions.len() as u32,
-                    positions: positions.into_iter().map(|p| p as u32).collect(),
-                    field_norm: 1.0 / (indexed_doc.terms.len() as f32).sqrt(),
-                };
-                self.segment_writer.add_index_entry(term, posting);
-            }
+            Self::add_index_entries(
+                &mut self.segment_writer,
+                &self.positionless_fields,
+                doc.id,
+                indexed_doc,
+            );
         }
 
         // Check if flush needed
         if self.segment_writer.segment.doc_count >= self.config.batch_size as u32 {
             // Do the flush logic inline to avoid borrowing issues
-            let new_writer = SegmentWriter::new(
-                &self.storage,
-                SegmentId::new(),
-                self.buffer_pool.clone(),
-                self.config.compression,
-            )?;
+            let new_writer = self.new_segment_writer(SegmentId::new())?;
 
             // Replace old writer and finish it
             let old_writer = mem::replace(&mut self.segment_writer, new_writer);
-            let segment = old_writer.finish(&self.storage)?; // Pass storage reference
+            let segment = self.finish_segment(old_writer)?;
+            self.unpublished_transactions = false;
 
             // Only add segment if it has documents
             if segment.doc_count > 0 {
                 // Update MVCC snapshot
                 let mut segments = self.mvcc.current_snapshot().segments.clone();
                 segments.push(Arc::new(segment));
+                let segments_to_merge = if self.merge_policy.should_merge(&segments) {
+                    self.merge_policy.select_segments_to_merge(&segments)
+                } else {
+                    Vec::new()
+                };
                 self.mvcc.create_snapshot(segments);
+
+                drop(_lock);
+                self.spawn_merge(segments_to_merge);
             }
         }
 
         Ok(())
     }
 
+    /// Buffer one posting per distinct term of an indexed document
+    fn add_index_entries(
+        segment_writer: &mut SegmentWriter,
+        positionless_fields: &HashSet<String>,
+        doc_id: DocId,
+        indexed_doc: &IndexedDoc,
+    ) {
+        // term -> (frequency, positions from position-enabled fields)
+        let mut term_positions: HashMap<_, (u32, Vec<u32>)> = HashMap::new();
+
+        for (field, range) in &indexed_doc.field_ranges {
+            let keep_positions = !positionless_fields.contains(field);
+            for pos in range.clone() {
+                let entry = term_positions
+                    .entry(indexed_doc.tokens[pos].text.clone())
+                    .or_insert_with(|| (0, Vec::new()));
+                entry.0 += 1;
+                if keep_positions {
+                    entry.1.push(pos as u32);
+                }
+            }
+        }
+
+        for (term_text, (term_freq, positions)) in term_positions {
+            let term = crate::index::inverted::Term::new(&term_text);
+            let posting = Posting {
+                doc_id,
+                term_freq,
+                positions,
+                field_norm: indexed_doc.field_norm(),
+            };
+            segment_writer.add_index_entry(term, posting);
+        }
+        for term in &indexed_doc.numeric_terms {
+            segment_writer.add_index_entry(term.clone(), indexed_doc.numeric_posting());
+        }
+    }
+
+    /// Apply a group of operations all-or-nothing. Everything that can reject
+    /// the group — indexing every document, the single WAL entry — runs before
+    /// any state changes; the deletes are then published in one snapshot.
+    /// Recovery replays the group as a unit, so a crash never applies half of it.
+    pub fn apply_batch(&mut self, operations: Vec<Operation>) -> Result<()> {
+        // Nested groups can't be applied as a unit; reject before anything is logged
+        if operations
+            .iter()
+            .any(|op| matches!(op, Operation::Batch(_) | Operation::Commit))
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                "Batches may only hold document operations".to_string(),
+            ));
+        }
+
+        {
+            let _lock = self.lock.lock().unwrap();
+
+            let docs: Vec<Document> = operations
+                .iter()
+                .filter_map(|op| match op {
+                    Operation::AddDocument(doc) | Operation::UpdateDocument(doc) => Some(doc.clone()),
+                    _ => None,
+                })
+                .collect();
+            let indexed_docs = self.parallel_indexer.index_batch(docs, &self.analyzer)?;
+
+            if !self.bulk_load {
+                self.wal.append(Operation::Batch(operations.clone()))?;
+            }
+
+            let snapshot = self.mvcc.current_snapshot();
+            let mut deleted_docs = (*snapshot.deleted_docs).clone();
+            let mut indexed_docs = indexed_docs.into_iter();
+            for operation in operations {
+                match operation {
+                    Operation::AddDocument(doc) | Operation::UpdateDocument(doc) => {
+                        self.segment_writer.write_document(&doc)?;
+                        if let Some(indexed_doc) = indexed_docs.next() {
+                            Self::add_index_entries(
+                                &mut self.segment_writer,
+                                &self.positionless_fields,
+                                doc.id,
+                                &indexed_doc,
+                            );
+                        }
+                    }
+                    Operation::DeleteDocument(doc_id) => {
+                        deleted_docs.insert(doc_id.0 as u32);
+                    }
+                    Operation::Batch(_) | Operation::Commit => unreachable!("rejected above"),
+                }
+            }
+
+            if deleted_docs.len() != snapshot.deleted_docs.len() {
+                self.mvcc
+                    .create_snapshot_with_deletes(snapshot.segments.clone(), Arc::new(deleted_docs));
+            }
+        }
+
+        if self.segment_writer.segment.doc_count >= self.config.batch_size as u32 {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
     /// Add documents in batch with parallel processing (M08 optimization)
     pub fn add_documents_batch(&mut self, docs: Vec<Document>) -> Result<()> {
-        if docs.len() > 100 {
+        if self.should_parallelize(&docs) {
             // Parallel processing: tokenize & index in parallel
             let indexed_docs = self
                 .parallel_indexer
@@ -185,6 +316,10 @@ impl IndexWriter {
                 ));
             }
 
+            // Segments the last flush below asked to merge, spawned once the
+            // lock is released
+            let mut segments_to_merge = Vec::new();
+
             // Write to WAL and segments (still sequential - single writer)
             {
                 let _lock = self.lock.lock().unwrap();
@@ -198,35 +333,39 @@ impl IndexWriter {
                     }
 
                     // Write to WAL
-                    self.wal.append(Operation::AddDocument(doc.clone()))?;
+                    if !self.bulk_load {
+                        self.wal.append(Operation::AddDocument(doc.clone()))?;
+                    }
 
-                    // Write to segment
+                    // Write to segment, with the postings indexed above
                     self.segment_writer.write_document(&doc)?;
+                    Self::add_index_entries(
+                        &mut self.segment_writer,
+                        &self.positionless_fields,
+                        doc.id,
+                        &indexed_doc,
+                    );
 
                     // Check if flush needed
                     if self.segment_writer.segment.doc_count >= self.config.batch_size as u32 {
                         // Inline flush logic to avoid borrowing issues
-                        let new_writer = SegmentWriter::new(
-                            &self.storage,
-                            SegmentId::new(),
-                            self.buffer_pool.clone(),
-                            self.config.compression,
-                        )?;
+                        let new_writer = self.new_segment_writer(SegmentId::new())?;
                         let old_writer = mem::replace(&mut self.segment_writer, new_writer);
-                        let segment = old_writer.finish(&self.storage)?;
+                        let segment = self.finish_segment(old_writer)?;
 
                         let mut segments = self.mvcc.current_snapshot().segments.clone();
                         segments.push(Arc::new(segment));
 
                         if self.merge_policy.should_merge(&segments) {
-                            // Note: Can't call async merge while holding lock
-                            // Would need to refactor to handle this differently
+                            segments_to_merge = self.merge_policy.select_segments_to_merge(&segments);
                         }
 
                         self.mvcc.create_snapshot(segments);
                     }
                 }
             } // Lock is dropped here
+
+            self.spawn_merge(segments_to_merge);
         } else {
             // Sequential for small batches (avoid parallelization overhead)
             for doc in docs {
@@ -234,46 +373,146 @@ impl IndexWriter {
             }
         }
 
+        if self.config.flush_on_batch_complete {
+            self.commit()?;
+        }
+
         Ok(())
     }
 
+    /// Segment writer using the configured codec and level
+    fn new_segment_writer(&self, segment_id: SegmentId) -> Result<SegmentWriter> {
+        Ok(SegmentWriter::new(
+            &self.storage,
+            segment_id,
+            self.buffer_pool.clone(),
+            self.config.compression,
+        )?
+        .with_compression_level(self.config.compression_level))
+    }
+
+    /// Publish a segment and record its terms in the global dictionary
+    fn finish_segment(&self, writer: SegmentWriter) -> Result<Segment> {
+        Self::publish_segment(&self.storage, &self.term_dictionary, writer)
+    }
+
+    fn publish_segment(
+        storage: &StorageLayout,
+        term_dictionary: &RwLock<GlobalTermDictionary>,
+        writer: SegmentWriter,
+    ) -> Result<Segment> {
+        let terms: Vec<(Term, u32)> = writer
+            .inverted_index
+            .iter()
+            .map(|(term, postings)| (term.clone(), postings.len() as u32))
+            .collect();
+        let segment = writer.finish(storage)?;
+        // Empty segments are never published
+        if segment.doc_count > 0 {
+            term_dictionary.write().add_segment(segment.id, terms);
+        }
+        Ok(segment)
+    }
+
+    /// Copy the postings of `doc_ids` from a segment's `.idx` into `writer`
+    fn copy_postings(
+        storage: &StorageLayout,
+        segment_id: SegmentId,
+        doc_ids: &HashSet<DocId>,
+        writer: &mut SegmentWriter,
+    ) -> Result<()> {
+        let index = IndexReader::open(storage, segment_id)?;
+        for (term, postings) in index.inverted_index {
+            for posting in postings {
+                if doc_ids.contains(&posting.doc_id) {
+                    writer.add_index_entry(term.clone(), posting);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a batch is big enough to be worth the parallel indexing overhead
+    fn should_parallelize(&self, docs: &[Document]) -> bool {
+        if docs.len() > self.config.parallel_batch_min_docs {
+            return true;
+        }
+        // A few huge documents are worth parallelizing too
+        let mut bytes = 0;
+        docs.iter().any(|doc| {
+            bytes += doc.estimated_size();
+            bytes > self.config.parallel_batch_min_bytes
+        })
+    }
+
     pub fn flush(&mut self) -> Result<()> {
         // Acquire lock before flushing to prevent concurrent modifications
         let _lock = self.lock.lock().unwrap();
 
+        // Nothing buffered: keep the current writer instead of rolling an empty segment
+        if self.segment_writer.segment.doc_count == 0 {
+            return Ok(());
+        }
+
         // Do flush inline to avoid borrowing issues
-        let new_writer = SegmentWriter::new(
-            &self.storage,
-            SegmentId::new(),
-            self.buffer_pool.clone(),
-            self.config.compression,
-        )?;
+        let new_writer = self.new_segment_writer(SegmentId::new())?;
 
         // Replace old writer and finish it
         let old_writer = mem::replace(&mut self.segment_writer, new_writer);
-        let segment = old_writer.finish(&self.storage)?;
+        let segment = self.finish_segment(old_writer)?;
+        self.unpublished_transactions = false;
 
-        // Only add segment if it has documents (skip empty segments)
-        if segment.doc_count > 0 {
-            // Update MVCC snapshot
-            let mut segments = self.mvcc.current_snapshot().segments.clone();
-            segments.push(Arc::new(segment));
+        // Update MVCC snapshot
+        let mut segments = self.mvcc.current_snapshot().segments.clone();
+        segments.push(Arc::new(segment));
 
-            // Check if we should merge segments
-            if self.merge_policy.should_merge(&segments) {
-                self.merge_segments_async(segments.clone());
-            }
+        // Check if we should merge segments
+        if self.merge_policy.should_merge(&segments) {
+            self.merge_segments_async(segments.clone());
+        }
 
-            self.mvcc.create_snapshot(segments);
+        self.mvcc.create_snapshot(segments);
+        if !self.bulk_load {
+            self.save_manifest()?;
         }
 
         Ok(())
     }
 
+    /// Persist the current snapshot's segments so they survive a restart
+    fn save_manifest(&self) -> Result<()> {
+        Manifest::from_snapshot(&self.mvcc.current_snapshot()).save(&self.storage)?;
+        self.save_tombstones()?;
+        self.term_dictionary.read().save(&self.storage)
+    }
+
+    /// Persist the current deletes to the `.del` file of each segment they hit
+    fn save_tombstones(&self) -> Result<()> {
+        let snapshot = self.mvcc.current_snapshot();
+        self.tombstones
+            .write()
+            .save(&self.storage, &snapshot.segments, &snapshot.deleted_docs)
+    }
+
+    /// Start a bulk-load session: until the next `commit`, writes skip the
+    /// WAL and flushed segments aren't recorded in the manifest. Use it only
+    /// when the source can be replayed — a crash before `commit` discards
+    /// everything loaded in the session.
+    pub fn begin_bulk_load(&mut self) {
+        self.bulk_load = true;
+    }
+
+    pub fn is_bulk_loading(&self) -> bool {
+        self.bulk_load
+    }
+
     /// Merge segments based on merge policy (runs asynchronously)
     fn merge_segments_async(&self, segments: Vec<Arc<Segment>>) {
-        let segments_to_merge = self.merge_policy.select_segments_to_merge(&segments);
+        self.spawn_merge(self.merge_policy.select_segments_to_merge(&segments));
+    }
 
+    /// Merge already-selected segments on a background thread
+    fn spawn_merge(&self, segments_to_merge: Vec<Arc<Segment>>) {
         if segments_to_merge.is_empty() {
             return;
         }
@@ -282,7 +521,10 @@ impl IndexWriter {
         let storage = self.storage.clone();
         let mvcc = self.mvcc.clone();
         let buffer_pool = self.buffer_pool.clone();
-        let compression = self.config.compression;
+        let config = self.config.clone();
+        let throttle = self.merge_throttle.clone();
+        let term_dictionary = self.term_dictionary.clone();
+        let lock = self.lock.clone();
 
         // Spawn background merge task
         std::thread::spawn(move || {
@@ -292,7 +534,10 @@ impl IndexWriter {
                 mvcc,
                 buffer_pool,
                 segments_to_merge,
-                compression,
+                &config,
+                &throttle,
+                &term_dictionary,
+                &lock,
             ) {
                 eprintln!("Background merge failed: {}", e);
             }
@@ -300,25 +545,37 @@ impl IndexWriter {
     }
 
     /// Implementation of segment merging
+    #[allow(clippy::too_many_arguments)]
     fn merge_segments_impl(
         storage: Arc<StorageLayout>,
         mvcc: Arc<MVCCController>,
         buffer_pool: Arc<BufferPool>,
         segments_to_merge: Vec<Arc<Segment>>,
-        compression: CompressionType,
+        config: &WriterConfig,
+        throttle: &MergeThrottle,
+        term_dictionary: &RwLock<GlobalTermDictionary>,
+        lock: &Mutex<()>,
     ) -> Result<()> {
+        let start = std::time::Instant::now();
+        let mut merged_bytes = 0u64;
         let merged_id = SegmentId::new();
-        let mut merged_writer = SegmentWriter::new(&storage, merged_id, buffer_pool, compression)?;
+        let mut merged_writer = SegmentWriter::new(&storage, merged_id, buffer_pool, config.compression)?
+            .with_compression_level(config.compression_level);
 
         // Copy all documents from segments to merge
-        use crate::storage::segment_reader::SegmentReader;
 
         for segment in &segments_to_merge {
             let mut reader = SegmentReader::open(&storage, segment.id)?;
             let mut doc_iter = reader.iter_documents()?;
+            let mut kept = HashSet::new();
 
             while let Some(doc) = doc_iter.next() {
                 let doc = doc?;
+                // Charged once as it is read, whether or not it is kept
+                let doc_bytes = bincode::serialized_size(&doc)?;
+                throttle.consume(doc_bytes);
+                merged_bytes += doc_bytes;
+
                 // Check if document is deleted
                 if !mvcc
                     .current_snapshot()
@@ -326,14 +583,28 @@ impl IndexWriter {
                     .contains(doc.id.0 as u32)
                 {
                     merged_writer.write_document(&doc)?;
+                    kept.insert(doc.id);
                 }
             }
+            Self::copy_postings(&storage, segment.id, &kept, &mut merged_writer)?;
         }
 
-        let merged_segment = merged_writer.finish(&storage)?;
+        let merged_segment = Self::publish_segment(&storage, term_dictionary, merged_writer)?;
+        throttle.record_merge(merged_bytes, start.elapsed());
 
-        // Update snapshot with merged segment
+        // Update snapshot with merged segment, under the writer lock so a
+        // concurrent flush can't rebuild the segment list from a stale snapshot
+        let _lock = lock.lock().unwrap();
         let current_snapshot = mvcc.current_snapshot();
+        let still_live = segments_to_merge
+            .iter()
+            .all(|s| current_snapshot.segments.iter().any(|seg| seg.id == s.id));
+        if !still_live {
+            // An overlapping merge already replaced some of these segments;
+            // publishing this one too would list their documents twice
+            term_dictionary.write().remove_segments(&[merged_id]);
+            return Ok(());
+        }
         let mut new_segments = Vec::new();
 
         // Keep segments not being merged
@@ -343,28 +614,92 @@ impl IndexWriter {
             }
         }
 
-        // Add the merged segment
-        new_segments.push(Arc::new(merged_segment));
+        // Add the merged segment unless every document in it was deleted
+        if !merged_segment.is_empty() {
+            new_segments.push(Arc::new(merged_segment));
+        }
 
-        // Create new snapshot
-        mvcc.create_snapshot(new_segments);
+        // Create new snapshot, keeping deletes of the segments left alone
+        mvcc.create_snapshot_with_deletes(new_segments, current_snapshot.deleted_docs.clone());
+        let merged_ids: Vec<SegmentId> = segments_to_merge.iter().map(|s| s.id).collect();
+        term_dictionary.write().remove_segments(&merged_ids);
 
         Ok(())
     }
 
     pub fn commit(&mut self) -> Result<()> {
+        // Ends a bulk-load session: the flush below records every segment it wrote
+        let ending_bulk_load = mem::take(&mut self.bulk_load);
         self.flush()?;
+        if ending_bulk_load {
+            // `flush` skips the manifest when nothing is left buffered
+            self.save_manifest()?;
+        } else {
+            // ...and with it deletes made since the last flush
+            self.save_tombstones()?;
+        }
         self.wal.append(Operation::Commit)?;
         self.wal.sync()?;
         Ok(())
     }
 
+    /// Force logged operations to disk without rolling a segment.
+    /// Recovery replays everything after the last commit marker, so synced
+    /// operations survive a crash even though they are not yet in a segment.
+    pub fn sync_wal(&mut self) -> Result<()> {
+        let _lock = self.lock.lock().unwrap();
+        self.wal.sync()
+    }
+
+    /// Apply a committed transaction's operations as one batch and make them
+    /// durable. Its documents stay in the segment buffer until a reader needs
+    /// them; see [`IndexWriter::publish_transactions`].
+    pub fn apply_transaction(&mut self, operations: Vec<Operation>) -> Result<()> {
+        let adds = operations
+            .iter()
+            .any(|op| matches!(op, Operation::AddDocument(_) | Operation::UpdateDocument(_)));
+        self.apply_batch(operations)?;
+        self.sync_wal()?;
+        // Deletes are published by `apply_batch`; a full buffer was flushed there too
+        self.unpublished_transactions |= adds && self.segment_writer.segment.doc_count > 0;
+        Ok(())
+    }
+
+    /// Whether committed transactions are waiting in the segment buffer
+    pub fn has_unpublished_transactions(&self) -> bool {
+        self.unpublished_transactions
+    }
+
+    /// Flush the segment buffer if it holds committed transactions, so
+    /// searches and other transactions see them
+    pub fn publish_transactions(&mut self) -> Result<()> {
+        if self.unpublished_transactions {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// `publish_transactions` on a shared writer, taking the write lock only
+    /// when there is something to publish
+    pub fn publish_shared(writer: &RwLock<IndexWriter>) -> Result<()> {
+        if writer.read().has_unpublished_transactions() {
+            writer.write().publish_transactions()?;
+        }
+        Ok(())
+    }
+
     /// Apply WAL operation during recovery without re-appending to WAL.
     pub(crate) fn apply_recovered_operation(&mut self, operation: Operation) -> Result<()> {
         match operation {
             Operation::AddDocument(doc) => self.add_document_internal(doc, false),
             Operation::UpdateDocument(doc) => self.add_document_internal(doc, false),
             Operation::DeleteDocument(doc_id) => self.delete_document_internal(doc_id, false),
+            Operation::Batch(operations) => {
+                for operation in operations {
+                    self.apply_recovered_operation(operation)?;
+                }
+                Ok(())
+            }
             Operation::Commit => self.flush(),
         }
     }
@@ -378,7 +713,7 @@ impl IndexWriter {
         let _lock = self.lock.lock().unwrap();
 
         // Write to WAL first for durability
-        if write_wal {
+        if write_wal && !self.bulk_load {
             self.wal.append(Operation::DeleteDocument(doc_id))?;
         }
 
@@ -408,58 +743,95 @@ impl IndexWriter {
             return Ok(());
         }
 
-        // Create new compacted segments
-        let mut new_segments = Vec::new();
+        // Each segment is rewritten independently, so up to
+        // `compaction_threads` of them are rewritten at once
+        let rewrite = |segment: &Arc<Segment>| self.rewrite_segment(segment, &deleted_docs);
+        let rewritten: Vec<Option<Segment>> = if self.config.compaction_threads <= 1 {
+            snapshot.segments.iter().map(rewrite).collect::<Result<_>>()?
+        } else {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.config.compaction_threads)
+                .build()
+                .map_err(|e| Error::new(ErrorKind::Internal, e.to_string()))?
+                .install(|| snapshot.segments.par_iter().map(rewrite).collect::<Result<_>>())?
+        };
+        // Segments keep their order; those left empty are dropped
+        let new_segments: Vec<Arc<Segment>> = rewritten.into_iter().flatten().map(Arc::new).collect();
 
-        for segment in &snapshot.segments {
-            // Check if this segment has any deleted documents
-            // We check if any document ID in the deleted bitmap might be in this segment
-            // For simplicity, we'll process segments with any deletes in the snapshot
-            let segment_has_deletes = !snapshot.deleted_docs.is_empty();
+        // Create new snapshot with compacted segments and empty deleted bitmap
+        self.mvcc
+            .create_snapshot_with_deletes(new_segments, Arc::new(RoaringBitmap::new()));
+        if !self.bulk_load {
+            self.save_manifest()?;
+        }
 
-            if !segment_has_deletes {
-                // No deletes in this segment, keep it as-is
-                new_segments.push(segment.clone());
-                continue;
-            }
+        // Write compaction to WAL
+        self.wal.append(Operation::Commit)?;
+        self.wal.sync()?;
 
-            // Create new segment without deleted documents
-            let new_segment_id = SegmentId::new();
-            let mut new_writer = SegmentWriter::new(
-                &self.storage,
-                new_segment_id,
-                self.buffer_pool.clone(),
-                self.config.compression,
-            )?;
-
-            // Copy non-deleted documents to new segment
-            use crate::storage::segment_reader::SegmentReader;
-            let mut reader = SegmentReader::open(&self.storage, segment.id)?;
-            let mut doc_iter = reader.iter_documents()?;
+        Ok(())
+    }
 
-            while let Some(doc) = doc_iter.next() {
-                let doc = doc?;
-                // Skip deleted documents
-                if !snapshot.deleted_docs.contains(doc.id.0 as u32) {
-                    new_writer.write_document(&doc)?;
-                }
+    /// Delete `doc_ids` and physically remove them in one pass, rewriting
+    /// only `segment_ids` (the segments holding them) and leaving every other
+    /// segment as-is. The ids stay in the deleted bitmap so older copies in
+    /// untouched segments remain hidden.
+    pub fn purge(&mut self, doc_ids: &HashSet<DocId>, segment_ids: &HashSet<SegmentId>) -> Result<()> {
+        let _lock = self.lock.lock().unwrap();
+
+        if !self.bulk_load {
+            for &doc_id in doc_ids {
+                self.wal.append(Operation::DeleteDocument(doc_id))?;
             }
+        }
 
-            let new_segment = new_writer.finish(&self.storage)?;
-            new_segments.push(Arc::new(new_segment));
+        let snapshot = self.mvcc.current_snapshot();
+        let mut deleted_docs = (*snapshot.deleted_docs).clone();
+        deleted_docs.extend(doc_ids.iter().map(|id| id.0 as u32));
+
+        let mut new_segments = Vec::with_capacity(snapshot.segments.len());
+        for segment in &snapshot.segments {
+            if !segment_ids.contains(&segment.id) {
+                new_segments.push(segment.clone());
+            } else if let Some(new_segment) = self.rewrite_segment(segment, &deleted_docs)? {
+                new_segments.push(Arc::new(new_segment));
+            }
         }
 
-        // Create new snapshot with compacted segments and empty deleted bitmap
-        use roaring::RoaringBitmap;
         self.mvcc
-            .create_snapshot_with_deletes(new_segments, Arc::new(RoaringBitmap::new()));
+            .create_snapshot_with_deletes(new_segments, Arc::new(deleted_docs));
+        if !self.bulk_load {
+            self.save_manifest()?;
+        }
 
-        // Write compaction to WAL
         self.wal.append(Operation::Commit)?;
         self.wal.sync()?;
 
         Ok(())
     }
+
+    /// Copy `segment` into a new segment without the `deleted` documents.
+    /// Returns `None` when nothing survives.
+    fn rewrite_segment(&self, segment: &Segment, deleted: &RoaringBitmap) -> Result<Option<Segment>> {
+        let mut new_writer = self.new_segment_writer(SegmentId::new())?;
+
+        let mut reader = SegmentReader::open(&self.storage, segment.id)?;
+        let mut doc_iter = reader.iter_documents()?;
+        let mut kept = HashSet::new();
+
+        while let Some(doc) = doc_iter.next() {
+            let doc = doc?;
+            if !deleted.contains(doc.id.0 as u32) {
+                new_writer.write_document(&doc)?;
+                kept.insert(doc.id);
+            }
+        }
+        Self::copy_postings(&self.storage, segment.id, &kept, &mut new_writer)?;
+
+        let new_segment = self.finish_segment(new_writer)?;
+        self.term_dictionary.write().remove_segments(&[segment.id]);
+        Ok((!new_segment.is_empty()).then_some(new_segment))
+    }
 }
 
 impl Default for WriterConfig {
@@ -469,6 +841,11 @@ impl Default for WriterConfig {
             commit_interval: Duration::from_secs(5),
             max_segment_size: 100_000,
             compression: CompressionType::LZ4,
+            compression_level: None,
+            parallel_batch_min_docs: 100,
+            parallel_batch_min_bytes: 1024 * 1024,
+            flush_on_batch_complete: false,
+            compaction_threads: num_cpus::get(),
         }
     }
 }
@@ -478,6 +855,8 @@ mod tests {
     use super::*;
     use crate::compression::compress::CompressedBlock;
     use crate::core::types::FieldValue;
+    use crate::index::index_file;
+    use crate::index::inverted::Term;
     use crate::storage::segment::SegmentHeader;
     use std::fs::File;
     use std::io::Read;
@@ -545,6 +924,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn batch_inserted_documents_keep_fields_and_postings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+        writer.config.parallel_batch_min_docs = 1;
+
+        let docs: Vec<Document> = (0..200)
+            .map(|i| make_doc(i, &format!("batch value{}", i)))
+            .collect();
+        writer.add_documents_batch(docs.clone()).unwrap();
+        writer.flush().unwrap();
+
+        let snapshot = mvcc.current_snapshot();
+        assert_eq!(snapshot.segments.len(), 1);
+        let segment_id = snapshot.segments[0].id;
+        let reader = SegmentReader::open(&storage, segment_id).unwrap();
+        let stored = reader.get_document(DocId(123)).unwrap().unwrap();
+        assert_eq!(stored.fields, docs[123].fields);
+
+        let idx_data = std::fs::read(storage.index_path(&segment_id)).unwrap();
+        let index = index_file::decode_index(&idx_data).unwrap();
+        assert_eq!(index[&Term::new("batch")].len(), 200);
+        let postings = &index[&Term::new("value123")];
+        assert_eq!(postings.len(), 1);
+        assert_eq!(postings[0].doc_id, DocId(123));
+        assert_eq!(postings[0].positions, vec![1]);
+    }
+
     #[test]
     fn segment_and_index_files_use_writer_compression_config() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -569,7 +978,385 @@ mod tests {
         assert!(matches!(segment_block.compression, CompressionType::Zstd));
 
         let idx_data = std::fs::read(storage.index_path(&segment.id)).unwrap();
-        let idx_block: CompressedBlock = bincode::deserialize(&idx_data).unwrap();
+        let mut cursor = std::io::Cursor::new(&idx_data);
+        let Some(index_file::TermDirectory::Blocks(block_index)) =
+            index_file::read_directory(&mut cursor, idx_data.len() as u64).unwrap()
+        else {
+            panic!("expected a dictionary block index");
+        };
+        let entries = index_file::read_dictionary_block(&mut cursor, block_index.blocks[0].1).unwrap();
+        let location = &entries[0].1;
+        let block = &idx_data[location.offset as usize..(location.offset + location.length) as usize];
+        let idx_block: CompressedBlock = bincode::deserialize(block).unwrap();
         assert!(matches!(idx_block.compression, CompressionType::Zstd));
     }
+
+    #[test]
+    fn rejected_batch_leaves_no_documents_and_no_wal_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::None);
+
+        for nested in [Operation::Commit, Operation::Batch(vec![Operation::DeleteDocument(DocId(9))])] {
+            let err = writer
+                .apply_batch(vec![
+                    Operation::AddDocument(make_doc(1, "first")),
+                    Operation::DeleteDocument(DocId(2)),
+                    nested,
+                    Operation::AddDocument(make_doc(3, "third")),
+                ])
+                .unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::InvalidArgument));
+        }
+
+        assert_eq!(writer.segment_writer.segment.doc_count, 0);
+        assert!(mvcc.current_snapshot().deleted_docs.is_empty());
+        assert!(writer.wal.read_entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn batch_insert_merges_segments_past_tier_limit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage, mvcc.clone(), CompressionType::LZ4);
+        writer.config.batch_size = 10;
+        writer.config.parallel_batch_min_docs = 1;
+        writer.merge_policy = Box::new(TieredMergePolicy {
+            max_segments_per_tier: 4,
+            min_segments_to_merge: usize::MAX,
+            ..TieredMergePolicy::default()
+        });
+
+        // 6 flushes of 10 docs: past the tier limit, but too few to merge
+        let docs: Vec<Document> = (0..60).map(|i| make_doc(i, &format!("batch doc {}", i))).collect();
+        writer.add_documents_batch(docs).unwrap();
+        assert_eq!(mvcc.current_snapshot().segments.len(), 6);
+
+        writer.merge_policy = Box::new(TieredMergePolicy {
+            max_segments_per_tier: 4,
+            ..TieredMergePolicy::default()
+        });
+        let docs: Vec<Document> = (60..70).map(|i| make_doc(i, &format!("batch doc {}", i))).collect();
+        writer.add_documents_batch(docs).unwrap();
+
+        // The merge runs in the background once the batch releases the lock
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while mvcc.current_snapshot().segments.len() > 4 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let snapshot = mvcc.current_snapshot();
+        assert!(snapshot.segments.len() <= 4, "{} segments", snapshot.segments.len());
+        assert_eq!(snapshot.segments.iter().map(|s| s.doc_count).sum::<u32>(), 70);
+    }
+
+    #[test]
+    fn merge_is_throttled_to_configured_rate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+
+        let docs: Vec<Document> = (0..20)
+            .map(|i| make_doc(i, &format!("merge payload number {}", i)))
+            .collect();
+        for chunk in docs.chunks(10) {
+            for doc in chunk {
+                writer.add_document(doc.clone()).unwrap();
+            }
+            writer.flush().unwrap();
+        }
+        let segments = mvcc.current_snapshot().segments.clone();
+
+        // Every document is charged exactly once
+        let data_size: u64 = docs.iter().map(|d| bincode::serialized_size(d).unwrap()).sum();
+        let rate = data_size * 5; // => at least 200ms
+        let throttle = MergeThrottle::new(Some(rate));
+
+        let start = std::time::Instant::now();
+        IndexWriter::merge_segments_impl(
+            storage,
+            mvcc.clone(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            segments,
+            &WriterConfig::default(),
+            &throttle,
+            &writer.term_dictionary,
+            &writer.lock,
+        )
+        .unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= Duration::from_millis(200), "merge took {:?}", elapsed);
+        assert_eq!(throttle.bytes_merged(), data_size);
+        assert!(throttle.throughput() > 0.0);
+    }
+
+    #[test]
+    fn flush_and_compact_never_publish_empty_segments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage, mvcc.clone(), CompressionType::LZ4);
+
+        // Nothing pending: no segment with an inverted (u64::MAX..0) range
+        writer.flush().unwrap();
+        assert!(mvcc.current_snapshot().segments.is_empty());
+
+        writer.add_document(make_doc(7, "only document")).unwrap();
+        writer.flush().unwrap();
+        let snapshot = mvcc.current_snapshot();
+        assert_eq!(snapshot.segments.len(), 1);
+        assert_eq!(
+            snapshot.segments[0].metadata.doc_id_range(),
+            Some((DocId(7), DocId(7)))
+        );
+
+        // Deleting every document and compacting leaves no segment behind
+        writer.delete_document(DocId(7)).unwrap();
+        writer.compact().unwrap();
+        assert!(mvcc.current_snapshot().segments.is_empty());
+
+        let empty = crate::storage::segment::SegmentMetadata::empty();
+        assert_eq!(empty.doc_id_range(), None);
+        assert!(!empty.may_contain(DocId(0)));
+    }
+
+    #[test]
+    fn flush_without_writes_is_a_no_op() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+
+        writer.add_document(make_doc(1, "first")).unwrap();
+        writer.flush().unwrap();
+        let segment_count = mvcc.current_snapshot().segments.len();
+        let version = mvcc.current_snapshot().version;
+        let files = std::fs::read_dir(&storage.segments_dir).unwrap().count();
+
+        for _ in 0..5 {
+            writer.flush().unwrap();
+        }
+
+        assert_eq!(mvcc.current_snapshot().segments.len(), segment_count);
+        assert_eq!(mvcc.current_snapshot().version, version);
+        assert_eq!(std::fs::read_dir(&storage.segments_dir).unwrap().count(), files);
+    }
+
+    #[test]
+    fn positionless_fields_shrink_index_and_keep_term_frequencies() {
+        let text = "rust search engine written in rust with fast rust indexing ".repeat(20);
+
+        let index_for = |positions: bool| {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+            let mvcc = Arc::new(MVCCController::new());
+            let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::None);
+            if !positions {
+                writer.positionless_fields.insert("content".to_string());
+            }
+            for id in 0..20 {
+                writer.add_document(make_doc(id, &text)).unwrap();
+            }
+            writer.flush().unwrap();
+
+            let segment = mvcc.current_snapshot().segments[0].clone();
+            let idx_data = std::fs::read(storage.index_path(&segment.id)).unwrap();
+            let postings = index_file::decode_index(&idx_data).unwrap();
+            (idx_data.len(), postings)
+        };
+
+        let (with_size, with_postings) = index_for(true);
+        let (without_size, without_postings) = index_for(false);
+
+        assert!(
+            without_size * 2 < with_size,
+            "positions off: {} bytes, on: {} bytes",
+            without_size,
+            with_size
+        );
+
+        let rust = Term::new("rust");
+        for (with, without) in with_postings[&rust].iter().zip(&without_postings[&rust]) {
+            assert_eq!(with.term_freq, without.term_freq);
+            assert_eq!(with.positions.len(), with.term_freq as usize);
+            assert!(without.positions.is_empty());
+        }
+    }
+
+    #[test]
+    fn batch_path_respects_configured_thresholds() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage, mvcc, CompressionType::LZ4);
+        writer.config.parallel_batch_min_docs = 10;
+        writer.config.parallel_batch_min_bytes = 4096;
+
+        // The parallel path indexes the whole batch in one call; the sequential
+        // path indexes one document per call
+        let mut next_id = 0u64;
+        let mut run = |writer: &mut IndexWriter, count: usize, text: &str| {
+            let docs: Vec<Document> = (0..count)
+                .map(|_| {
+                    next_id += 1;
+                    make_doc(next_id, text)
+                })
+                .collect();
+            writer.add_documents_batch(docs).unwrap();
+            writer.parallel_indexer.get_progress()
+        };
+
+        assert_eq!(run(&mut writer, 10, "small"), 1);
+        assert_eq!(run(&mut writer, 11, "small"), 11);
+
+        // Few documents, but over the byte threshold
+        let large = "word ".repeat(1000);
+        assert_eq!(run(&mut writer, 3, &large), 3);
+        assert_eq!(run(&mut writer, 3, "small"), 1);
+    }
+
+    #[test]
+    fn global_term_dictionary_probes_only_segments_holding_the_term() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+        writer.merge_policy = Box::new(TieredMergePolicy {
+            max_segments_per_tier: usize::MAX,
+            min_segments_to_merge: usize::MAX,
+            ..TieredMergePolicy::default()
+        });
+
+        for (id, word) in [(1, "rust"), (2, "java"), (3, "kotlin")] {
+            writer.add_document(make_doc(id, &format!("{} lang", word))).unwrap();
+            writer.flush().unwrap();
+        }
+        assert_eq!(mvcc.current_snapshot().segments.len(), 3);
+
+        let probe = |dictionary: &GlobalTermDictionary, word: &str| {
+            let mut probes = 0;
+            let found = dictionary
+                .postings(&Term::new(word), |segment_id, term| {
+                    probes += 1;
+                    Ok(IndexReader::open(&storage, segment_id)?.get_postings(term).cloned())
+                })
+                .unwrap();
+            let docs: Vec<u64> = found.iter().flat_map(|(_, p)| p.iter().map(|p| p.doc_id.0)).collect();
+            (probes, docs)
+        };
+
+        let dictionary = writer.term_dictionary.read().clone();
+        assert_eq!(probe(&dictionary, "java"), (1, vec![2]));
+        assert_eq!(probe(&dictionary, "lang").0, 3);
+        assert_eq!(probe(&dictionary, "missing"), (0, vec![]));
+        assert_eq!(dictionary.doc_freq(&Term::new("lang")), 3);
+
+        // Saved with the manifest and reloaded on open
+        let reopened_mvcc = Arc::new(MVCCController::new());
+        let (segments, deleted) = Manifest::load(&storage).unwrap().unwrap().into_parts();
+        reopened_mvcc.create_snapshot_with_deletes(segments, deleted);
+        let reopened = make_writer(storage.clone(), reopened_mvcc, CompressionType::LZ4);
+        assert_eq!(probe(&reopened.term_dictionary.read(), "kotlin"), (1, vec![3]));
+
+        // A merge moves every term onto the merged segment
+        let segments = mvcc.current_snapshot().segments.clone();
+        IndexWriter::merge_segments_impl(
+            storage.clone(),
+            mvcc.clone(),
+            Arc::new(BufferPool::new(1024 * 1024)),
+            segments,
+            &WriterConfig::default(),
+            &MergeThrottle::unlimited(),
+            &writer.term_dictionary,
+            &writer.lock,
+        )
+        .unwrap();
+        let merged = mvcc.current_snapshot().segments[0].id;
+        let dictionary = writer.term_dictionary.read().clone();
+        assert_eq!(dictionary.segments_for(&Term::new("lang")).len(), 1);
+        assert_eq!(dictionary.segments_for(&Term::new("rust"))[0].segment_id, merged);
+        assert_eq!(probe(&dictionary, "lang"), (1, vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn compact_drops_terms_left_only_in_deleted_documents() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+        let mvcc = Arc::new(MVCCController::new());
+        let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+
+        for (id, text) in [(1, "common qux"), (2, "common words"), (3, "common qux")] {
+            writer.add_document(make_doc(id, text)).unwrap();
+        }
+        writer.flush().unwrap();
+        writer.delete_document(DocId(1)).unwrap();
+        writer.delete_document(DocId(3)).unwrap();
+        assert_eq!(writer.term_dictionary.read().doc_freq(&Term::new("qux")), 2);
+
+        writer.compact().unwrap();
+        let dictionary = writer.term_dictionary.read();
+        assert!(dictionary.segments_for(&Term::new("qux")).is_empty());
+        assert_eq!(dictionary.doc_freq(&Term::new("common")), 1);
+        let segment = mvcc.current_snapshot().segments[0].id;
+        assert!(IndexReader::open(&storage, segment).unwrap().get_postings(&Term::new("qux")).is_none());
+    }
+
+    #[test]
+    fn parallel_compaction_matches_sequential() {
+        let compact_with = |threads: usize| {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let storage = Arc::new(StorageLayout::new(temp_dir.path().to_path_buf()).unwrap());
+            let mvcc = Arc::new(MVCCController::new());
+            let mut writer = make_writer(storage.clone(), mvcc.clone(), CompressionType::LZ4);
+            writer.merge_policy = Box::new(TieredMergePolicy {
+                max_segments_per_tier: usize::MAX,
+                min_segments_to_merge: usize::MAX,
+                ..TieredMergePolicy::default()
+            });
+            writer.config.compaction_threads = threads;
+
+            for segment in 0..8u64 {
+                for n in 0..20 {
+                    let id = segment * 20 + n;
+                    writer.add_document(make_doc(id, &format!("entry{} shared", id))).unwrap();
+                }
+                writer.flush().unwrap();
+            }
+            // Every third document, and all of segment 5
+            for id in (0..160).filter(|id| id % 3 == 0 || (100..120).contains(id)) {
+                writer.delete_document(DocId(id)).unwrap();
+            }
+            writer.compact().unwrap();
+
+            let snapshot = mvcc.current_snapshot();
+            assert!(snapshot.deleted_docs.is_empty());
+            snapshot
+                .segments
+                .iter()
+                .map(|segment| {
+                    let mut reader = SegmentReader::open(&storage, segment.id).unwrap();
+                    let docs: Vec<u64> = reader
+                        .iter_documents()
+                        .unwrap()
+                        .map(|doc| doc.unwrap().id.0)
+                        .collect();
+                    let mut terms: Vec<(Term, Vec<DocId>)> = IndexReader::open(&storage, segment.id)
+                        .unwrap()
+                        .inverted_index
+                        .into_iter()
+                        .map(|(term, postings)| (term, postings.iter().map(|p| p.doc_id).collect()))
+                        .collect();
+                    terms.sort_by(|a, b| a.0.as_str().unwrap().cmp(b.0.as_str().unwrap()));
+                    (docs, terms)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = compact_with(1);
+        assert_eq!(sequential.len(), 7);
+        assert_eq!(compact_with(4), sequential);
+    }
 }